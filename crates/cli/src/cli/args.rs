@@ -6,12 +6,20 @@ use clap::{ArgAction, Parser, Subcommand};
 #[command(
     name = "goodcommit",
     version,
+    disable_version_flag = true,
     about = "Good Commit: fast AI commit messages"
 )]
 pub(crate) struct Cli {
     #[command(subcommand)]
     pub(crate) command: Option<Commands>,
 
+    /// Print version (with --verbose, also the git sha, build date, and features).
+    #[arg(short = 'V', long, action = ArgAction::SetTrue)]
+    pub(crate) version: bool,
+    /// With --version, print build info as JSON.
+    #[arg(long, action = ArgAction::SetTrue, requires = "version")]
+    pub(crate) json: bool,
+
     #[arg(value_name = "message", trailing_var_arg = true)]
     pub(crate) message: Vec<String>,
 
@@ -28,6 +36,11 @@ pub(crate) struct Cli {
     #[arg(long)]
     pub(crate) timeout: Option<u64>,
     #[arg(long)]
+    pub(crate) temperature: Option<f32>,
+    /// Sampling seed for reproducible output (OpenAI chat and Ollama).
+    #[arg(long)]
+    pub(crate) seed: Option<u64>,
+    #[arg(long)]
     pub(crate) max_input_tokens: Option<u32>,
     #[arg(long)]
     pub(crate) max_output_tokens: Option<u32>,
@@ -41,6 +54,15 @@ pub(crate) struct Cli {
     pub(crate) max_files: Option<u32>,
     #[arg(long)]
     pub(crate) lang: Option<String>,
+    #[arg(long, alias = "max-subject")]
+    pub(crate) max_subject_length: Option<u32>,
+    #[arg(long)]
+    pub(crate) body_wrap: Option<u32>,
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) conventional_types: Option<Vec<String>>,
+    /// Send the full diff of files matching this glob, however many lines changed (repeatable).
+    #[arg(long, value_name = "GLOB")]
+    pub(crate) allow_large: Vec<String>,
 
     #[arg(short = 'l', long, action = ArgAction::SetTrue)]
     pub(crate) local: bool,
@@ -64,6 +86,12 @@ pub(crate) struct Cli {
     pub(crate) push: bool,
     #[arg(long, action = ArgAction::SetTrue)]
     pub(crate) no_push: bool,
+    /// In `split`, push once after the session instead of after each commit.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) push_at_end: bool,
+    /// Skip pushing and queue the commit for `goodcommit push`.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) push_later: bool,
 
     #[arg(long, action = ArgAction::SetTrue)]
     pub(crate) stage_all: bool,
@@ -71,13 +99,73 @@ pub(crate) struct Cli {
     pub(crate) no_stage: bool,
     #[arg(long, action = ArgAction::SetTrue)]
     pub(crate) interactive: bool,
+    /// Rewrite HEAD's message from HEAD's own diff, keeping its tree; nothing is staged.
+    #[arg(
+        long,
+        action = ArgAction::SetTrue,
+        conflicts_with_all = ["message", "stage_all", "interactive"]
+    )]
+    pub(crate) amend_reword: bool,
+    /// Fail with an error instead of a warning when nothing is staged.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) require_staged: bool,
+    /// Commit even when staged files still contain conflict markers.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) allow_conflicts: bool,
+
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) notes: bool,
+    /// Ask the model why it chose the message (one more AI call) and print the answer; it is not committed.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) explain: bool,
+    /// Also draft a pull request title and body in the same AI call, printed after committing.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) pr: bool,
+    /// Write the pull request draft to this file instead of stdout (implies --pr).
+    #[arg(long, value_name = "FILE")]
+    pub(crate) pr_file: Option<PathBuf>,
+
+    /// Send files matched by the ignore patterns to the AI for this run.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) include_ignored: bool,
+
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) retry_on_invalid: bool,
+    /// Fail instead of committing the generic fallback message when the provider fails.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) no_fallback: bool,
 
     #[arg(long, action = ArgAction::SetTrue)]
     pub(crate) yes: bool,
     #[arg(long, action = ArgAction::SetTrue)]
     pub(crate) dry_run: bool,
-    #[arg(long, action = ArgAction::SetTrue)]
+    /// Open the generated message in your editor before committing.
+    #[arg(long, alias = "edit-first", action = ArgAction::SetTrue)]
     pub(crate) edit: bool,
+    /// Open the editor with this text first and the generated message below it to merge (implies --edit).
+    #[arg(long, value_name = "text")]
+    pub(crate) edit_message: Option<String>,
+    /// Use this text as the message body; the AI writes only the subject.
+    #[arg(long, value_name = "text", conflicts_with_all = ["message", "body_file"])]
+    pub(crate) body: Option<String>,
+    /// Use this file's contents as the message body; the AI writes only the subject.
+    #[arg(long, value_name = "FILE", conflicts_with = "message")]
+    pub(crate) body_file: Option<PathBuf>,
+    /// Also write the final message to this file (`-` for stdout); with --dry-run, instead of committing.
+    #[arg(long, value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+    /// Let --output replace an existing file.
+    #[arg(long, action = ArgAction::SetTrue, requires = "output")]
+    pub(crate) force: bool,
+    /// Regenerate instead of prompting when staged changes move under the message.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) auto_refresh: bool,
+    /// Run the pre-commit hook before generating, and skip it at commit time if it passed.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) precheck: bool,
+    /// Commit on a branch listed in `protected_branches` without asking.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) allow_protected: bool,
     #[arg(long, action = ArgAction::SetTrue)]
     pub(crate) no_verify: bool,
     #[arg(long, action = ArgAction::SetTrue)]
@@ -85,21 +173,151 @@ pub(crate) struct Cli {
 
     #[arg(long, action = ArgAction::SetTrue)]
     pub(crate) verbose: bool,
+    /// Print the diff sent to the AI (after ignore patterns and truncation) to stderr.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) print_diff: bool,
+    /// Print how long each phase (staging, diff, provider, commit, push) took.
+    #[arg(long, action = ArgAction::SetTrue)]
+    pub(crate) profile_time: bool,
+    /// Write a run report (redacted config, prompts, timings, result) under this directory.
+    #[arg(long, value_name = "DIR")]
+    pub(crate) report: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
 pub(crate) enum Commands {
-    Config,
-    Doctor,
+    /// Show the effective config, or edit a config file.
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+    /// Edit the AI ignore file.
+    Ignore {
+        #[command(subcommand)]
+        action: IgnoreAction,
+    },
+    Doctor {
+        /// Print the checks as JSON, for CI and setup scripts.
+        #[arg(long)]
+        json: bool,
+    },
     #[command(alias = "init")]
-    Setup,
-    Split,
+    Setup {
+        /// Change one setting in the existing config.toml, keeping everything else.
+        #[arg(long)]
+        edit: bool,
+        /// Write config.toml from flags and `GOODCOMMIT_*` variables without prompting.
+        #[arg(long, conflicts_with = "edit")]
+        non_interactive: bool,
+        /// Provider to configure (required with --non-interactive unless GOODCOMMIT_PROVIDER is set).
+        #[arg(long, requires = "non_interactive")]
+        provider: Option<String>,
+        /// Default model (defaults to the provider's recommended model).
+        #[arg(long, requires = "non_interactive")]
+        model: Option<String>,
+        #[arg(long, requires = "non_interactive", conflicts_with = "no_push")]
+        push: bool,
+        #[arg(long, requires = "non_interactive")]
+        no_push: bool,
+        /// Save the OpenAI API key from the environment to config.toml (plaintext).
+        #[arg(long, requires = "non_interactive")]
+        save_key: bool,
+        /// Overwrite an existing config.toml.
+        #[arg(long, requires = "non_interactive")]
+        force: bool,
+    },
+    Split {
+        /// Print the end-of-session summary as JSON on stdout; progress goes to stderr.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check a commit message against the configured rules.
+    Lint {
+        /// Commit to lint (defaults to HEAD).
+        #[arg(conflicts_with_all = ["message", "file", "range"])]
+        rev: Option<String>,
+        /// Lint every commit in a range such as `main..HEAD`.
+        #[arg(long, conflicts_with_all = ["message", "file"])]
+        range: Option<String>,
+        #[arg(long, conflicts_with = "file")]
+        message: Option<String>,
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Push commits queued with `--push-later`.
+    Push,
+    /// Summarize locally recorded usage stats (see `record_stats`).
+    Stats {
+        /// Show p50/p95 latency per phase and per model instead.
+        #[arg(long)]
+        perf: bool,
+    },
+    /// Show what the last run in this repo did (kept in `.git/goodcommit/state.json`).
+    Status,
+    /// Replace this binary with the latest GitHub release (for install-script installs).
+    SelfUpdate {
+        /// Only report whether a newer release exists.
+        #[arg(long)]
+        check: bool,
+    },
+    /// Write Markdown release notes for a range of commits.
+    Changelog {
+        /// Commits to summarize, such as `v1.2.0..HEAD`.
+        #[arg(conflicts_with = "since_last_tag")]
+        range: Option<String>,
+        /// Summarize the commits since the most recent tag (all commits when there are none).
+        #[arg(long)]
+        since_last_tag: bool,
+    },
+    /// Create an annotated release tag whose message highlights the commits since the last tag.
+    Tag {
+        /// Name of the new tag, such as `v1.4.0`.
+        name: String,
+        /// Previous tag to start from (defaults to the most recent tag).
+        #[arg(long, value_name = "TAG")]
+        from: Option<String>,
+        /// Push the new tag after creating it.
+        #[arg(long)]
+        push_tag: bool,
+    },
+    /// Revert a commit and commit the result as `revert: <original subject>`.
+    Revert {
+        /// Commit to revert.
+        sha: String,
+        /// Why the commit is being reverted; explained in the message body.
+        #[arg(long)]
+        context: Option<String>,
+    },
+    /// Learn this repo's commit style (scopes, types, body usage) from recent history.
+    Learn {
+        /// How many recent commits to scan.
+        #[arg(long, default_value_t = 200)]
+        commits: usize,
+    },
     Hook {
         #[command(subcommand)]
         action: HookAction,
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub(crate) enum ConfigAction {
+    /// Open the repo config (or the global one) in your editor, creating it if missing.
+    Edit {
+        #[arg(long)]
+        global: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum IgnoreAction {
+    /// Open `.goodcommit-ignore` (or the global ignore file) in your editor.
+    Edit {
+        #[arg(long)]
+        global: bool,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub(crate) enum HookAction {
     Install,
@@ -109,5 +327,8 @@ pub(crate) enum HookAction {
         path: PathBuf,
         source: Option<String>,
         sha: Option<String>,
+        /// Print what the hook would write instead of writing it.
+        #[arg(long)]
+        dry_run: bool,
     },
 }
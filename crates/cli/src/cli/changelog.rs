@@ -0,0 +1,61 @@
+use anyhow::{anyhow, Result};
+
+use goodcommit_core::git::{GitBackend, SystemGit};
+use goodcommit_core::pipeline::generate_changelog;
+use goodcommit_core::providers::build_provider;
+
+use crate::ui;
+
+use super::args::Cli;
+use super::config::config_for_repo;
+
+/// Print Markdown release notes for `range`, or for the commits since the last tag.
+pub(crate) async fn run_changelog(
+    cli: &Cli,
+    range: Option<String>,
+    since_last_tag: bool,
+) -> Result<()> {
+    let git = SystemGit::new();
+    git.ensure_git_repo()?;
+    let repo_root = git.repo_root().ok();
+    let (config, _paths) = config_for_repo(cli, repo_root.as_deref())?;
+
+    let (range, heading) = match range {
+        Some(range) => {
+            let heading = format!("Changes in {range}");
+            (range, heading)
+        }
+        None if since_last_tag => resolve_since_last_tag(&git)?,
+        None => {
+            return Err(anyhow!(
+                "pass a range such as `v1.2.0..HEAD`, or --since-last-tag"
+            ))
+        }
+    };
+
+    let provider = build_provider(&config).ok();
+    let Some(changelog) =
+        generate_changelog(&git, provider.as_deref(), &config, &range, &heading).await?
+    else {
+        ui::info(&format!("no commits in {range}"));
+        return Ok(());
+    };
+
+    for warning in &changelog.warnings {
+        ui::warn(warning);
+    }
+    print!("{}", changelog.markdown);
+    Ok(())
+}
+
+/// `<tag>..HEAD` for the last tag, or all of HEAD's history when there are no tags.
+pub(super) fn resolve_since_last_tag(git: &impl GitBackend) -> Result<(String, String)> {
+    if let Some(tag) = git.last_tag()? {
+        return Ok((format!("{tag}..HEAD"), format!("Changes since {tag}")));
+    }
+    if git.head_subject()?.is_none() {
+        return Err(anyhow!("no commits yet; nothing to summarize"));
+    }
+    ui::warn("no tags found; summarizing every commit since the first one");
+    Ok(("HEAD".to_string(), "All changes".to_string()))
+}
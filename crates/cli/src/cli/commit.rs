@@ -1,24 +1,46 @@
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use dialoguer::{theme::ColorfulTheme, Confirm, MultiSelect};
-use tracing::info_span;
+use tracing::{debug, info_span};
 
-use goodcommit_core::config::{config_dir, EffectiveConfig, ProviderKind, StageMode};
-use goodcommit_core::git::{GitBackend, SystemGit};
-use goodcommit_core::ignore::build_ignore_matcher;
-use goodcommit_core::pipeline::{generate_commit_message, PipelineResult};
-use goodcommit_core::providers::build_provider;
+use goodcommit_core::config::{config_dir, ConfigPaths, EffectiveConfig, ProviderKind, StageMode};
+use goodcommit_core::git::{in_sparse_cone, is_utf8_encoding, GitBackend, GitFileStat, SystemGit};
+use goodcommit_core::ignore::{build_ignore_matcher, IgnoreMatcher};
+use goodcommit_core::pipeline::{
+    collect_staged_diff, collected_diff, estimate_cost, generate_commit_message,
+    generate_commit_note, generate_explanation, generate_from_staged_diff, record_stats,
+    sanitize_edited_message, stage_overflow, staged_content_unchanged, PipelineOutcome,
+    PipelineResult, PrDraft, StagedDiff,
+};
+use goodcommit_core::providers::{build_provider, Provider};
+use goodcommit_core::status::{FileEntry, RepoStatus};
+use goodcommit_core::timing::Timings;
 
-use crate::hooks;
+use crate::editor;
+use crate::exit::{ExitCode, NOTHING_TO_COMMIT};
+use crate::hooks::{self, hook_decision, HookDecision};
 use crate::setup;
 use crate::ui;
 use crate::util::{is_interactive, join_message_args};
 
 use super::args::Cli;
 use super::config::config_for_repo;
+use super::confirm::confirm_commit;
+use super::conflicts::check_conflict_markers;
+use super::profile::print_timings;
+use super::push;
+use super::report::Reporter;
+use super::state::RunState;
+use super::summary::{print_split_summary, SplitCommit, SplitSummary};
+use super::warmup::{should_warm_up, Warmup};
+use super::warnings::{explain_fallback, print_warnings};
 
 pub(crate) async fn run_commit(cli: Cli) -> Result<()> {
+    if output_to_stdout(&cli) {
+        ui::reserve_stdout();
+    }
     if maybe_setup_from_message(&cli)? {
         return Ok(());
     }
@@ -27,73 +49,131 @@ pub(crate) async fn run_commit(cli: Cli) -> Result<()> {
     git.ensure_git_repo()?;
     let repo_root = git.repo_root()?;
     maybe_prompt_setup(&cli, Some(&repo_root))?;
-    let (config, paths) = config_for_repo(&cli, Some(&repo_root))?;
+    let (mut config, paths) = config_for_repo(&cli, Some(&repo_root))?;
+    check_output_path(&cli)?;
+    config.user_body = user_body(&cli)?;
 
+    let run_id = generate_run_id();
     let span = info_span!(
         "commit_run",
-        run_id = %generate_run_id(),
+        run_id = %run_id,
         provider = %config.provider.as_str(),
         model = %config.model,
         stage_mode = ?config.stage_mode,
     );
     let _enter = span.enter();
 
-    let ignore_matcher = build_ignore_matcher(&config.ignore, &paths)?;
+    let status = git.status()?;
+    // Local providers are built before staging so model load overlaps with git work, but
+    // only once there is something to stage or commit.
+    let early_provider: Option<Arc<dyn Provider>> = if should_warm_up(&config)
+        && join_message_args(&cli.message).is_none()
+        && has_changes_to_commit(&status, config.stage_mode)
+    {
+        build_provider(&config).ok().map(Arc::from)
+    } else {
+        None
+    };
+    let warmup = early_provider.clone().map(Warmup::spawn);
 
-    match config.stage_mode {
-        StageMode::All => git.stage_all()?,
-        StageMode::Interactive => git.stage_interactive()?,
-        StageMode::None => {}
-        StageMode::Auto => {
-            let staged_files = git.staged_files()?;
-            if staged_files.is_empty() {
-                git.stage_all()?;
-            }
-        }
+    if !confirm_stage_size(&git, &config, &cli, &status)? {
+        ui::info("staging canceled");
+        return Ok(());
     }
+    let mut timings = Timings::new();
+    timings.time("staging", || -> Result<()> {
+        match config.stage_mode {
+            StageMode::All => git.stage_all()?,
+            StageMode::Interactive => git.stage_interactive()?,
+            StageMode::None => {}
+            StageMode::Auto if status.staged.is_empty() => git.stage_all()?,
+            StageMode::Auto => {}
+        }
+        Ok(())
+    })?;
 
     if let Some(message) = join_message_args(&cli.message) {
-        return commit_with_message(&git, &config, &cli, &message);
-    }
-
-    let provider = match build_provider(&config) {
-        Ok(provider) => Some(provider),
-        Err(err) => {
-            ui::warn(&format!("provider setup failed, using fallback: {err}"));
-            print_provider_help(&config);
-            None
+        if !check_branch(&git, &cli, &mut config)? {
+            return Ok(());
         }
-    };
+        commit_with_message(&git, &config, &cli, Draft::new(message), &mut timings)?;
+        if cli.profile_time {
+            print_timings(&timings);
+        }
+        return Ok(());
+    }
 
-    let pipeline_result =
-        generate_commit_message(&git, provider.as_deref(), &config, &ignore_matcher).await?;
+    let status = git.status()?;
+    if status.staged.is_empty() {
+        return report_no_changes(&status, &config, &cli, &timings);
+    }
+    if !check_branch(&git, &cli, &mut config)? {
+        return Ok(());
+    }
 
-    let outcome = match pipeline_result {
-        PipelineResult::NoChanges => {
-            if git.has_unstaged_changes()? {
-                ui::warn("no staged changes; stage files or use --stage-all");
-            } else {
-                ui::info("working tree clean");
+    let ignore_matcher = ignore_matcher_for(&cli, &config, &paths)?;
+    let mut reporter = Reporter::for_run(&cli, &config, &run_id, &git, &ignore_matcher);
+    let provider = match early_provider {
+        Some(provider) => Some(provider),
+        None => match build_provider(&config) {
+            Ok(provider) => Some(Arc::from(provider)),
+            Err(err) => {
+                ui::warn(&format!("provider setup failed, using fallback: {err}"));
+                print_provider_help(&config);
+                None
             }
-            return Ok(());
-        }
-        PipelineResult::Message(outcome) => outcome,
+        },
+    };
+    let provider = match (&reporter, provider) {
+        (Some(reporter), Some(provider)) => Some(reporter.wrap(provider)),
+        (_, provider) => provider,
     };
 
-    for warning in &outcome.warnings {
-        ui::warn(warning);
+    if let Some(warmup) = &warmup {
+        warmup.log_overlap();
     }
-    if has_provider_warning(&outcome.warnings) {
-        print_provider_help(&config);
+    let generated = generate_and_commit(
+        &git,
+        provider.as_deref(),
+        &config,
+        &ignore_matcher,
+        &cli,
+        &mut timings,
+        reporter.as_mut(),
+    )
+    .await;
+    drop(warmup);
+    if let Some(reporter) = reporter {
+        reporter.finish(&config, &timings, generated.as_ref().err());
     }
+    let generated = generated?;
 
-    commit_with_message(&git, &config, &cli, &outcome.message)
+    if matches!(generated, Generated::NoChanges) {
+        return report_no_changes(&git.status()?, &config, &cli, &timings);
+    }
+    if cli.profile_time {
+        print_timings(&timings);
+    }
+    Ok(())
 }
 
-pub(crate) async fn run_split(cli: Cli) -> Result<()> {
+pub(crate) async fn run_split(cli: Cli, json: bool) -> Result<()> {
     if !is_interactive() {
         return Err(anyhow!("split requires an interactive terminal"));
     }
+    if json {
+        ui::reserve_stdout();
+    }
+    if cli.output.is_some() {
+        return Err(anyhow!(
+            "--output writes a single message and cannot be used with split"
+        ));
+    }
+    if cli.body.is_some() || cli.body_file.is_some() {
+        return Err(anyhow!(
+            "--body and --body-file describe a single commit and cannot be used with split"
+        ));
+    }
 
     let git = SystemGit::new();
     git.ensure_git_repo()?;
@@ -111,8 +191,7 @@ pub(crate) async fn run_split(cli: Cli) -> Result<()> {
     );
     let _enter = span.enter();
 
-    let staged = git.staged_files()?;
-    if !staged.is_empty() {
+    if !git.status()?.staged.is_empty() {
         ui::warn("staged changes detected");
         let confirm = Confirm::with_theme(&ColorfulTheme::default())
             .with_prompt("unstage all and continue with split?")
@@ -124,8 +203,11 @@ pub(crate) async fn run_split(cli: Cli) -> Result<()> {
         }
         git.unstage_all()?;
     }
+    if !check_branch(&git, &cli, &mut config)? {
+        return Ok(());
+    }
 
-    let ignore_matcher = build_ignore_matcher(&config.ignore, &paths)?;
+    let ignore_matcher = ignore_matcher_for(&cli, &config, &paths)?;
 
     let provider = match build_provider(&config) {
         Ok(provider) => Some(provider),
@@ -136,11 +218,21 @@ pub(crate) async fn run_split(cli: Cli) -> Result<()> {
         }
     };
 
+    let push_at_end = cli.push_at_end && !cli.no_push && !cli.push_later;
+    if push_at_end {
+        config.push = false;
+    }
+
+    let mut summary = SplitSummary::default();
+    let mut timings = Timings::new();
     loop {
-        let mut remaining = git.working_tree_files()?;
+        let mut remaining = git.status()?.working_tree_paths();
+        if let Some(dirs) = git.sparse_cone()? {
+            remaining.retain(|path| in_sparse_cone(path, &dirs));
+        }
         if remaining.is_empty() {
             ui::info("working tree clean");
-            return Ok(());
+            break;
         }
         remaining.sort();
 
@@ -156,7 +248,7 @@ pub(crate) async fn run_split(cli: Cli) -> Result<()> {
                 .interact()?;
             if done {
                 ui::info("split complete");
-                return Ok(());
+                break;
             }
             continue;
         }
@@ -166,45 +258,97 @@ pub(crate) async fn run_split(cli: Cli) -> Result<()> {
             .map(|index| remaining[*index].clone())
             .collect();
 
-        git.stage_paths(&chosen)?;
-
-        let pipeline_result =
-            generate_commit_message(&git, provider.as_deref(), &config, &ignore_matcher).await?;
+        let mut commit_timings = Timings::new();
+        commit_timings.time("staging", || git.stage_paths(&chosen))?;
 
-        let outcome = match pipeline_result {
-            PipelineResult::NoChanges => {
-                ui::warn("no staged diff for selection");
-                git.unstage_all()?;
-                continue;
-            }
-            PipelineResult::Message(outcome) => outcome,
-        };
+        let generated = generate_and_commit(
+            &git,
+            provider.as_deref(),
+            &config,
+            &ignore_matcher,
+            &cli,
+            &mut commit_timings,
+            None,
+        )
+        .await?;
+        timings.extend(&commit_timings);
+        git.unstage_all()?;
 
-        for warning in &outcome.warnings {
-            ui::warn(warning);
+        if matches!(generated, Generated::NoChanges) {
+            ui::warn("no staged diff for selection");
+            continue;
         }
-        if has_provider_warning(&outcome.warnings) {
-            print_provider_help(&config);
+        if let Generated::Committed {
+            message,
+            used_fallback,
+        } = generated
+        {
+            summary.commits.push(SplitCommit {
+                sha: git.short_head().unwrap_or_default(),
+                subject: message.lines().next().unwrap_or_default().to_string(),
+                files: chosen.len(),
+                used_fallback,
+            });
         }
 
-        commit_with_message(&git, &config, &cli, &outcome.message)?;
-        git.unstage_all()?;
-
         if cli.dry_run {
             return Ok(());
         }
     }
+
+    if push_at_end && !summary.commits.is_empty() {
+        let push_commits = if summary.commits.iter().any(|commit| commit.used_fallback) {
+            let (push_commits, notice) = push::fallback_push_decision(config.push_on_fallback)?;
+            ui::warn(&notice);
+            push_commits
+        } else {
+            true
+        };
+        if push_commits {
+            summary.pushed = timings.time("push", || push_and_report(&git));
+        }
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        print_split_summary(&summary);
+    }
+    if cli.profile_time {
+        print_timings(&timings);
+    }
+
+    Ok(())
 }
 
+/// Run the prepare-commit-msg hook.
+///
+/// The hook fails open: any error is logged and the message file is left
+/// untouched, so a broken config or provider never blocks `git commit`.
 pub(crate) async fn run_hook(
     path: std::path::PathBuf,
     source: Option<String>,
+    dry_run: bool,
     cli: Cli,
+) -> Result<()> {
+    if let Err(err) = generate_hook_message(&path, source, dry_run, &cli).await {
+        debug!("hook skipped: {err:#}");
+        if dry_run {
+            ui::info(&format!("would skip: {err}"));
+        }
+    }
+    Ok(())
+}
+
+async fn generate_hook_message(
+    path: &std::path::Path,
+    source: Option<String>,
+    dry_run: bool,
+    cli: &Cli,
 ) -> Result<()> {
     let git = SystemGit::new();
     git.ensure_git_repo()?;
     let repo_root = git.repo_root()?;
-    let (mut config, paths) = config_for_repo(&cli, Some(&repo_root))?;
+    let (mut config, paths) = config_for_repo(cli, Some(&repo_root))?;
 
     config.confirm = false;
     config.push = false;
@@ -219,34 +363,57 @@ pub(crate) async fn run_hook(
     );
     let _enter = span.enter();
 
-    if let Some(source) = source.as_deref() {
-        if !source.trim().is_empty() {
-            return Ok(());
-        }
-    }
-
-    if let Ok(existing) = std::fs::read_to_string(&path) {
-        let has_message = existing
-            .lines()
-            .map(str::trim)
-            .any(|line| !line.is_empty() && !line.starts_with('#'));
-        if has_message {
-            return Ok(());
+    let existing = std::fs::read_to_string(path).ok();
+    let decision = hook_decision(source.as_deref(), existing.as_deref());
+    if let HookDecision::Skip(reason) = decision {
+        if dry_run {
+            ui::info(&format!("would skip: {reason}"));
         }
+        return Ok(());
     }
+    let mut state = RunState::new("hook");
 
-    let ignore_matcher = build_ignore_matcher(&config.ignore, &paths)?;
+    let ignore_matcher = ignore_matcher_for(cli, &config, &paths)?;
     let provider = build_provider(&config).ok();
 
     let pipeline_result =
-        generate_commit_message(&git, provider.as_deref(), &config, &ignore_matcher).await?;
+        generate_commit_message(&git, provider.as_deref(), &config, &ignore_matcher).await;
 
     let outcome = match pipeline_result {
-        PipelineResult::NoChanges => return Ok(()),
-        PipelineResult::Message(outcome) => outcome,
+        Ok(PipelineResult::Message(outcome)) => outcome,
+        Ok(PipelineResult::NoChanges) => {
+            if dry_run {
+                ui::info("would skip: no staged changes");
+            }
+            state.finish(&git, "no-changes");
+            return Ok(());
+        }
+        Err(err) => {
+            state.error = Some(err.to_string());
+            state.finish(&git, "failed");
+            return Err(err.into());
+        }
+    };
+    record_stats(&config, &outcome, None);
+    state.record_outcome(&outcome);
+
+    let message = if decision == HookDecision::PrependSubject {
+        let subject = outcome.message.lines().next().unwrap_or_default();
+        hooks::prepend_subject(subject, existing.as_deref().unwrap_or_default())
+    } else {
+        outcome.message
     };
 
-    hooks::write_hook_message(&path, &outcome.message)?;
+    state.message = Some(message.clone());
+    if dry_run {
+        ui::info(&format!("would write to {}:", path.display()));
+        ui::preview_message(&message);
+        state.finish(&git, "previewed");
+        return Ok(());
+    }
+
+    hooks::write_hook_message(path, &message)?;
+    state.finish(&git, "written");
     Ok(())
 }
 
@@ -271,59 +438,774 @@ fn maybe_setup_from_message(cli: &Cli) -> Result<bool> {
     Ok(false)
 }
 
+/// The model's reason for the message, for `--explain`; the fallback message has none.
+async fn maybe_explain(
+    git: &impl GitBackend,
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+    outcome: &PipelineOutcome,
+) -> Option<String> {
+    let Some(provider) = provider.filter(|_| !outcome.used_fallback) else {
+        ui::info("no explanation: the fallback message was not written by the model");
+        return None;
+    };
+    match generate_explanation(git, provider, config, ignore, &outcome.message).await {
+        Ok(explanation) => explanation,
+        Err(err) => {
+            ui::warn(&format!("explanation failed: {err}"));
+            None
+        }
+    }
+}
+
+async fn maybe_generate_note(
+    git: &impl GitBackend,
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+    message: &str,
+) -> Option<String> {
+    if !config.notes {
+        return None;
+    }
+    let provider = provider?;
+
+    match generate_commit_note(git, provider, config, ignore, message).await {
+        Ok(note) => note,
+        Err(err) => {
+            ui::warn(&format!("note generation failed: {err}"));
+            None
+        }
+    }
+}
+
+/// A message ready to commit, plus the optional extras produced alongside it.
+struct Draft {
+    message: String,
+    note: Option<String>,
+    dropped_body: Option<String>,
+    /// Staged content the message describes; `None` skips the staleness check.
+    fingerprint: Option<String>,
+    /// The pre-commit hook already passed for this staged content.
+    verified: bool,
+    /// The staged diff was already checked for conflict markers.
+    conflicts_checked: bool,
+    /// Where the message came from, shown in the preview; `None` for messages given on the command line.
+    source: Option<String>,
+    pr: Option<PrDraft>,
+    /// Why the model chose the message, shown with the preview for `--explain`.
+    explanation: Option<String>,
+    /// The message is the fallback, so pushing follows `push_on_fallback`.
+    used_fallback: bool,
+}
+
+impl Draft {
+    fn new(message: String) -> Self {
+        Self {
+            message,
+            note: None,
+            dropped_body: None,
+            fingerprint: None,
+            verified: false,
+            conflicts_checked: false,
+            source: None,
+            pr: None,
+            explanation: None,
+            used_fallback: false,
+        }
+    }
+}
+
+enum CommitOutcome {
+    Committed {
+        message: String,
+        /// Why a fallback commit was or was not pushed.
+        push_notice: Option<String>,
+    },
+    Skipped,
+    /// Staged content changed after generation and `--auto-refresh` asked to regenerate.
+    Stale,
+}
+
+enum Generated {
+    NoChanges,
+    Committed {
+        message: String,
+        used_fallback: bool,
+    },
+    Skipped,
+}
+
+const MAX_REFRESHES: usize = 2;
+
+/// Generate a message for the staged changes and commit it, regenerating on stale staging.
+async fn generate_and_commit(
+    git: &impl GitBackend,
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+    cli: &Cli,
+    timings: &mut Timings,
+    mut reporter: Option<&mut Reporter>,
+) -> Result<Generated> {
+    let mut state = RunState::new(if cli.dry_run { "dry-run" } else { "commit" });
+    let mut staging = timings.phase("staging");
+    let mut refreshes = 0;
+    loop {
+        let verified = timings.time("precheck", || run_precheck(git, config, cli))?;
+        let staged = collect_staged_diff(git, config, ignore)?;
+        if cli.print_diff {
+            print_collected_diff(&staged);
+        }
+        if !check_conflict_markers(git, config, cli)? {
+            ui::info("commit canceled");
+            return Ok(Generated::Skipped);
+        }
+        if provider.is_some() && !confirm_cost(config, &staged)? {
+            ui::info("generation canceled");
+            return Ok(Generated::Skipped);
+        }
+        let outcome = match generate_from_staged_diff(git, provider, config, staged).await {
+            Ok(PipelineResult::Message(outcome)) => outcome,
+            Ok(PipelineResult::NoChanges) => {
+                state.finish(git, "no-changes");
+                return Ok(Generated::NoChanges);
+            }
+            Err(err) => {
+                state.error = Some(err.to_string());
+                state.finish(git, "failed");
+                return Err(err.into());
+            }
+        };
+
+        // A regeneration after stale staging did not stage again.
+        record_stats(config, &outcome, staging.take());
+        state.record_outcome(&outcome);
+        timings.extend(&outcome.timings);
+        if let Some(reporter) = reporter.as_deref_mut() {
+            reporter.record_outcome(&outcome);
+        }
+        print_warnings(&outcome.warnings, cli.verbose);
+        if has_timeout_warning(&outcome.warnings) {
+            print_timeout_help(config);
+        } else if has_provider_warning(&outcome.warnings) {
+            print_provider_help(config);
+        }
+        explain_fallback(
+            outcome.fallback_reason.as_ref(),
+            &outcome.trace,
+            cli.verbose,
+            config.confirm,
+        )?;
+
+        let note = maybe_generate_note(git, provider, config, ignore, &outcome.message).await;
+        let explanation = if cli.explain {
+            maybe_explain(git, provider, config, ignore, &outcome).await
+        } else {
+            None
+        };
+        let used_fallback = outcome.used_fallback;
+        let draft = Draft {
+            message: outcome.message,
+            note,
+            dropped_body: outcome.dropped_body,
+            fingerprint: outcome.staged_fingerprint,
+            verified,
+            conflicts_checked: true,
+            source: Some(
+                outcome
+                    .generated_by
+                    .unwrap_or_else(|| "fallback".to_string()),
+            ),
+            pr: outcome.pr,
+            explanation,
+            used_fallback,
+        };
+
+        match commit_with_message(git, config, cli, draft, timings)? {
+            CommitOutcome::Committed {
+                message,
+                push_notice,
+            } => {
+                state.message = Some(message.clone());
+                state.warnings.extend(push_notice);
+                state.finish(git, "committed");
+                return Ok(Generated::Committed {
+                    message,
+                    used_fallback,
+                });
+            }
+            CommitOutcome::Skipped => {
+                state.finish(git, if cli.dry_run { "previewed" } else { "skipped" });
+                return Ok(Generated::Skipped);
+            }
+            CommitOutcome::Stale if refreshes < MAX_REFRESHES => {
+                refreshes += 1;
+                ui::info("regenerating for the new staged changes");
+            }
+            CommitOutcome::Stale => {
+                return Err(anyhow!(
+                    "staged changes kept changing during generation; aborting"
+                ))
+            }
+        }
+    }
+}
+
+/// Ask before `git add .` stages more than `auto_stage_max_files` files.
+///
+/// An explicit `--stage-all` skips the check; non-interactive runs fail instead of asking.
+fn confirm_stage_size(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+    cli: &Cli,
+    status: &RepoStatus,
+) -> Result<bool> {
+    let stages_everything = match config.stage_mode {
+        StageMode::All => true,
+        StageMode::Auto => status.staged.is_empty(),
+        StageMode::Interactive | StageMode::None => false,
+    };
+    if cli.stage_all || !stages_everything {
+        return Ok(true);
+    }
+    let Some(overflow) = stage_overflow(git, config.auto_stage_max_files)? else {
+        return Ok(true);
+    };
+
+    let dirs = overflow
+        .top_dirs
+        .iter()
+        .take(5)
+        .map(|(dir, count)| format!("{dir} ({count})"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let summary = format!(
+        "staging would add {} files (auto_stage_max_files = {}): {dirs}",
+        overflow.count, config.auto_stage_max_files
+    );
+    if !is_interactive() {
+        return Err(anyhow!(
+            "{summary}; pass --stage-all to stage them anyway, or stage files yourself"
+        ));
+    }
+    ui::warn(&summary);
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("stage all of them?")
+        .default(false)
+        .interact()?)
+}
+
+/// Dump the diff the pipeline will send to stderr, for `--print-diff`.
+fn print_collected_diff(staged: &StagedDiff) {
+    let diff = collected_diff(staged);
+    if diff.is_empty() {
+        eprintln!("--- collected diff: empty (everything staged was ignored or binary) ---");
+    } else {
+        eprintln!(
+            "--- collected diff ---\n{}\n--- end collected diff ---",
+            diff.trim_end()
+        );
+    }
+}
+
+/// Ask before an expensive provider call when `confirm_cost` is set.
+fn confirm_cost(config: &EffectiveConfig, staged: &StagedDiff) -> Result<bool> {
+    let Some(threshold) = config.confirm_cost else {
+        return Ok(true);
+    };
+    if !config.confirm || !is_interactive() {
+        return Ok(true);
+    }
+    let Some(estimate) = estimate_cost(staged, config) else {
+        return Ok(true);
+    };
+    if estimate.usd < threshold {
+        return Ok(true);
+    }
+
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "~${:.4} with {} (~{} input, up to {} output tokens), proceed?",
+            estimate.usd, config.model, estimate.input_tokens, estimate.output_tokens
+        ))
+        .default(true)
+        .interact()?)
+}
+
+/// Guard against committing on a protected branch or a detached `HEAD`.
+///
+/// Returns `false` when the user declined; a detached `HEAD` turns push off.
+pub(super) fn check_branch(
+    git: &impl GitBackend,
+    cli: &Cli,
+    config: &mut EffectiveConfig,
+) -> Result<bool> {
+    if cli.dry_run {
+        return Ok(true);
+    }
+    let Some(branch) = git.current_branch()? else {
+        ui::warn("HEAD is detached; committing without pushing");
+        config.push = false;
+        return Ok(true);
+    };
+
+    let protected = IgnoreMatcher::from_patterns(&config.protected_branches)?;
+    if cli.allow_protected || !protected.is_ignored(&branch) {
+        return Ok(true);
+    }
+    if !is_interactive() {
+        return Err(anyhow!(
+            "refusing to commit on protected branch `{branch}` (pass --allow-protected to override)"
+        ));
+    }
+
+    let action = if config.push && !cli.no_push && !cli.push_later {
+        "commit and push"
+    } else {
+        "commit"
+    };
+    let confirm = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("you are on {branch} — {action} anyway?"))
+        .default(false)
+        .interact()?;
+    if !confirm {
+        ui::info("commit canceled");
+    }
+    Ok(confirm)
+}
+
+/// Preview, confirm, and commit a message built outside the staged-diff pipeline.
+///
+/// Returns `false` when no commit was made (dry run, canceled, or empty after editing).
+pub(super) fn commit_prepared(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+    cli: &Cli,
+    message: String,
+    source: String,
+    timings: &mut Timings,
+) -> Result<bool> {
+    let draft = Draft {
+        source: Some(source),
+        ..Draft::new(message)
+    };
+    let outcome = commit_with_message(git, config, cli, draft, timings)?;
+    Ok(matches!(outcome, CommitOutcome::Committed { .. }))
+}
+
+/// Editor text for `--edit-message`: the seed first, then the suggestion to merge into it.
+fn seeded_message(seed: &str, suggestion: &str) -> String {
+    format!(
+        "{}\n\n# Suggested message below; merge it into the text above and delete what you don't keep.\n{suggestion}",
+        seed.trim_end_matches('\n')
+    )
+}
+
+/// Preview, confirm, and commit a draft.
+///
+/// Refuses to commit a message for staged content that changed since generation.
 fn commit_with_message(
     git: &impl GitBackend,
     config: &EffectiveConfig,
     cli: &Cli,
-    message: &str,
-) -> Result<()> {
-    ui::info("commit message preview:");
-    ui::preview_message(message);
+    draft: Draft,
+    timings: &mut Timings,
+) -> Result<CommitOutcome> {
+    if !draft.conflicts_checked && !check_conflict_markers(git, config, cli)? {
+        ui::info("commit canceled");
+        return Ok(CommitOutcome::Skipped);
+    }
+    match &draft.source {
+        Some(source) => ui::info(&format!("commit message preview ({source}):")),
+        None => ui::info("commit message preview:"),
+    }
+    ui::preview_message(&draft.message);
+
+    if let Some(note) = &draft.note {
+        ui::info("git note preview:");
+        ui::preview_message(note);
+    }
+    if let Some(explanation) = &draft.explanation {
+        ui::info("why this message (not committed):");
+        ui::preview_message(explanation);
+    }
+    if cli.verbose && draft.source.is_some() {
+        let seed = config
+            .seed
+            .map_or_else(|| "none".to_string(), |seed| seed.to_string());
+        ui::info(&format!(
+            "sampling: temperature {}, seed {seed}",
+            config.temperature
+        ));
+    }
 
     if cli.dry_run {
+        if let Some(pr) = &draft.pr {
+            print_pr_draft(pr);
+        }
+        write_output(cli, &draft.message)?;
         ui::info("dry run enabled; skipping commit");
-        return Ok(());
+        return Ok(CommitOutcome::Skipped);
+    }
+
+    let mut message = draft.message;
+    if cli.edit || cli.edit_message.is_some() {
+        let initial = match &draft.dropped_body {
+            Some(body) => format!(
+                "{message}\n\n{body}\n\n# The body above was dropped by one_line; delete it to keep a single line."
+            ),
+            None => message.clone(),
+        };
+        let initial = match &cli.edit_message {
+            Some(seed) => seeded_message(seed, &initial),
+            None => initial,
+        };
+        let path = git.git_dir()?.join("GOODCOMMIT_EDITMSG");
+        let diff = git.staged_diff().ok();
+        match editor::edit_message(&path, &initial, diff.as_deref())? {
+            Some(edited) => message = sanitize_edited_message(&edited, config),
+            None => {
+                ui::info("commit aborted: empty message");
+                return Ok(CommitOutcome::Skipped);
+            }
+        }
+    } else if config.confirm && is_interactive() && !confirm_commit(config)? {
+        ui::info("commit canceled");
+        return Ok(CommitOutcome::Skipped);
     }
 
-    if config.confirm && is_interactive() {
+    let mut verified = draft.verified;
+    if let Some(fingerprint) = &draft.fingerprint {
+        if !staged_content_unchanged(git, fingerprint)? {
+            ui::warn("staged changes were modified after the message was generated");
+            if cli.auto_refresh {
+                return Ok(CommitOutcome::Stale);
+            }
+            if !is_interactive() {
+                return Err(anyhow!(
+                    "staged changes no longer match the message; rerun or use --auto-refresh"
+                ));
+            }
+            let confirm = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt("commit anyway?")
+                .default(false)
+                .interact()?;
+            if !confirm {
+                ui::info("commit canceled");
+                return Ok(CommitOutcome::Skipped);
+            }
+            // The precheck covered the old content; let git run the hooks again.
+            verified = false;
+        }
+    }
+
+    // `--yes` and non-interactive runs commit without asking.
+    if config.confirm && is_interactive() && repeats_head(git, &message)? {
+        ui::warn("this subject matches the last commit and the staged diff is tiny");
         let confirm = Confirm::with_theme(&ColorfulTheme::default())
-            .with_prompt("commit with this message?")
-            .default(true)
+            .with_prompt("commit again?")
+            .default(false)
             .interact()?;
         if !confirm {
             ui::info("commit canceled");
-            return Ok(());
+            return Ok(CommitOutcome::Skipped);
         }
     }
 
-    let no_verify = cli.no_verify || cli.skip_verify;
-    let output = git.commit(message, cli.edit, no_verify)?;
+    write_output(cli, &message)?;
+    warn_commit_encoding(git, &message)?;
+    let no_verify = cli.no_verify || cli.skip_verify || verified;
+    let output = timings.time("commit", || git.commit(&message, no_verify))?;
     if !output.is_empty() {
         ui::info(&output);
     }
 
-    if config.push && !cli.no_push {
-        match git.push() {
-            Ok(push_output) => {
-                if !push_output.is_empty() {
-                    ui::info(&push_output);
-                }
-            }
-            Err(err) => {
-                ui::warn(&format!("push failed: {err}"));
-            }
+    if let Some(note) = &draft.note {
+        if let Err(err) = git.add_note("HEAD", note) {
+            ui::warn(&format!("commit created but note was not attached: {err}"));
+        }
+    }
+    if let Some(pr) = &draft.pr {
+        emit_pr_draft(pr, cli);
+    }
+
+    let mut push_notice = None;
+    if cli.push_later {
+        if let Err(err) = push::queue_head(git) {
+            ui::warn(&format!("commit created but not queued for push: {err}"));
+        }
+    } else if config.push && !cli.no_push {
+        let push_commit = if draft.used_fallback {
+            let (push_commit, notice) = push::fallback_push_decision(config.push_on_fallback)?;
+            ui::warn(&notice);
+            push_notice = Some(notice);
+            push_commit
+        } else {
+            true
+        };
+        if push_commit {
+            timings.time("push", || push_and_report(git));
         }
     }
 
+    Ok(CommitOutcome::Committed {
+        message,
+        push_notice,
+    })
+}
+
+fn print_pr_draft(pr: &PrDraft) {
+    ui::info("pull request draft:");
+    ui::preview_message(&format!("{}\n\n{}", pr.title, pr.body));
+}
+
+/// Write the PR draft to `--pr-file`, or print it when no file was given.
+fn emit_pr_draft(pr: &PrDraft, cli: &Cli) {
+    let Some(path) = &cli.pr_file else {
+        print_pr_draft(pr);
+        return;
+    };
+    match std::fs::write(path, format!("{}\n\n{}\n", pr.title, pr.body.trim_end())) {
+        Ok(()) => ui::info(&format!("pull request draft written to {}", path.display())),
+        Err(err) => ui::warn(&format!(
+            "commit created but PR draft was not written: {err}"
+        )),
+    }
+}
+
+/// Warn when git records messages in a legacy encoding but `message` has non-ASCII text,
+/// which would be stored as UTF-8 bytes under that encoding's name.
+fn warn_commit_encoding(git: &impl GitBackend, message: &str) -> Result<()> {
+    if message.is_ascii() {
+        return Ok(());
+    }
+    if let Some(encoding) = git.commit_encoding()?.filter(|enc| !is_utf8_encoding(enc)) {
+        ui::warn(&format!(
+            "i18n.commitEncoding is {encoding}, but the message is UTF-8; non-ASCII characters \
+             may show up garbled (set i18n.commitEncoding to UTF-8 or edit them out)"
+        ));
+    }
+    Ok(())
+}
+
+/// `--output -`: write the message to stdout.
+fn output_to_stdout(cli: &Cli) -> bool {
+    cli.output
+        .as_deref()
+        .is_some_and(|path| path.as_os_str() == "-")
+}
+
+/// Fail before generating when `--output` would overwrite a file without `--force`.
+fn check_output_path(cli: &Cli) -> Result<()> {
+    match &cli.output {
+        Some(path) if !cli.force && !output_to_stdout(cli) && path.exists() => Err(anyhow!(
+            "{} already exists; pass --force to overwrite it",
+            path.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The body from `--body` or `--body-file`, if either was given.
+pub(super) fn user_body(cli: &Cli) -> Result<Option<String>> {
+    match (&cli.body, &cli.body_file) {
+        (Some(body), _) => Ok(Some(body.clone())),
+        (None, Some(path)) => std::fs::read_to_string(path)
+            .map(Some)
+            .with_context(|| format!("failed to read --body-file {}", path.display())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Write the final message to `--output` with a trailing newline, creating parent directories.
+/// `-` prints it to stdout instead, where it is the only output.
+fn write_output(cli: &Cli, message: &str) -> Result<()> {
+    let Some(path) = &cli.output else {
+        return Ok(());
+    };
+    if output_to_stdout(cli) {
+        println!("{}", message.trim_end());
+        return Ok(());
+    }
+    check_output_path(cli)?;
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, format!("{}\n", message.trim_end()))
+        .map_err(|err| anyhow!("failed to write {}: {err}", path.display()))?;
+    ui::info(&format!("commit message written to {}", path.display()));
     Ok(())
 }
 
+/// Staged changes at or below this many lines count as trivial for duplicate detection.
+const TRIVIAL_DIFF_LINES: u32 = 3;
+
+/// Whether `message` repeats the `HEAD` subject for a trivial staged diff.
+fn repeats_head(git: &impl GitBackend, message: &str) -> Result<bool> {
+    let Some(head) = git.head_subject()? else {
+        return Ok(false);
+    };
+    if !same_subject(message, &head) {
+        return Ok(false);
+    }
+    Ok(is_trivial(&git.staged_numstat()?))
+}
+
+fn same_subject(message: &str, head: &str) -> bool {
+    let subject = message.lines().next().unwrap_or_default().trim();
+    subject.eq_ignore_ascii_case(head.trim())
+}
+
+/// At most [`TRIVIAL_DIFF_LINES`] changed lines in total.
+fn is_trivial(stats: &[GitFileStat]) -> bool {
+    let changed = stats.iter().fold(0u32, |total, stat| {
+        // Binary changes have no line counts, so never treat them as trivial.
+        let lines = if stat.is_binary {
+            u32::MAX
+        } else {
+            stat.additions.saturating_add(stat.deletions)
+        };
+        total.saturating_add(lines)
+    });
+    changed <= TRIVIAL_DIFF_LINES
+}
+
+/// Run the pre-commit hook before any tokens are spent; `true` when it ran and passed.
+fn run_precheck(git: &impl GitBackend, config: &EffectiveConfig, cli: &Cli) -> Result<bool> {
+    if !config.precheck || cli.no_verify || cli.skip_verify {
+        return Ok(false);
+    }
+    let Some(hook) = git.run_hook("pre-commit")? else {
+        debug!("precheck skipped: no pre-commit hook installed");
+        return Ok(false);
+    };
+    if hook.success {
+        return Ok(true);
+    }
+
+    if !hook.output.is_empty() {
+        eprintln!("{}", hook.output);
+    }
+    Err(anyhow!(
+        "pre-commit hook failed; aborting before generating a message"
+    ))
+}
+
+fn push_and_report(git: &impl GitBackend) -> bool {
+    match push::push_with_rebase(git) {
+        Ok(push_output) => {
+            if !push_output.is_empty() {
+                ui::info(&push_output);
+            }
+            true
+        }
+        Err(err) => {
+            ui::warn(&format!("push failed: {err}"));
+            false
+        }
+    }
+}
+
+fn report_no_changes(
+    status: &RepoStatus,
+    config: &EffectiveConfig,
+    cli: &Cli,
+    timings: &Timings,
+) -> Result<()> {
+    if cli.profile_time {
+        print_timings(timings);
+    }
+    if status.unstaged.is_empty() && status.untracked.is_empty() {
+        ui::info("working tree clean");
+        return Ok(());
+    }
+    nothing_staged(status, config, cli)
+}
+
+const MAX_LISTED_UNSTAGED: usize = 5;
+
+/// Report unstaged-only changes and end the run with a non-zero exit code.
+fn nothing_staged(status: &RepoStatus, config: &EffectiveConfig, cli: &Cli) -> Result<()> {
+    let mut parts = Vec::new();
+    if !status.unstaged.is_empty() {
+        parts.push(format!(
+            "{}: {}",
+            count_files(status.unstaged.len(), "unstaged"),
+            list_paths(&status.unstaged)
+        ));
+    }
+    if !status.untracked.is_empty() {
+        let reason = if config.stage_mode == StageMode::None {
+            " not staged because stage mode is none"
+        } else {
+            ""
+        };
+        parts.push(format!(
+            "{}{reason}: {}",
+            count_files(status.untracked.len(), "untracked"),
+            list_paths(&status.untracked)
+        ));
+    }
+    let detail = format!(" ({})", parts.join("; "));
+
+    if cli.require_staged {
+        return Err(anyhow!("no staged changes{detail}"));
+    }
+    ui::warn(&format!(
+        "no staged changes; stage files or use --stage-all{detail}"
+    ));
+    Err(ExitCode(NOTHING_TO_COMMIT).into())
+}
+
+fn count_files(count: usize, kind: &str) -> String {
+    let noun = if count == 1 { "file" } else { "files" };
+    format!("{count} {kind} {noun}")
+}
+
+fn list_paths(entries: &[FileEntry]) -> String {
+    let mut listed = entries
+        .iter()
+        .take(MAX_LISTED_UNSTAGED)
+        .map(|entry| entry.path.clone())
+        .collect::<Vec<_>>();
+    if entries.len() > MAX_LISTED_UNSTAGED {
+        listed.push(format!("and {} more", entries.len() - MAX_LISTED_UNSTAGED));
+    }
+    listed.join(", ")
+}
+
 fn has_provider_warning(warnings: &[String]) -> bool {
     warnings
         .iter()
         .any(|warning| warning.contains("ai generation failed") || warning.contains("provider"))
 }
 
+fn has_timeout_warning(warnings: &[String]) -> bool {
+    warnings
+        .iter()
+        .any(|warning| warning.contains("(timeout_secs"))
+}
+
+fn print_timeout_help(config: &EffectiveConfig) {
+    ui::info(&format!(
+        "fix: raise timeout_secs (now {}) or GOODCOMMIT_TIMEOUT_SECS",
+        config.timeout_secs
+    ));
+    ui::info(&format!(
+        "or lower max_files (now {}) so fewer files are summarized",
+        config.max_files
+    ));
+    ui::info(&format!(
+        "or switch to a faster model than {}",
+        config.model
+    ));
+}
+
 fn print_provider_help(config: &EffectiveConfig) {
     match config.provider {
         ProviderKind::OpenAi => {
@@ -348,6 +1230,10 @@ fn maybe_prompt_setup(cli: &Cli, repo_root: Option<&std::path::Path>) -> Result<
     }
 
     let paths = goodcommit_core::config::resolve_paths(repo_root)?;
+    if paths.legacy_config.is_some() {
+        setup::offer_legacy_migration_once()?;
+        return Ok(());
+    }
     let has_config = paths.global_config.is_some() || paths.repo_config.is_some();
     if has_config {
         return Ok(());
@@ -367,9 +1253,79 @@ fn maybe_prompt_setup(cli: &Cli, repo_root: Option<&std::path::Path>) -> Result<
     Ok(())
 }
 
+pub(super) fn ignore_matcher_for(
+    cli: &Cli,
+    config: &EffectiveConfig,
+    paths: &ConfigPaths,
+) -> Result<IgnoreMatcher> {
+    if cli.include_ignored {
+        return Ok(IgnoreMatcher::none());
+    }
+    Ok(build_ignore_matcher(&config.ignore, paths)?)
+}
+
 fn generate_run_id() -> String {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default();
     format!("{}-{}", now.as_millis(), std::process::id())
 }
+
+/// Whether the commit will have staged changes once `stage_mode` has run.
+fn has_changes_to_commit(status: &RepoStatus, stage_mode: StageMode) -> bool {
+    let unstaged = !status.unstaged.is_empty() || !status.untracked.is_empty();
+    !status.staged.is_empty() || (stage_mode != StageMode::None && unstaged)
+}
+
+#[cfg(test)]
+mod tests {
+    use goodcommit_core::status::FileChange;
+
+    use super::*;
+
+    fn stat(additions: u32, deletions: u32, is_binary: bool) -> GitFileStat {
+        GitFileStat {
+            path: "src/lib.rs".to_string(),
+            additions,
+            deletions,
+            is_binary,
+        }
+    }
+
+    #[test]
+    fn same_subject_compares_the_first_line_ignoring_case() {
+        assert!(same_subject("Fix: typo\n\nbody", " fix: typo "));
+        assert!(!same_subject("fix: typo in docs", "fix: typo"));
+    }
+
+    #[test]
+    fn only_small_text_changes_are_trivial() {
+        assert!(is_trivial(&[stat(1, 1, false), stat(1, 0, false)]));
+        assert!(!is_trivial(&[stat(2, 2, false)]));
+        assert!(!is_trivial(&[stat(0, 0, true)]));
+    }
+
+    #[test]
+    fn changes_to_commit_depend_on_what_the_stage_mode_stages() {
+        let entry = FileEntry {
+            path: "src/lib.rs".to_string(),
+            change: FileChange::Modified,
+            original_path: None,
+            submodule: false,
+        };
+        let clean = RepoStatus::default();
+        let unstaged = RepoStatus {
+            unstaged: vec![entry.clone()],
+            ..RepoStatus::default()
+        };
+        let staged = RepoStatus {
+            staged: vec![entry],
+            ..RepoStatus::default()
+        };
+
+        assert!(!has_changes_to_commit(&clean, StageMode::Auto));
+        assert!(has_changes_to_commit(&unstaged, StageMode::Auto));
+        assert!(!has_changes_to_commit(&unstaged, StageMode::None));
+        assert!(has_changes_to_commit(&staged, StageMode::None));
+    }
+}
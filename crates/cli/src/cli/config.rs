@@ -1,12 +1,18 @@
 use std::path::Path;
 
 use anyhow::{anyhow, Result};
+use tracing::debug;
 
 use goodcommit_core::config::{
-    config_from_env, load_config, resolve_paths, Config, ConfigPaths, EffectiveConfig, StageMode,
+    config_cache_path, config_dir, config_from_env, load_cached_config, load_config, resolve_paths,
+    save_cached_config, Config, ConfigCacheKey, ConfigPaths, EffectiveConfig, StageMode,
 };
+use goodcommit_core::git::{GitBackend, SystemGit};
+
+use crate::ui;
 
 use super::args::Cli;
+use super::learn::learned_style;
 
 pub(crate) fn build_cli_overrides(cli: &Cli) -> Result<Config> {
     let mut config = Config::default();
@@ -35,6 +41,14 @@ pub(crate) fn build_cli_overrides(cli: &Cli) -> Result<Config> {
         config.timeout_secs = Some(timeout);
     }
 
+    if let Some(temperature) = cli.temperature {
+        config.temperature = Some(temperature);
+    }
+
+    if let Some(seed) = cli.seed {
+        config.seed = Some(seed);
+    }
+
     if let Some(max_input) = cli.max_input_tokens {
         config.max_input_tokens = Some(max_input);
     }
@@ -63,6 +77,26 @@ pub(crate) fn build_cli_overrides(cli: &Cli) -> Result<Config> {
         config.lang = Some(lang.clone());
     }
 
+    if let Some(max_subject_length) = cli.max_subject_length {
+        config.max_subject_length = Some(max_subject_length);
+    }
+
+    if let Some(body_wrap) = cli.body_wrap {
+        config.body_wrap = Some(body_wrap);
+    }
+
+    if let Some(types) = &cli.conventional_types {
+        config.conventional_types = Some(types.clone());
+    }
+
+    if cli.pr || cli.pr_file.is_some() {
+        config.pr_draft = Some(true);
+    }
+
+    if !cli.allow_large.is_empty() {
+        config.allow_large = Some(cli.allow_large.clone());
+    }
+
     if cli.conventional {
         config.conventional = Some(true);
     }
@@ -70,6 +104,9 @@ pub(crate) fn build_cli_overrides(cli: &Cli) -> Result<Config> {
         config.conventional = Some(false);
     }
 
+    if cli.precheck {
+        config.precheck = Some(true);
+    }
     if cli.one_line {
         config.one_line = Some(true);
     }
@@ -99,6 +136,18 @@ pub(crate) fn build_cli_overrides(cli: &Cli) -> Result<Config> {
         config.confirm = Some(false);
     }
 
+    if cli.notes {
+        config.notes = Some(true);
+    }
+
+    if cli.retry_on_invalid {
+        config.retry_on_invalid = Some(true);
+    }
+
+    if cli.no_fallback {
+        config.allow_fallback = Some(false);
+    }
+
     if cli.stage_all {
         config.stage_mode = Some(StageMode::All);
     }
@@ -152,6 +201,67 @@ fn invocation_stage_mode() -> Option<StageMode> {
         .and_then(|arg0| stage_mode_for_invocation(&arg0))
 }
 
+fn language_sample_paths() -> Vec<String> {
+    let git = SystemGit::new();
+    match git.status() {
+        Ok(status) if !status.staged.is_empty() => {
+            status.staged.into_iter().map(|entry| entry.path).collect()
+        }
+        Ok(status) => status.working_tree_paths(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The effective config with the API key hidden, for display and reports.
+pub(crate) fn redacted_config(config: &EffectiveConfig) -> Config {
+    let mut printable = config.to_config();
+    if printable.openai_api_key.is_some() {
+        printable.openai_api_key = Some("[redacted]".to_string());
+    }
+    printable
+}
+
+/// Report `GOODCOMMIT_*` values that could not be parsed and are ignored. The commit hook
+/// only logs them, so `git commit` output stays clean.
+pub(crate) fn report_env_warnings(hook: bool) {
+    for warning in config_from_env().1 {
+        if hook {
+            debug!("{warning}");
+        } else {
+            ui::warn(&warning);
+        }
+    }
+}
+
+/// Defaults, environment and config files merged, before language overrides and flags.
+fn base_config(paths: &ConfigPaths) -> Result<Config> {
+    let file_config = load_config(paths)?;
+    Ok(Config::defaults()
+        .merge(config_from_env().0)
+        .merge(file_config))
+}
+
+/// [`base_config`], reused from `.git/goodcommit-cache` while the config files and
+/// `GOODCOMMIT_*` variables are unchanged. The cache is rebuilt whenever it is missing.
+fn cached_base_config(paths: &ConfigPaths) -> Result<Config> {
+    let cache = SystemGit::new()
+        .git_dir()
+        .ok()
+        .map(|git_dir| config_cache_path(&git_dir));
+    let (Some(cache), Some(key)) = (cache, ConfigCacheKey::current(paths)) else {
+        return base_config(paths);
+    };
+    if let Some(config) = load_cached_config(&cache, &key) {
+        return Ok(config);
+    }
+
+    let config = base_config(paths)?;
+    if let Err(err) = save_cached_config(&cache, &key, &config) {
+        debug!("failed to save config cache: {err}");
+    }
+    Ok(config)
+}
+
 pub(crate) fn config_for_repo(
     cli: &Cli,
     repo_root: Option<&Path>,
@@ -159,8 +269,13 @@ pub(crate) fn config_for_repo(
     stage_mode_conflicts(cli)?;
 
     let paths = resolve_paths(repo_root)?;
-    let file_config = load_config(&paths)?;
-    let env_config = config_from_env();
+    if let Some(legacy) = &paths.legacy_config {
+        ui::warn(&format!(
+            "using the old git-ai-commit config at {}; run `goodcommit setup` to copy it to {}",
+            legacy.display(),
+            config_dir()?.display()
+        ));
+    }
     let mut cli_config = build_cli_overrides(cli)?;
     if !has_stage_flag(cli) {
         if let Some(stage_mode) = invocation_stage_mode() {
@@ -168,19 +283,53 @@ pub(crate) fn config_for_repo(
         }
     }
 
-    let config = Config::defaults()
-        .merge(env_config)
-        .merge(file_config)
-        .merge(cli_config)
-        .resolve()?;
+    let mut config = match repo_root {
+        Some(_) => cached_base_config(&paths)?,
+        None => base_config(&paths)?,
+    };
+    if repo_root.is_some() && config.has_language_overrides() {
+        config = config.with_language_overrides(&language_sample_paths());
+    }
+    let mut config = config.merge(cli_config).resolve()?;
+    if repo_root.is_some() {
+        config.style = learned_style(&SystemGit::new(), &config);
+    }
 
     Ok((config, paths))
 }
 
 #[cfg(test)]
 mod tests {
+    use clap::Parser;
+
     use super::*;
 
+    #[test]
+    fn build_cli_overrides_maps_lint_flags() {
+        let cli = Cli::try_parse_from([
+            "goodcommit",
+            "--max-subject",
+            "60",
+            "--body-wrap",
+            "80",
+            "--conventional-types",
+            "feat,fix,revert",
+        ])
+        .expect("parse");
+
+        let config = build_cli_overrides(&cli).expect("overrides");
+        assert_eq!(config.max_subject_length, Some(60));
+        assert_eq!(config.body_wrap, Some(80));
+        assert_eq!(
+            config.conventional_types,
+            Some(vec![
+                "feat".to_string(),
+                "fix".to_string(),
+                "revert".to_string()
+            ])
+        );
+    }
+
     #[test]
     fn stage_mode_for_invocation_matches_aliases() {
         assert_eq!(stage_mode_for_invocation("g."), Some(StageMode::All));
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+use goodcommit_core::config::{ConfirmTimeoutAction, EffectiveConfig};
+
+use crate::ui;
+
+const PROMPT: &str = "commit with this message?";
+
+/// Ask "commit with this message?", answering for the user after `confirm_timeout_secs`.
+pub(crate) fn confirm_commit(config: &EffectiveConfig) -> Result<bool> {
+    let Some(secs) = config.confirm_timeout_secs else {
+        return Ok(Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(PROMPT)
+            .default(true)
+            .interact()?);
+    };
+    if let Some(answer) = timed_answer(Duration::from_secs(secs))? {
+        return Ok(answer);
+    }
+
+    let accept = config.confirm_timeout_action == ConfirmTimeoutAction::Accept;
+    let outcome = if accept { "committing" } else { "canceling" };
+    ui::warn(&format!(
+        "no answer after {secs}s (confirm_timeout_secs); {outcome}"
+    ));
+    Ok(accept)
+}
+
+/// The answer a key press gives: Enter takes the default (yes), and Ctrl-C or Escape
+/// say no. Other keys are ignored.
+fn answer_for_key(key: u8) -> Option<bool> {
+    match key {
+        b'y' | b'Y' | b'\n' | b'\r' => Some(true),
+        b'n' | b'N' | 0x03 | 0x1b => Some(false),
+        _ => None,
+    }
+}
+
+/// Read one answer key from the terminal, or `None` after `timeout`. Stdin is polled and
+/// read a byte at a time, so nothing is left reading it to swallow keys meant for later
+/// prompts.
+#[cfg(unix)]
+fn timed_answer(timeout: Duration) -> Result<Option<bool>> {
+    use std::io::Write;
+    use std::time::Instant;
+
+    use rustix::event::{poll, PollFd, PollFlags, Timespec};
+    use rustix::io::Errno;
+
+    let stdin = std::io::stdin();
+    let _raw = RawInput::enable(&stdin)?;
+    eprint!("{PROMPT} [Y/n] ");
+    std::io::stderr().flush()?;
+
+    let deadline = Instant::now() + timeout;
+    let answer = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break None;
+        }
+        let wait = Timespec::try_from(remaining).unwrap_or(Timespec {
+            tv_sec: i64::MAX,
+            tv_nsec: 0,
+        });
+        let mut fds = [PollFd::new(&stdin, PollFlags::IN)];
+        match poll(&mut fds, Some(&wait)) {
+            Ok(0) | Err(Errno::INTR) => continue,
+            Ok(_) => {}
+            Err(err) => return Err(err.into()),
+        }
+        let mut key = [0_u8];
+        if rustix::io::read(&stdin, &mut key)? == 0 {
+            break None;
+        }
+        if let Some(answer) = answer_for_key(key[0]) {
+            break Some(answer);
+        }
+    };
+    match answer {
+        Some(true) => eprintln!("yes"),
+        Some(false) => eprintln!("no"),
+        None => eprintln!(),
+    }
+    Ok(answer)
+}
+
+/// Without a way to poll stdin here, the prompt waits for an answer and the timeout does
+/// not apply.
+#[cfg(not(unix))]
+fn timed_answer(_timeout: Duration) -> Result<Option<bool>> {
+    Ok(Some(
+        Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(PROMPT)
+            .default(true)
+            .interact()?,
+    ))
+}
+
+/// Keys arrive one at a time without echo while this is alive; the terminal settings
+/// are restored on drop.
+#[cfg(unix)]
+struct RawInput<'a> {
+    stdin: &'a std::io::Stdin,
+    saved: rustix::termios::Termios,
+}
+
+#[cfg(unix)]
+impl<'a> RawInput<'a> {
+    fn enable(stdin: &'a std::io::Stdin) -> Result<Self> {
+        use rustix::termios::{tcgetattr, tcsetattr, LocalModes, OptionalActions};
+
+        let saved = tcgetattr(stdin)?;
+        let mut raw = saved.clone();
+        raw.local_modes
+            .remove(LocalModes::ICANON | LocalModes::ECHO | LocalModes::ISIG);
+        tcsetattr(stdin, OptionalActions::Now, &raw)?;
+        Ok(Self { stdin, saved })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawInput<'_> {
+    fn drop(&mut self) {
+        let _ = rustix::termios::tcsetattr(
+            self.stdin,
+            rustix::termios::OptionalActions::Now,
+            &self.saved,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_map_to_answers() {
+        assert_eq!(answer_for_key(b'y'), Some(true));
+        assert_eq!(answer_for_key(b'\r'), Some(true));
+        assert_eq!(answer_for_key(b'N'), Some(false));
+        assert_eq!(answer_for_key(0x03), Some(false));
+        assert_eq!(answer_for_key(b'x'), None);
+    }
+}
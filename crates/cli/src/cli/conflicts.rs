@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Result};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+use goodcommit_core::config::EffectiveConfig;
+use goodcommit_core::git::GitBackend;
+use goodcommit_core::pipeline::find_conflict_markers;
+
+use crate::ui;
+use crate::util::is_interactive;
+
+use super::args::Cli;
+
+/// Stop before generating or committing when staged files still contain conflict markers.
+///
+/// Returns `false` when the user declined. `--allow-conflicts` skips the check, a dry run
+/// only warns, and runs that cannot ask (non-interactive or `--yes`) fail instead.
+pub(super) fn check_conflict_markers(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+    cli: &Cli,
+) -> Result<bool> {
+    if cli.allow_conflicts {
+        return Ok(true);
+    }
+    let conflicts = find_conflict_markers(git, config)?;
+    if conflicts.is_empty() {
+        return Ok(true);
+    }
+    for conflict in &conflicts {
+        let lines = conflict
+            .lines
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        ui::warn(&format!(
+            "conflict markers in {} (line {lines})",
+            conflict.path
+        ));
+    }
+    if cli.dry_run {
+        return Ok(true);
+    }
+    if !config.confirm || !is_interactive() {
+        return Err(anyhow!(
+            "staged files still contain conflict markers; resolve them, or pass \
+             --allow-conflicts to commit anyway"
+        ));
+    }
+    Ok(Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("commit with conflict markers?")
+        .default(false)
+        .interact()?)
+}
@@ -1,12 +1,18 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+
 use anyhow::Result;
+use serde::Serialize;
 
-use goodcommit_core::config::ProviderKind;
+use goodcommit_core::config::{ConfigPaths, ProviderKind};
 use goodcommit_core::git::{GitBackend, SystemGit};
 
 use crate::ui;
 
 use super::args::Cli;
-use super::config::config_for_repo;
+use super::config::{config_for_repo, redacted_config};
+use super::version;
 
 pub(crate) fn run_config(cli: &Cli) -> Result<()> {
     let git = SystemGit::new();
@@ -32,47 +38,146 @@ pub(crate) fn run_config(cli: &Cli) -> Result<()> {
         ui::info("repo ignore: (none)");
     }
 
-    let mut printable = config.to_config();
-    if printable.openai_api_key.is_some() {
-        printable.openai_api_key = Some("[redacted]".to_string());
-    }
-    let toml = toml::to_string_pretty(&printable)?;
+    let toml = toml::to_string_pretty(&redacted_config(&config))?;
     ui::info("effective config:");
     println!("{toml}");
 
     Ok(())
 }
 
-pub(crate) fn run_doctor(cli: &Cli) -> Result<()> {
+/// How long `doctor` waits to connect to the Ollama endpoint.
+const REACHABLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What `doctor` checked, as printed by `doctor --json`.
+#[derive(Debug, Serialize)]
+struct DoctorReport {
+    /// `git --version` output; `None` when git is not on `PATH`.
+    git_version: Option<String>,
+    provider: String,
+    model: String,
+    api_key_present: bool,
+    /// Whether the Ollama endpoint accepts connections; `None` for other providers.
+    ollama_reachable: Option<bool>,
+    config_paths: ReportPaths,
+}
+
+#[derive(Debug, Serialize)]
+struct ReportPaths {
+    global_config: Option<PathBuf>,
+    repo_config: Option<PathBuf>,
+    global_ignore: PathBuf,
+    repo_ignore: Option<PathBuf>,
+}
+
+impl From<ConfigPaths> for ReportPaths {
+    fn from(paths: ConfigPaths) -> Self {
+        Self {
+            global_config: paths.global_config,
+            repo_config: paths.repo_config,
+            global_ignore: paths.global_ignore,
+            repo_ignore: paths.repo_ignore,
+        }
+    }
+}
+
+pub(crate) fn run_doctor(cli: &Cli, json: bool) -> Result<()> {
     let git = SystemGit::new();
     let repo_root = git.repo_root().ok();
-    let (config, _paths) = config_for_repo(cli, repo_root.as_deref())?;
+    let (config, paths) = config_for_repo(cli, repo_root.as_deref())?;
 
     let git_version = std::process::Command::new("git")
         .arg("--version")
         .output()
         .ok()
         .and_then(|out| String::from_utf8(out.stdout).ok())
-        .unwrap_or_else(|| "git not found".to_string());
+        .map(|out| out.trim().to_string());
+    let report = DoctorReport {
+        git_version,
+        provider: config.provider.as_str().to_string(),
+        model: config.model.clone(),
+        api_key_present: config.openai_api_key.is_some(),
+        ollama_reachable: (config.provider == ProviderKind::Ollama)
+            .then(|| endpoint_reachable(&config.ollama_endpoint)),
+        config_paths: paths.into(),
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
 
-    ui::info(&format!("git: {}", git_version.trim()));
-    ui::info(&format!("provider: {}", config.provider.as_str()));
-    ui::info(&format!("model: {}", config.model));
+    version::report_installs();
+    ui::info(&format!(
+        "git: {}",
+        report.git_version.as_deref().unwrap_or("git not found")
+    ));
+    ui::info(&format!("provider: {}", report.provider));
+    ui::info(&format!("model: {}", report.model));
 
     match config.provider {
         ProviderKind::OpenAi => {
-            if config.openai_api_key.is_some() {
+            if report.api_key_present {
                 ui::info("openai api key: detected");
             } else {
                 ui::warn(
-                    "openai api key: missing (run setup or set OPENAI_API_KEY or GOODCOMMIT_OPENAI_API_KEY)",
+                    "openai api key: missing (run setup, set OPENAI_API_KEY or GOODCOMMIT_OPENAI_API_KEY, or set openai_api_key_file)",
                 );
             }
         }
         ProviderKind::Ollama => {
             ui::info(&format!("ollama endpoint: {}", config.ollama_endpoint));
+            if report.ollama_reachable == Some(false) {
+                ui::warn("ollama endpoint: not reachable (start it with: ollama serve)");
+            }
         }
     }
 
     Ok(())
 }
+
+/// Whether something accepts TCP connections at the host and port of `endpoint`.
+fn endpoint_reachable(endpoint: &str) -> bool {
+    let Some(authority) = endpoint_authority(endpoint) else {
+        return false;
+    };
+    authority
+        .to_socket_addrs()
+        .into_iter()
+        .flatten()
+        .any(|addr| TcpStream::connect_timeout(&addr, REACHABLE_TIMEOUT).is_ok())
+}
+
+/// `host:port` of a URL such as `http://localhost:11434/api/chat`, with the scheme's
+/// default port when none is given.
+fn endpoint_authority(endpoint: &str) -> Option<String> {
+    let (scheme, rest) = endpoint.split_once("://")?;
+    let authority = rest
+        .split('/')
+        .next()
+        .filter(|authority| !authority.is_empty())?;
+    let has_port = authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    if has_port {
+        return Some(authority.to_string());
+    }
+    let port = if scheme == "https" { 443 } else { 80 };
+    Some(format!("{authority}:{port}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_authority_adds_the_default_port() {
+        assert_eq!(
+            endpoint_authority("http://localhost:11434/api/chat").as_deref(),
+            Some("localhost:11434")
+        );
+        assert_eq!(
+            endpoint_authority("https://ollama.internal/api/chat").as_deref(),
+            Some("ollama.internal:443")
+        );
+        assert_eq!(endpoint_authority("localhost:11434"), None);
+    }
+}
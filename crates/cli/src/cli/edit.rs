@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use goodcommit_core::config::{config_dir, read_config_file, resolve_paths, Config};
+use goodcommit_core::git::{GitBackend, SystemGit};
+use goodcommit_core::ignore::{invalid_patterns, read_ignore_file};
+
+use crate::editor::edit_file;
+use crate::ui;
+
+const CONFIG_HEADER: &str =
+    "# goodcommit config. Every setting below is the default; uncomment a line to change it.\n";
+const IGNORE_HEADER: &str =
+    "# Paths matching these globs are never sent to the AI, one pattern per line.\n# Example: fixtures/**\n";
+
+pub(crate) fn run_config_edit(global: bool) -> Result<()> {
+    let path = config_file_path(global)?;
+    if !path.exists() {
+        create_file(&path, &default_config_text()?)?;
+        ui::info(&format!("created {}", path.display()));
+    }
+
+    edit_file(&path)?;
+
+    match read_config_file(&path) {
+        Ok(_) => ui::success(&format!("saved {}", path.display())),
+        Err(err) => ui::warn(&format!(
+            "{err}; your edits were kept, fix the file before the next run"
+        )),
+    }
+    Ok(())
+}
+
+pub(crate) fn run_ignore_edit(global: bool) -> Result<()> {
+    let path = if global {
+        resolve_paths(None)?.global_ignore
+    } else {
+        repo_root()?.join(".goodcommit-ignore")
+    };
+    if !path.exists() {
+        create_file(&path, IGNORE_HEADER)?;
+        ui::info(&format!("created {}", path.display()));
+    }
+
+    edit_file(&path)?;
+
+    let invalid = invalid_patterns(&read_ignore_file(&path));
+    if invalid.is_empty() {
+        ui::success(&format!("saved {}", path.display()));
+    } else {
+        ui::warn(&format!(
+            "{} has invalid patterns that will be skipped: {}",
+            path.display(),
+            invalid.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+fn config_file_path(global: bool) -> Result<PathBuf> {
+    if global {
+        let paths = resolve_paths(None)?;
+        return Ok(match paths.global_config {
+            Some(path) => path,
+            None => config_dir()?.join("config.toml"),
+        });
+    }
+
+    let root = repo_root()?;
+    Ok(resolve_paths(Some(&root))?
+        .repo_config
+        .unwrap_or_else(|| root.join(".goodcommit.toml")))
+}
+
+fn repo_root() -> Result<PathBuf> {
+    SystemGit::new()
+        .repo_root()
+        .map_err(|_| anyhow!("not in a git repository; use --global to edit the global file"))
+}
+
+/// The defaults as TOML, commented out so a new file changes nothing until edited.
+fn default_config_text() -> Result<String> {
+    let defaults = toml::to_string_pretty(&Config::defaults())?;
+    let mut text = String::from(CONFIG_HEADER);
+    for line in defaults.lines() {
+        if line.trim().is_empty() {
+            text.push('\n');
+        } else {
+            text.push_str("# ");
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    Ok(text)
+}
+
+fn create_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_text_parses_as_empty_config() {
+        let text = default_config_text().expect("defaults");
+        assert!(text.contains("# provider = "));
+        let parsed: Config = toml::from_str(&text).expect("parse");
+        assert!(parsed.provider.is_none());
+    }
+}
@@ -0,0 +1,67 @@
+use anyhow::{anyhow, Result};
+use tracing::debug;
+
+use goodcommit_core::config::EffectiveConfig;
+use goodcommit_core::git::{GitBackend, SystemGit};
+use goodcommit_core::language::dominant_language;
+use goodcommit_core::style::{load_profile, save_profile, style_path, StyleProfile};
+
+use crate::ui;
+
+/// Commits scanned when a stale profile is relearned automatically.
+const RELEARN_COMMITS: usize = 200;
+
+pub(crate) fn run_learn(commits: usize) -> Result<()> {
+    let git = SystemGit::new();
+    git.ensure_git_repo()?;
+
+    let profile = learn(&git, commits)?;
+    let path = style_path(&git.git_dir()?);
+    save_profile(&path, &profile)?;
+
+    ui::success(&format!(
+        "learned from {} commits; saved {}",
+        profile.commits,
+        path.display()
+    ));
+    let lines = profile.prompt_lines();
+    if !lines.is_empty() {
+        print!("{lines}");
+    }
+    Ok(())
+}
+
+/// The saved style profile for this repo, relearned once it is older than
+/// `style_max_age_days`. Repos that never ran `goodcommit learn` have none.
+pub(crate) fn learned_style(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+) -> Option<StyleProfile> {
+    let path = style_path(&git.git_dir().ok()?);
+    let profile = load_profile(&path)?;
+    if !profile.is_stale(config.style_max_age_days) {
+        return Some(profile);
+    }
+
+    match learn(git, RELEARN_COMMITS) {
+        Ok(fresh) => {
+            if let Err(err) = save_profile(&path, &fresh) {
+                debug!("failed to save relearned style: {err}");
+            }
+            Some(fresh)
+        }
+        Err(err) => {
+            debug!("failed to relearn style: {err}");
+            Some(profile)
+        }
+    }
+}
+
+fn learn(git: &impl GitBackend, commits: usize) -> Result<StyleProfile> {
+    let messages = git.recent_messages(commits)?;
+    if messages.is_empty() {
+        return Err(anyhow!("no commits to learn from"));
+    }
+    let language = dominant_language(&git.tracked_files()?);
+    Ok(StyleProfile::from_messages(&messages, language))
+}
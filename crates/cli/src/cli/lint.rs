@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+
+use goodcommit_core::git::{GitBackend, SystemGit};
+use goodcommit_core::lint::{check_message, strip_comments, LintConfig};
+
+use crate::ui;
+
+use super::args::Cli;
+use super::config::config_for_repo;
+
+pub(crate) fn run_lint(
+    cli: &Cli,
+    rev: Option<String>,
+    range: Option<String>,
+    message: Option<String>,
+    file: Option<PathBuf>,
+) -> Result<()> {
+    let git = SystemGit::new();
+    let repo_root = git.repo_root().ok();
+    let (config, _paths) = config_for_repo(cli, repo_root.as_deref())?;
+    let lint_config = LintConfig::from_config(&config);
+
+    if let Some(range) = range {
+        git.ensure_git_repo()?;
+        return lint_range(&git, &range, &lint_config);
+    }
+
+    let message = if let Some(message) = message {
+        message
+    } else if let Some(path) = file {
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        strip_comments(&raw)
+    } else {
+        git.ensure_git_repo()?;
+        git.commit_message(rev.as_deref().unwrap_or("HEAD"))?
+    };
+
+    let violations = check_message(&message, &lint_config);
+    if violations.is_empty() {
+        ui::success("commit message ok");
+        return Ok(());
+    }
+
+    for violation in &violations {
+        ui::warn(&violation.to_string());
+    }
+
+    Err(anyhow!(
+        "commit message has {} lint violation(s)",
+        violations.len()
+    ))
+}
+
+fn lint_range(git: &SystemGit, range: &str, config: &LintConfig) -> Result<()> {
+    let commits = git.rev_list(range)?;
+    let mut failing = 0;
+    for sha in &commits {
+        let violations = check_message(&git.commit_message(sha)?, config);
+        if violations.is_empty() {
+            continue;
+        }
+        failing += 1;
+        let short = &sha[..sha.len().min(7)];
+        for violation in &violations {
+            ui::warn(&format!("{short}:{violation}"));
+        }
+    }
+
+    if failing == 0 {
+        ui::success(&format!("{} commit message(s) ok", commits.len()));
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "{failing} of {} commit message(s) have lint violations",
+        commits.len()
+    ))
+}
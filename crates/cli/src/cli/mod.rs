@@ -5,35 +5,160 @@ use goodcommit_core::git::GitBackend;
 use crate::{hooks, setup, ui};
 
 mod args;
+mod changelog;
 mod commit;
 mod config;
+mod confirm;
+mod conflicts;
 mod doctor;
+mod edit;
+mod learn;
+mod lint;
+mod profile;
+mod push;
+mod report;
+mod revert;
+mod reword;
+#[cfg(feature = "self-update")]
+mod self_update;
+mod state;
+mod stats;
+mod summary;
+mod tag;
 mod tracing;
+mod version;
+mod warmup;
+mod warnings;
 
-pub(crate) use args::{Cli, Commands, HookAction};
+pub(crate) use args::{Cli, Commands, ConfigAction, HookAction, IgnoreAction};
 
 pub async fn run() -> Result<()> {
     let mut cli = Cli::parse();
+    if cli.version {
+        return version::print_version(cli.verbose, cli.json);
+    }
     tracing::init_tracing(cli.verbose);
 
     let command = cli.command.take();
+    let hook_run = matches!(
+        command,
+        Some(Commands::Hook {
+            action: HookAction::Run { .. }
+        })
+    );
+    config::report_env_warnings(hook_run);
 
     match command {
-        Some(Commands::Setup) => {
+        Some(Commands::Setup { edit: true, .. }) => {
+            setup::run_setup_edit()?;
+            return Ok(());
+        }
+        Some(Commands::Setup {
+            non_interactive: true,
+            provider,
+            model,
+            push,
+            no_push,
+            save_key,
+            force,
+            ..
+        }) => {
+            setup::run_setup_non_interactive(&setup::SetupAnswers {
+                provider,
+                model,
+                push: if no_push {
+                    Some(false)
+                } else {
+                    push.then_some(true)
+                },
+                save_key,
+                force,
+            })?;
+            ui::success("setup complete");
+            return Ok(());
+        }
+        Some(Commands::Setup { .. }) => {
             setup::run_setup()?;
             ui::success("setup complete");
             return Ok(());
         }
-        Some(Commands::Config) => {
+        Some(Commands::Config { action: None }) => {
             doctor::run_config(&cli)?;
             return Ok(());
         }
-        Some(Commands::Doctor) => {
-            doctor::run_doctor(&cli)?;
+        Some(Commands::Config {
+            action: Some(ConfigAction::Edit { global }),
+        }) => {
+            edit::run_config_edit(global)?;
+            return Ok(());
+        }
+        Some(Commands::Ignore {
+            action: IgnoreAction::Edit { global },
+        }) => {
+            edit::run_ignore_edit(global)?;
+            return Ok(());
+        }
+        Some(Commands::Doctor { json }) => {
+            doctor::run_doctor(&cli, json)?;
+            return Ok(());
+        }
+        Some(Commands::Split { json }) => {
+            commit::run_split(cli, json).await?;
+            return Ok(());
+        }
+        Some(Commands::Lint {
+            rev,
+            range,
+            message,
+            file,
+        }) => {
+            lint::run_lint(&cli, rev, range, message, file)?;
+            return Ok(());
+        }
+        Some(Commands::Push) => {
+            push::run_push()?;
+            return Ok(());
+        }
+        Some(Commands::Stats { perf }) => {
+            stats::run_stats(perf)?;
+            return Ok(());
+        }
+        Some(Commands::Status) => {
+            state::run_status()?;
+            return Ok(());
+        }
+        #[cfg(feature = "self-update")]
+        Some(Commands::SelfUpdate { check }) => {
+            self_update::run_self_update(check).await?;
             return Ok(());
         }
-        Some(Commands::Split) => {
-            commit::run_split(cli).await?;
+        #[cfg(not(feature = "self-update"))]
+        Some(Commands::SelfUpdate { .. }) => {
+            return Err(anyhow::anyhow!(
+                "this build of goodcommit has no self-update; update it with the package manager that installed it"
+            ));
+        }
+        Some(Commands::Changelog {
+            range,
+            since_last_tag,
+        }) => {
+            changelog::run_changelog(&cli, range, since_last_tag).await?;
+            return Ok(());
+        }
+        Some(Commands::Tag {
+            name,
+            from,
+            push_tag,
+        }) => {
+            tag::run_tag(&cli, &name, from.as_deref(), push_tag).await?;
+            return Ok(());
+        }
+        Some(Commands::Revert { sha, context }) => {
+            revert::run_revert(&cli, &sha, context.as_deref()).await?;
+            return Ok(());
+        }
+        Some(Commands::Learn { commits }) => {
+            learn::run_learn(commits)?;
             return Ok(());
         }
         Some(Commands::Hook { action }) => match action {
@@ -51,13 +176,22 @@ pub async fn run() -> Result<()> {
                 ui::success("hook removed");
                 return Ok(());
             }
-            HookAction::Run { path, source, .. } => {
-                commit::run_hook(path, source, cli).await?;
+            HookAction::Run {
+                path,
+                source,
+                dry_run,
+                ..
+            } => {
+                let dry_run = dry_run || cli.dry_run;
+                commit::run_hook(path, source, dry_run, cli).await?;
                 return Ok(());
             }
         },
         None => {}
     }
 
+    if cli.amend_reword {
+        return reword::run_reword(&cli).await;
+    }
     commit::run_commit(cli).await
 }
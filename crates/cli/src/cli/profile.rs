@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use goodcommit_core::timing::Timings;
+
+use crate::ui;
+
+/// Print a phase timing table for `--profile-time`.
+pub(crate) fn print_timings(timings: &Timings) {
+    ui::info("timings:");
+    for line in render_timings(timings) {
+        ui::info(&line);
+    }
+}
+
+fn render_timings(timings: &Timings) -> Vec<String> {
+    let width = timings
+        .phases()
+        .iter()
+        .map(|(phase, _)| phase.len())
+        .max()
+        .unwrap_or(0)
+        .max("total".len());
+
+    let mut lines = timings
+        .phases()
+        .iter()
+        .map(|(phase, duration)| format!("  {phase:<width$}  {}", format_duration(*duration)))
+        .collect::<Vec<_>>();
+    lines.push(format!(
+        "  {:<width$}  {}",
+        "total",
+        format_duration(timings.total())
+    ));
+    lines
+}
+
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_secs_f64() * 1000.0;
+    format!("{millis:>9.1} ms")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_timings_aligns_phases_and_total() {
+        let mut timings = Timings::new();
+        timings.record("staging", Duration::from_millis(12));
+        timings.record("provider", Duration::from_millis(1500));
+
+        let lines = render_timings(&timings);
+        assert_eq!(lines[0], "  staging        12.0 ms");
+        assert_eq!(lines[1], "  provider     1500.0 ms");
+        assert_eq!(lines[2], "  total        1512.0 ms");
+    }
+}
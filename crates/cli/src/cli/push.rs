@@ -0,0 +1,221 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+use goodcommit_core::config::PushOnFallback;
+use goodcommit_core::git::{is_auth_failure, is_non_fast_forward, GitBackend, SystemGit};
+
+use crate::ui;
+use crate::util::is_interactive;
+
+const REBASE_COMMAND: &str = "git pull --rebase --autostash && git push";
+
+/// Record HEAD as committed-but-not-pushed for a later `goodcommit push`.
+pub(crate) fn queue_head(git: &impl GitBackend) -> Result<()> {
+    let sha = git.short_head()?;
+    append_queue(&queue_path(git)?, &sha)?;
+    ui::info(&format!("queued {sha} for `goodcommit push`"));
+    Ok(())
+}
+
+pub(crate) fn run_push() -> Result<()> {
+    let git = SystemGit::new();
+    git.ensure_git_repo()?;
+    let path = queue_path(&git)?;
+    let queued = read_queue(&path)?;
+    if queued.is_empty() {
+        ui::info("no queued commits");
+        return Ok(());
+    }
+
+    ui::info(&format!(
+        "pushing {} queued commit(s): {}",
+        queued.len(),
+        queued.join(", ")
+    ));
+    let output =
+        push_with_rebase(&git).map_err(|err| anyhow!("push failed; commits stay queued: {err}"))?;
+    if !output.is_empty() {
+        ui::info(&output);
+    }
+
+    fs::remove_file(&path).with_context(|| format!("failed to clear {}", path.display()))?;
+    ui::success("push complete");
+    Ok(())
+}
+
+/// Whether to push a commit made with the fallback message, and a notice saying why,
+/// for the output and the run state. `Ask` prompts only in interactive runs.
+pub(crate) fn fallback_push_decision(mode: PushOnFallback) -> Result<(bool, String)> {
+    decide_fallback_push(mode, is_interactive(), || {
+        Ok(Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("commit used fallback message — push anyway?")
+            .default(false)
+            .interact()?)
+    })
+}
+
+fn decide_fallback_push(
+    mode: PushOnFallback,
+    interactive: bool,
+    ask: impl FnOnce() -> Result<bool>,
+) -> Result<(bool, String)> {
+    let (push, reason) = match mode {
+        PushOnFallback::Always => (true, "push_on_fallback = always"),
+        PushOnFallback::Never => (false, "push_on_fallback = never"),
+        PushOnFallback::Ask if !interactive => (false, "not asked in a non-interactive run"),
+        PushOnFallback::Ask if ask()? => (true, "confirmed"),
+        PushOnFallback::Ask => (false, "declined"),
+    };
+    let action = if push { "pushed" } else { "skipped push of" };
+    Ok((
+        push,
+        format!("{action} a commit with the fallback message ({reason})"),
+    ))
+}
+
+/// Push, and when the remote has moved on, offer to rebase onto it and push again.
+pub(crate) fn push_with_rebase(git: &impl GitBackend) -> Result<String> {
+    let err = match git.push() {
+        Ok(output) => return Ok(output),
+        Err(err) => err,
+    };
+    if is_auth_failure(&err.to_string()) {
+        return push_with_credentials(git, &err.to_string());
+    }
+    if !is_non_fast_forward(&err.to_string()) {
+        return Err(err.into());
+    }
+    if !is_interactive() {
+        return Err(anyhow!(
+            "the remote has commits you do not have; run `{REBASE_COMMAND}`"
+        ));
+    }
+
+    ui::warn("push rejected: the remote has commits you do not have");
+    let rebase = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("pull --rebase --autostash and push again?")
+        .default(true)
+        .interact()?;
+    if !rebase {
+        return Err(anyhow!("not pushed; run `{REBASE_COMMAND}` when ready"));
+    }
+
+    let pulled = git
+        .pull_rebase()
+        .map_err(|err| anyhow!("pull --rebase failed; resolve it and run `git push`: {err}"))?;
+    if !pulled.is_empty() {
+        ui::info(&pulled);
+    }
+    Ok(git.push()?)
+}
+
+/// Retry a push that needed credentials with git's prompts enabled, or explain how to
+/// set them up when there is no terminal to prompt on.
+fn push_with_credentials(git: &impl GitBackend, push_error: &str) -> Result<String> {
+    if !is_interactive() {
+        return Err(anyhow!("{push_error}\n{}", auth_guidance(push_error)));
+    }
+    ui::warn("push needs credentials; retrying with git's credential prompt");
+    git.push_with_prompt()
+        .map_err(|err| anyhow!("{err}\n{}", auth_guidance(push_error)))?;
+    Ok(String::new())
+}
+
+fn auth_guidance(push_error: &str) -> &'static str {
+    if push_error.contains("Permission denied (publickey") {
+        "the SSH key was rejected: load it with `ssh-add`, or check it is added to your account \
+         on the remote host"
+    } else {
+        "the remote needs credentials: set up a credential helper (`git config --global \
+         credential.helper <helper>`, or `gh auth setup-git` for GitHub), or switch to an SSH \
+         remote with `git remote set-url <remote> git@<host>:<owner>/<repo>.git`"
+    }
+}
+
+fn queue_path(git: &impl GitBackend) -> Result<PathBuf> {
+    Ok(git.git_dir()?.join("goodcommit").join("push-queue"))
+}
+
+fn append_queue(path: &Path, sha: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{sha}")?;
+    Ok(())
+}
+
+fn read_queue(path: &Path) -> Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_guidance_matches_the_transport() {
+        let https =
+            "fatal: could not read Username for 'https://github.com': terminal prompts disabled";
+        assert!(is_auth_failure(https));
+        assert!(auth_guidance(https).contains("credential.helper"));
+
+        let ssh = "git@github.com: Permission denied (publickey).\nfatal: Could not read from remote repository.";
+        assert!(is_auth_failure(ssh));
+        assert!(auth_guidance(ssh).contains("ssh-add"));
+
+        assert!(!is_auth_failure("! [rejected] main -> main (fetch first)"));
+    }
+
+    #[test]
+    fn fallback_push_follows_the_mode() {
+        let never_asked = || -> Result<bool> { panic!("should not ask") };
+        let (push, notice) =
+            decide_fallback_push(PushOnFallback::Always, false, never_asked).expect("always");
+        assert!(push);
+        assert!(notice.contains("push_on_fallback = always"));
+
+        let (push, notice) =
+            decide_fallback_push(PushOnFallback::Never, true, never_asked).expect("never");
+        assert!(!push);
+        assert!(notice.starts_with("skipped push"));
+
+        let (push, notice) =
+            decide_fallback_push(PushOnFallback::Ask, false, never_asked).expect("ask");
+        assert!(!push);
+        assert!(notice.contains("non-interactive"));
+
+        let (push, notice) =
+            decide_fallback_push(PushOnFallback::Ask, true, || Ok(true)).expect("ask");
+        assert!(push);
+        assert!(notice.contains("confirmed"));
+        let (push, _) = decide_fallback_push(PushOnFallback::Ask, true, || Ok(false)).expect("ask");
+        assert!(!push);
+    }
+
+    #[test]
+    fn queue_round_trip_skips_blank_lines() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let path = dir.path().join("goodcommit").join("push-queue");
+        assert!(read_queue(&path).expect("read").is_empty());
+
+        append_queue(&path, "abc1234").expect("append");
+        append_queue(&path, "def5678").expect("append");
+        fs::write(&path, fs::read_to_string(&path).expect("read") + "\n").expect("write");
+
+        assert_eq!(read_queue(&path).expect("read"), vec!["abc1234", "def5678"]);
+    }
+}
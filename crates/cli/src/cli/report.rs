@@ -0,0 +1,296 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+
+use goodcommit_core::config::{config_dir, openai_api_key_env, Config, EffectiveConfig};
+use goodcommit_core::error::CoreResult;
+use goodcommit_core::git::GitBackend;
+use goodcommit_core::ignore::IgnoreMatcher;
+use goodcommit_core::pipeline::PipelineOutcome;
+use goodcommit_core::providers::{Provider, ProviderRequest};
+use goodcommit_core::timing::Timings;
+
+use crate::ui;
+
+use super::args::Cli;
+use super::config::redacted_config;
+
+const REDACTED: &str = "[redacted]";
+
+/// One provider call as seen by [`RecordingProvider`].
+#[derive(Debug, Clone)]
+struct Exchange {
+    system_prompt: String,
+    user_prompt: String,
+    elapsed_ms: u64,
+    response_chars: Option<usize>,
+    error: Option<String>,
+}
+
+/// Wraps a provider and keeps the prompts and response metadata of every call.
+pub(crate) struct RecordingProvider {
+    inner: Arc<dyn Provider>,
+    exchanges: Arc<Mutex<Vec<Exchange>>>,
+}
+
+#[async_trait]
+impl Provider for RecordingProvider {
+    async fn complete(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        request: ProviderRequest,
+    ) -> CoreResult<String> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .complete(system_prompt, user_prompt, request)
+            .await;
+        let exchange = Exchange {
+            system_prompt: system_prompt.to_string(),
+            user_prompt: user_prompt.to_string(),
+            elapsed_ms: u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+            response_chars: result.as_ref().ok().map(|text| text.chars().count()),
+            error: result.as_ref().err().map(ToString::to_string),
+        };
+        if let Ok(mut exchanges) = self.exchanges.lock() {
+            exchanges.push(exchange);
+        }
+        result
+    }
+
+    async fn warm_up(&self) -> CoreResult<()> {
+        self.inner.warm_up().await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct GitState {
+    head: Option<String>,
+    staged_files: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExchangeSummary {
+    prompt_file: String,
+    elapsed_ms: u64,
+    response_chars: Option<usize>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PhaseTiming {
+    phase: &'static str,
+    ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct RunReport {
+    run_id: String,
+    /// Seconds since the Unix epoch.
+    timestamp: u64,
+    config: Config,
+    git: GitState,
+    /// Staged paths kept out of the prompt by ignore rules.
+    excluded: Vec<String>,
+    exchanges: Vec<ExchangeSummary>,
+    timings: Vec<PhaseTiming>,
+    warnings: Vec<String>,
+    used_fallback: bool,
+    message: Option<String>,
+    error: Option<String>,
+}
+
+/// Collects what a commit run did and writes it out as a self-contained report.
+pub(crate) struct Reporter {
+    run_id: String,
+    dir: Option<PathBuf>,
+    on_failure: bool,
+    /// Git state captured before generation, since committing changes it.
+    git: GitState,
+    excluded: Vec<String>,
+    exchanges: Arc<Mutex<Vec<Exchange>>>,
+    warnings: Vec<String>,
+    used_fallback: bool,
+    message: Option<String>,
+}
+
+impl Reporter {
+    /// A reporter when `--report` or `report_on_failure` asks for one.
+    pub(crate) fn for_run(
+        cli: &Cli,
+        config: &EffectiveConfig,
+        run_id: &str,
+        git: &impl GitBackend,
+        ignore: &IgnoreMatcher,
+    ) -> Option<Self> {
+        if cli.report.is_none() && !config.report_on_failure {
+            return None;
+        }
+        let staged_files = git.staged_files().unwrap_or_default();
+        let excluded = staged_files
+            .iter()
+            .filter(|path| ignore.is_ignored(path))
+            .cloned()
+            .collect();
+        Some(Self {
+            run_id: run_id.to_string(),
+            dir: cli.report.clone(),
+            on_failure: config.report_on_failure,
+            git: GitState {
+                head: git.short_head().ok(),
+                staged_files,
+            },
+            excluded,
+            exchanges: Arc::new(Mutex::new(Vec::new())),
+            warnings: Vec::new(),
+            used_fallback: false,
+            message: None,
+        })
+    }
+
+    pub(crate) fn wrap(&self, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        Arc::new(RecordingProvider {
+            inner: provider,
+            exchanges: Arc::clone(&self.exchanges),
+        })
+    }
+
+    /// Remember the latest pipeline outcome; regenerations replace earlier ones.
+    pub(crate) fn record_outcome(&mut self, outcome: &PipelineOutcome) {
+        self.warnings.clone_from(&outcome.warnings);
+        self.used_fallback = outcome.used_fallback;
+        self.message = Some(outcome.message.clone());
+    }
+
+    /// Write the report if it was requested, or if generation failed or fell back.
+    pub(crate) fn finish(
+        self,
+        config: &EffectiveConfig,
+        timings: &Timings,
+        error: Option<&anyhow::Error>,
+    ) {
+        let failed = error.is_some() || self.used_fallback;
+        let dir = match (&self.dir, self.on_failure && failed) {
+            (Some(dir), _) => dir.clone(),
+            (None, true) => match config_dir() {
+                Ok(dir) => dir.join("reports"),
+                Err(err) => {
+                    ui::warn(&format!("failed to write report: {err}"));
+                    return;
+                }
+            },
+            (None, false) => return,
+        };
+
+        match self.write(&dir, config, timings, error) {
+            Ok(path) => ui::info(&format!("report written to {}", path.display())),
+            Err(err) => ui::warn(&format!("failed to write report: {err}")),
+        }
+    }
+
+    fn write(
+        self,
+        dir: &Path,
+        config: &EffectiveConfig,
+        timings: &Timings,
+        error: Option<&anyhow::Error>,
+    ) -> Result<PathBuf> {
+        let run_dir = dir.join(&self.run_id);
+        fs::create_dir_all(&run_dir)
+            .with_context(|| format!("failed to create {}", run_dir.display()))?;
+
+        let secrets = secrets(config);
+        let exchanges = self
+            .exchanges
+            .lock()
+            .map(|exchanges| exchanges.clone())
+            .unwrap_or_default();
+        let mut summaries = Vec::new();
+        for (index, exchange) in exchanges.iter().enumerate() {
+            let prompt_file = format!("prompt-{}.txt", index + 1);
+            let text = format!(
+                "# system\n{}\n\n# user\n{}\n",
+                exchange.system_prompt, exchange.user_prompt
+            );
+            fs::write(run_dir.join(&prompt_file), redact(&text, &secrets))?;
+            summaries.push(ExchangeSummary {
+                prompt_file,
+                elapsed_ms: exchange.elapsed_ms,
+                response_chars: exchange.response_chars,
+                error: exchange.error.as_deref().map(|err| redact(err, &secrets)),
+            });
+        }
+
+        let report = RunReport {
+            run_id: self.run_id,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+            config: redacted_config(config),
+            git: self.git,
+            excluded: self.excluded,
+            exchanges: summaries,
+            timings: timings
+                .phases()
+                .iter()
+                .map(|(phase, duration)| PhaseTiming {
+                    phase,
+                    ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+                })
+                .collect(),
+            warnings: self.warnings,
+            used_fallback: self.used_fallback,
+            message: self.message,
+            error: error.map(|err| redact(&format!("{err:#}"), &secrets)),
+        };
+
+        let path = run_dir.join("report.json");
+        fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+        Ok(path)
+    }
+}
+
+fn secrets(config: &EffectiveConfig) -> Vec<String> {
+    config
+        .openai_api_key
+        .iter()
+        .cloned()
+        .chain(openai_api_key_env())
+        .filter(|secret| !secret.is_empty())
+        .collect()
+}
+
+fn redact(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret, REDACTED)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_replaces_every_secret() {
+        let secrets = vec!["sk-one".to_string(), "sk-two".to_string()];
+        assert_eq!(
+            redact("key sk-one and sk-two, sk-one", &secrets),
+            "key [redacted] and [redacted], [redacted]"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+
+use goodcommit_core::git::{is_revert_conflict, GitBackend, SystemGit};
+use goodcommit_core::pipeline::generate_revert_message;
+use goodcommit_core::providers::build_provider;
+use goodcommit_core::timing::Timings;
+
+use crate::ui;
+
+use super::args::Cli;
+use super::commit::{check_branch, commit_prepared};
+use super::config::config_for_repo;
+use super::profile::print_timings;
+
+/// Revert `sha` without committing, then commit it with a conventional revert message.
+///
+/// A revert that is not committed (dry run, canceled, or failed) is undone with
+/// `git revert --abort`.
+pub(crate) async fn run_revert(cli: &Cli, sha: &str, reason: Option<&str>) -> Result<()> {
+    let git = SystemGit::new();
+    git.ensure_git_repo()?;
+    let repo_root = git.repo_root()?;
+    let (mut config, _paths) = config_for_repo(cli, Some(&repo_root))?;
+
+    if git.has_staged_changes()? {
+        return Err(anyhow!(
+            "staged changes would be mixed into the revert; commit or unstage them first"
+        ));
+    }
+    if !check_branch(&git, cli, &mut config)? {
+        return Ok(());
+    }
+
+    let mut timings = Timings::new();
+    let full = match timings.time("revert", || git.revert_no_commit(sha)) {
+        Ok(full) => full,
+        Err(err) if is_revert_conflict(&err.to_string()) => {
+            return Err(anyhow!(
+                "{err}\nrevert of {sha} conflicts with later changes; resolve the conflicts, \
+                 `git add` them, and run `git revert --continue`, or run `git revert --abort` \
+                 to cancel"
+            ));
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let provider = if reason.is_some() {
+        build_provider(&config).ok()
+    } else {
+        None
+    };
+    let short = &full[..full.len().min(7)];
+    let start = Instant::now();
+    let revert =
+        match generate_revert_message(&git, provider.as_deref(), &config, &full, reason).await {
+            Ok(revert) => revert,
+            Err(err) => return Err(undo_failed_revert(&git, short, &err.into())),
+        };
+    timings.record("provider", start.elapsed());
+    for warning in &revert.warnings {
+        ui::warn(warning);
+    }
+
+    let source = format!("revert of {short}");
+    let committed = match commit_prepared(&git, &config, cli, revert.message, source, &mut timings)
+    {
+        Ok(committed) => committed,
+        Err(err) => return Err(undo_failed_revert(&git, short, &err)),
+    };
+    if !committed {
+        git.abort_revert()?;
+        ui::info(&format!(
+            "revert of {short} undone; the working tree is back at HEAD"
+        ));
+    }
+    if cli.profile_time {
+        print_timings(&timings);
+    }
+    Ok(())
+}
+
+/// Undo the uncommitted revert of `short` after `err`, or, if that fails too, say how to
+/// finish or cancel it by hand.
+fn undo_failed_revert(git: &SystemGit, short: &str, err: &anyhow::Error) -> anyhow::Error {
+    match git.abort_revert() {
+        Ok(()) => anyhow!("{err}\nrevert of {short} undone; the working tree is back at HEAD"),
+        Err(abort_err) => anyhow!(
+            "{err}\n{abort_err}; commit the staged revert with `git revert --continue`, or run \
+             `git revert --abort` to cancel"
+        ),
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+
+use goodcommit_core::git::{GitBackend, SystemGit};
+use goodcommit_core::pipeline::{generate_commit_message, record_stats, PipelineResult};
+use goodcommit_core::providers::build_provider;
+use goodcommit_core::timing::Timings;
+
+use crate::ui;
+
+use super::args::Cli;
+use super::commit::{check_branch, commit_prepared, ignore_matcher_for, user_body};
+use super::config::config_for_repo;
+use super::profile::print_timings;
+
+/// `--amend-reword`: write a new message for HEAD from HEAD's own diff and amend only the
+/// message. Nothing is staged, and staged changes stay out of the amended commit.
+pub(crate) async fn run_reword(cli: &Cli) -> Result<()> {
+    let git = SystemGit::reword_head();
+    git.ensure_git_repo()?;
+    let repo_root = git.repo_root()?;
+    let (mut config, paths) = config_for_repo(cli, Some(&repo_root))?;
+    config.user_body = user_body(cli)?;
+
+    if git.head_subject()?.is_none() {
+        return Err(anyhow!("no commit to reword yet"));
+    }
+    if !check_branch(&git, cli, &mut config)? {
+        return Ok(());
+    }
+    // HEAD may already be on the remote, where the amended commit needs a force push.
+    config.push = false;
+
+    let ignore = ignore_matcher_for(cli, &config, &paths)?;
+    let provider = build_provider(&config).ok();
+    let mut timings = Timings::new();
+    let start = Instant::now();
+    let result = generate_commit_message(&git, provider.as_deref(), &config, &ignore).await?;
+    timings.record("provider", start.elapsed());
+    let PipelineResult::Message(outcome) = result else {
+        return Err(anyhow!("HEAD changes no files; nothing to describe"));
+    };
+    record_stats(&config, &outcome, None);
+    for warning in &outcome.warnings {
+        ui::warn(warning);
+    }
+
+    let short = git.short_head()?;
+    let source = format!("reword of {short}");
+    let reworded = commit_prepared(&git, &config, cli, outcome.message, source, &mut timings)?;
+    if reworded {
+        ui::info("if the old commit was already pushed, push with --force-with-lease");
+    }
+    if cli.profile_time {
+        print_timings(&timings);
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use goodcommit_core::config::parse_bool;
+
+use crate::ui;
+
+const BIN_NAME: &str = "goodcommit";
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/Bikz/goodcommit/releases/latest";
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// `goodcommit self-update`: replace this binary with the latest GitHub release, after
+/// checking its SHA-256. With `check`, only report whether a newer release exists.
+pub(crate) async fn run_self_update(check: bool) -> Result<()> {
+    if std::env::var("GOODCOMMIT_NO_SELF_UPDATE")
+        .is_ok_and(|value| parse_bool(&value).unwrap_or(true))
+    {
+        return Err(anyhow!(
+            "self-update is disabled by GOODCOMMIT_NO_SELF_UPDATE; update goodcommit the way it was installed"
+        ));
+    }
+    let exe = fs::canonicalize(std::env::current_exe()?)?;
+    if let Some(hint) = package_manager_hint(&exe) {
+        return Err(anyhow!("{} is managed by {hint}", exe.display()));
+    }
+    let target = release_target(std::env::consts::ARCH, std::env::consts::OS)
+        .ok_or_else(|| anyhow!("no goodcommit release builds for this platform"))?;
+
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("goodcommit/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(120))
+        .build()?;
+    let url = std::env::var("GOODCOMMIT_SELF_UPDATE_URL")
+        .unwrap_or_else(|_| LATEST_RELEASE_URL.to_string());
+    let release: Release = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("failed to check {url} for releases"))?
+        .json()
+        .await
+        .context("failed to parse the latest release")?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = release.tag_name.trim_start_matches('v');
+    if !is_newer(latest, current) {
+        ui::success(&format!("goodcommit {current} is up to date"));
+        return Ok(());
+    }
+    if check {
+        ui::info(&format!(
+            "goodcommit {latest} is available (installed: {current}); run `goodcommit self-update`"
+        ));
+        return Ok(());
+    }
+
+    let archive_name = format!("{BIN_NAME}-{target}.tar.gz");
+    let asset = release
+        .asset(&archive_name)
+        .ok_or_else(|| anyhow!("release {latest} has no {archive_name}"))?;
+    let archive = download(&client, &asset.browser_download_url).await?;
+    let expected = match release
+        .body
+        .as_deref()
+        .and_then(|body| find_checksum(body, &archive_name))
+    {
+        Some(sum) => sum,
+        None => published_checksum(&client, &release, target, &archive_name).await?,
+    };
+    let actual = sha256_hex(&archive);
+    if actual != expected {
+        return Err(anyhow!(
+            "checksum mismatch for {archive_name}: expected {expected}, got {actual}; nothing was installed"
+        ));
+    }
+
+    let binary = binary_from_archive(&archive)?;
+    replace_binary(&exe, &binary)?;
+    ui::success(&format!("updated goodcommit {current} -> {latest}"));
+    Ok(())
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+    let bytes = client
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("failed to download {url}"))?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+/// The checksum from the `checksums-<target>.txt` asset that release builds upload.
+async fn published_checksum(
+    client: &reqwest::Client,
+    release: &Release,
+    target: &str,
+    archive_name: &str,
+) -> Result<String> {
+    let missing = || anyhow!("no checksum published for {archive_name}; nothing was installed");
+    let asset = release
+        .asset(&format!("checksums-{target}.txt"))
+        .ok_or_else(missing)?;
+    let listing = download(client, &asset.browser_download_url).await?;
+    find_checksum(&String::from_utf8_lossy(&listing), archive_name).ok_or_else(missing)
+}
+
+/// Rust target triple of the release archive for this platform; only the targets
+/// `release.yml` builds are mapped.
+fn release_target(arch: &str, os: &str) -> Option<&'static str> {
+    match (arch, os) {
+        ("x86_64", "linux") => Some("x86_64-unknown-linux-gnu"),
+        ("x86_64", "macos") => Some("x86_64-apple-darwin"),
+        ("aarch64", "macos") => Some("aarch64-apple-darwin"),
+        _ => None,
+    }
+}
+
+/// How to update a binary that a package manager installed, so self-update leaves it alone.
+fn package_manager_hint(exe: &Path) -> Option<&'static str> {
+    let path = exe.to_string_lossy();
+    if path.contains("/Cellar/") || path.contains("/homebrew/") {
+        Some("Homebrew; run `brew upgrade goodcommit`")
+    } else if path.contains("node_modules") {
+        Some("npm; run `npm update -g goodcommit`")
+    } else if path.contains("/.cargo/bin/") {
+        Some("cargo; run `cargo install goodcommit`")
+    } else {
+        None
+    }
+}
+
+/// Whether dotted version `latest` is newer than `current`; pre-release suffixes are ignored.
+fn is_newer(latest: &str, current: &str) -> bool {
+    fn parts(version: &str) -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    parts(latest) > parts(current)
+}
+
+/// The SHA-256 listed for `name` in `sha256sum` output (`<hex>  <name>`), wherever it
+/// appears in `text`.
+fn find_checksum(text: &str, name: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let sum = fields.next()?;
+        let file = fields.next()?.trim_start_matches('*');
+        (file == name && sum.len() == 64 && sum.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| sum.to_ascii_lowercase())
+    })
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn binary_from_archive(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut entries = tar::Archive::new(GzDecoder::new(archive));
+    for entry in entries.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?;
+        let name = path.file_name().and_then(|name| name.to_str());
+        if matches!(name, Some(BIN_NAME | "goodcommit.exe")) {
+            let mut binary = Vec::new();
+            entry.read_to_end(&mut binary)?;
+            return Ok(binary);
+        }
+    }
+    Err(anyhow!("release archive has no {BIN_NAME} binary"))
+}
+
+/// Swap in the new binary with renames in the same directory, so an interrupted update
+/// leaves the old binary in place. Windows cannot replace a running executable, but can
+/// rename it, so the old one is moved aside first and removed on the next update.
+fn replace_binary(exe: &Path, binary: &[u8]) -> Result<()> {
+    let dir = exe
+        .parent()
+        .ok_or_else(|| anyhow!("cannot find the directory of {}", exe.display()))?;
+    let staged = dir.join(format!(".{BIN_NAME}-update.tmp"));
+    fs::write(&staged, binary).with_context(|| format!("failed to write to {}", dir.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+
+    let result = if cfg!(windows) {
+        let old = old_binary_path(exe);
+        let _ = fs::remove_file(&old);
+        fs::rename(exe, &old).and_then(|()| {
+            fs::rename(&staged, exe).inspect_err(|_| {
+                let _ = fs::rename(&old, exe);
+            })
+        })
+    } else {
+        fs::rename(&staged, exe)
+    };
+    if let Err(err) = result {
+        let _ = fs::remove_file(&staged);
+        return Err(anyhow!("failed to replace {}: {err}", exe.display()));
+    }
+    Ok(())
+}
+
+fn old_binary_path(exe: &Path) -> PathBuf {
+    exe.with_extension("old")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_versions_compare_numerically() {
+        assert!(is_newer("0.10.0", "0.9.9"));
+        assert!(is_newer("1.0.0", "0.3.3"));
+        assert!(!is_newer("0.3.3", "0.3.3"));
+        assert!(!is_newer("0.3.3-rc.1", "0.3.3"));
+        assert!(!is_newer("0.3.2", "0.3.3"));
+    }
+
+    #[test]
+    fn checksums_are_found_by_archive_name() {
+        let sum = "a".repeat(64);
+        let text = format!(
+            "Release notes\n\n{}  goodcommit-x86_64-apple-darwin.tar.gz\n{sum} *goodcommit-x86_64-unknown-linux-gnu.tar.gz\n",
+            "b".repeat(64)
+        );
+        assert_eq!(
+            find_checksum(&text, "goodcommit-x86_64-unknown-linux-gnu.tar.gz"),
+            Some(sum)
+        );
+        assert_eq!(find_checksum(&text, "goodcommit-other.tar.gz"), None);
+        assert_eq!(
+            find_checksum("abc  goodcommit.tar.gz", "goodcommit.tar.gz"),
+            None
+        );
+    }
+
+    #[test]
+    fn release_targets_match_the_published_archives() {
+        assert_eq!(
+            release_target("aarch64", "macos"),
+            Some("aarch64-apple-darwin")
+        );
+        assert_eq!(
+            release_target("x86_64", "linux"),
+            Some("x86_64-unknown-linux-gnu")
+        );
+        assert_eq!(release_target("riscv64", "linux"), None);
+        assert_eq!(release_target("aarch64", "linux"), None);
+        assert_eq!(release_target("x86_64", "windows"), None);
+    }
+
+    #[test]
+    fn binary_is_swapped_in_place() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let exe = dir.path().join(BIN_NAME);
+        fs::write(&exe, "old").expect("write exe");
+        replace_binary(&exe, b"new").expect("replace");
+        assert_eq!(fs::read(&exe).expect("read exe"), b"new");
+        assert_eq!(fs::read_dir(dir.path()).expect("read dir").count(), 1);
+    }
+}
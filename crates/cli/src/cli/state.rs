@@ -0,0 +1,195 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use goodcommit_core::git::{GitBackend, SystemGit};
+use goodcommit_core::pipeline::PipelineOutcome;
+
+use crate::ui;
+
+/// Longest message kept in the state file, in bytes.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024;
+/// Most warnings kept in the state file.
+const MAX_WARNINGS: usize = 20;
+
+/// What the last run in a repo did, written to `.git/goodcommit/state.json` for editor
+/// plugins and `goodcommit status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RunState {
+    /// `commit`, `dry-run`, or `hook`.
+    pub(crate) mode: String,
+    /// `committed`, `previewed`, `written`, `skipped`, `no-changes`, or `failed`.
+    pub(crate) outcome: String,
+    /// Seconds since the Unix epoch.
+    pub(crate) started_at: u64,
+    pub(crate) finished_at: u64,
+    /// `provider/model` that wrote the message; `None` for a fallback or no message.
+    pub(crate) generated_by: Option<String>,
+    pub(crate) used_fallback: bool,
+    pub(crate) fallback_reason: Option<String>,
+    pub(crate) warnings: Vec<String>,
+    pub(crate) message: Option<String>,
+    pub(crate) error: Option<String>,
+}
+
+impl RunState {
+    pub(crate) fn new(mode: &str) -> Self {
+        Self {
+            mode: mode.to_string(),
+            outcome: String::new(),
+            started_at: unix_now(),
+            finished_at: 0,
+            generated_by: None,
+            used_fallback: false,
+            fallback_reason: None,
+            warnings: Vec::new(),
+            message: None,
+            error: None,
+        }
+    }
+
+    /// Take the message and metadata from a pipeline outcome.
+    pub(crate) fn record_outcome(&mut self, outcome: &PipelineOutcome) {
+        self.generated_by.clone_from(&outcome.generated_by);
+        self.used_fallback = outcome.used_fallback;
+        self.fallback_reason = outcome.fallback_reason.as_ref().map(ToString::to_string);
+        self.warnings = outcome
+            .warnings
+            .iter()
+            .take(MAX_WARNINGS)
+            .cloned()
+            .collect();
+        self.message = Some(outcome.message.clone());
+    }
+
+    /// Finish with `outcome` and write the state file. Best effort: a failed write is only
+    /// logged, so it never fails the run.
+    pub(crate) fn finish(&mut self, git: &impl GitBackend, outcome: &str) {
+        self.outcome = outcome.to_string();
+        self.finished_at = unix_now();
+        if let Some(message) = &mut self.message {
+            truncate_at_char(message, MAX_MESSAGE_BYTES);
+        }
+        let written = git
+            .git_dir()
+            .map_err(anyhow::Error::from)
+            .and_then(|git_dir| write_state(&state_path(&git_dir), self));
+        if let Err(err) = written {
+            debug!("failed to write run state: {err:#}");
+        }
+    }
+}
+
+/// Where the last run's state lives, inside the repo's git directory.
+pub(crate) fn state_path(git_dir: &Path) -> PathBuf {
+    git_dir.join("goodcommit").join("state.json")
+}
+
+/// Replace the state file under an exclusive lock on `state.lock`, so concurrent runs
+/// never interleave their writes; readers see either the old or the new file.
+fn write_state(path: &Path, state: &RunState) -> Result<()> {
+    let dir = path.parent().context("state path has no parent")?;
+    fs::create_dir_all(dir)?;
+    let lock = File::create(dir.join("state.lock"))?;
+    lock.lock()?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(state)?)?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// `goodcommit status`: print what the last run in this repo did.
+pub(crate) fn run_status() -> Result<()> {
+    let git = SystemGit::new();
+    git.ensure_git_repo()?;
+    let path = state_path(&git.git_dir()?);
+    let Ok(content) = fs::read_to_string(&path) else {
+        ui::info("no goodcommit runs recorded in this repo yet");
+        return Ok(());
+    };
+    let state: RunState = serde_json::from_str(&content)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+
+    let ago = unix_now().saturating_sub(state.finished_at);
+    ui::info(&format!(
+        "last run: {} ({}), {} ago",
+        state.mode,
+        state.outcome,
+        format_age(ago)
+    ));
+    match (&state.generated_by, &state.fallback_reason) {
+        (Some(generated_by), _) => ui::info(&format!("generated by: {generated_by}")),
+        (None, Some(reason)) => ui::info(&format!("fallback message used because {reason}")),
+        (None, None) if state.used_fallback => ui::info("fallback message used"),
+        (None, None) => {}
+    }
+    for warning in &state.warnings {
+        ui::warn(warning);
+    }
+    if let Some(error) = &state.error {
+        ui::error(error);
+    }
+    if let Some(message) = &state.message {
+        ui::preview_message(message);
+    }
+    Ok(())
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86_400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+fn truncate_at_char(text: &mut String, max_bytes: usize) {
+    if text.len() <= max_bytes {
+        return;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_messages_are_cut_on_a_char_boundary() {
+        let mut text = "é".repeat(10);
+        truncate_at_char(&mut text, 5);
+        assert_eq!(text, "éé");
+    }
+
+    #[test]
+    fn state_file_is_replaced_whole() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let path = state_path(dir.path());
+        let mut state = RunState::new("commit");
+        state.outcome = "committed".to_string();
+        write_state(&path, &state).expect("write");
+        state.outcome = "previewed".to_string();
+        write_state(&path, &state).expect("rewrite");
+
+        let read: RunState =
+            serde_json::from_str(&fs::read_to_string(&path).expect("read")).expect("parse");
+        assert_eq!(read.outcome, "previewed");
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+}
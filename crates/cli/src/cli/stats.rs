@@ -0,0 +1,64 @@
+use anyhow::Result;
+
+use goodcommit_core::stats::{perf_summary, read_records, stats_path, summarize, StatsRecord};
+
+use crate::ui;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+pub(crate) fn run_stats(perf: bool) -> Result<()> {
+    let path = stats_path()?;
+    let records = read_records(&path)?;
+    if records.is_empty() {
+        ui::info(&format!("no stats recorded yet ({})", path.display()));
+        ui::info("enable with `record_stats = true` or GOODCOMMIT_RECORD_STATS=1");
+        return Ok(());
+    }
+
+    if perf {
+        print_perf(&records);
+        return Ok(());
+    }
+
+    let summary = summarize(&records);
+    ui::info(&format!("stats file: {}", path.display()));
+    if let (Some(first), Some(last)) = (summary.first_timestamp, summary.last_timestamp) {
+        let days = (last.saturating_sub(first) / SECONDS_PER_DAY) + 1;
+        ui::info(&format!("period: {days} day(s)"));
+    }
+    ui::info(&format!(
+        "runs: {} ({} fallback)",
+        summary.runs, summary.fallback_runs
+    ));
+    ui::info(&format!("estimated tokens: {}", summary.tokens));
+
+    ui::divider();
+    for (model, stats) in &summary.by_model {
+        ui::info(&format!(
+            "{model}: {} run(s), {} fallback, ~{} tokens",
+            stats.runs, stats.fallback_runs, stats.tokens
+        ));
+    }
+
+    Ok(())
+}
+
+/// `goodcommit stats --perf`: latency percentiles per model, then per phase and call.
+fn print_perf(records: &[StatsRecord]) {
+    let perf = perf_summary(records);
+    if perf.is_empty() {
+        ui::info("no timings recorded yet; they are kept unless `record_timings = false`");
+        return;
+    }
+    for (model, phases) in &perf {
+        ui::divider();
+        ui::info(model);
+        let width = phases.keys().map(String::len).max().unwrap_or(0);
+        for (phase, stats) in phases {
+            ui::info(&format!(
+                "  {phase:<width$}  p50 {:>6} ms  p95 {:>6} ms  ({} sample(s))",
+                stats.p50, stats.p95, stats.samples
+            ));
+        }
+    }
+}
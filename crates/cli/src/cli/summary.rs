@@ -0,0 +1,131 @@
+use serde::Serialize;
+
+use crate::ui;
+
+/// One commit created during a split session.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct SplitCommit {
+    pub(crate) sha: String,
+    pub(crate) subject: String,
+    pub(crate) files: usize,
+    pub(crate) used_fallback: bool,
+}
+
+/// Everything a split session produced, for the end-of-run report and `split --json`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct SplitSummary {
+    pub(crate) commits: Vec<SplitCommit>,
+    pub(crate) pushed: bool,
+}
+
+impl SplitSummary {
+    pub(crate) fn total_files(&self) -> usize {
+        self.commits.iter().map(|commit| commit.files).sum()
+    }
+}
+
+pub(crate) fn print_split_summary(summary: &SplitSummary) {
+    if summary.commits.is_empty() {
+        ui::info("no commits created");
+        return;
+    }
+
+    ui::divider();
+    for line in render_split_summary(summary) {
+        ui::info(&line);
+    }
+    ui::divider();
+}
+
+fn render_split_summary(summary: &SplitSummary) -> Vec<String> {
+    let sha_width = summary
+        .commits
+        .iter()
+        .map(|commit| commit.sha.len())
+        .max()
+        .unwrap_or(0)
+        .max("sha".len());
+
+    let mut lines = vec![format!("{:<sha_width$}  files  source    subject", "sha")];
+    for commit in &summary.commits {
+        let source = if commit.used_fallback {
+            "fallback"
+        } else {
+            "ai"
+        };
+        lines.push(format!(
+            "{:<sha_width$}  {:>5}  {source:<8}  {}",
+            commit.sha, commit.files, commit.subject
+        ));
+    }
+
+    let pushed = if summary.pushed { ", pushed" } else { "" };
+    lines.push(format!(
+        "{} commit(s), {} file(s){pushed}",
+        summary.commits.len(),
+        summary.total_files()
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_split_summary_lists_commits_and_total() {
+        let summary = SplitSummary {
+            commits: vec![
+                SplitCommit {
+                    sha: "abc1234".to_string(),
+                    subject: "feat: add search".to_string(),
+                    files: 3,
+                    used_fallback: false,
+                },
+                SplitCommit {
+                    sha: "def5678".to_string(),
+                    subject: "chore: update Cargo.lock".to_string(),
+                    files: 1,
+                    used_fallback: true,
+                },
+            ],
+            pushed: true,
+        };
+
+        let lines = render_split_summary(&summary);
+        assert_eq!(lines[0], "sha      files  source    subject");
+        assert_eq!(lines[1], "abc1234      3  ai        feat: add search");
+        assert_eq!(
+            lines[2],
+            "def5678      1  fallback  chore: update Cargo.lock"
+        );
+        assert_eq!(lines[3], "2 commit(s), 4 file(s), pushed");
+    }
+
+    #[test]
+    fn split_summary_serializes_for_json_output() {
+        let summary = SplitSummary {
+            commits: vec![SplitCommit {
+                sha: "abc1234".to_string(),
+                subject: "feat: add search".to_string(),
+                files: 3,
+                used_fallback: false,
+            }],
+            pushed: false,
+        };
+
+        let json = serde_json::to_value(&summary).expect("serialize");
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "commits": [{
+                    "sha": "abc1234",
+                    "subject": "feat: add search",
+                    "files": 3,
+                    "used_fallback": false,
+                }],
+                "pushed": false,
+            })
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{anyhow, Result};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+use goodcommit_core::git::{GitBackend, SystemGit};
+use goodcommit_core::pipeline::generate_tag_message;
+use goodcommit_core::providers::build_provider;
+
+use crate::ui;
+use crate::util::is_interactive;
+
+use super::args::Cli;
+use super::changelog::resolve_since_last_tag;
+use super::config::config_for_repo;
+
+/// Create the annotated tag `name` on HEAD with highlights of the commits since `from`
+/// (or since the most recent tag), optionally pushing it.
+pub(crate) async fn run_tag(
+    cli: &Cli,
+    name: &str,
+    from: Option<&str>,
+    push_tag: bool,
+) -> Result<()> {
+    let git = SystemGit::new();
+    git.ensure_git_repo()?;
+    let repo_root = git.repo_root().ok();
+    let (config, _paths) = config_for_repo(cli, repo_root.as_deref())?;
+    // Fail before generating rather than after the user has reviewed the message.
+    if git.tag_exists(name)? {
+        return Err(anyhow!("tag {name} already exists"));
+    }
+
+    let range = match from {
+        Some(from) => format!("{from}..HEAD"),
+        None => resolve_since_last_tag(&git)?.0,
+    };
+
+    let provider = build_provider(&config).ok();
+    let Some(tag) = generate_tag_message(&git, provider.as_deref(), &config, &range, name).await?
+    else {
+        return Err(anyhow!("no commits in {range}; nothing new to tag"));
+    };
+    for warning in &tag.warnings {
+        ui::warn(warning);
+    }
+
+    ui::info(&format!("tag message preview ({} commit(s)):", tag.commits));
+    ui::preview_message(&tag.message);
+    if cli.dry_run {
+        ui::info("dry run enabled; skipping tag");
+        return Ok(());
+    }
+    if config.confirm && is_interactive() {
+        let confirm = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("create tag {name} with this message?"))
+            .default(true)
+            .interact()?;
+        if !confirm {
+            ui::info("tag canceled");
+            return Ok(());
+        }
+    }
+
+    git.create_annotated_tag(name, &tag.message)?;
+    ui::success(&format!("created tag {name}"));
+
+    if push_tag {
+        let output = git
+            .push_tag(name)
+            .map_err(|err| anyhow!("tag {name} created but not pushed: {err}"))?;
+        if !output.is_empty() {
+            ui::info(&output);
+        }
+        ui::success(&format!("pushed tag {name}"));
+    }
+    Ok(())
+}
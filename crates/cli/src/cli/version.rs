@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::ui;
+
+/// Names the binary is installed under; `g` and `g.` are aliases for `goodcommit`.
+const INSTALLED_NAMES: [&str; 3] = ["goodcommit", "g", "g."];
+
+/// Version and build metadata embedded by `build.rs`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct BuildInfo {
+    pub(crate) version: String,
+    pub(crate) git_sha: String,
+    pub(crate) build_date: String,
+    pub(crate) features: Vec<String>,
+}
+
+impl BuildInfo {
+    pub(crate) fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("GOODCOMMIT_GIT_SHA").to_string(),
+            build_date: env!("GOODCOMMIT_BUILD_DATE").to_string(),
+            features: env!("GOODCOMMIT_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// `0.3.3 (1a2b3c4d5e6f, built 2026-01-31)`
+    fn summary(&self) -> String {
+        format!(
+            "{} ({}, built {})",
+            self.version, self.git_sha, self.build_date
+        )
+    }
+}
+
+/// `--version`, with build metadata under `--verbose` or as JSON under `--json`.
+pub(crate) fn print_version(verbose: bool, json: bool) -> Result<()> {
+    let info = BuildInfo::current();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+    println!("goodcommit {}", info.version);
+    if verbose {
+        println!("git sha: {}", info.git_sha);
+        println!("build date: {}", info.build_date);
+        let features = if info.features.is_empty() {
+            "none".to_string()
+        } else {
+            info.features.join(", ")
+        };
+        println!("features: {features}");
+    }
+    Ok(())
+}
+
+/// Doctor lines for this build and for each installed name on `PATH`, warning on drift.
+///
+/// Installs are compared with this binary by path and then by contents; they are never run.
+pub(crate) fn report_installs() {
+    let info = BuildInfo::current();
+    ui::info(&format!("goodcommit: {}", info.summary()));
+    let current_exe = std::env::current_exe()
+        .ok()
+        .and_then(|path| path.canonicalize().ok());
+
+    for name in INSTALLED_NAMES {
+        let Some(path) = find_on_path(name) else {
+            ui::info(&format!("{name}: not on PATH"));
+            continue;
+        };
+        let resolved = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if current_exe.as_ref() == Some(&resolved) {
+            ui::info(&format!("{name}: {} (this binary)", path.display()));
+            continue;
+        }
+        match current_exe.as_deref().map(|exe| same_contents(&resolved, exe)) {
+            Some(Ok(true)) => ui::info(&format!("{name}: {} (same build)", path.display())),
+            Some(Ok(false)) => ui::warn(&format!(
+                "{name}: {} is a different build than this one ({}); reinstall or remove the stale copy",
+                path.display(),
+                info.summary()
+            )),
+            Some(Err(err)) => ui::warn(&format!(
+                "{name}: {} could not be compared with this binary: {err}",
+                path.display()
+            )),
+            None => ui::warn(&format!(
+                "{name}: {} could not be compared; this binary's path is unknown",
+                path.display()
+            )),
+        }
+    }
+}
+
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether two files hold the same bytes, checking sizes first.
+fn same_contents(path: &Path, other: &Path) -> std::io::Result<bool> {
+    if fs::metadata(path)?.len() != fs::metadata(other)?.len() {
+        return Ok(false);
+    }
+    Ok(fs::read(path)? == fs::read(other)?)
+}
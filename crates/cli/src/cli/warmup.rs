@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use goodcommit_core::config::{EffectiveConfig, ProviderKind, WarmupMode};
+use goodcommit_core::providers::Provider;
+
+/// A background warm-up request; aborted if still running when dropped.
+pub(crate) struct Warmup {
+    handle: JoinHandle<()>,
+    started: Instant,
+}
+
+impl Warmup {
+    pub(crate) fn spawn(provider: Arc<dyn Provider>) -> Self {
+        let started = Instant::now();
+        let handle = tokio::spawn(async move {
+            match provider.warm_up().await {
+                Ok(()) => debug!(
+                    elapsed_ms = started.elapsed().as_millis(),
+                    "provider warm-up finished"
+                ),
+                Err(err) => debug!("provider warm-up failed: {err}"),
+            }
+        });
+        Self { handle, started }
+    }
+
+    /// Log how much git work the warm-up overlapped with.
+    pub(crate) fn log_overlap(&self) {
+        debug!(
+            overlap_ms = self.started.elapsed().as_millis(),
+            finished = self.handle.is_finished(),
+            "provider warm-up overlapped with staging and diff collection"
+        );
+    }
+}
+
+impl Drop for Warmup {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+pub(crate) fn should_warm_up(config: &EffectiveConfig) -> bool {
+    match config.warmup {
+        WarmupMode::Never => false,
+        WarmupMode::Always => true,
+        WarmupMode::Auto => config.provider == ProviderKind::Ollama,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use goodcommit_core::config::Config;
+
+    use super::*;
+
+    fn config(provider: ProviderKind, warmup: WarmupMode) -> EffectiveConfig {
+        let mut config = Config::defaults();
+        config.provider = Some(provider);
+        config.warmup = Some(warmup);
+        config.resolve().expect("config")
+    }
+
+    #[test]
+    fn should_warm_up_skips_cloud_providers_in_auto() {
+        assert!(should_warm_up(&config(
+            ProviderKind::Ollama,
+            WarmupMode::Auto
+        )));
+        assert!(!should_warm_up(&config(
+            ProviderKind::OpenAi,
+            WarmupMode::Auto
+        )));
+        assert!(should_warm_up(&config(
+            ProviderKind::OpenAi,
+            WarmupMode::Always
+        )));
+        assert!(!should_warm_up(&config(
+            ProviderKind::Ollama,
+            WarmupMode::Never
+        )));
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::Result;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+use goodcommit_core::pipeline::FallbackReason;
+
+use crate::ui;
+use crate::util::is_interactive;
+
+const MAX_WARNING_LINES: usize = 5;
+
+/// Print pipeline warnings, collapsing per-file repeats unless verbose.
+pub(crate) fn print_warnings(warnings: &[String], verbose: bool) {
+    if verbose {
+        for warning in warnings {
+            ui::warn(warning);
+        }
+        return;
+    }
+
+    for line in summarize_warnings(warnings) {
+        ui::warn(&line);
+    }
+}
+
+/// Say why the fallback message was used, with the decision chain under `--verbose`.
+///
+/// Interactive runs that confirm the commit are offered the chain instead.
+pub(crate) fn explain_fallback(
+    reason: Option<&FallbackReason>,
+    trace: &[String],
+    verbose: bool,
+    confirm: bool,
+) -> Result<()> {
+    let Some(reason) = reason else {
+        return Ok(());
+    };
+    ui::warn(&format!("fallback message used because {reason}"));
+    // A single step adds nothing to the line above.
+    if trace.len() < 2 {
+        return Ok(());
+    }
+    let show = verbose
+        || (confirm
+            && is_interactive()
+            && Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt("show why?")
+                .default(false)
+                .interact()?);
+    if show {
+        for line in decision_lines(trace) {
+            ui::info(&line);
+        }
+    }
+    Ok(())
+}
+
+fn decision_lines(trace: &[String]) -> Vec<String> {
+    let mut lines = vec!["why:".to_string()];
+    lines.extend(
+        trace
+            .iter()
+            .enumerate()
+            .map(|(index, step)| format!("  {}. {step}", index + 1)),
+    );
+    lines
+}
+
+fn summarize_warnings(warnings: &[String]) -> Vec<String> {
+    let mut groups: Vec<(&str, Vec<&String>)> = Vec::new();
+    for warning in warnings {
+        let kind = warning_kind(warning);
+        if let Some((_, members)) = groups.iter_mut().find(|(existing, _)| *existing == kind) {
+            if !members.contains(&warning) {
+                members.push(warning);
+            }
+        } else {
+            groups.push((kind, vec![warning]));
+        }
+    }
+
+    let mut lines = groups
+        .into_iter()
+        .map(|(kind, members)| {
+            if members.len() == 1 {
+                members[0].clone()
+            } else {
+                format!("{kind} for {} files (see --verbose)", members.len())
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if lines.len() > MAX_WARNING_LINES {
+        let hidden = lines.len() - (MAX_WARNING_LINES - 1);
+        lines.truncate(MAX_WARNING_LINES - 1);
+        lines.push(format!("{hidden} more warnings (see --verbose)"));
+    }
+
+    lines
+}
+
+fn warning_kind(warning: &str) -> &str {
+    warning
+        .split_once(" for ")
+        .map_or(warning, |(kind, _)| kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_warnings_groups_per_file_kinds() {
+        let warnings = vec![
+            "diff truncated for a.rs".to_string(),
+            "diff omitted for big.json (5000 lines)".to_string(),
+            "diff truncated for b.rs".to_string(),
+            "diff truncated for b.rs".to_string(),
+            "only first 40 files used for AI summary".to_string(),
+        ];
+
+        assert_eq!(
+            summarize_warnings(&warnings),
+            vec![
+                "diff truncated for 2 files (see --verbose)".to_string(),
+                "diff omitted for big.json (5000 lines)".to_string(),
+                "only first 40 files used for AI summary".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn decision_lines_number_each_step() {
+        let trace = vec![
+            "provider: openai/gpt-5".to_string(),
+            "provider call failed: timed out".to_string(),
+        ];
+
+        assert_eq!(
+            decision_lines(&trace),
+            vec![
+                "why:".to_string(),
+                "  1. provider: openai/gpt-5".to_string(),
+                "  2. provider call failed: timed out".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn summarize_warnings_caps_output() {
+        let warnings = (0..8)
+            .map(|index| format!("warning number {index}"))
+            .collect::<Vec<_>>();
+
+        let lines = summarize_warnings(&warnings);
+        assert_eq!(lines.len(), MAX_WARNING_LINES);
+        assert_eq!(
+            lines[MAX_WARNING_LINES - 1],
+            "4 more warnings (see --verbose)"
+        );
+    }
+}
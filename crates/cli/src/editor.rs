@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+
+use goodcommit_core::lint::{strip_comments, SCISSORS};
+
+const EDIT_HELP: &str = "\n# Edit the commit message above. Lines starting with '#' are ignored.\n# An empty message aborts the commit.\n";
+
+/// Open the user's git editor on `initial` and return the edited message.
+///
+/// Like `git commit --verbose`, `diff` is shown below a scissors line and never committed.
+/// Returns `None` when the user leaves the message empty.
+pub fn edit_message(path: &Path, initial: &str, diff: Option<&str>) -> Result<Option<String>> {
+    let mut contents = format!("{}\n{EDIT_HELP}", initial.trim_end());
+    if let Some(diff) = diff.filter(|diff| !diff.trim().is_empty()) {
+        contents.push_str(&format!(
+            "{SCISSORS}\n# Do not modify or remove the line above.\n# Everything below it will be ignored.\n{diff}"
+        ));
+    }
+    fs::write(path, contents).context("failed to write message file")?;
+
+    edit_file(path)?;
+
+    let edited = fs::read_to_string(path).context("failed to read edited message")?;
+    let _ = fs::remove_file(path);
+
+    let message = strip_comments(&edited);
+    Ok(if message.is_empty() {
+        None
+    } else {
+        Some(message)
+    })
+}
+
+/// Open `path` in the user's git editor (`GIT_EDITOR`, `core.editor`, `VISUAL`, `EDITOR`).
+pub fn edit_file(path: &Path) -> Result<()> {
+    let editor = git_editor()?;
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg(&editor)
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to launch editor `{editor}`"))?;
+    if !status.success() {
+        return Err(anyhow!("editor `{editor}` exited with {status}"));
+    }
+    Ok(())
+}
+
+fn git_editor() -> Result<String> {
+    let output = Command::new("git")
+        .args(["var", "GIT_EDITOR"])
+        .output()
+        .context("failed to resolve git editor")?;
+    let editor = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || editor.is_empty() {
+        return Err(anyhow!(
+            "no editor configured (set GIT_EDITOR or core.editor)"
+        ));
+    }
+    Ok(editor)
+}
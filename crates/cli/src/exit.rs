@@ -0,0 +1,16 @@
+use std::fmt;
+
+/// Staged nothing, but the working tree has changes.
+pub(crate) const NOTHING_TO_COMMIT: i32 = 2;
+
+/// Ends the run with a specific exit code; the reason has already been printed.
+#[derive(Debug)]
+pub(crate) struct ExitCode(pub(crate) i32);
+
+impl fmt::Display for ExitCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exit code {}", self.0)
+    }
+}
+
+impl std::error::Error for ExitCode {}
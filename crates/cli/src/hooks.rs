@@ -39,3 +39,98 @@ pub fn uninstall_hook(git: &impl GitBackend) -> Result<()> {
 pub fn write_hook_message(path: &Path, message: &str) -> Result<()> {
     fs::write(path, format!("{message}\n")).context("failed to write hook message")
 }
+
+/// Keep `template` and put `subject` on the first line, above the template body.
+pub fn prepend_subject(subject: &str, template: &str) -> String {
+    let body = template
+        .split_inclusive('\n')
+        .skip_while(|line| line.trim().is_empty())
+        .collect::<String>();
+    format!("{subject}\n\n{body}").trim_end().to_string()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HookDecision {
+    Generate,
+    /// `git commit -t` or `commit.template`: add a subject above the template.
+    PrependSubject,
+    Skip(String),
+}
+
+/// Decide whether the hook should write a message, given git's source argument
+/// and the current contents of the message file.
+///
+/// `message` (`-m`/`-F`), `commit` (`-c`/`-C`/`--amend`), `merge`, and `squash`
+/// already carry a message, so only `template` and no source generate one.
+pub fn hook_decision(source: Option<&str>, existing: Option<&str>) -> HookDecision {
+    match source.map(str::trim).filter(|source| !source.is_empty()) {
+        None => {}
+        Some("template") => return HookDecision::PrependSubject,
+        Some(source) => return HookDecision::Skip(format!("source={source}")),
+    }
+
+    let has_message = existing.is_some_and(|content| {
+        content
+            .lines()
+            .map(str::trim)
+            .any(|line| !line.is_empty() && !line.starts_with('#'))
+    });
+    if has_message {
+        return HookDecision::Skip("message already present".to_string());
+    }
+
+    HookDecision::Generate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hook_decision_skips_sources_and_existing_messages() {
+        assert_eq!(
+            hook_decision(Some("merge"), None),
+            HookDecision::Skip("source=merge".to_string())
+        );
+        assert_eq!(
+            hook_decision(None, Some("fix: typed by hand\n# comment\n")),
+            HookDecision::Skip("message already present".to_string())
+        );
+    }
+
+    #[test]
+    fn hook_decision_branches_on_each_source() {
+        for source in ["message", "commit", "merge", "squash"] {
+            assert_eq!(
+                hook_decision(Some(source), Some("existing\n")),
+                HookDecision::Skip(format!("source={source}"))
+            );
+        }
+        assert_eq!(
+            hook_decision(Some("template"), Some("Ticket:\n\n# fill in\n")),
+            HookDecision::PrependSubject
+        );
+        assert_eq!(
+            hook_decision(Some("template"), None),
+            HookDecision::PrependSubject
+        );
+    }
+
+    #[test]
+    fn prepend_subject_keeps_template_body() {
+        assert_eq!(
+            prepend_subject("feat: add search", "\n\nTicket:\n# fill in\n"),
+            "feat: add search\n\nTicket:\n# fill in"
+        );
+        assert_eq!(prepend_subject("fix: typo", ""), "fix: typo");
+    }
+
+    #[test]
+    fn hook_decision_generates_for_empty_template() {
+        assert_eq!(
+            hook_decision(Some(""), Some("\n# Please enter the commit message\n")),
+            HookDecision::Generate
+        );
+        assert_eq!(hook_decision(None, None), HookDecision::Generate);
+    }
+}
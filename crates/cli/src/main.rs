@@ -1,4 +1,6 @@
 mod cli;
+mod editor;
+mod exit;
 mod hooks;
 mod setup;
 mod ui;
@@ -9,6 +11,9 @@ async fn main() {
     tokio::select! {
         result = cli::run() => {
             if let Err(err) = result {
+                if let Some(exit::ExitCode(code)) = err.downcast_ref::<exit::ExitCode>() {
+                    std::process::exit(*code);
+                }
                 ui::error(&format!("{err}"));
                 std::process::exit(1);
             }
@@ -1,15 +1,17 @@
 use std::fs;
 use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::{anyhow, Context, Result};
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Password, Select};
+use toml_edit::{DocumentMut, Item, Value};
 
 use crate::ui;
 use crate::util::is_interactive;
 use goodcommit_core::config::{
-    config_dir, openai_api_key_env, Config, OpenAiMode, ProviderKind, StageMode,
+    config_dir, config_from_env, legacy_config_dir, migrate_legacy_config, openai_api_key_env,
+    resolve_paths, Config, OpenAiMode, ProviderKind, StageMode,
 };
 use goodcommit_core::ignore::default_patterns;
 
@@ -18,6 +20,10 @@ pub fn run_setup() -> Result<()> {
         return Err(anyhow!("setup requires an interactive terminal"));
     }
 
+    if resolve_paths(None)?.legacy_config.is_some() && offer_legacy_migration()? {
+        return Ok(());
+    }
+
     let theme = ColorfulTheme::default();
     let config_dir = config_dir()?;
     fs::create_dir_all(&config_dir).context("failed to create config directory")?;
@@ -36,14 +42,14 @@ pub fn run_setup() -> Result<()> {
 
     let provider = Select::with_theme(&theme)
         .with_prompt("Choose your default provider")
-        .items(&["ollama (local)", "openai"])
+        .items(["ollama (local)", "openai"])
         .default(0)
         .interact()?;
 
-    let (provider_kind, default_model) = if provider == 1 {
-        (ProviderKind::OpenAi, "gpt-5-nano-2025-08-07")
+    let provider_kind = if provider == 1 {
+        ProviderKind::OpenAi
     } else {
-        (ProviderKind::Ollama, "qwen2.5-coder:1.5b")
+        ProviderKind::Ollama
     };
 
     let mut openai_key = None;
@@ -79,14 +85,133 @@ pub fn run_setup() -> Result<()> {
 
     let model: String = Input::with_theme(&theme)
         .with_prompt("Default model")
-        .default(default_model.to_string())
+        .default(default_model(provider_kind).to_string())
         .interact_text()?;
 
     if provider_kind == ProviderKind::Ollama {
         check_ollama(&model)?;
     }
 
-    let openai_mode = if provider_kind == ProviderKind::OpenAi {
+    let push = Confirm::with_theme(&theme)
+        .with_prompt("Push by default after commit?")
+        .default(true)
+        .interact()?;
+
+    let config = setup_config(provider_kind, model, openai_key, push);
+    write_setup(&config_dir, &config)
+}
+
+/// Offer to copy the config left by `git-ai-commit` (the old binary name) into the
+/// goodcommit config directory. Returns whether it was copied.
+pub fn offer_legacy_migration() -> Result<bool> {
+    let legacy_dir = legacy_config_dir()?;
+    let migrate = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "found a git-ai-commit config in {}; copy it to {}?",
+            legacy_dir.display(),
+            config_dir()?.display()
+        ))
+        .default(true)
+        .interact()?;
+    if !migrate {
+        return Ok(false);
+    }
+    match migrate_legacy_config()? {
+        Some(path) => {
+            ui::success(&format!(
+                "copied config to {}; {} can be removed",
+                path.display(),
+                legacy_dir.display()
+            ));
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Marker in the config directory recording that the legacy migration was declined.
+const LEGACY_DECLINED_MARKER: &str = "legacy-migration-declined";
+
+/// [`offer_legacy_migration`] for commit runs: asked once, and a decline is remembered so
+/// later runs stop asking. `goodcommit setup` still offers it.
+pub fn offer_legacy_migration_once() -> Result<()> {
+    let config_dir = config_dir()?;
+    let marker = config_dir.join(LEGACY_DECLINED_MARKER);
+    if marker.exists() || offer_legacy_migration()? {
+        return Ok(());
+    }
+    fs::create_dir_all(&config_dir).context("failed to create config directory")?;
+    fs::write(&marker, "").context("failed to record the declined migration")?;
+    ui::info("not asking again; run `goodcommit setup` to copy it later");
+    Ok(())
+}
+
+/// Answers for `goodcommit setup --non-interactive`; unset values come from `GOODCOMMIT_*`.
+pub struct SetupAnswers {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub push: Option<bool>,
+    /// Save the API key found in the environment to config.toml.
+    pub save_key: bool,
+    /// Replace an existing config.toml.
+    pub force: bool,
+}
+
+/// Write config.toml and the ignore file without prompting, for containers and dotfile
+/// bootstrap scripts.
+pub fn run_setup_non_interactive(answers: &SetupAnswers) -> Result<()> {
+    let (env, _) = config_from_env();
+    let provider_kind = match &answers.provider {
+        Some(provider) => provider.parse().map_err(|err: String| anyhow!(err))?,
+        None => env.provider.ok_or_else(|| {
+            anyhow!("--non-interactive needs --provider (or GOODCOMMIT_PROVIDER)")
+        })?,
+    };
+    let model = answers
+        .model
+        .clone()
+        .or(env.model)
+        .unwrap_or_else(|| default_model(provider_kind).to_string());
+    let push = answers.push.or(env.push).unwrap_or(true);
+
+    let config_dir = config_dir()?;
+    let config_path = config_dir.join("config.toml");
+    if config_path.exists() && !answers.force {
+        return Err(anyhow!(
+            "{} already exists; pass --force to overwrite it",
+            config_path.display()
+        ));
+    }
+
+    let mut openai_key = None;
+    if provider_kind == ProviderKind::OpenAi {
+        let env_key = openai_api_key_env();
+        if env_key.is_none() {
+            ui::warn("no OpenAI API key found; set OPENAI_API_KEY or GOODCOMMIT_OPENAI_API_KEY");
+        }
+        openai_key = env_key.filter(|_| answers.save_key);
+    } else {
+        check_ollama(&model)?;
+    }
+
+    let config = setup_config(provider_kind, model, openai_key, push);
+    write_setup(&config_dir, &config)
+}
+
+fn default_model(provider: ProviderKind) -> &'static str {
+    match provider {
+        ProviderKind::OpenAi => "gpt-5-nano-2025-08-07",
+        ProviderKind::Ollama => "qwen2.5-coder:1.5b",
+    }
+}
+
+fn setup_config(
+    provider: ProviderKind,
+    model: String,
+    openai_key: Option<String>,
+    push: bool,
+) -> Config {
+    let openai_mode = if provider == ProviderKind::OpenAi {
         if model.trim().to_lowercase().starts_with("gpt-5") {
             Some(OpenAiMode::Responses)
         } else {
@@ -96,13 +221,8 @@ pub fn run_setup() -> Result<()> {
         None
     };
 
-    let push = Confirm::with_theme(&theme)
-        .with_prompt("Push by default after commit?")
-        .default(true)
-        .interact()?;
-
-    let config = Config {
-        provider: Some(provider_kind),
+    Config {
+        provider: Some(provider),
         model: Some(model),
         openai_mode,
         openai_api_key: openai_key,
@@ -111,12 +231,16 @@ pub fn run_setup() -> Result<()> {
         one_line: Some(true),
         timeout_secs: Some(20),
         max_input_tokens: Some(6000),
-        max_output_tokens: Some(2048),
         stage_mode: Some(StageMode::Auto),
         ..Config::default()
-    };
+    }
+}
 
-    let toml = toml::to_string_pretty(&config).context("failed to serialize config")?;
+/// Write `config` to config.toml (owner-only) and create the default ignore file.
+fn write_setup(config_dir: &Path, config: &Config) -> Result<()> {
+    fs::create_dir_all(config_dir).context("failed to create config directory")?;
+    let config_path = config_dir.join("config.toml");
+    let toml = toml::to_string_pretty(config).context("failed to serialize config")?;
     fs::write(&config_path, toml).context("failed to write config")?;
     set_config_permissions(&config_path)?;
 
@@ -125,6 +249,168 @@ pub fn run_setup() -> Result<()> {
     Ok(())
 }
 
+/// Settings offered by `goodcommit setup --edit`, as (config key, menu label).
+const EDITABLE_SETTINGS: &[(&str, &str)] = &[
+    ("provider", "provider"),
+    ("model", "model"),
+    ("openai_api_key", "API key storage"),
+    ("push", "push"),
+    ("conventional", "conventional"),
+    ("one_line", "one_line"),
+    ("stage_mode", "stage_mode"),
+];
+
+const STAGE_MODES: &[&str] = &["auto", "all", "none", "interactive"];
+
+/// Change one setting in the global config.toml, keeping every other key and comment.
+pub fn run_setup_edit() -> Result<()> {
+    if !is_interactive() {
+        return Err(anyhow!("setup --edit requires an interactive terminal"));
+    }
+
+    let theme = ColorfulTheme::default();
+    let config_path = config_dir()?.join("config.toml");
+    let text = match fs::read_to_string(&config_path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(anyhow!(
+                "no config.toml at {}; run `goodcommit setup` first",
+                config_path.display()
+            ));
+        }
+        Err(err) => return Err(err).context("failed to read config"),
+    };
+    let doc = parse_document(&text)?;
+
+    let items: Vec<String> = EDITABLE_SETTINGS
+        .iter()
+        .map(|(key, label)| format!("{label} ({})", describe_current(&doc, key)))
+        .collect();
+    let choice = Select::with_theme(&theme)
+        .with_prompt("Setting to change")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    let key = EDITABLE_SETTINGS[choice].0;
+
+    let value = prompt_value(&theme, &doc, key)?;
+    let updated = set_setting(&text, key, value)?;
+    if updated == text {
+        ui::info(&format!("{key} unchanged"));
+        return Ok(());
+    }
+
+    fs::write(&config_path, updated).context("failed to write config")?;
+    set_config_permissions(&config_path)?;
+    ui::success(&format!("updated {key} in {}", config_path.display()));
+    Ok(())
+}
+
+fn prompt_value(theme: &ColorfulTheme, doc: &DocumentMut, key: &str) -> Result<Option<Value>> {
+    let current = doc.get(key).and_then(Item::as_value);
+    let value = match key {
+        "provider" => {
+            let options = ["ollama", "openai"];
+            let default = usize::from(current.and_then(Value::as_str) == Some("openai"));
+            let index = Select::with_theme(theme)
+                .with_prompt("Provider")
+                .items(options)
+                .default(default)
+                .interact()?;
+            Some(Value::from(options[index]))
+        }
+        "model" => {
+            let model: String = Input::with_theme(theme)
+                .with_prompt("Model")
+                .with_initial_text(current.and_then(Value::as_str).unwrap_or_default())
+                .interact_text()?;
+            Some(Value::from(model.trim()))
+        }
+        "openai_api_key" => {
+            let index = Select::with_theme(theme)
+                .with_prompt("Where should the OpenAI API key live?")
+                .items([
+                    "config.toml (stored in plaintext)",
+                    "environment only (OPENAI_API_KEY or GOODCOMMIT_OPENAI_API_KEY)",
+                ])
+                .default(usize::from(current.is_none()))
+                .interact()?;
+            if index == 1 {
+                None
+            } else {
+                let key = Password::with_theme(theme)
+                    .with_prompt("Enter OpenAI API key (leave empty to keep the current one)")
+                    .allow_empty_password(true)
+                    .interact()?;
+                match (key.trim(), current) {
+                    ("", Some(existing)) => Some(existing.clone()),
+                    ("", None) => return Err(anyhow!("no API key entered")),
+                    (key, _) => Some(Value::from(key)),
+                }
+            }
+        }
+        "stage_mode" => {
+            let default = current
+                .and_then(Value::as_str)
+                .and_then(|mode| STAGE_MODES.iter().position(|candidate| *candidate == mode))
+                .unwrap_or(0);
+            let index = Select::with_theme(theme)
+                .with_prompt("Stage mode")
+                .items(STAGE_MODES)
+                .default(default)
+                .interact()?;
+            Some(Value::from(STAGE_MODES[index]))
+        }
+        _ => {
+            let enabled = Confirm::with_theme(theme)
+                .with_prompt(format!("Enable {key}?"))
+                .default(current.and_then(Value::as_bool).unwrap_or(true))
+                .interact()?;
+            Some(Value::from(enabled))
+        }
+    };
+    Ok(value)
+}
+
+fn describe_current(doc: &DocumentMut, key: &str) -> String {
+    let current = doc.get(key).and_then(Item::as_value);
+    if key == "openai_api_key" {
+        return if current.is_some() {
+            "stored in config.toml".to_string()
+        } else {
+            "environment only".to_string()
+        };
+    }
+    match current {
+        Some(value) => value.to_string().trim().to_string(),
+        None => "default".to_string(),
+    }
+}
+
+fn parse_document(text: &str) -> Result<DocumentMut> {
+    text.parse::<DocumentMut>()
+        .context("failed to parse config.toml")
+}
+
+/// Set (or with `None`, remove) one top-level key, leaving the rest of the file as written.
+///
+/// A comment trailing the old value on the same line is kept.
+fn set_setting(text: &str, key: &str, value: Option<Value>) -> Result<String> {
+    let mut doc = parse_document(text)?;
+    match value {
+        Some(mut value) => {
+            if let Some(existing) = doc.get(key).and_then(Item::as_value) {
+                *value.decor_mut() = existing.decor().clone();
+            }
+            doc[key] = Item::Value(value);
+        }
+        None => {
+            doc.remove(key);
+        }
+    }
+    Ok(doc.to_string())
+}
+
 fn ensure_ignore_file(path: &PathBuf) -> Result<()> {
     if path.exists() {
         return Ok(());
@@ -193,3 +479,51 @@ fn set_config_permissions(path: &PathBuf) -> Result<()> {
 fn set_config_permissions(_path: &PathBuf) -> Result<()> {
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMENTED: &str = "\
+# goodcommit settings
+provider = \"ollama\" # local first
+model = \"qwen2.5-coder:1.5b\"
+
+# push after every commit
+push = true
+stage_mode = \"auto\"
+";
+
+    #[test]
+    fn set_setting_keeps_other_keys_and_comments() {
+        let updated =
+            set_setting(COMMENTED, "push", Some(Value::from(false))).expect("set setting");
+        assert_eq!(updated, COMMENTED.replace("push = true", "push = false"));
+    }
+
+    #[test]
+    fn set_setting_keeps_a_trailing_comment_on_the_changed_line() {
+        let updated =
+            set_setting(COMMENTED, "provider", Some(Value::from("openai"))).expect("set setting");
+        assert!(updated.contains("provider = \"openai\" # local first\n"));
+        assert!(updated.starts_with("# goodcommit settings\n"));
+    }
+
+    #[test]
+    fn set_setting_appends_missing_keys_and_removes_cleared_ones() {
+        let added =
+            set_setting(COMMENTED, "one_line", Some(Value::from(true))).expect("set setting");
+        assert!(added.starts_with(COMMENTED));
+        assert!(added.ends_with("one_line = true\n"));
+
+        let stored = set_setting(COMMENTED, "openai_api_key", Some(Value::from("sk-test")))
+            .expect("set setting");
+        let removed = set_setting(&stored, "openai_api_key", None).expect("set setting");
+        assert_eq!(removed, COMMENTED);
+    }
+
+    #[test]
+    fn set_setting_rejects_invalid_toml() {
+        assert!(set_setting("provider = ", "push", Some(Value::from(true))).is_err());
+    }
+}
@@ -1,5 +1,23 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set when stdout carries the commit message (`--output -`), so progress goes to stderr.
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+/// Send everything but the message itself to stderr for the rest of the run.
+pub fn reserve_stdout() {
+    STDOUT_RESERVED.store(true, Ordering::Relaxed);
+}
+
+fn say(message: &str) {
+    if STDOUT_RESERVED.load(Ordering::Relaxed) {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
+}
+
 pub fn info(message: &str) {
-    println!("{message}");
+    say(message);
 }
 
 pub fn warn(message: &str) {
@@ -11,15 +29,15 @@ pub fn error(message: &str) {
 }
 
 pub fn success(message: &str) {
-    println!("{message}");
+    say(message);
 }
 
 pub fn divider() {
-    println!("----------------------------------------------------------------");
+    say("----------------------------------------------------------------");
 }
 
 pub fn preview_message(message: &str) {
     divider();
-    println!("{message}");
+    say(message);
     divider();
 }
@@ -3,6 +3,7 @@ use std::path::Path;
 use std::process::Command as StdCommand;
 
 use assert_cmd::Command;
+use predicates::prelude::*;
 use predicates::str::contains;
 use tempfile::TempDir;
 
@@ -89,3 +90,1997 @@ fn setup_requires_interactive_terminal() {
         .failure()
         .stderr(contains("setup requires an interactive terminal"));
 }
+
+#[test]
+fn non_interactive_setup_writes_config_from_flags_and_env() {
+    let home = TempDir::new().expect("tempdir");
+    let setup = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(home.path())
+            .env("HOME", home.path())
+            .env_remove("GOODCOMMIT_PROVIDER")
+            .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+            .env("OPENAI_API_KEY", "sk-from-env")
+            .args(["setup", "--non-interactive"]);
+        cmd
+    };
+
+    setup()
+        .assert()
+        .failure()
+        .stderr(contains("needs --provider (or GOODCOMMIT_PROVIDER)"));
+
+    setup()
+        .args(["--provider", "openai", "--no-push"])
+        .assert()
+        .success()
+        .stdout(contains("setup complete"));
+    let dir = home.path().join(".config/goodcommit");
+    let config = fs::read_to_string(dir.join("config.toml")).expect("config");
+    assert!(config.contains("provider = \"openai\""), "{config}");
+    assert!(
+        config.contains("model = \"gpt-5-nano-2025-08-07\""),
+        "{config}"
+    );
+    assert!(config.contains("push = false"), "{config}");
+    assert!(!config.contains("sk-from-env"), "{config}");
+    assert!(dir.join("ignore").exists());
+
+    setup()
+        .args(["--provider", "openai"])
+        .assert()
+        .failure()
+        .stderr(contains("pass --force to overwrite"));
+
+    setup()
+        .env("GOODCOMMIT_PROVIDER", "openai")
+        .args(["--model", "gpt-4o-mini", "--save-key", "--force"])
+        .assert()
+        .success();
+    let config = fs::read_to_string(dir.join("config.toml")).expect("config");
+    assert!(config.contains("model = \"gpt-4o-mini\""), "{config}");
+    assert!(
+        config.contains("openai_api_key = \"sk-from-env\""),
+        "{config}"
+    );
+    assert!(config.contains("push = true"), "{config}");
+}
+
+#[test]
+fn version_json_reports_build_info() {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"))
+        .args(["--version", "--json"])
+        .output()
+        .expect("run goodcommit");
+    assert!(output.status.success());
+
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert!(info["git_sha"].is_string());
+    assert!(info["build_date"].is_string());
+    assert!(info["features"].is_array());
+}
+
+#[cfg(unix)]
+#[test]
+fn doctor_warns_when_alias_on_path_is_a_different_build_without_running_it() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let bin = TempDir::new().expect("tempdir");
+    let ran = bin.path().join("ran");
+    let alias = bin.path().join("g.");
+    fs::write(
+        &alias,
+        format!(
+            "#!/bin/sh\ntouch '{}'\necho 'goodcommit 0.0.1'\n",
+            ran.display()
+        ),
+    )
+    .expect("write alias");
+    fs::set_permissions(&alias, fs::Permissions::from_mode(0o755)).expect("chmod alias");
+    fs::copy(
+        assert_cmd::cargo::cargo_bin!("goodcommit"),
+        bin.path().join("g"),
+    )
+    .expect("copy binary");
+    let path = format!(
+        "{}:{}",
+        bin.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("PATH", path)
+        .arg("doctor");
+
+    cmd.assert()
+        .success()
+        .stdout(contains(format!(
+            "goodcommit: {}",
+            env!("CARGO_PKG_VERSION")
+        )))
+        .stdout(contains("(same build)"))
+        .stderr(contains("is a different build than this one"))
+        .stderr(contains("reinstall or remove the stale copy"));
+    assert!(!ran.exists(), "doctor ran the binary on PATH");
+}
+
+#[test]
+fn doctor_json_reports_the_checks() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let doctor = |args: &[&str]| {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"))
+            .current_dir(repo.path())
+            .env("HOME", home.path())
+            .env_remove("OPENAI_API_KEY")
+            .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+            .args(args)
+            .args(["doctor", "--json"])
+            .output()
+            .expect("run doctor");
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).expect("json report")
+    };
+
+    let report = doctor(&["--provider", "ollama", "--model", "llama3"]);
+    assert_eq!(report["provider"], "ollama");
+    assert_eq!(report["model"], "llama3");
+    assert_eq!(report["api_key_present"], false);
+    assert!(report["git_version"]
+        .as_str()
+        .is_some_and(|version| version.starts_with("git version")));
+    assert!(report["ollama_reachable"].is_boolean());
+    assert!(report["config_paths"]["repo_config"].is_null());
+    assert!(report["config_paths"]["global_ignore"].is_string());
+
+    let report = doctor(&["--provider", "openai"]);
+    assert_eq!(report["provider"], "openai");
+    assert!(report["ollama_reachable"].is_null());
+}
+
+#[test]
+fn lint_reports_violations() {
+    let repo = init_repo();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("lint")
+        .arg("--message")
+        .arg("updated stuff");
+
+    cmd.assert()
+        .failure()
+        .stderr(contains("conventional"))
+        .stderr(contains("lint violation"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("lint")
+        .arg("--message")
+        .arg("fix: handle empty diff");
+
+    cmd.assert().success().stdout(contains("commit message ok"));
+}
+
+#[test]
+fn lint_range_reports_each_failing_commit() {
+    let repo = init_repo();
+    for (file, message) in [
+        ("a.txt", "chore: init"),
+        ("b.txt", "feat: add b"),
+        ("c.txt", "Added c"),
+    ] {
+        fs::write(repo.path().join(file), "x\n").expect("write file");
+        run_git(repo.path(), &["add", file]);
+        run_git(repo.path(), &["commit", "-m", message]);
+    }
+    let bad = run_git(repo.path(), &["rev-parse", "--short=7", "HEAD"]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("lint")
+        .arg("--range")
+        .arg("HEAD~2..HEAD");
+
+    cmd.assert()
+        .failure()
+        .stderr(contains(format!("{bad}:1:1: conventional")))
+        .stderr(contains("1 of 2 commit message(s)"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("lint")
+        .arg("--range")
+        .arg("HEAD~2..HEAD~1");
+
+    cmd.assert()
+        .success()
+        .stdout(contains("1 commit message(s) ok"));
+}
+
+#[test]
+fn hook_run_fails_open_without_config_dir() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+    run_git(repo.path(), &["add", "README.md"]);
+    let message_path = repo.path().join(".git").join("COMMIT_EDITMSG");
+    fs::write(&message_path, "\n# Please enter the commit message\n").expect("write message");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env_remove("HOME")
+        .env_remove("USERPROFILE")
+        .arg("hook")
+        .arg("run")
+        .arg(&message_path);
+
+    cmd.assert().success();
+
+    let message = fs::read_to_string(&message_path).expect("read message");
+    assert_eq!(message, "\n# Please enter the commit message\n");
+}
+
+#[test]
+fn hook_run_prepends_subject_to_template() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+    run_git(repo.path(), &["add", "README.md"]);
+    let message_path = repo.path().join(".git").join("COMMIT_EDITMSG");
+    fs::write(
+        &message_path,
+        "\nTicket: ABC-1\n\n# Please enter the commit message\n",
+    )
+    .expect("write message");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+        .arg("hook")
+        .arg("run")
+        .arg(&message_path)
+        .arg("template");
+    cmd.assert().success();
+
+    let message = fs::read_to_string(&message_path).expect("read message");
+    let (subject, rest) = message.split_once("\n\n").expect("subject and body");
+    assert!(subject.contains("README.md"), "{message}");
+    assert_eq!(rest, "Ticket: ABC-1\n\n# Please enter the commit message\n");
+}
+
+#[test]
+fn no_stage_with_unstaged_changes_exits_nothing_to_commit() {
+    let repo = init_repo();
+    fs::write(repo.path().join("notes.txt"), "todo\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path()).arg("--no-stage");
+
+    cmd.assert()
+        .code(2)
+        .stderr(contains("no staged changes"))
+        .stderr(contains("notes.txt"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--no-stage")
+        .arg("--require-staged");
+
+    cmd.assert()
+        .code(1)
+        .stderr(contains("error: no staged changes"));
+}
+
+#[test]
+fn push_later_queues_until_push_command() {
+    let repo = init_repo();
+    let remote = TempDir::new().expect("tempdir");
+    run_git(remote.path(), &["init", "--bare"]);
+    let remote_path = remote.path().to_str().expect("utf8 path");
+    run_git(repo.path(), &["remote", "add", "origin", remote_path]);
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--push")
+        .arg("--push-later")
+        .arg("--yes")
+        .arg("chore: init");
+    cmd.assert().success().stdout(contains("queued"));
+
+    let remote_log = run_git(remote.path(), &["log", "--all", "--pretty=%s"]);
+    assert!(!remote_log.contains("chore: init"), "pushed too early");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path()).arg("push");
+    cmd.assert()
+        .success()
+        .stdout(contains("pushing 1 queued commit(s)"));
+
+    let remote_log = run_git(remote.path(), &["log", "--all", "--pretty=%s"]);
+    assert_eq!(remote_log, "chore: init");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path()).arg("push");
+    cmd.assert().success().stdout(contains("no queued commits"));
+}
+
+#[test]
+fn push_honors_auto_setup_remote() {
+    let repo = init_repo();
+    let remote = TempDir::new().expect("tempdir");
+    run_git(remote.path(), &["init", "--bare"]);
+    let remote_path = remote.path().to_str().expect("utf8 path");
+    run_git(repo.path(), &["remote", "add", "origin", remote_path]);
+    run_git(repo.path(), &["config", "push.autoSetupRemote", "true"]);
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--push")
+        .arg("--yes")
+        .arg("chore: init");
+    cmd.assert().success();
+
+    let branch = run_git(repo.path(), &["branch", "--show-current"]);
+    let upstream = run_git(repo.path(), &["rev-parse", "--abbrev-ref", "@{u}"]);
+    assert_eq!(upstream, format!("origin/{branch}"));
+}
+
+#[test]
+fn rejected_push_suggests_rebase_command() {
+    let repo = init_repo();
+    let remote = TempDir::new().expect("tempdir");
+    run_git(remote.path(), &["init", "--bare"]);
+    let remote_path = remote.path().to_str().expect("utf8 path");
+    run_git(repo.path(), &["remote", "add", "origin", remote_path]);
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+    run_git(repo.path(), &["add", "."]);
+    run_git(repo.path(), &["commit", "-m", "chore: init"]);
+    let branch = run_git(repo.path(), &["branch", "--show-current"]);
+    run_git(repo.path(), &["push", "-u", "origin", &branch]);
+
+    let other = TempDir::new().expect("tempdir");
+    run_git(other.path(), &["clone", remote_path, "."]);
+    run_git(other.path(), &["config", "user.name", "Other User"]);
+    run_git(other.path(), &["config", "user.email", "other@example.com"]);
+    fs::write(other.path().join("other.txt"), "theirs\n").expect("write file");
+    run_git(other.path(), &["add", "."]);
+    run_git(other.path(), &["commit", "-m", "feat: remote change"]);
+    run_git(other.path(), &["push"]);
+
+    fs::write(repo.path().join("local.txt"), "ours\n").expect("write file");
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--push")
+        .arg("--yes")
+        .arg("feat: local change");
+    cmd.assert()
+        .success()
+        .stderr(contains("the remote has commits you do not have"))
+        .stderr(contains("git pull --rebase --autostash && git push"));
+
+    let subject = run_git(repo.path(), &["log", "-1", "--pretty=%s"]);
+    assert_eq!(subject, "feat: local change");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn push_needing_credentials_explains_how_to_set_them_up() {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(401).insert_header("WWW-Authenticate", "Basic realm=\"git\""),
+        )
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let remote_url = format!("{}/owner/repo.git", server.uri());
+    run_git(repo.path(), &["remote", "add", "origin", &remote_url]);
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    let assert = tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env_remove("GIT_ASKPASS")
+            .env_remove("SSH_ASKPASS")
+            .arg("--push")
+            .arg("--yes")
+            .arg("chore: init");
+        cmd.assert()
+    })
+    .await
+    .expect("join");
+
+    assert
+        .success()
+        .stderr(contains("terminal prompts disabled"))
+        .stderr(contains("credential.helper"));
+    let subject = run_git(repo.path(), &["log", "-1", "--pretty=%s"]);
+    assert_eq!(subject, "chore: init");
+}
+
+#[test]
+fn clean_tree_skips_provider_setup() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("GOODCOMMIT_PROVIDER", "openai")
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+        .arg("--no-stage");
+
+    cmd.assert()
+        .success()
+        .stdout(contains("working tree clean"))
+        .stderr(contains("provider setup failed").not());
+}
+
+#[test]
+fn edit_commits_the_edited_message() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("GIT_EDITOR", "printf '`docs: add readme`\\n' >")
+        .arg("--no-push")
+        .arg("--edit")
+        .arg("chore: init");
+    cmd.assert().success();
+
+    let subject = run_git(repo.path(), &["log", "-1", "--pretty=%s"]);
+    assert_eq!(subject, "docs: add readme");
+}
+
+#[test]
+fn edit_aborts_on_empty_message() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("GIT_EDITOR", ": >")
+        .arg("--no-push")
+        .arg("--edit")
+        .arg("chore: init");
+    cmd.assert()
+        .success()
+        .stdout(contains("commit aborted: empty message"));
+
+    let log = run_git(repo.path(), &["log", "--oneline"]);
+    assert!(!log.contains("chore: init"), "unexpected commit: {log}");
+}
+
+#[test]
+fn edit_message_seeds_the_editor_above_the_suggestion() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+    let seen = repo.path().join(".git").join("seen-message");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env(
+            "GIT_EDITOR",
+            format!(
+                "sh -c 'cp \"$1\" {} && printf \"fix: merged parser\\n\" > \"$1\"' editor",
+                seen.display()
+            ),
+        )
+        .arg("--no-push")
+        .arg("--edit-message")
+        .arg("fix: ")
+        .arg("add")
+        .arg("parser");
+    cmd.assert().success();
+
+    let shown = fs::read_to_string(&seen).expect("editor contents");
+    assert!(
+        shown.starts_with("fix: \n\n# Suggested message below"),
+        "{shown}"
+    );
+    assert!(shown.contains("\nadd parser\n"), "{shown}");
+    let subject = run_git(repo.path(), &["log", "-1", "--pretty=%s"]);
+    assert_eq!(subject, "fix: merged parser");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn generates_commit_from_mock_openai_server() {
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer test-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": "\"feat: add greeting\"" } }
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    let base_url = format!("{}/v1", server.uri());
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("OPENAI_API_KEY", "test-key")
+            .arg("--provider")
+            .arg("openai")
+            .arg("--model")
+            .arg("gpt-4o-mini")
+            .arg("--openai-mode")
+            .arg("chat")
+            .arg("--openai-base-url")
+            .arg(&base_url)
+            .arg("--no-push")
+            .arg("--yes");
+        cmd.assert()
+            .success()
+            .stdout(contains("commit message preview (openai/gpt-4o-mini):"));
+    })
+    .await
+    .expect("goodcommit run");
+
+    let subject = run_git(repo.path(), &["log", "-1", "--pretty=%s"]);
+    assert_eq!(subject, "feat: add greeting");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verbose_fallback_explains_the_decision_chain() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": "Sure! Here is a message." } }
+            ]
+        })))
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    let base_url = format!("{}/v1", server.uri());
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("OPENAI_API_KEY", "test-key")
+            .args(["--provider", "openai", "--model", "gpt-4o-mini"])
+            .args(["--openai-mode", "chat", "--openai-base-url", &base_url])
+            .args(["--verbose", "--dry-run"]);
+        cmd.assert()
+            .success()
+            .stderr(contains(
+                "fallback message used because the model's reply was rejected: no line was a conventional commit subject (first line: \"Sure! Here is a message.\")",
+            ))
+            .stdout(contains("why:"))
+            .stdout(contains("1. provider: openai/gpt-4o-mini"))
+            .stdout(contains("sent in one request"))
+            .stdout(contains("budget (timeout_secs)"));
+    })
+    .await
+    .expect("goodcommit run");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_timeout_names_the_phase_and_suggests_fixes() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_delay(std::time::Duration::from_secs(5))
+                .set_body_json(serde_json::json!({
+                    "choices": [
+                        { "message": { "role": "assistant", "content": "feat: too late" } }
+                    ]
+                })),
+        )
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    let base_url = format!("{}/v1", server.uri());
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("OPENAI_API_KEY", "test-key")
+            .env("GOODCOMMIT_TIMEOUT_SECS", "1")
+            .arg("--provider")
+            .arg("openai")
+            .arg("--openai-mode")
+            .arg("chat")
+            .arg("--openai-base-url")
+            .arg(&base_url)
+            .arg("--no-push")
+            .arg("--yes");
+        cmd.assert()
+            .success()
+            .stderr(contains("commit message request timed out after"))
+            .stderr(contains("(timeout_secs = 1)"))
+            .stdout(contains("fix: raise timeout_secs (now 1)"))
+            .stdout(contains("lower max_files"));
+    })
+    .await
+    .expect("goodcommit run");
+}
+
+#[test]
+fn changelog_since_last_tag_lists_commits_after_the_tag() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    for subject in [
+        "feat: first release",
+        "fix: handle empty input",
+        "docs: add usage",
+    ] {
+        run_git(repo.path(), &["commit", "--allow-empty", "-m", subject]);
+        if subject.starts_with("feat") {
+            run_git(repo.path(), &["tag", "v0.1.0"]);
+        }
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+        .args(["--provider", "openai", "changelog", "--since-last-tag"]);
+
+    cmd.assert()
+        .success()
+        .stdout(contains("## Changes since v0.1.0"))
+        .stdout(contains("### Fixes\n\n- handle empty input"))
+        .stdout(contains("### Other\n\n- docs: add usage"))
+        .stdout(contains("first release").not())
+        .stderr(contains("using commit subjects"));
+}
+
+#[test]
+fn tag_creates_an_annotated_tag_with_highlights_since_the_last_tag() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let remote = TempDir::new().expect("tempdir");
+    run_git(remote.path(), &["init", "--bare"]);
+    run_git(
+        repo.path(),
+        &[
+            "remote",
+            "add",
+            "origin",
+            &remote.path().display().to_string(),
+        ],
+    );
+    for subject in [
+        "feat: first release",
+        "feat: add search",
+        "fix: handle empty input",
+    ] {
+        run_git(repo.path(), &["commit", "--allow-empty", "-m", subject]);
+        if subject == "feat: first release" {
+            run_git(repo.path(), &["tag", "v0.1.0"]);
+        }
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+        .args([
+            "--provider",
+            "openai",
+            "--yes",
+            "tag",
+            "v0.2.0",
+            "--push-tag",
+        ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("tag message preview (2 commit(s)):"))
+        .stdout(contains("pushed tag v0.2.0"));
+
+    assert_eq!(
+        run_git(
+            repo.path(),
+            &["tag", "-l", "--format=%(contents)", "v0.2.0"]
+        ),
+        "v0.2.0\n\nFeatures:\n- add search\n\nFixes:\n- handle empty input"
+    );
+    assert_eq!(
+        run_git(remote.path(), &["tag", "-l"]),
+        "v0.2.0",
+        "only the new tag is pushed"
+    );
+}
+
+#[test]
+fn tag_dry_run_with_from_previews_without_tagging() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    for subject in ["feat: one", "fix: two", "docs: three"] {
+        run_git(repo.path(), &["commit", "--allow-empty", "-m", subject]);
+        if subject == "feat: one" {
+            run_git(repo.path(), &["tag", "v1.0.0"]);
+        }
+        if subject == "fix: two" {
+            run_git(repo.path(), &["tag", "v1.0.1"]);
+        }
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+        .args(["--provider", "openai", "--dry-run"])
+        .args(["tag", "v1.1.0", "--from", "v1.0.0"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("- two"))
+        .stdout(contains("- docs: three"))
+        .stdout(contains("dry run enabled; skipping tag"));
+
+    assert_eq!(run_git(repo.path(), &["tag", "-l", "v1.1.0"]), "");
+}
+
+#[test]
+fn tag_refuses_an_existing_tag_before_generating() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    run_git(repo.path(), &["commit", "--allow-empty", "-m", "feat: one"]);
+    run_git(repo.path(), &["tag", "v1.0.0"]);
+    run_git(repo.path(), &["commit", "--allow-empty", "-m", "fix: two"]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+        .args(["--provider", "openai", "--yes", "tag", "v1.0.0"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("tag v1.0.0 already exists"))
+        .stdout(contains("tag message preview").not());
+}
+
+fn history_for_revert() -> TempDir {
+    let repo = init_repo();
+    for (contents, subject) in [
+        ("one\n", "chore: add notes"),
+        ("two\n", "feat: rewrite notes"),
+    ] {
+        fs::write(repo.path().join("notes.txt"), contents).expect("write file");
+        run_git(repo.path(), &["add", "notes.txt"]);
+        run_git(repo.path(), &["commit", "-m", subject]);
+    }
+    repo
+}
+
+fn revert_cmd(repo: &TempDir, home: &TempDir) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+        .args(["--provider", "openai", "--no-push", "--yes"]);
+    cmd
+}
+
+#[test]
+fn revert_commits_a_conventional_revert_message() {
+    let repo = history_for_revert();
+    let home = TempDir::new().expect("tempdir");
+    let reverted = run_git(repo.path(), &["rev-parse", "HEAD"]);
+
+    revert_cmd(&repo, &home)
+        .args(["revert", "HEAD", "--context", "the rewrite lost data"])
+        .assert()
+        .success()
+        .stderr(contains("using the reason as written"));
+
+    assert_eq!(
+        run_git(repo.path(), &["log", "-1", "--format=%B"]),
+        format!(
+            "revert: feat: rewrite notes\n\nthe rewrite lost data\n\nThis reverts commit {reverted}."
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(repo.path().join("notes.txt")).expect("read"),
+        "one\n"
+    );
+    assert!(!repo.path().join(".git/REVERT_HEAD").exists());
+}
+
+#[test]
+fn revert_dry_run_leaves_the_tree_at_head() {
+    let repo = history_for_revert();
+    let home = TempDir::new().expect("tempdir");
+
+    revert_cmd(&repo, &home)
+        .args(["--dry-run", "revert", "HEAD"])
+        .assert()
+        .success()
+        .stdout(contains("revert: feat: rewrite notes"))
+        .stdout(contains("the working tree is back at HEAD"));
+
+    assert_eq!(run_git(repo.path(), &["status", "--porcelain"]), "");
+    assert_eq!(run_git(repo.path(), &["rev-list", "--count", "HEAD"]), "2");
+}
+
+#[test]
+fn revert_conflict_stops_with_git_instructions() {
+    let repo = history_for_revert();
+    let home = TempDir::new().expect("tempdir");
+
+    revert_cmd(&repo, &home)
+        .args(["revert", "HEAD~1"])
+        .assert()
+        .failure()
+        .stderr(contains("conflicts with later changes"))
+        .stderr(contains("git revert --continue"));
+
+    assert!(repo.path().join(".git/REVERT_HEAD").exists());
+    assert_eq!(run_git(repo.path(), &["rev-list", "--count", "HEAD"]), "2");
+}
+
+#[cfg(unix)]
+#[test]
+fn revert_rejected_by_a_hook_is_undone() {
+    let repo = history_for_revert();
+    let home = TempDir::new().expect("tempdir");
+    write_pre_commit_hook(repo.path(), "#!/bin/sh\necho 'lint failed'\nexit 1\n");
+
+    revert_cmd(&repo, &home)
+        .args(["revert", "HEAD"])
+        .assert()
+        .failure()
+        .stderr(contains("the working tree is back at HEAD"));
+
+    assert!(!repo.path().join(".git/REVERT_HEAD").exists());
+    assert_eq!(run_git(repo.path(), &["status", "--porcelain"]), "");
+    assert_eq!(run_git(repo.path(), &["rev-list", "--count", "HEAD"]), "2");
+}
+
+#[test]
+fn changelog_without_tags_starts_from_the_first_commit() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    run_git(
+        repo.path(),
+        &["commit", "--allow-empty", "-m", "feat: first"],
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("GOODCOMMIT_OPENAI_API_KEY")
+        .args(["--provider", "openai", "changelog", "--since-last-tag"]);
+
+    cmd.assert()
+        .success()
+        .stdout(contains("## All changes"))
+        .stdout(contains("- first ("))
+        .stderr(contains("no tags found"));
+}
+
+#[test]
+fn no_fallback_fails_instead_of_committing_the_generic_message() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("OPENAI_API_KEY", "sk-test")
+        .arg("--provider")
+        .arg("openai")
+        .arg("--openai-base-url")
+        .arg("http://127.0.0.1:9/v1")
+        .arg("--no-fallback")
+        .arg("--no-push")
+        .arg("--yes");
+    cmd.assert()
+        .failure()
+        .stderr(contains("allow_fallback = false: the provider call failed"));
+
+    assert_eq!(run_git(repo.path(), &["rev-list", "--all", "--count"]), "0");
+}
+
+#[test]
+fn report_records_fallback_run_without_secrets() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let reports = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("OPENAI_API_KEY", "sk-report-secret")
+        .arg("--provider")
+        .arg("openai")
+        .arg("--openai-base-url")
+        .arg("http://127.0.0.1:9/v1")
+        .arg("--report")
+        .arg(reports.path())
+        .arg("--no-push")
+        .arg("--yes");
+    cmd.assert().success().stdout(contains("report written to"));
+
+    let run_dir = fs::read_dir(reports.path())
+        .expect("reports dir")
+        .next()
+        .expect("run dir")
+        .expect("entry")
+        .path();
+    let report = fs::read_to_string(run_dir.join("report.json")).expect("report");
+    assert!(!report.contains("sk-report-secret"), "{report}");
+
+    let report: serde_json::Value = serde_json::from_str(&report).expect("json");
+    assert_eq!(report["used_fallback"], true);
+    assert_eq!(report["git"]["staged_files"][0], "hello.txt");
+    let prompt_file = report["exchanges"][0]["prompt_file"]
+        .as_str()
+        .expect("prompt file");
+    let prompt = fs::read_to_string(run_dir.join(prompt_file)).expect("prompt");
+    assert!(prompt.contains("hello.txt"), "{prompt}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stop_sequences_from_the_environment_reach_the_chat_payload() {
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(
+            serde_json::json!({ "stop": ["\n\n\n", "Explanation:"] }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": "feat: add greeting" } }
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    let base_url = format!("{}/v1", server.uri());
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("OPENAI_API_KEY", "test-key")
+            .env("GOODCOMMIT_STOP_SEQUENCES", "\\n\\n\\n,Explanation:")
+            .args(["--provider", "openai", "--model", "gpt-4o-mini"])
+            .args(["--openai-mode", "chat", "--openai-base-url", &base_url])
+            .arg("--dry-run");
+        cmd.assert()
+            .success()
+            .stdout(contains("feat: add greeting"));
+    })
+    .await
+    .expect("goodcommit run");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn seed_and_temperature_reach_the_provider_and_report() {
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(
+            serde_json::json!({ "seed": 42, "temperature": 0.0 }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": "feat: add greeting" } }
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let reports = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    let base_url = format!("{}/v1", server.uri());
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    let reports_path = reports.path().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("OPENAI_API_KEY", "test-key")
+            .arg("--provider")
+            .arg("openai")
+            .arg("--model")
+            .arg("gpt-4o-mini")
+            .arg("--openai-mode")
+            .arg("chat")
+            .arg("--openai-base-url")
+            .arg(&base_url)
+            .arg("--temperature")
+            .arg("0")
+            .arg("--seed")
+            .arg("42")
+            .arg("--verbose")
+            .arg("--report")
+            .arg(&reports_path)
+            .arg("--dry-run");
+        cmd.assert()
+            .success()
+            .stdout(contains("sampling: temperature 0, seed 42"));
+    })
+    .await
+    .expect("goodcommit run");
+
+    let run_dir = fs::read_dir(reports.path())
+        .expect("reports dir")
+        .next()
+        .expect("run dir")
+        .expect("entry")
+        .path();
+    let report = fs::read_to_string(run_dir.join("report.json")).expect("report");
+    let report: serde_json::Value = serde_json::from_str(&report).expect("json");
+    assert_eq!(report["config"]["seed"], 42);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pr_file_receives_pull_request_draft() {
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let reply = serde_json::json!({
+        "commit": "feat: add greeting",
+        "pr": { "title": "Add a greeting", "body": "Adds hello.txt.\n\n- greets the reader" }
+    });
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(
+            serde_json::json!({ "response_format": { "type": "json_schema" } }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": reply.to_string() } }
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    let base_url = format!("{}/v1", server.uri());
+    let pr_path = home.path().join("pr.md");
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    let pr_arg = pr_path.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("OPENAI_API_KEY", "test-key")
+            .arg("--provider")
+            .arg("openai")
+            .arg("--model")
+            .arg("gpt-4o-mini")
+            .arg("--openai-mode")
+            .arg("chat")
+            .arg("--openai-base-url")
+            .arg(&base_url)
+            .arg("--pr-file")
+            .arg(&pr_arg)
+            .arg("--no-push")
+            .arg("--yes");
+        cmd.assert()
+            .success()
+            .stdout(contains("pull request draft written to"));
+    })
+    .await
+    .expect("goodcommit run");
+
+    let subject = run_git(repo.path(), &["log", "-1", "--pretty=%s"]);
+    assert_eq!(subject, "feat: add greeting");
+    let pr = fs::read_to_string(&pr_path).expect("pr file");
+    assert_eq!(
+        pr,
+        "Add a greeting\n\nAdds hello.txt.\n\n- greets the reader\n"
+    );
+}
+
+fn write_pre_commit_hook(repo: &Path, script: &str) {
+    let hook = repo.join(".git").join("hooks").join("pre-commit");
+    fs::write(&hook, script).expect("write hook");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).expect("chmod hook");
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn precheck_failure_aborts_before_provider_call() {
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    write_pre_commit_hook(repo.path(), "#!/bin/sh\necho 'fmt check failed'\nexit 1\n");
+    let base_url = format!("{}/v1", server.uri());
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("OPENAI_API_KEY", "test-key")
+            .arg("--provider")
+            .arg("openai")
+            .arg("--openai-base-url")
+            .arg(&base_url)
+            .arg("--precheck")
+            .arg("--no-push")
+            .arg("--yes");
+        cmd.assert()
+            .failure()
+            .stderr(contains("fmt check failed"))
+            .stderr(contains("pre-commit hook failed"));
+    })
+    .await
+    .expect("goodcommit run");
+
+    let log = run_git(repo.path(), &["log", "--oneline"]);
+    assert!(!log.contains("hello"), "unexpected commit: {log}");
+}
+
+#[cfg(unix)]
+#[test]
+fn precheck_runs_pre_commit_hook_once() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    write_pre_commit_hook(repo.path(), "#!/bin/sh\necho run >> .git/precheck-runs\n");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env_remove("OPENAI_API_KEY")
+        .arg("--provider")
+        .arg("openai")
+        .arg("--precheck")
+        .arg("--no-push")
+        .arg("--yes");
+    cmd.assert().success();
+
+    let runs = fs::read_to_string(repo.path().join(".git").join("precheck-runs")).expect("runs");
+    assert_eq!(runs.lines().count(), 1);
+    let subject = run_git(repo.path(), &["log", "-1", "--pretty=%s"]);
+    assert!(subject.contains("hello.txt"), "{subject}");
+}
+
+#[test]
+fn config_edit_creates_repo_config_and_warns_on_invalid_edits() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("GIT_EDITOR", "printf 'one_line = true\\n' >>")
+        .arg("config")
+        .arg("edit");
+    cmd.assert().success().stdout(contains("created"));
+
+    let path = repo.path().join(".goodcommit.toml");
+    let content = fs::read_to_string(&path).expect("config");
+    assert!(content.contains("# provider = "));
+    assert!(content.ends_with("one_line = true\n"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("GIT_EDITOR", "printf 'one_line = maybe\\n' >>")
+        .arg("config")
+        .arg("edit");
+    cmd.assert()
+        .success()
+        .stderr(contains("your edits were kept"));
+    let content = fs::read_to_string(&path).expect("config");
+    assert!(content.ends_with("one_line = maybe\n"));
+}
+
+#[test]
+fn config_cache_is_rebuilt_when_config_or_env_changes() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let config_path = repo.path().join(".goodcommit.toml");
+    let show_config = |lang: &str| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(repo.path())
+            .env("HOME", home.path())
+            .env("GOODCOMMIT_LANG", lang)
+            .arg("config");
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).expect("utf8")
+    };
+
+    fs::write(&config_path, "model = \"first-model\"\n").expect("write config");
+    assert!(show_config("en").contains("first-model"));
+    let cache = repo.path().join(".git").join("goodcommit-cache");
+    assert!(cache.exists());
+    assert!(show_config("en").contains("first-model"));
+
+    fs::write(&config_path, "model = \"second-model\"\n").expect("rewrite config");
+    assert!(show_config("en").contains("second-model"));
+    assert!(show_config("fr").contains("lang = \"fr\""));
+
+    fs::remove_file(&cache).expect("delete cache");
+    assert!(show_config("fr").contains("second-model"));
+}
+
+#[test]
+fn learn_saves_style_profile() {
+    let repo = init_repo();
+    for (index, message) in ["feat(cli): add a", "fix(cli): fix b", "fix(core): fix c"]
+        .into_iter()
+        .enumerate()
+    {
+        let file = format!("file{index}.rs");
+        fs::write(repo.path().join(&file), "x\n").expect("write file");
+        run_git(repo.path(), &["add", &file]);
+        run_git(repo.path(), &["commit", "-m", message]);
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path()).arg("learn");
+    cmd.assert()
+        .success()
+        .stdout(contains("learned from 3 commits"))
+        .stdout(contains("scopes: cli."));
+
+    let style = fs::read_to_string(repo.path().join(".git/goodcommit/style.toml")).expect("style");
+    assert!(style.contains("language = \"rust\""), "{style}");
+}
+
+#[test]
+fn protected_branch_requires_allow_protected_when_not_interactive() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+    let branch = run_git(repo.path(), &["symbolic-ref", "--short", "HEAD"]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env(
+            "GOODCOMMIT_PROTECTED_BRANCHES",
+            format!("release/*,{branch}"),
+        )
+        .arg("--no-push")
+        .arg("--yes")
+        .arg("chore: init");
+    cmd.assert()
+        .failure()
+        .stderr(contains("protected branch"))
+        .stderr(contains("--allow-protected"));
+    assert!(
+        run_git(repo.path(), &["log", "-1", "--pretty=%s"]).contains("does not have any commits")
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("GOODCOMMIT_PROTECTED_BRANCHES", branch)
+        .arg("--no-push")
+        .arg("--yes")
+        .arg("--allow-protected")
+        .arg("chore: init");
+    cmd.assert().success();
+    assert_eq!(
+        run_git(repo.path(), &["log", "-1", "--pretty=%s"]),
+        "chore: init"
+    );
+}
+
+#[test]
+fn detached_head_commits_without_pushing() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+    run_git(repo.path(), &["add", "-A"]);
+    run_git(repo.path(), &["commit", "-m", "chore: init"]);
+    run_git(repo.path(), &["checkout", "--detach"]);
+    fs::write(repo.path().join("README.md"), "hello again\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--push")
+        .arg("--yes")
+        .arg("docs: update readme");
+    cmd.assert().success().stderr(contains("HEAD is detached"));
+    assert_eq!(
+        run_git(repo.path(), &["log", "-1", "--pretty=%s"]),
+        "docs: update readme"
+    );
+}
+
+#[test]
+fn print_diff_shows_collected_diff_without_ignored_files() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello from the diff\n").expect("write file");
+    fs::write(repo.path().join("Cargo.lock"), "# lock\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .arg("--provider")
+        .arg("ollama")
+        .arg("--ollama-endpoint")
+        .arg("http://127.0.0.1:9")
+        .arg("--print-diff")
+        .arg("--dry-run");
+    cmd.assert()
+        .success()
+        .stderr(contains("--- collected diff ---"))
+        .stderr(contains("hello from the diff"))
+        .stderr(contains("# lock").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_shows_staged_diff_below_scissors_and_drops_it() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = init_repo();
+    let tools = TempDir::new().expect("tempdir");
+    let seen = tools.path().join("seen.txt");
+    let editor = tools.path().join("editor.sh");
+    fs::write(
+        &editor,
+        format!(
+            "#!/bin/sh\ncp \"$1\" '{}'\nsed -i.bak '1s/.*/docs: add readme/' \"$1\"\n",
+            seen.display()
+        ),
+    )
+    .expect("write editor");
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).expect("chmod");
+    fs::write(repo.path().join("README.md"), "hello from readme\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("GIT_EDITOR", editor.to_str().expect("utf8 path"))
+        .arg("--no-push")
+        .arg("--edit")
+        .arg("chore: init");
+    cmd.assert().success();
+
+    let seen = fs::read_to_string(seen).expect("editor input");
+    assert!(seen.contains(">8"), "{seen}");
+    assert!(seen.contains("+hello from readme"), "{seen}");
+    let message = run_git(repo.path(), &["log", "-1", "--pretty=%B"]);
+    assert_eq!(message, "docs: add readme");
+}
+
+#[test]
+fn auto_stage_refuses_huge_changes_unless_stage_all() {
+    let repo = init_repo();
+    fs::create_dir_all(repo.path().join("vendor/pkg")).expect("mkdir");
+    for index in 0..4 {
+        fs::write(repo.path().join(format!("vendor/pkg/{index}.js")), "x\n").expect("write");
+    }
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("GOODCOMMIT_AUTO_STAGE_MAX_FILES", "3")
+        .arg("--no-push")
+        .arg("--yes")
+        .arg("chore: init");
+    cmd.assert()
+        .failure()
+        .stderr(contains("staging would add 5 files"))
+        .stderr(contains("vendor/ (4), README.md (1)"));
+    assert!(run_git(repo.path(), &["diff", "--cached", "--name-only"]).is_empty());
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("GOODCOMMIT_AUTO_STAGE_MAX_FILES", "3")
+        .arg("--no-push")
+        .arg("--yes")
+        .arg("--stage-all")
+        .arg("chore: init");
+    cmd.assert().success();
+    assert_eq!(
+        run_git(repo.path(), &["log", "-1", "--pretty=%s"]),
+        "chore: init"
+    );
+}
+
+#[test]
+fn nothing_staged_separates_unstaged_and_untracked_files() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+    run_git(repo.path(), &["add", "-A"]);
+    run_git(repo.path(), &["commit", "-m", "chore: init"]);
+    fs::write(repo.path().join("README.md"), "hello again\n").expect("write file");
+    fs::write(repo.path().join("a.txt"), "a\n").expect("write file");
+    fs::write(repo.path().join("b.txt"), "b\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path()).arg("--no-stage");
+    cmd.assert().code(2).stderr(contains(
+        "(1 unstaged file: README.md; 2 untracked files not staged because stage mode is none: a.txt, b.txt)",
+    ));
+}
+
+#[test]
+fn stage_all_skips_paths_outside_a_sparse_checkout() {
+    let repo = init_repo();
+    fs::create_dir_all(repo.path().join("a/x")).expect("create dir");
+    fs::create_dir_all(repo.path().join("b")).expect("create dir");
+    fs::write(repo.path().join("a/x/n.txt"), "n\n").expect("write file");
+    fs::write(repo.path().join("b/keep.txt"), "keep\n").expect("write file");
+    run_git(repo.path(), &["add", "-A"]);
+    run_git(repo.path(), &["commit", "-m", "chore: init"]);
+    run_git(repo.path(), &["sparse-checkout", "set", "a"]);
+    fs::write(repo.path().join("a/x/n.txt"), "changed\n").expect("write file");
+    fs::create_dir_all(repo.path().join("b")).expect("create dir");
+    fs::write(repo.path().join("b/new.txt"), "new\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--no-push")
+        .arg("--yes")
+        .arg("--stage-all")
+        .arg("chore: update n");
+    cmd.assert().success();
+
+    let committed = run_git(repo.path(), &["show", "--name-only", "--pretty=", "HEAD"]);
+    assert_eq!(committed, "a/x/n.txt");
+}
+
+#[test]
+fn output_writes_the_message_and_refuses_to_overwrite_without_force() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+    let output = repo.path().join("out/msg.txt");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--dry-run")
+        .arg("--output")
+        .arg(&output)
+        .arg("chore: init");
+    cmd.assert()
+        .success()
+        .stdout(contains("commit message written to"));
+    assert_eq!(
+        fs::read_to_string(&output).expect("read output"),
+        "chore: init\n"
+    );
+    assert!(run_git(repo.path(), &["log", "-1"]).contains("does not have any commits"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--dry-run")
+        .arg("--output")
+        .arg(&output)
+        .arg("chore: second");
+    cmd.assert()
+        .code(1)
+        .stderr(contains("already exists; pass --force to overwrite it"));
+    assert_eq!(
+        fs::read_to_string(&output).expect("read output"),
+        "chore: init\n"
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--no-push")
+        .arg("--yes")
+        .arg("--output")
+        .arg(&output)
+        .arg("--force")
+        .arg("chore: second");
+    cmd.assert().success();
+    assert_eq!(
+        fs::read_to_string(&output).expect("read output"),
+        "chore: second\n"
+    );
+    assert_eq!(
+        run_git(repo.path(), &["log", "-1", "--pretty=%s"]),
+        "chore: second"
+    );
+}
+
+#[test]
+fn dry_run_output_dash_prints_only_the_message() {
+    let repo = init_repo();
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--dry-run")
+        .arg("--output")
+        .arg("-")
+        .arg("chore: init");
+    cmd.assert()
+        .success()
+        .stdout("chore: init\n")
+        .stderr(contains("dry run enabled"));
+    assert!(!repo.path().join("-").exists());
+    assert!(run_git(repo.path(), &["log", "-1"]).contains("does not have any commits"));
+}
+
+#[test]
+fn legacy_commit_encoding_warns_about_non_ascii_messages() {
+    let repo = init_repo();
+    run_git(
+        repo.path(),
+        &["config", "i18n.commitEncoding", "ISO-8859-1"],
+    );
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--no-push")
+        .arg("--yes")
+        .arg("chore: init");
+    cmd.assert()
+        .success()
+        .stderr(contains("i18n.commitEncoding").not());
+
+    fs::write(repo.path().join("README.md"), "hello again\n").expect("write file");
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .arg("--no-push")
+        .arg("--yes")
+        .arg("docs: mention the café");
+    cmd.assert().success().stderr(contains(
+        "i18n.commitEncoding is ISO-8859-1, but the message is UTF-8",
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn explain_prints_the_reason_without_committing_it() {
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_string_contains("Commit message:"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": "hello.txt is a new file, hence feat" } }
+            ]
+        })))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [
+                { "message": { "role": "assistant", "content": "feat: add greeting" } }
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+    let base_url = format!("{}/v1", server.uri());
+
+    let repo_path = repo.path().to_path_buf();
+    let home_path = home.path().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.current_dir(&repo_path)
+            .env("HOME", &home_path)
+            .env("OPENAI_API_KEY", "test-key")
+            .arg("--provider")
+            .arg("openai")
+            .arg("--model")
+            .arg("gpt-4o-mini")
+            .arg("--openai-mode")
+            .arg("chat")
+            .arg("--openai-base-url")
+            .arg(&base_url)
+            .arg("--explain")
+            .arg("--no-push")
+            .arg("--yes");
+        cmd.assert()
+            .success()
+            .stdout(contains("why this message (not committed):"))
+            .stdout(contains("hello.txt is a new file, hence feat"));
+    })
+    .await
+    .expect("goodcommit run");
+
+    let message = run_git(repo.path(), &["log", "-1", "--pretty=%B"]);
+    assert_eq!(message, "feat: add greeting");
+}
+
+#[test]
+fn dry_run_records_state_for_status() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("hello.txt"), "hello\n").expect("write file");
+
+    let mut status = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    status.current_dir(repo.path()).arg("status");
+    status
+        .assert()
+        .success()
+        .stdout(contains("no goodcommit runs recorded"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .env("OPENAI_API_KEY", "test-key")
+        .arg("--provider")
+        .arg("openai")
+        .arg("--openai-base-url")
+        .arg("http://127.0.0.1:9/v1")
+        .arg("--dry-run");
+    cmd.assert().success();
+
+    let state = fs::read_to_string(repo.path().join(".git/goodcommit/state.json")).expect("state");
+    let state: serde_json::Value = serde_json::from_str(&state).expect("json");
+    assert_eq!(state["mode"], "dry-run");
+    assert_eq!(state["outcome"], "previewed");
+    assert_eq!(state["used_fallback"], true);
+    assert_eq!(state["message"], "chore: update hello.txt");
+
+    let mut status = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    status.current_dir(repo.path()).arg("status");
+    status
+        .assert()
+        .success()
+        .stdout(contains("last run: dry-run (previewed)"))
+        .stdout(contains("fallback message used because"))
+        .stdout(contains("chore: update hello.txt"));
+}
+
+fn fallback_commit(repo: &Path, home: &Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo)
+        .env("HOME", home)
+        .env("OPENAI_API_KEY", "sk-test")
+        .arg("--provider")
+        .arg("openai")
+        .arg("--openai-base-url")
+        .arg("http://127.0.0.1:9/v1")
+        .arg("--no-push")
+        .arg("--yes");
+    cmd
+}
+
+#[test]
+fn fallback_commits_push_only_as_push_on_fallback_allows() {
+    for (mode, pushes) in [("ask", false), ("never", false), ("always", true)] {
+        let repo = init_repo();
+        let home = TempDir::new().expect("tempdir");
+        let remote = TempDir::new().expect("tempdir");
+        run_git(remote.path(), &["init", "--bare"]);
+        let remote_path = remote.path().to_str().expect("utf8 path");
+        run_git(repo.path(), &["remote", "add", "origin", remote_path]);
+        run_git(repo.path(), &["config", "push.autoSetupRemote", "true"]);
+        fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+        Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"))
+            .current_dir(repo.path())
+            .env("HOME", home.path())
+            .env("OPENAI_API_KEY", "sk-test")
+            .env("GOODCOMMIT_PUSH_ON_FALLBACK", mode)
+            .args([
+                "--provider",
+                "openai",
+                "--openai-base-url",
+                "http://127.0.0.1:9/v1",
+            ])
+            .args(["--push", "--yes"])
+            .assert()
+            .success()
+            .stderr(contains("a commit with the fallback message"));
+
+        let remote_head = run_git(remote.path(), &["rev-parse", "--verify", "-q", "HEAD"]);
+        let local_head = run_git(repo.path(), &["rev-parse", "HEAD"]);
+        assert_eq!(
+            remote_head == local_head,
+            pushes,
+            "push_on_fallback = {mode}"
+        );
+
+        let state =
+            fs::read_to_string(repo.path().join(".git/goodcommit/state.json")).expect("read state");
+        let state: serde_json::Value = serde_json::from_str(&state).expect("parse state");
+        let notice = if pushes {
+            "pushed a commit"
+        } else {
+            "skipped push"
+        };
+        assert!(
+            state["warnings"]
+                .as_array()
+                .expect("warnings")
+                .iter()
+                .any(|warning| warning.as_str().is_some_and(|w| w.starts_with(notice))),
+            "push_on_fallback = {mode}"
+        );
+    }
+}
+
+#[test]
+fn stats_perf_reports_recorded_phase_timings() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("README.md"), "hello\n").expect("write file");
+
+    fallback_commit(repo.path(), home.path())
+        .env("GOODCOMMIT_RECORD_STATS", "1")
+        .assert()
+        .success();
+
+    let stats =
+        fs::read_to_string(home.path().join(".config/goodcommit/stats.jsonl")).expect("read stats");
+    let record: serde_json::Value =
+        serde_json::from_str(stats.lines().next().expect("one record")).expect("parse record");
+    assert!(record["phases_ms"]["staging"].is_u64());
+    assert!(record["phases_ms"]["total"].is_u64());
+    assert_eq!(
+        record["calls_ms"]["final"].as_array().map(Vec::len),
+        Some(1)
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+    cmd.current_dir(repo.path())
+        .env("HOME", home.path())
+        .args(["stats", "--perf"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("openai/"))
+        .stdout(contains("final call"))
+        .stdout(contains("staging"))
+        .stdout(contains("p95"));
+}
+
+#[test]
+fn conflict_markers_stop_the_commit_unless_allowed() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(
+        repo.path().join("merge.txt"),
+        "start\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\n",
+    )
+    .expect("write file");
+
+    fallback_commit(repo.path(), home.path())
+        .assert()
+        .failure()
+        .stderr(contains("conflict markers in merge.txt (line 2, 4, 6)"))
+        .stderr(contains("--allow-conflicts"));
+    assert_eq!(run_git(repo.path(), &["rev-list", "--all", "--count"]), "0");
+
+    fallback_commit(repo.path(), home.path())
+        .env("GOODCOMMIT_CONFLICT_MARKER_IGNORE", "*.txt")
+        .assert()
+        .success();
+    assert_eq!(run_git(repo.path(), &["rev-list", "--all", "--count"]), "1");
+
+    fs::write(
+        repo.path().join("merge.txt"),
+        "<<<<<<< HEAD\nours again\n>>>>>>> feature\n",
+    )
+    .expect("rewrite file");
+    fallback_commit(repo.path(), home.path())
+        .arg("--allow-conflicts")
+        .assert()
+        .success();
+    assert_eq!(run_git(repo.path(), &["rev-list", "--all", "--count"]), "2");
+}
+
+#[test]
+fn conflict_markers_stop_a_message_given_on_the_command_line() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let mut big = "line\n".repeat(2000);
+    big.push_str("<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\n");
+    fs::write(repo.path().join("big.txt"), big).expect("write file");
+
+    fallback_commit(repo.path(), home.path())
+        .arg("fix: typed message")
+        .assert()
+        .failure()
+        .stderr(contains(
+            "conflict markers in big.txt (line 2001, 2003, 2005)",
+        ));
+    assert_eq!(run_git(repo.path(), &["rev-list", "--all", "--count"]), "0");
+}
+
+#[test]
+fn body_flag_is_kept_under_the_subject() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("notes.txt"), "hello\n").expect("write file");
+
+    fallback_commit(repo.path(), home.path())
+        .arg("--body")
+        .arg("Written by hand.")
+        .assert()
+        .success();
+    let message = run_git(repo.path(), &["log", "-1", "--format=%B"]);
+    let (subject, body) = message.split_once("\n\n").expect("subject and body");
+    assert!(subject.starts_with("chore: "), "{message}");
+    assert_eq!(body.trim(), "Written by hand.");
+
+    fallback_commit(repo.path(), home.path())
+        .args(["--body", "x", "fix: typed message"])
+        .assert()
+        .code(2)
+        .stderr(contains("cannot be used with"));
+}
+
+#[test]
+fn legacy_git_ai_commit_config_is_still_read() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    let legacy = home.path().join(".config").join("git-ai-commit");
+    fs::create_dir_all(&legacy).expect("legacy dir");
+    fs::write(
+        legacy.join("config.toml"),
+        "fallback_template = \"chore: legacy {count}\"\n",
+    )
+    .expect("write legacy config");
+    fs::write(repo.path().join("notes.txt"), "hello\n").expect("write file");
+
+    fallback_commit(repo.path(), home.path())
+        .assert()
+        .success()
+        .stderr(contains("using the old git-ai-commit config"))
+        .stderr(contains("goodcommit setup"));
+    assert_eq!(
+        run_git(repo.path(), &["log", "-1", "--format=%s"]),
+        "chore: legacy 1"
+    );
+}
+
+#[test]
+fn amend_reword_changes_only_the_head_message() {
+    let repo = init_repo();
+    let home = TempDir::new().expect("tempdir");
+    fs::write(repo.path().join("notes.txt"), "hello\n").expect("write file");
+    run_git(repo.path(), &["add", "notes.txt"]);
+    run_git(repo.path(), &["commit", "-m", "wip"]);
+    let tree = run_git(repo.path(), &["rev-parse", "HEAD^{tree}"]);
+    fs::write(repo.path().join("later.txt"), "staged\n").expect("write file");
+    run_git(repo.path(), &["add", "later.txt"]);
+
+    fallback_commit(repo.path(), home.path())
+        .arg("--amend-reword")
+        .assert()
+        .success()
+        .stdout(contains("reword of"));
+
+    assert_eq!(
+        run_git(repo.path(), &["log", "-1", "--format=%s"]),
+        "chore: update notes.txt"
+    );
+    assert_eq!(run_git(repo.path(), &["rev-parse", "HEAD^{tree}"]), tree);
+    assert_eq!(run_git(repo.path(), &["rev-list", "--all", "--count"]), "1");
+    assert_eq!(
+        run_git(repo.path(), &["diff", "--staged", "--name-only"]),
+        "later.txt"
+    );
+}
+
+#[cfg(feature = "self-update")]
+#[tokio::test(flavor = "multi_thread")]
+async fn self_update_check_only_reports_and_honors_the_opt_out() {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "tag_name": "v99.0.0",
+            "body": "",
+            "assets": []
+        })))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/releases/latest", server.uri());
+    tokio::task::spawn_blocking(move || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.env("GOODCOMMIT_SELF_UPDATE_URL", &url)
+            .env_remove("GOODCOMMIT_NO_SELF_UPDATE")
+            .args(["self-update", "--check"]);
+        cmd.assert()
+            .success()
+            .stdout(contains("goodcommit 99.0.0 is available"));
+
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("goodcommit"));
+        cmd.env("GOODCOMMIT_SELF_UPDATE_URL", &url)
+            .env("GOODCOMMIT_NO_SELF_UPDATE", "1")
+            .args(["self-update", "--check"]);
+        cmd.assert()
+            .failure()
+            .stderr(contains("disabled by GOODCOMMIT_NO_SELF_UPDATE"));
+    })
+    .await
+    .expect("goodcommit run");
+}
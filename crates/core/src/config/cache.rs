@@ -0,0 +1,181 @@
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreResult;
+
+use super::io::ConfigPaths;
+use super::values::Config;
+
+const CACHE_FILE: &str = "goodcommit-cache";
+
+/// Where the resolved config cache lives, inside the repo's git directory.
+#[must_use]
+pub fn config_cache_path(git_dir: &Path) -> PathBuf {
+    git_dir.join(CACHE_FILE)
+}
+
+/// What a cached config was built from: the config files' sizes and mtimes, the
+/// `GOODCOMMIT_*` environment, and the goodcommit version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigCacheKey {
+    version: String,
+    env: String,
+    files: Vec<FileStamp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    path: PathBuf,
+    len: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: ConfigCacheKey,
+    config: Config,
+}
+
+impl ConfigCacheKey {
+    /// Key for the config files in `paths` and the current environment; `None` when a
+    /// file's metadata cannot be read, so the config is loaded without the cache.
+    #[must_use]
+    pub fn current(paths: &ConfigPaths) -> Option<Self> {
+        let files = [&paths.global_config, &paths.repo_config]
+            .into_iter()
+            .flatten()
+            .map(|path| FileStamp::read(path))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            env: env_fingerprint(env::vars()),
+            files,
+        })
+    }
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            len: metadata.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+        })
+    }
+}
+
+/// Hash of the `GOODCOMMIT_*` variables, so a changed value invalidates the cache
+/// without writing the values themselves to disk.
+fn env_fingerprint(vars: impl Iterator<Item = (String, String)>) -> String {
+    let mut vars = vars
+        .filter(|(name, _)| name.starts_with("GOODCOMMIT_"))
+        .collect::<Vec<_>>();
+    vars.sort();
+    let mut hasher = DefaultHasher::new();
+    vars.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// The cached config when it was built from exactly `key`; `None` when there is no
+/// cache, it is stale, or it cannot be parsed.
+#[must_use]
+pub fn load_cached_config(path: &Path, key: &ConfigCacheKey) -> Option<Config> {
+    let content = fs::read_to_string(path).ok()?;
+    let entry = toml::from_str::<CacheEntry>(&content).ok()?;
+    (entry.key == *key).then_some(entry.config)
+}
+
+/// Cache `config` under `key`. A config holding `openai_api_key` is not cached, so the
+/// key is never copied into the git directory.
+///
+/// # Errors
+/// Returns an error when the cache cannot be serialized or written.
+pub fn save_cached_config(path: &Path, key: &ConfigCacheKey, config: &Config) -> CoreResult<()> {
+    if config.openai_api_key.is_some() {
+        let _ = fs::remove_file(path);
+        return Ok(());
+    }
+    let entry = CacheEntry {
+        key: key.clone(),
+        config: config.clone(),
+    };
+    fs::write(path, toml::to_string(&entry)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn env_fingerprint_only_tracks_goodcommit_variables() {
+        let base = env_fingerprint(vars(&[("GOODCOMMIT_MODEL", "a"), ("PATH", "/bin")]));
+        assert_eq!(
+            base,
+            env_fingerprint(vars(&[("PATH", "/usr/bin"), ("GOODCOMMIT_MODEL", "a")]))
+        );
+        assert_ne!(base, env_fingerprint(vars(&[("GOODCOMMIT_MODEL", "b")])));
+        assert_ne!(base, env_fingerprint(vars(&[])));
+    }
+
+    #[test]
+    fn cache_round_trips_until_a_config_file_changes() {
+        let dir = std::env::temp_dir().join(format!("goodcommit-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create dir");
+        let repo_config = dir.join(".goodcommit.toml");
+        fs::write(&repo_config, "push = false\n").expect("write config");
+        let paths = ConfigPaths {
+            global_config: None,
+            repo_config: Some(repo_config.clone()),
+            global_ignore: dir.join("ignore"),
+            repo_ignore: None,
+            legacy_config: None,
+        };
+        let cache = config_cache_path(&dir);
+        let config = Config {
+            push: Some(false),
+            ..Config::defaults()
+        };
+
+        let key = ConfigCacheKey::current(&paths).expect("key");
+        assert!(load_cached_config(&cache, &key).is_none());
+        save_cached_config(&cache, &key, &config).expect("save");
+        let cached = load_cached_config(&cache, &key).expect("cached");
+        assert_eq!(cached.push, Some(false));
+        assert_eq!(cached.model, config.model);
+
+        fs::write(&repo_config, "push = true\n").expect("rewrite config");
+        let changed = ConfigCacheKey::current(&paths).expect("key");
+        assert_ne!(changed, key);
+        assert!(load_cached_config(&cache, &changed).is_none());
+
+        fs::write(&cache, "not a cache").expect("corrupt cache");
+        assert!(load_cached_config(&cache, &changed).is_none());
+
+        let with_key = Config {
+            openai_api_key: Some("sk-secret".to_string()),
+            ..config
+        };
+        save_cached_config(&cache, &changed, &with_key).expect("save");
+        assert!(!cache.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,155 +1,344 @@
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use super::types::StageMode;
 use super::values::Config;
+use tracing::warn;
 
+/// Config from `GOODCOMMIT_*` variables, plus a warning for each value that could not be
+/// parsed and is ignored.
 #[must_use]
-pub fn config_from_env() -> Config {
-    let mut config = Config::default();
+pub fn config_from_env() -> (Config, Vec<String>) {
+    config_from_vars(|key| env::var(key).ok())
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_PROVIDER") {
-        if let Ok(provider) = value.parse() {
-            config.provider = Some(provider);
-        }
-    }
+fn config_from_vars(lookup: impl Fn(&str) -> Option<String>) -> (Config, Vec<String>) {
+    let mut env = EnvReader {
+        lookup,
+        warnings: Vec::new(),
+    };
+    let config = Config {
+        provider: env.parse("GOODCOMMIT_PROVIDER"),
+        model: env.string("GOODCOMMIT_MODEL"),
+        openai_mode: env.parse("GOODCOMMIT_OPENAI_MODE"),
+        openai_base_url: env.string("GOODCOMMIT_OPENAI_BASE_URL"),
+        ollama_endpoint: env.string("GOODCOMMIT_OLLAMA_ENDPOINT"),
+        conventional: env.bool("GOODCOMMIT_CONVENTIONAL"),
+        one_line: env.bool("GOODCOMMIT_ONE_LINE"),
+        emoji: env.bool("GOODCOMMIT_EMOJI"),
+        lang: env.string("GOODCOMMIT_LANG"),
+        push: env.bool("GOODCOMMIT_PUSH"),
+        timeout_secs: env.secs("GOODCOMMIT_TIMEOUT_SECS"),
+        max_input_tokens: env.whole("GOODCOMMIT_MAX_INPUT_TOKENS"),
+        max_output_tokens: env.whole("GOODCOMMIT_MAX_OUTPUT_TOKENS"),
+        max_file_bytes: env.whole("GOODCOMMIT_MAX_FILE_BYTES"),
+        max_file_lines: env.whole("GOODCOMMIT_MAX_FILE_LINES"),
+        summary_concurrency: env.whole("GOODCOMMIT_SUMMARY_CONCURRENCY"),
+        max_files: env.whole("GOODCOMMIT_MAX_FILES"),
+        stage_mode: env.parse("GOODCOMMIT_STAGE"),
+        confirm: env.bool("GOODCOMMIT_CONFIRM"),
+        temperature: env.decimal("GOODCOMMIT_TEMPERATURE"),
+        max_subject_length: env.whole("GOODCOMMIT_MAX_SUBJECT_LENGTH"),
+        body_wrap: env.whole("GOODCOMMIT_BODY_WRAP"),
+        conventional_types: env.list("GOODCOMMIT_CONVENTIONAL_TYPES"),
+        summary_file_list: env.bool("GOODCOMMIT_SUMMARY_FILE_LIST"),
+        notes: env.bool("GOODCOMMIT_NOTES"),
+        retry_on_invalid: env.bool("GOODCOMMIT_RETRY_ON_INVALID"),
+        one_line_keep_body_in_editor: env.bool("GOODCOMMIT_ONE_LINE_KEEP_BODY_IN_EDITOR"),
+        record_stats: env.bool("GOODCOMMIT_RECORD_STATS"),
+        warmup: env.parse("GOODCOMMIT_WARMUP"),
+        subject_prefix: env.string("GOODCOMMIT_SUBJECT_PREFIX"),
+        subject_suffix: env.string("GOODCOMMIT_SUBJECT_SUFFIX"),
+        subject_prefix_placement: env.parse("GOODCOMMIT_SUBJECT_PREFIX_PLACEMENT"),
+        summary_max_file_tokens: env.whole("GOODCOMMIT_SUMMARY_MAX_FILE_TOKENS"),
+        report_on_failure: env.bool("GOODCOMMIT_REPORT_ON_FAILURE"),
+        skip_lockfile_only: env.bool("GOODCOMMIT_SKIP_LOCKFILE_ONLY"),
+        infer_type: env.parse("GOODCOMMIT_INFER_TYPE"),
+        precheck: env.bool("GOODCOMMIT_PRECHECK"),
+        style_max_age_days: env.whole("GOODCOMMIT_STYLE_MAX_AGE_DAYS"),
+        protected_branches: env.list("GOODCOMMIT_PROTECTED_BRANCHES"),
+        confirm_cost: env.decimal("GOODCOMMIT_CONFIRM_COST"),
+        subject_case: env.parse("GOODCOMMIT_SUBJECT_CASE"),
+        context_files: env.list("GOODCOMMIT_CONTEXT_FILES"),
+        auto_stage_max_files: env.whole("GOODCOMMIT_AUTO_STAGE_MAX_FILES"),
+        summary_style: env.parse("GOODCOMMIT_SUMMARY_STYLE"),
+        seed: env.whole("GOODCOMMIT_SEED"),
+        allow_large: env.list("GOODCOMMIT_ALLOW_LARGE"),
+        pr_draft: env.bool("GOODCOMMIT_PR_DRAFT"),
+        ollama_format: env.parse("GOODCOMMIT_OLLAMA_FORMAT"),
+        confirm_timeout_secs: env.secs("GOODCOMMIT_CONFIRM_TIMEOUT_SECS"),
+        confirm_timeout_action: env.parse("GOODCOMMIT_CONFIRM_TIMEOUT_ACTION"),
+        infer_scope: env.bool("GOODCOMMIT_INFER_SCOPE"),
+        allow_fallback: env.bool("GOODCOMMIT_ALLOW_FALLBACK"),
+        openai_api_key_file: env
+            .string("GOODCOMMIT_OPENAI_API_KEY_FILE")
+            .map(PathBuf::from),
+        stop_sequences: env.list("GOODCOMMIT_STOP_SEQUENCES").map(|stops| {
+            // `\n` in the variable stands for a newline, e.g. `\n\n\n`.
+            stops.iter().map(|stop| stop.replace("\\n", "\n")).collect()
+        }),
+        fallback_template: env.string("GOODCOMMIT_FALLBACK_TEMPLATE"),
+        style_detection: env.bool("GOODCOMMIT_STYLE_DETECTION"),
+        body_file_summary: env.bool("GOODCOMMIT_BODY_FILE_SUMMARY"),
+        conflict_marker_ignore: env.list("GOODCOMMIT_CONFLICT_MARKER_IGNORE"),
+        deprioritize: env.list("GOODCOMMIT_DEPRIORITIZE"),
+        summary_max_hunk_groups: env.whole("GOODCOMMIT_SUMMARY_MAX_HUNK_GROUPS"),
+        deletion_weight: env.whole("GOODCOMMIT_DELETION_WEIGHT"),
+        push_on_fallback: env.parse("GOODCOMMIT_PUSH_ON_FALLBACK"),
+        record_timings: env.bool("GOODCOMMIT_RECORD_TIMINGS"),
+        ..Config::default()
+    };
+    (config, env.warnings)
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_MODEL") {
-        config.model = Some(value);
+/// Parse a boolean flag from a string.
+///
+/// # Errors
+/// Returns an error when the value is not a recognized boolean.
+pub fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("invalid bool: {value}")),
     }
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_OPENAI_MODE") {
-        if let Ok(mode) = value.parse() {
-            config.openai_mode = Some(mode);
-        }
-    }
+/// Split a comma-separated list, dropping empty entries.
+#[must_use]
+pub fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_OPENAI_BASE_URL") {
-        config.openai_base_url = Some(value);
-    }
+#[must_use]
+pub fn openai_api_key_env() -> Option<String> {
+    env_any(&["GOODCOMMIT_OPENAI_API_KEY", "OPENAI_API_KEY"])
+}
 
-    if let Some(value) = openai_api_key_env() {
-        config.openai_api_key = Some(value);
-    }
+/// The `OpenAI` API key: an explicit `openai_api_key`, else the contents of
+/// `openai_api_key_file` (trailing newlines trimmed), else [`openai_api_key_env`].
+///
+/// The key file is only read without an explicit key; if it cannot be read, that is logged
+/// as a warning and no key is returned.
+#[must_use]
+pub fn resolve_openai_api_key(explicit: Option<String>, key_file: Option<&Path>) -> Option<String> {
+    pick_openai_api_key(explicit, openai_api_key_env, key_file)
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_OLLAMA_ENDPOINT") {
-        config.ollama_endpoint = Some(value);
+pub(super) fn pick_openai_api_key(
+    explicit: Option<String>,
+    env_key: impl FnOnce() -> Option<String>,
+    key_file: Option<&Path>,
+) -> Option<String> {
+    if let Some(key) = explicit.filter(|key| !key.is_empty()) {
+        return Some(key);
     }
-
-    if let Ok(value) = env::var("GOODCOMMIT_CONVENTIONAL") {
-        if let Ok(flag) = parse_bool(&value) {
-            config.conventional = Some(flag);
+    let Some(path) = key_file else {
+        return env_key();
+    };
+    match fs::read_to_string(path) {
+        Ok(contents) => {
+            let key = contents.trim_end_matches(['\r', '\n']);
+            (!key.is_empty()).then(|| key.to_string())
+        }
+        Err(err) => {
+            warn!(
+                "failed reading openai_api_key_file {}: {err}",
+                path.display()
+            );
+            None
         }
     }
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_ONE_LINE") {
-        if let Ok(flag) = parse_bool(&value) {
-            config.one_line = Some(flag);
-        }
+/// Parse seconds given as `30`, `30s`, or `2m`.
+fn parse_secs(value: &str) -> Option<u64> {
+    if let Some(minutes) = value.strip_suffix('m') {
+        return minutes.parse::<u64>().ok()?.checked_mul(60);
     }
+    value.strip_suffix('s').unwrap_or(value).parse().ok()
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_EMOJI") {
-        if let Ok(flag) = parse_bool(&value) {
-            config.emoji = Some(flag);
-        }
+/// Reads variables through `lookup`, recording a warning for each value it has to ignore.
+struct EnvReader<F> {
+    lookup: F,
+    warnings: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> EnvReader<F> {
+    fn string(&self, key: &str) -> Option<String> {
+        (self.lookup)(key)
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_LANG") {
-        config.lang = Some(value);
+    fn list(&self, key: &str) -> Option<Vec<String>> {
+        self.string(key).map(|value| parse_list(&value))
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_PUSH") {
-        if let Ok(flag) = parse_bool(&value) {
-            config.push = Some(flag);
-        }
+    fn bool(&mut self, key: &str) -> Option<bool> {
+        self.checked(
+            key,
+            |value| parse_bool(value).ok(),
+            "expected true/false, yes/no, on/off, or 1/0",
+        )
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_TIMEOUT_SECS") {
-        if let Ok(parsed) = value.parse::<u64>() {
-            config.timeout_secs = Some(parsed);
-        }
+    fn whole<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        self.checked(
+            key,
+            |value| value.parse().ok(),
+            "expected a whole number, e.g. 30",
+        )
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_MAX_INPUT_TOKENS") {
-        if let Ok(parsed) = value.parse::<u32>() {
-            config.max_input_tokens = Some(parsed);
-        }
+    fn decimal<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        self.checked(
+            key,
+            |value| value.parse().ok(),
+            "expected a number, e.g. 0.5",
+        )
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_MAX_OUTPUT_TOKENS") {
-        if let Ok(parsed) = value.parse::<u32>() {
-            config.max_output_tokens = Some(parsed);
-        }
+    fn secs(&mut self, key: &str) -> Option<u64> {
+        self.checked(key, parse_secs, "expected seconds, e.g. 30, 30s, or 2m")
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_MAX_FILE_BYTES") {
-        if let Ok(parsed) = value.parse::<u64>() {
-            config.max_file_bytes = Some(parsed);
+    /// Enum-like values, reported with the type's own parse error.
+    fn parse<T: FromStr<Err = String>>(&mut self, key: &str) -> Option<T> {
+        let value = self.string(key)?;
+        match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(err) => {
+                self.warnings
+                    .push(format!("ignoring {key}={value:?}: {err}"));
+                None
+            }
         }
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_MAX_FILE_LINES") {
-        if let Ok(parsed) = value.parse::<u32>() {
-            config.max_file_lines = Some(parsed);
+    fn checked<T>(
+        &mut self,
+        key: &str,
+        parse: impl Fn(&str) -> Option<T>,
+        expected: &str,
+    ) -> Option<T> {
+        let value = self.string(key)?;
+        let parsed = parse(&value);
+        if parsed.is_none() {
+            self.warnings
+                .push(format!("ignoring {key}={value:?}: {expected}"));
         }
+        parsed
     }
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_SUMMARY_CONCURRENCY") {
-        if let Ok(parsed) = value.parse::<u32>() {
-            config.summary_concurrency = Some(parsed);
+fn env_any(keys: &[&str]) -> Option<String> {
+    for key in keys {
+        if let Ok(value) = env::var(key) {
+            if !value.is_empty() {
+                return Some(value);
+            }
         }
     }
+    None
+}
 
-    if let Ok(value) = env::var("GOODCOMMIT_MAX_FILES") {
-        if let Ok(parsed) = value.parse::<u32>() {
-            config.max_files = Some(parsed);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StageMode;
+
+    fn from_vars(pairs: &[(&str, &str)]) -> (Config, Vec<String>) {
+        config_from_vars(|key| {
+            pairs
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| (*value).to_string())
+        })
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_STAGE") {
-        if let Ok(stage) = value.parse::<StageMode>() {
-            config.stage_mode = Some(stage);
-        }
+    #[test]
+    fn valid_values_parse_without_warnings() {
+        let (config, warnings) = from_vars(&[
+            ("GOODCOMMIT_PUSH", "off"),
+            ("GOODCOMMIT_MAX_FILES", "12"),
+            ("GOODCOMMIT_TEMPERATURE", "0.5"),
+            ("GOODCOMMIT_STAGE", "all"),
+        ]);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(config.push, Some(false));
+        assert_eq!(config.max_files, Some(12));
+        assert_eq!(config.temperature, Some(0.5));
+        assert_eq!(config.stage_mode, Some(StageMode::All));
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_CONFIRM") {
-        if let Ok(flag) = parse_bool(&value) {
-            config.confirm = Some(flag);
-        }
+    #[test]
+    fn invalid_bool_is_reported_with_accepted_forms() {
+        let (config, warnings) = from_vars(&[("GOODCOMMIT_PUSH", "maybe")]);
+        assert_eq!(config.push, None);
+        assert_eq!(
+            warnings,
+            vec![
+                "ignoring GOODCOMMIT_PUSH=\"maybe\": expected true/false, yes/no, on/off, or 1/0"
+                    .to_string()
+            ]
+        );
     }
 
-    if let Ok(value) = env::var("GOODCOMMIT_TEMPERATURE") {
-        if let Ok(parsed) = value.parse::<f32>() {
-            config.temperature = Some(parsed);
-        }
+    #[test]
+    fn number_with_trailing_space_or_unit_is_reported() {
+        let (config, warnings) = from_vars(&[
+            ("GOODCOMMIT_MAX_FILES", "12 "),
+            ("GOODCOMMIT_MAX_OUTPUT_TOKENS", "200tok"),
+            ("GOODCOMMIT_TEMPERATURE", "warm"),
+        ]);
+        assert_eq!(config.max_files, None);
+        assert_eq!(config.max_output_tokens, None);
+        assert_eq!(config.temperature, None);
+        assert_eq!(
+            warnings,
+            vec![
+                "ignoring GOODCOMMIT_MAX_OUTPUT_TOKENS=\"200tok\": expected a whole number, e.g. 30"
+                    .to_string(),
+                "ignoring GOODCOMMIT_MAX_FILES=\"12 \": expected a whole number, e.g. 30"
+                    .to_string(),
+                "ignoring GOODCOMMIT_TEMPERATURE=\"warm\": expected a number, e.g. 0.5".to_string(),
+            ]
+        );
     }
 
-    config
-}
+    #[test]
+    fn timeouts_accept_seconds_and_minutes_units() {
+        let (config, warnings) = from_vars(&[
+            ("GOODCOMMIT_TIMEOUT_SECS", "30s"),
+            ("GOODCOMMIT_CONFIRM_TIMEOUT_SECS", "2m"),
+        ]);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(config.timeout_secs, Some(30));
+        assert_eq!(config.confirm_timeout_secs, Some(120));
 
-/// Parse a boolean flag from a string.
-///
-/// # Errors
-/// Returns an error when the value is not a recognized boolean.
-pub fn parse_bool(value: &str) -> Result<bool, String> {
-    match value.to_lowercase().as_str() {
-        "1" | "true" | "yes" | "on" => Ok(true),
-        "0" | "false" | "no" | "off" => Ok(false),
-        _ => Err(format!("invalid bool: {value}")),
+        let (config, warnings) = from_vars(&[("GOODCOMMIT_TIMEOUT_SECS", "30 s")]);
+        assert_eq!(config.timeout_secs, None);
+        assert_eq!(
+            warnings,
+            vec![
+                "ignoring GOODCOMMIT_TIMEOUT_SECS=\"30 s\": expected seconds, e.g. 30, 30s, or 2m"
+                    .to_string()
+            ]
+        );
     }
-}
 
-#[must_use]
-pub fn openai_api_key_env() -> Option<String> {
-    env_any(&["GOODCOMMIT_OPENAI_API_KEY", "OPENAI_API_KEY"])
-}
-
-fn env_any(keys: &[&str]) -> Option<String> {
-    for key in keys {
-        if let Ok(value) = env::var(key) {
-            if !value.is_empty() {
-                return Some(value);
-            }
-        }
+    #[test]
+    fn unknown_enum_value_is_reported_with_its_parse_error() {
+        let (config, warnings) = from_vars(&[("GOODCOMMIT_STAGE", "some")]);
+        assert_eq!(config.stage_mode, None);
+        assert_eq!(
+            warnings,
+            vec!["ignoring GOODCOMMIT_STAGE=\"some\": unknown stage mode: some".to_string()]
+        );
     }
-    None
 }
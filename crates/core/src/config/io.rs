@@ -2,6 +2,8 @@ use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use tracing::warn;
+
 use crate::error::{CoreError, CoreResult};
 
 use super::values::Config;
@@ -12,21 +14,36 @@ pub struct ConfigPaths {
     pub repo_config: Option<PathBuf>,
     pub global_ignore: PathBuf,
     pub repo_ignore: Option<PathBuf>,
+    /// Set when the global config was only found in the old `git-ai-commit` directory;
+    /// it is still read until [`migrate_legacy_config`] copies it over.
+    pub legacy_config: Option<PathBuf>,
 }
 
+const GLOBAL_CONFIG_NAMES: [&str; 3] = ["config.toml", "config.yaml", "config.yml"];
+
 /// Resolve the base configuration directory.
 ///
 /// # Errors
 /// Returns an error when the home directory cannot be resolved.
 pub fn config_dir() -> CoreResult<PathBuf> {
+    Ok(user_config_home()?.join("goodcommit"))
+}
+
+/// Where goodcommit kept its config when the binary was still called `git-ai-commit`.
+///
+/// # Errors
+/// Returns an error when the home directory cannot be resolved.
+pub fn legacy_config_dir() -> CoreResult<PathBuf> {
+    Ok(user_config_home()?.join("git-ai-commit"))
+}
+
+fn user_config_home() -> CoreResult<PathBuf> {
     if let Ok(home) = env::var("HOME") {
-        return Ok(PathBuf::from(home).join(".config").join("goodcommit"));
+        return Ok(PathBuf::from(home).join(".config"));
     }
 
     if let Ok(userprofile) = env::var("USERPROFILE") {
-        return Ok(PathBuf::from(userprofile)
-            .join(".config")
-            .join("goodcommit"));
+        return Ok(PathBuf::from(userprofile).join(".config"));
     }
 
     Err(CoreError::Config(
@@ -41,8 +58,19 @@ pub fn config_dir() -> CoreResult<PathBuf> {
 pub fn resolve_paths(repo_root: Option<&Path>) -> CoreResult<ConfigPaths> {
     let config_dir = config_dir()?;
 
-    let global_config =
-        find_config_file(&config_dir, &["config.toml", "config.yaml", "config.yml"]);
+    let mut global_config = find_config_file(&config_dir, &GLOBAL_CONFIG_NAMES);
+    let mut global_ignore = config_dir.join("ignore");
+    let mut legacy_config = None;
+    if global_config.is_none() {
+        let legacy_dir = legacy_config_dir()?;
+        if let Some(path) = find_config_file(&legacy_dir, &GLOBAL_CONFIG_NAMES) {
+            if !global_ignore.exists() && legacy_dir.join("ignore").exists() {
+                global_ignore = legacy_dir.join("ignore");
+            }
+            global_config = Some(path.clone());
+            legacy_config = Some(path);
+        }
+    }
 
     let repo_config = repo_root.and_then(|root| {
         find_config_file(
@@ -51,7 +79,6 @@ pub fn resolve_paths(repo_root: Option<&Path>) -> CoreResult<ConfigPaths> {
         )
     });
 
-    let global_ignore = config_dir.join("ignore");
     let repo_ignore = repo_root.and_then(|root| {
         let path = root.join(".goodcommit-ignore");
         if path.exists() {
@@ -66,9 +93,36 @@ pub fn resolve_paths(repo_root: Option<&Path>) -> CoreResult<ConfigPaths> {
         repo_config,
         global_ignore,
         repo_ignore,
+        legacy_config,
     })
 }
 
+/// Copy the old `git-ai-commit` config and ignore file into the goodcommit config
+/// directory, leaving the originals in place. Returns the new config path, or `None`
+/// when there is nothing to migrate or goodcommit already has a config.
+///
+/// # Errors
+/// Returns an error when the home directory cannot be resolved or a file cannot be copied.
+pub fn migrate_legacy_config() -> CoreResult<Option<PathBuf>> {
+    let config_dir = config_dir()?;
+    if find_config_file(&config_dir, &GLOBAL_CONFIG_NAMES).is_some() {
+        return Ok(None);
+    }
+    let legacy_dir = legacy_config_dir()?;
+    let Some(legacy) = find_config_file(&legacy_dir, &GLOBAL_CONFIG_NAMES) else {
+        return Ok(None);
+    };
+
+    fs::create_dir_all(&config_dir)?;
+    let target = config_dir.join(legacy.file_name().unwrap_or_default());
+    fs::copy(&legacy, &target)?;
+    let ignore = config_dir.join("ignore");
+    if !ignore.exists() && legacy_dir.join("ignore").exists() {
+        fs::copy(legacy_dir.join("ignore"), ignore)?;
+    }
+    Ok(Some(target))
+}
+
 /// Load config files from the resolved paths.
 ///
 /// # Errors
@@ -81,12 +135,31 @@ pub fn load_config(paths: &ConfigPaths) -> CoreResult<Config> {
     }
 
     if let Some(path) = &paths.repo_config {
-        config = config.merge(read_config_file(path)?);
+        config = config.merge(repo_config(read_config_file(path)?, path));
     }
 
     Ok(config)
 }
 
+/// A repo config without `[stamp]` or `openai_api_key_file`: stamp commands run through `sh`,
+/// and the key file is read and sent as a bearer token, so only the user's own global config
+/// may set them, never a cloned repo.
+fn repo_config(mut config: Config, path: &Path) -> Config {
+    if config.stamp.take().is_some_and(|stamp| !stamp.is_empty()) {
+        warn!(
+            "ignoring [stamp] in {}; stamp commands are only read from the global config",
+            path.display()
+        );
+    }
+    if config.openai_api_key_file.take().is_some() {
+        warn!(
+            "ignoring openai_api_key_file in {}; the key file is only read from the global config",
+            path.display()
+        );
+    }
+    config
+}
+
 /// Read and parse a single config file.
 ///
 /// # Errors
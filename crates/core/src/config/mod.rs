@@ -1,3 +1,4 @@
+mod cache;
 mod env;
 mod io;
 mod types;
@@ -6,7 +7,19 @@ mod values;
 #[cfg(test)]
 mod tests;
 
-pub use env::{config_from_env, openai_api_key_env, parse_bool};
-pub use io::{config_dir, load_config, read_config_file, resolve_paths, ConfigPaths};
-pub use types::{OpenAiMode, ProviderKind, StageMode};
-pub use values::{Config, EffectiveConfig};
+pub use cache::{config_cache_path, load_cached_config, save_cached_config, ConfigCacheKey};
+pub use env::{
+    config_from_env, openai_api_key_env, parse_bool, parse_list, resolve_openai_api_key,
+};
+pub use io::{
+    config_dir, legacy_config_dir, load_config, migrate_legacy_config, read_config_file,
+    resolve_paths, ConfigPaths,
+};
+pub use types::{
+    ConfirmTimeoutAction, OllamaFormat, OpenAiMode, PrefixPlacement, ProviderKind, PushOnFallback,
+    StageMode, SubjectCase, SummaryStyle, TypeInference, WarmupMode,
+};
+pub use values::{
+    default_conventional_types, default_max_output_tokens, Config, EffectiveConfig, LanguageConfig,
+    StampConfig, DEFAULT_MAX_OUTPUT_TOKENS, MIN_INPUT_TOKENS,
+};
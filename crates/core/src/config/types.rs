@@ -72,3 +72,192 @@ impl std::str::FromStr for StageMode {
         }
     }
 }
+
+/// When to send a warm-up request so model load overlaps with git work.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WarmupMode {
+    /// Warm up local providers only; cloud requests cost money.
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for WarmupMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "auto" => Ok(WarmupMode::Auto),
+            "always" => Ok(WarmupMode::Always),
+            "never" => Ok(WarmupMode::Never),
+            other => Err(format!("unknown warmup mode: {other}")),
+        }
+    }
+}
+
+/// Where `subject_prefix` goes in a conventional subject.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrefixPlacement {
+    /// `feat(cli): PREFIX add flag`, which keeps the message conventional.
+    AfterType,
+    /// `PREFIX feat(cli): add flag`.
+    Start,
+}
+
+impl std::str::FromStr for PrefixPlacement {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "after-type" => Ok(PrefixPlacement::AfterType),
+            "start" => Ok(PrefixPlacement::Start),
+            other => Err(format!("unknown prefix placement: {other}")),
+        }
+    }
+}
+
+/// How the diff-based commit type suggestion is used.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TypeInference {
+    Off,
+    /// Pass the suggestion to the model only.
+    Hint,
+    /// Also warn when the model picks a different type than a confident suggestion.
+    Warn,
+    /// Replace the model's type with a confident suggestion.
+    Enforce,
+}
+
+impl std::str::FromStr for TypeInference {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "off" => Ok(TypeInference::Off),
+            "hint" => Ok(TypeInference::Hint),
+            "warn" => Ok(TypeInference::Warn),
+            "enforce" => Ok(TypeInference::Enforce),
+            other => Err(format!("unknown type inference mode: {other}")),
+        }
+    }
+}
+
+/// Casing of the first letter of the subject description.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubjectCase {
+    /// `feat: add search`.
+    Lower,
+    /// `feat: Add search`.
+    Sentence,
+    /// Keep whatever the model or the user wrote.
+    Preserve,
+}
+
+impl std::str::FromStr for SubjectCase {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "lower" => Ok(SubjectCase::Lower),
+            "sentence" => Ok(SubjectCase::Sentence),
+            "preserve" => Ok(SubjectCase::Preserve),
+            other => Err(format!("unknown subject case: {other}")),
+        }
+    }
+}
+
+/// Shape of the per-file summaries fed into the final commit prompt.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryStyle {
+    /// Short bullet points per file.
+    Bullets,
+    /// A sentence or two of plain prose per file.
+    Prose,
+}
+
+impl std::str::FromStr for SummaryStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "bullets" => Ok(SummaryStyle::Bullets),
+            "prose" => Ok(SummaryStyle::Prose),
+            other => Err(format!("unknown summary style: {other}")),
+        }
+    }
+}
+
+/// Structured output requested from Ollama for the commit message call.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OllamaFormat {
+    /// Plain text replies.
+    Off,
+    /// `"format": "json"`: any JSON object.
+    Json,
+    /// `"format"` set to a JSON schema with a single `message` field.
+    Schema,
+}
+
+impl std::str::FromStr for OllamaFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "off" => Ok(OllamaFormat::Off),
+            "json" => Ok(OllamaFormat::Json),
+            "schema" => Ok(OllamaFormat::Schema),
+            other => Err(format!("unknown ollama format: {other}")),
+        }
+    }
+}
+
+/// Answer given to the commit confirmation when `confirm_timeout_secs` runs out.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmTimeoutAction {
+    /// Commit as if the prompt was accepted.
+    Accept,
+    /// Cancel the commit.
+    Cancel,
+}
+
+impl std::str::FromStr for ConfirmTimeoutAction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "accept" => Ok(ConfirmTimeoutAction::Accept),
+            "cancel" => Ok(ConfirmTimeoutAction::Cancel),
+            other => Err(format!("unknown confirm timeout action: {other}")),
+        }
+    }
+}
+
+/// Whether to push a commit that was made with the fallback message.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PushOnFallback {
+    /// Ask first; runs that cannot prompt skip the push.
+    Ask,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for PushOnFallback {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "ask" => Ok(PushOnFallback::Ask),
+            "always" => Ok(PushOnFallback::Always),
+            "never" => Ok(PushOnFallback::Never),
+            other => Err(format!("unknown push on fallback mode: {other}")),
+        }
+    }
+}
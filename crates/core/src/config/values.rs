@@ -1,8 +1,53 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::{CoreError, CoreResult};
+use crate::language::dominant_language;
+use crate::style::StyleProfile;
+use crate::template::check_fallback_template;
+
+use super::env::{openai_api_key_env, resolve_openai_api_key};
+use super::types::{
+    ConfirmTimeoutAction, OllamaFormat, OpenAiMode, PrefixPlacement, ProviderKind, PushOnFallback,
+    StageMode, SubjectCase, SummaryStyle, TypeInference, WarmupMode,
+};
+
+/// Smallest input budget that still leaves room for a useful diff or summary.
+pub const MIN_INPUT_TOKENS: u32 = 512;
+
+/// Output budget for models missing from [`MODEL_OUTPUT_TOKENS`].
+pub const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 2048;
 
-use crate::error::CoreResult;
+// Reasoning models spend output tokens thinking, so a small cap truncates them into the
+// fallback; plain chat models only need room for a message. Longest matching prefix wins.
+const MODEL_OUTPUT_TOKENS: &[(&str, u32)] = &[
+    ("gpt-5", 4096),
+    ("o1", 4096),
+    ("o3", 4096),
+    ("o4", 4096),
+    ("gpt-4.1", 512),
+    ("gpt-4o", 512),
+    ("gpt-3.5", 512),
+    ("qwen2.5", 512),
+    ("llama", 512),
+    ("mistral", 512),
+    ("gemma", 512),
+    ("deepseek-r1", 4096),
+];
 
-use super::types::{OpenAiMode, ProviderKind, StageMode};
+/// Default `max_output_tokens` for `model` when it is not configured.
+#[must_use]
+pub fn default_max_output_tokens(model: &str) -> u32 {
+    let model = model.trim().to_lowercase();
+    MODEL_OUTPUT_TOKENS
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map_or(DEFAULT_MAX_OUTPUT_TOKENS, |(_, tokens)| *tokens)
+}
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct Config {
@@ -28,6 +73,69 @@ pub struct Config {
     pub confirm: Option<bool>,
     pub temperature: Option<f32>,
     pub ignore: Option<Vec<String>>,
+    pub max_subject_length: Option<u32>,
+    pub body_wrap: Option<u32>,
+    pub conventional_types: Option<Vec<String>>,
+    pub language: Option<BTreeMap<String, LanguageConfig>>,
+    pub summary_file_list: Option<bool>,
+    pub notes: Option<bool>,
+    pub retry_on_invalid: Option<bool>,
+    pub one_line_keep_body_in_editor: Option<bool>,
+    pub record_stats: Option<bool>,
+    pub warmup: Option<WarmupMode>,
+    pub subject_prefix: Option<String>,
+    pub subject_suffix: Option<String>,
+    pub subject_prefix_placement: Option<PrefixPlacement>,
+    pub summary_max_file_tokens: Option<u32>,
+    pub report_on_failure: Option<bool>,
+    pub skip_lockfile_only: Option<bool>,
+    pub infer_type: Option<TypeInference>,
+    pub precheck: Option<bool>,
+    pub style_max_age_days: Option<u32>,
+    pub protected_branches: Option<Vec<String>>,
+    pub confirm_cost: Option<f64>,
+    pub summary_hints: Option<BTreeMap<String, Vec<String>>>,
+    pub subject_case: Option<SubjectCase>,
+    pub context_files: Option<Vec<String>>,
+    pub auto_stage_max_files: Option<u32>,
+    pub summary_style: Option<SummaryStyle>,
+    pub seed: Option<u64>,
+    pub allow_large: Option<Vec<String>>,
+    pub pr_draft: Option<bool>,
+    pub ollama_format: Option<OllamaFormat>,
+    pub confirm_timeout_secs: Option<u64>,
+    pub confirm_timeout_action: Option<ConfirmTimeoutAction>,
+    pub infer_scope: Option<bool>,
+    pub allow_fallback: Option<bool>,
+    pub openai_api_key_file: Option<PathBuf>,
+    pub stop_sequences: Option<Vec<String>>,
+    pub fallback_template: Option<String>,
+    pub style_detection: Option<bool>,
+    pub body_file_summary: Option<bool>,
+    pub conflict_marker_ignore: Option<Vec<String>>,
+    pub deprioritize: Option<Vec<String>>,
+    pub stamp: Option<BTreeMap<String, StampConfig>>,
+    pub summary_max_hunk_groups: Option<u32>,
+    pub deletion_weight: Option<u32>,
+    pub push_on_fallback: Option<PushOnFallback>,
+    pub record_timings: Option<bool>,
+}
+
+/// Overrides applied when the staged changes are mostly in one language.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
+pub struct LanguageConfig {
+    pub provider: Option<ProviderKind>,
+    pub model: Option<String>,
+    pub openai_mode: Option<OpenAiMode>,
+}
+
+/// A trailer whose value is the trimmed output of `command`, added when a staged path
+/// matches one of `paths` (or always, when `paths` is empty).
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct StampConfig {
+    pub command: String,
+    #[serde(default)]
+    pub paths: Vec<String>,
 }
 
 impl Config {
@@ -47,7 +155,7 @@ impl Config {
             push: Some(true),
             timeout_secs: Some(20),
             max_input_tokens: Some(6000),
-            max_output_tokens: Some(2048),
+            max_output_tokens: None,
             max_file_bytes: Some(200_000),
             max_file_lines: Some(2_000),
             summary_concurrency: Some(4),
@@ -56,6 +164,52 @@ impl Config {
             confirm: Some(true),
             temperature: Some(0.2),
             ignore: Some(Vec::new()),
+            max_subject_length: Some(50),
+            body_wrap: Some(72),
+            conventional_types: Some(default_conventional_types()),
+            language: None,
+            summary_file_list: Some(true),
+            notes: Some(false),
+            retry_on_invalid: Some(false),
+            one_line_keep_body_in_editor: Some(false),
+            record_stats: Some(false),
+            warmup: Some(WarmupMode::Auto),
+            subject_prefix: None,
+            subject_suffix: None,
+            subject_prefix_placement: Some(PrefixPlacement::AfterType),
+            summary_max_file_tokens: Some(2000),
+            report_on_failure: Some(false),
+            skip_lockfile_only: Some(true),
+            infer_type: Some(TypeInference::Warn),
+            precheck: Some(false),
+            style_max_age_days: Some(30),
+            protected_branches: Some(Vec::new()),
+            confirm_cost: None,
+            summary_hints: Some(BTreeMap::new()),
+            subject_case: Some(SubjectCase::Lower),
+            context_files: Some(Vec::new()),
+            auto_stage_max_files: Some(500),
+            summary_style: Some(SummaryStyle::Bullets),
+            seed: None,
+            allow_large: Some(Vec::new()),
+            pr_draft: Some(false),
+            ollama_format: Some(OllamaFormat::Off),
+            confirm_timeout_secs: None,
+            confirm_timeout_action: Some(ConfirmTimeoutAction::Accept),
+            infer_scope: Some(false),
+            allow_fallback: Some(true),
+            openai_api_key_file: None,
+            stop_sequences: Some(Vec::new()),
+            fallback_template: None,
+            style_detection: Some(true),
+            body_file_summary: Some(false),
+            conflict_marker_ignore: Some(Vec::new()),
+            deprioritize: Some(Vec::new()),
+            stamp: Some(BTreeMap::new()),
+            summary_max_hunk_groups: Some(8),
+            deletion_weight: Some(2),
+            push_on_fallback: Some(PushOnFallback::Ask),
+            record_timings: Some(true),
         }
     }
 
@@ -84,13 +238,98 @@ impl Config {
             confirm: other.confirm.or(self.confirm),
             temperature: other.temperature.or(self.temperature),
             ignore: other.ignore.or(self.ignore),
+            max_subject_length: other.max_subject_length.or(self.max_subject_length),
+            body_wrap: other.body_wrap.or(self.body_wrap),
+            conventional_types: other.conventional_types.or(self.conventional_types),
+            language: other.language.or(self.language),
+            summary_file_list: other.summary_file_list.or(self.summary_file_list),
+            notes: other.notes.or(self.notes),
+            retry_on_invalid: other.retry_on_invalid.or(self.retry_on_invalid),
+            one_line_keep_body_in_editor: other
+                .one_line_keep_body_in_editor
+                .or(self.one_line_keep_body_in_editor),
+            record_stats: other.record_stats.or(self.record_stats),
+            warmup: other.warmup.or(self.warmup),
+            subject_prefix: other.subject_prefix.or(self.subject_prefix),
+            subject_suffix: other.subject_suffix.or(self.subject_suffix),
+            subject_prefix_placement: other
+                .subject_prefix_placement
+                .or(self.subject_prefix_placement),
+            summary_max_file_tokens: other
+                .summary_max_file_tokens
+                .or(self.summary_max_file_tokens),
+            report_on_failure: other.report_on_failure.or(self.report_on_failure),
+            skip_lockfile_only: other.skip_lockfile_only.or(self.skip_lockfile_only),
+            infer_type: other.infer_type.or(self.infer_type),
+            precheck: other.precheck.or(self.precheck),
+            style_max_age_days: other.style_max_age_days.or(self.style_max_age_days),
+            protected_branches: other.protected_branches.or(self.protected_branches),
+            confirm_cost: other.confirm_cost.or(self.confirm_cost),
+            summary_hints: other.summary_hints.or(self.summary_hints),
+            subject_case: other.subject_case.or(self.subject_case),
+            context_files: other.context_files.or(self.context_files),
+            auto_stage_max_files: other.auto_stage_max_files.or(self.auto_stage_max_files),
+            summary_style: other.summary_style.or(self.summary_style),
+            seed: other.seed.or(self.seed),
+            allow_large: other.allow_large.or(self.allow_large),
+            pr_draft: other.pr_draft.or(self.pr_draft),
+            ollama_format: other.ollama_format.or(self.ollama_format),
+            confirm_timeout_secs: other.confirm_timeout_secs.or(self.confirm_timeout_secs),
+            confirm_timeout_action: other.confirm_timeout_action.or(self.confirm_timeout_action),
+            infer_scope: other.infer_scope.or(self.infer_scope),
+            allow_fallback: other.allow_fallback.or(self.allow_fallback),
+            openai_api_key_file: other.openai_api_key_file.or(self.openai_api_key_file),
+            stop_sequences: other.stop_sequences.or(self.stop_sequences),
+            fallback_template: other.fallback_template.or(self.fallback_template),
+            style_detection: other.style_detection.or(self.style_detection),
+            body_file_summary: other.body_file_summary.or(self.body_file_summary),
+            conflict_marker_ignore: other.conflict_marker_ignore.or(self.conflict_marker_ignore),
+            deprioritize: other.deprioritize.or(self.deprioritize),
+            stamp: other.stamp.or(self.stamp),
+            summary_max_hunk_groups: other
+                .summary_max_hunk_groups
+                .or(self.summary_max_hunk_groups),
+            deletion_weight: other.deletion_weight.or(self.deletion_weight),
+            push_on_fallback: other.push_on_fallback.or(self.push_on_fallback),
+            record_timings: other.record_timings.or(self.record_timings),
         }
     }
 
+    /// Apply the `[language.<name>]` overrides matching the dominant language of `paths`.
+    #[must_use]
+    pub fn with_language_overrides(self, paths: &[String]) -> Self {
+        let Some(language) = dominant_language(paths) else {
+            return self;
+        };
+        let Some(overrides) = self
+            .language
+            .as_ref()
+            .and_then(|languages| languages.get(language))
+            .cloned()
+        else {
+            return self;
+        };
+
+        self.merge(Config {
+            provider: overrides.provider,
+            model: overrides.model,
+            openai_mode: overrides.openai_mode,
+            ..Config::default()
+        })
+    }
+
+    #[must_use]
+    pub fn has_language_overrides(&self) -> bool {
+        self.language
+            .as_ref()
+            .is_some_and(|languages| !languages.is_empty())
+    }
+
     /// Resolve the merged config into concrete defaults.
     ///
     /// # Errors
     /// Returns an error when config values are inconsistent.
+    #[allow(clippy::too_many_lines)]
     pub fn resolve(self) -> CoreResult<EffectiveConfig> {
         let provider = self.provider.unwrap_or(ProviderKind::Ollama);
         let model = self
@@ -101,6 +340,16 @@ impl Config {
             openai_mode = OpenAiMode::Responses;
         }
 
+        let mut max_input_tokens = self.max_input_tokens.unwrap_or(6000);
+        if max_input_tokens < MIN_INPUT_TOKENS {
+            warn!("max_input_tokens = {max_input_tokens} is too small; using {MIN_INPUT_TOKENS}");
+            max_input_tokens = MIN_INPUT_TOKENS;
+        }
+
+        let max_output_tokens = self
+            .max_output_tokens
+            .unwrap_or_else(|| default_max_output_tokens(&model));
+
         Ok(EffectiveConfig {
             provider,
             model,
@@ -108,7 +357,11 @@ impl Config {
             openai_base_url: self
                 .openai_base_url
                 .unwrap_or_else(|| "https://api.openai.com/v1".to_string()),
-            openai_api_key: self.openai_api_key,
+            openai_api_key: if provider == ProviderKind::OpenAi {
+                resolve_openai_api_key(self.openai_api_key, self.openai_api_key_file.as_deref())
+            } else {
+                self.openai_api_key.or_else(openai_api_key_env)
+            },
             ollama_endpoint: self
                 .ollama_endpoint
                 .unwrap_or_else(|| "http://localhost:11434/api/chat".to_string()),
@@ -118,8 +371,8 @@ impl Config {
             lang: self.lang,
             push: self.push.unwrap_or(true),
             timeout_secs: self.timeout_secs.unwrap_or(20),
-            max_input_tokens: self.max_input_tokens.unwrap_or(6000),
-            max_output_tokens: self.max_output_tokens.unwrap_or(2048),
+            max_input_tokens,
+            max_output_tokens,
             max_file_bytes: self.max_file_bytes.unwrap_or(200_000),
             max_file_lines: self.max_file_lines.unwrap_or(2_000),
             summary_concurrency: self.summary_concurrency.unwrap_or(4) as usize,
@@ -128,6 +381,61 @@ impl Config {
             confirm: self.confirm.unwrap_or(true),
             temperature: self.temperature.unwrap_or(0.2),
             ignore: self.ignore.unwrap_or_default(),
+            max_subject_length: self.max_subject_length.unwrap_or(50) as usize,
+            body_wrap: self.body_wrap.unwrap_or(72) as usize,
+            conventional_types: self
+                .conventional_types
+                .filter(|types| !types.is_empty())
+                .unwrap_or_else(default_conventional_types),
+            language: self.language.unwrap_or_default(),
+            summary_file_list: self.summary_file_list.unwrap_or(true),
+            notes: self.notes.unwrap_or(false),
+            retry_on_invalid: self.retry_on_invalid.unwrap_or(false),
+            one_line_keep_body_in_editor: self.one_line_keep_body_in_editor.unwrap_or(false),
+            record_stats: self.record_stats.unwrap_or(false),
+            warmup: self.warmup.unwrap_or(WarmupMode::Auto),
+            subject_prefix: non_empty(self.subject_prefix),
+            subject_suffix: non_empty(self.subject_suffix),
+            subject_prefix_placement: self
+                .subject_prefix_placement
+                .unwrap_or(PrefixPlacement::AfterType),
+            summary_max_file_tokens: self.summary_max_file_tokens.unwrap_or(2000),
+            report_on_failure: self.report_on_failure.unwrap_or(false),
+            skip_lockfile_only: self.skip_lockfile_only.unwrap_or(true),
+            infer_type: self.infer_type.unwrap_or(TypeInference::Warn),
+            precheck: self.precheck.unwrap_or(false),
+            style_max_age_days: self.style_max_age_days.unwrap_or(30),
+            style: None,
+            user_body: None,
+            protected_branches: self.protected_branches.unwrap_or_default(),
+            confirm_cost: self.confirm_cost,
+            summary_hints: self.summary_hints.unwrap_or_default(),
+            subject_case: self.subject_case.unwrap_or(SubjectCase::Lower),
+            context_files: self.context_files.unwrap_or_default(),
+            auto_stage_max_files: self.auto_stage_max_files.unwrap_or(500) as usize,
+            summary_style: self.summary_style.unwrap_or(SummaryStyle::Bullets),
+            seed: self.seed,
+            allow_large: self.allow_large.unwrap_or_default(),
+            pr_draft: self.pr_draft.unwrap_or(false),
+            ollama_format: self.ollama_format.unwrap_or(OllamaFormat::Off),
+            confirm_timeout_secs: self.confirm_timeout_secs.filter(|secs| *secs > 0),
+            confirm_timeout_action: self
+                .confirm_timeout_action
+                .unwrap_or(ConfirmTimeoutAction::Accept),
+            infer_scope: self.infer_scope.unwrap_or(false),
+            allow_fallback: self.allow_fallback.unwrap_or(true),
+            openai_api_key_file: self.openai_api_key_file.clone(),
+            stop_sequences: self.stop_sequences.unwrap_or_default(),
+            fallback_template: resolve_fallback_template(self.fallback_template)?,
+            style_detection: self.style_detection.unwrap_or(true),
+            body_file_summary: self.body_file_summary.unwrap_or(false),
+            conflict_marker_ignore: self.conflict_marker_ignore.unwrap_or_default(),
+            deprioritize: self.deprioritize.unwrap_or_default(),
+            stamp: resolve_stamp(self.stamp)?,
+            summary_max_hunk_groups: self.summary_max_hunk_groups.unwrap_or(8),
+            deletion_weight: self.deletion_weight.unwrap_or(2),
+            push_on_fallback: self.push_on_fallback.unwrap_or(PushOnFallback::Ask),
+            record_timings: self.record_timings.unwrap_or(true),
         })
     }
 }
@@ -156,9 +464,71 @@ pub struct EffectiveConfig {
     pub confirm: bool,
     pub temperature: f32,
     pub ignore: Vec<String>,
+    pub max_subject_length: usize,
+    pub body_wrap: usize,
+    pub conventional_types: Vec<String>,
+    pub language: BTreeMap<String, LanguageConfig>,
+    pub summary_file_list: bool,
+    pub notes: bool,
+    pub retry_on_invalid: bool,
+    pub one_line_keep_body_in_editor: bool,
+    pub record_stats: bool,
+    pub warmup: WarmupMode,
+    pub subject_prefix: Option<String>,
+    pub subject_suffix: Option<String>,
+    pub subject_prefix_placement: PrefixPlacement,
+    pub summary_max_file_tokens: u32,
+    pub report_on_failure: bool,
+    pub skip_lockfile_only: bool,
+    pub infer_type: TypeInference,
+    pub precheck: bool,
+    pub style_max_age_days: u32,
+    /// Learned by `goodcommit learn`; never read from config files.
+    pub style: Option<StyleProfile>,
+    pub protected_branches: Vec<String>,
+    pub confirm_cost: Option<f64>,
+    pub summary_hints: BTreeMap<String, Vec<String>>,
+    pub subject_case: SubjectCase,
+    pub context_files: Vec<String>,
+    pub auto_stage_max_files: usize,
+    pub summary_style: SummaryStyle,
+    pub seed: Option<u64>,
+    pub allow_large: Vec<String>,
+    pub pr_draft: bool,
+    pub ollama_format: OllamaFormat,
+    pub confirm_timeout_secs: Option<u64>,
+    pub confirm_timeout_action: ConfirmTimeoutAction,
+    pub infer_scope: bool,
+    pub allow_fallback: bool,
+    pub openai_api_key_file: Option<PathBuf>,
+    pub stop_sequences: Vec<String>,
+    pub fallback_template: Option<String>,
+    pub style_detection: bool,
+    pub body_file_summary: bool,
+    pub conflict_marker_ignore: Vec<String>,
+    pub deprioritize: Vec<String>,
+    pub stamp: BTreeMap<String, StampConfig>,
+    pub summary_max_hunk_groups: u32,
+    /// Body from `--body` or `--body-file`, placed under the AI subject; never read from
+    /// config files.
+    pub user_body: Option<String>,
+    pub deletion_weight: u32,
+    pub push_on_fallback: PushOnFallback,
+    pub record_timings: bool,
 }
 
 impl EffectiveConfig {
+    /// Characters of the subject line left for the model after prefix and suffix.
+    #[must_use]
+    pub fn subject_budget(&self) -> usize {
+        let affix_len = [&self.subject_prefix, &self.subject_suffix]
+            .into_iter()
+            .flatten()
+            .map(|affix| affix.chars().count() + 1)
+            .sum::<usize>();
+        self.max_subject_length.saturating_sub(affix_len)
+    }
+
     #[must_use]
     pub fn to_config(&self) -> Config {
         Config {
@@ -184,6 +554,101 @@ impl EffectiveConfig {
             confirm: Some(self.confirm),
             temperature: Some(self.temperature),
             ignore: Some(self.ignore.clone()),
+            max_subject_length: Some(u32::try_from(self.max_subject_length).unwrap_or(u32::MAX)),
+            body_wrap: Some(u32::try_from(self.body_wrap).unwrap_or(u32::MAX)),
+            conventional_types: Some(self.conventional_types.clone()),
+            language: if self.language.is_empty() {
+                None
+            } else {
+                Some(self.language.clone())
+            },
+            summary_file_list: Some(self.summary_file_list),
+            notes: Some(self.notes),
+            retry_on_invalid: Some(self.retry_on_invalid),
+            one_line_keep_body_in_editor: Some(self.one_line_keep_body_in_editor),
+            record_stats: Some(self.record_stats),
+            warmup: Some(self.warmup),
+            subject_prefix: self.subject_prefix.clone(),
+            subject_suffix: self.subject_suffix.clone(),
+            subject_prefix_placement: Some(self.subject_prefix_placement),
+            summary_max_file_tokens: Some(self.summary_max_file_tokens),
+            report_on_failure: Some(self.report_on_failure),
+            skip_lockfile_only: Some(self.skip_lockfile_only),
+            infer_type: Some(self.infer_type),
+            precheck: Some(self.precheck),
+            style_max_age_days: Some(self.style_max_age_days),
+            protected_branches: Some(self.protected_branches.clone()),
+            confirm_cost: self.confirm_cost,
+            summary_hints: Some(self.summary_hints.clone()),
+            subject_case: Some(self.subject_case),
+            context_files: Some(self.context_files.clone()),
+            auto_stage_max_files: Some(
+                u32::try_from(self.auto_stage_max_files).unwrap_or(u32::MAX),
+            ),
+            summary_style: Some(self.summary_style),
+            seed: self.seed,
+            allow_large: Some(self.allow_large.clone()),
+            pr_draft: Some(self.pr_draft),
+            ollama_format: Some(self.ollama_format),
+            confirm_timeout_secs: self.confirm_timeout_secs,
+            confirm_timeout_action: Some(self.confirm_timeout_action),
+            infer_scope: Some(self.infer_scope),
+            allow_fallback: Some(self.allow_fallback),
+            openai_api_key_file: self.openai_api_key_file.clone(),
+            stop_sequences: Some(self.stop_sequences.clone()),
+            fallback_template: self.fallback_template.clone(),
+            style_detection: Some(self.style_detection),
+            body_file_summary: Some(self.body_file_summary),
+            conflict_marker_ignore: Some(self.conflict_marker_ignore.clone()),
+            deprioritize: Some(self.deprioritize.clone()),
+            stamp: Some(self.stamp.clone()),
+            summary_max_hunk_groups: Some(self.summary_max_hunk_groups),
+            deletion_weight: Some(self.deletion_weight),
+            push_on_fallback: Some(self.push_on_fallback),
+            record_timings: Some(self.record_timings),
         }
     }
 }
+
+#[must_use]
+pub fn default_conventional_types() -> Vec<String> {
+    [
+        "feat", "fix", "build", "chore", "ci", "docs", "style", "refactor", "perf", "test",
+        "revert",
+    ]
+    .iter()
+    .map(|kind| (*kind).to_string())
+    .collect()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// The fallback template, checked for unknown placeholders; an empty one means the default.
+fn resolve_fallback_template(template: Option<String>) -> CoreResult<Option<String>> {
+    let template = template.filter(|template| !template.is_empty());
+    if let Some(template) = &template {
+        check_fallback_template(template)?;
+    }
+    Ok(template)
+}
+
+/// The `[stamp]` table, checked that each name is one word such as `Rust-Toolchain`, since
+/// names become trailer keys.
+fn resolve_stamp(
+    stamp: Option<BTreeMap<String, StampConfig>>,
+) -> CoreResult<BTreeMap<String, StampConfig>> {
+    let stamp = stamp.unwrap_or_default();
+    let invalid = stamp.keys().find(|name| {
+        name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    match invalid {
+        Some(name) => Err(CoreError::Config(format!(
+            "stamp trailer name `{name}` must be letters, digits, and dashes, like Rust-Toolchain"
+        ))),
+        None => Ok(stamp),
+    }
+}
@@ -18,12 +18,22 @@ pub fn diff_files_to_string(files: &[DiffFile]) -> String {
         .join("\n")
 }
 
+/// Columns a tab counts as in [`estimate_tokens`]; tokenizers see the expanded indentation.
+const TAB_WIDTH: usize = 4;
+
 #[must_use]
 pub fn estimate_tokens(text: &str) -> usize {
     let chars = text.chars().count();
-    chars.saturating_add(3) / 4
+    let tabs = text.matches('\t').count();
+    chars
+        .saturating_add(tabs.saturating_mul(TAB_WIDTH - 1))
+        .saturating_add(3)
+        / 4
 }
 
+/// Keep the first `max_lines` lines, joined with LF.
+///
+/// CRLF endings are normalized, including a `\r` left dangling by a byte cap.
 #[must_use]
 pub fn truncate_lines(text: &str, max_lines: u32) -> (String, bool) {
     if max_lines == 0 {
@@ -37,7 +47,7 @@ pub fn truncate_lines(text: &str, max_lines: u32) -> (String, bool) {
         if count >= max_lines {
             return (buffer.trim_end().to_string(), true);
         }
-        buffer.push_str(line);
+        buffer.push_str(line.trim_end_matches('\r'));
         buffer.push('\n');
     }
 
@@ -50,6 +60,7 @@ pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
     let mut count = 0usize;
 
     for line in text.lines() {
+        let line = line.trim_end_matches('\r');
         let line_tokens = estimate_tokens(line);
         if count + line_tokens > max_tokens {
             break;
@@ -62,6 +73,142 @@ pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
     buffer.trim_end().to_string()
 }
 
+/// Split a file's diff into groups of consecutive hunks (split on `@@` headers) of at most
+/// `max_tokens` tokens each, every group starting with the file header so it reads as a
+/// diff on its own. A hunk over the cap by itself is cut to fit.
+#[must_use]
+pub fn hunk_groups(diff: &str, max_tokens: usize) -> Vec<String> {
+    let (header, hunks) = split_hunks(diff);
+    let budget = max_tokens.saturating_sub(estimate_tokens(&header)).max(1);
+    let mut groups = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    for hunk in hunks {
+        let hunk = if estimate_tokens(&hunk) > budget {
+            truncate_to_tokens(&hunk, budget)
+        } else {
+            hunk
+        };
+        let tokens = estimate_tokens(&hunk);
+        if !current.is_empty() && current_tokens + tokens > budget {
+            groups.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        current.push_str(&hunk);
+        current.push('\n');
+        current_tokens += tokens;
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+        .into_iter()
+        .map(|group| format!("{header}{}", group.trim_end()))
+        .collect()
+}
+
+/// The lines before the first hunk (ending in a newline when present), then each hunk
+/// from its `@@` header up to the next one.
+fn split_hunks(diff: &str) -> (String, Vec<String>) {
+    let mut header = String::new();
+    let mut hunks: Vec<String> = Vec::new();
+    for line in diff.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with("@@") {
+            hunks.push(line.to_string());
+        } else if let Some(hunk) = hunks.last_mut() {
+            hunk.push('\n');
+            hunk.push_str(line);
+        } else {
+            header.push_str(line);
+            header.push('\n');
+        }
+    }
+    (header, hunks)
+}
+
+/// Split a multi-file unified diff into `(path, diff)` per file, the path taken from the
+/// `+++ b/…` line. Deleted and binary files, which have none, are left out.
+#[must_use]
+pub fn split_file_diffs(diff: &str) -> Vec<(String, &str)> {
+    let mut starts = diff
+        .match_indices("diff --git ")
+        .filter(|(at, _)| *at == 0 || diff.as_bytes()[at - 1] == b'\n')
+        .map(|(at, _)| at)
+        .collect::<Vec<_>>();
+    starts.push(diff.len());
+    starts
+        .windows(2)
+        .filter_map(|bounds| {
+            let section = &diff[bounds[0]..bounds[1]];
+            let path = section
+                .lines()
+                .take_while(|line| !line.starts_with("@@"))
+                .find_map(|line| line.strip_prefix("+++ "))?
+                .trim_end_matches(['\t', '\r'])
+                .trim_matches('"')
+                .strip_prefix("b/")?;
+            Some((path.to_string(), section))
+        })
+        .collect()
+}
+
+/// New-file line numbers of the conflict markers (`<<<<<<<`, `|||||||`, `=======`, `>>>>>>>`)
+/// added in a unified diff. A lone `=======` is also a heading underline, so markers only
+/// count when the diff adds a `<<<<<<<` or `>>>>>>>` line too.
+#[must_use]
+pub fn conflict_marker_lines(diff: &str) -> Vec<u32> {
+    let mut lines = Vec::new();
+    let mut has_open_or_close = false;
+    let mut new_line: Option<u32> = None;
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            new_line = hunk_new_start(header);
+            continue;
+        }
+        let Some(number) = new_line.as_mut() else {
+            continue;
+        };
+        if let Some(added) = line.strip_prefix('+') {
+            let added = added.trim_end_matches('\r');
+            if let Some(marker) = conflict_marker(added) {
+                has_open_or_close |= marker != '=' && marker != '|';
+                lines.push(*number);
+            }
+            *number += 1;
+        } else if line.starts_with(' ') || line.is_empty() {
+            *number += 1;
+        }
+    }
+    if has_open_or_close {
+        lines
+    } else {
+        Vec::new()
+    }
+}
+
+/// Start line in the new file from a hunk header such as `-3,7 +3,8 @@`.
+fn hunk_new_start(header: &str) -> Option<u32> {
+    let new = header.split(' ').find_map(|part| part.strip_prefix('+'))?;
+    new.split(',').next()?.parse().ok()
+}
+
+/// The marker character when `line` is a conflict marker: seven of `<`, `|`, `=`, or `>`,
+/// then the end of the line or (except for `=`) a space and a label.
+fn conflict_marker(line: &str) -> Option<char> {
+    let marker = line.chars().next()?;
+    if !matches!(marker, '<' | '|' | '=' | '>') {
+        return None;
+    }
+    let rest = line.strip_prefix(&marker.to_string().repeat(7))?;
+    let ends = if marker == '=' {
+        rest.trim_end().is_empty()
+    } else {
+        rest.is_empty() || rest.starts_with(' ')
+    };
+    ends.then_some(marker)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,4 +228,98 @@ mod tests {
         assert_eq!(out, "one\ntwo");
         assert!(!truncated);
     }
+
+    const CRLF_DIFF: &str = "+first line\r\n+second line\r\n+third\r";
+
+    #[test]
+    fn truncation_never_emits_carriage_returns() {
+        let (out, truncated) = truncate_lines(CRLF_DIFF, 2);
+        assert_eq!(out, "+first line\n+second line");
+        assert!(truncated);
+
+        let (out, truncated) = truncate_lines(CRLF_DIFF, 3);
+        assert_eq!(out, "+first line\n+second line\n+third");
+        assert!(!truncated);
+        assert!(out.len() < CRLF_DIFF.len());
+
+        let out = truncate_to_tokens(CRLF_DIFF, 100);
+        assert_eq!(out, "+first line\n+second line\n+third");
+    }
+
+    #[test]
+    fn estimate_tokens_expands_tabs_and_ignores_line_endings() {
+        assert_eq!(estimate_tokens("\t\tx"), estimate_tokens("        x"));
+        assert_eq!(
+            estimate_tokens(&truncate_lines("a\r\nb\r\n", 10).0),
+            estimate_tokens("a\nb")
+        );
+    }
+
+    #[test]
+    fn conflict_markers_are_found_on_added_lines_with_new_file_numbers() {
+        let diff = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n\
+                    @@ -1,3 +1,7 @@\n fn main() {\n-    old();\n+<<<<<<< HEAD\n+    ours();\n\
+                    +=======\n+    theirs();\n+>>>>>>> feature\n }\n\
+                    @@ -40,2 +44,3 @@\n context\n+=======\n";
+        assert_eq!(conflict_marker_lines(diff), vec![2, 4, 6, 45]);
+    }
+
+    #[test]
+    fn removed_markers_and_heading_underlines_are_not_conflicts() {
+        let removed = "@@ -1,3 +1,1 @@\n-<<<<<<< HEAD\n-=======\n->>>>>>> feature\n kept\n";
+        assert!(conflict_marker_lines(removed).is_empty());
+        let heading = "@@ -0,0 +1,2 @@\n+Title\n+=======\n";
+        assert!(conflict_marker_lines(heading).is_empty());
+        let longer = "@@ -0,0 +1,2 @@\n+<<<<<<<<< not a marker\n+<<<<<<<x\n";
+        assert!(conflict_marker_lines(longer).is_empty());
+    }
+
+    #[test]
+    fn split_file_diffs_names_each_new_file() {
+        let diff = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n\
+                    @@ -1 +1 @@\n-a\n+b\n\
+                    diff --git a/gone.rs b/gone.rs\n--- a/gone.rs\n+++ /dev/null\n@@ -1 +0,0 @@\n-x\n\
+                    diff --git a/my file.txt b/my file.txt\n--- a/my file.txt\t\n+++ b/my file.txt\t\n\
+                    @@ -0,0 +1 @@\n+diff --git inside\n";
+        let files = split_file_diffs(diff);
+        let paths = files
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["src/a.rs", "my file.txt"]);
+        assert!(files[0].1.ends_with("+b\n"));
+        assert!(files[1].1.ends_with("+diff --git inside\n"));
+    }
+
+    const THREE_HUNKS: &str = "diff --git a/big.rs b/big.rs\n--- a/big.rs\n+++ b/big.rs\n\
+                               @@ -1,2 +1,2 @@\n-one\n+uno\n\
+                               @@ -10,2 +10,2 @@\n-ten\n+diez\n\
+                               @@ -20,2 +20,2 @@\n-twenty\n+veinte\n";
+
+    #[test]
+    fn hunk_groups_keep_the_header_and_whole_hunks_within_the_cap() {
+        let header = "diff --git a/big.rs b/big.rs\n--- a/big.rs\n+++ b/big.rs\n";
+        let hunk_tokens = estimate_tokens("@@ -10,2 +10,2 @@\n-ten\n+diez");
+        let cap = estimate_tokens(header) + hunk_tokens * 2 + 1;
+
+        let groups = hunk_groups(THREE_HUNKS, cap);
+
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.starts_with(header)));
+        assert!(groups[0].ends_with("+uno\n@@ -10,2 +10,2 @@\n-ten\n+diez"));
+        assert_eq!(
+            groups[1],
+            format!("{header}@@ -20,2 +20,2 @@\n-twenty\n+veinte")
+        );
+        assert_eq!(hunk_groups(THREE_HUNKS, usize::MAX).len(), 1);
+    }
+
+    #[test]
+    fn an_oversized_hunk_is_cut_to_the_cap() {
+        let diff = format!("@@ -1,1 +1,200 @@\n{}", "+added line\n".repeat(200));
+        let groups = hunk_groups(&diff, 50);
+        assert_eq!(groups.len(), 1);
+        assert!(estimate_tokens(&groups[0]) <= 50);
+        assert!(groups[0].starts_with("@@ -1,1 +1,200 @@\n+added line"));
+    }
 }
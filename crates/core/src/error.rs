@@ -1,3 +1,6 @@
+use std::fmt;
+use std::time::Duration;
+
 use thiserror::Error;
 
 pub type CoreResult<T> = Result<T, CoreError>;
@@ -14,10 +17,12 @@ pub enum CoreError {
     Config(String),
     #[error("diff error: {0}")]
     Diff(String),
-    #[error("timeout after {0} seconds")]
-    Timeout(u64),
+    #[error("{0}")]
+    Timeout(TimeoutError),
     #[error("cancelled")]
     Cancelled,
+    #[error("no usable AI message and allow_fallback = false: {0}")]
+    FallbackDisabled(String),
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
@@ -33,3 +38,75 @@ pub enum CoreError {
     #[error(transparent)]
     Utf8(#[from] std::string::FromUtf8Error),
 }
+
+/// Which provider call ran out of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallPhase {
+    /// Per-file summary before the final message.
+    Summary,
+    /// The commit message itself.
+    Final,
+    /// Rewrite of output that failed conventional validation.
+    Repair,
+    /// Commit note generation.
+    Note,
+    /// Why the model chose the message, for `--explain`.
+    Explain,
+    /// Release notes for a range of commits.
+    Changelog,
+    /// Why a commit is being reverted.
+    Revert,
+    /// Highlights for an annotated release tag.
+    Tag,
+}
+
+impl fmt::Display for CallPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Summary => "summary request",
+            Self::Final => "commit message request",
+            Self::Repair => "repair request",
+            Self::Note => "note request",
+            Self::Explain => "explanation request",
+            Self::Changelog => "changelog request",
+            Self::Revert => "revert request",
+            Self::Tag => "tag message request",
+        })
+    }
+}
+
+/// A provider call that missed the `timeout_secs` deadline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeoutError {
+    pub phase: CallPhase,
+    /// File being summarized, for [`CallPhase::Summary`].
+    pub path: Option<String>,
+    pub timeout_secs: u64,
+    /// Time since generation started.
+    pub elapsed: Duration,
+    /// The deadline had already passed, so the call was never sent.
+    pub before_call: bool,
+}
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elapsed = self.elapsed.as_secs_f64();
+        let phase = match &self.path {
+            Some(path) => format!("{} for {path}", self.phase),
+            None => self.phase.to_string(),
+        };
+        if self.before_call {
+            write!(
+                f,
+                "{phase} not sent: the {}s timeout (timeout_secs) was used up after {elapsed:.1}s",
+                self.timeout_secs
+            )
+        } else {
+            write!(
+                f,
+                "{phase} timed out after {elapsed:.1}s (timeout_secs = {})",
+                self.timeout_secs
+            )
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 
 use crate::error::{CoreError, CoreResult};
+use crate::status::{parse_porcelain_v2, RepoStatus};
 
 #[derive(Debug, Clone)]
 pub struct GitFileStat {
@@ -18,6 +19,14 @@ pub struct GitDiff {
     pub truncated: bool,
 }
 
+/// Result of running a repository hook.
+#[derive(Debug, Clone)]
+pub struct HookOutput {
+    pub success: bool,
+    /// Combined stdout and stderr.
+    pub output: String,
+}
+
 #[allow(clippy::missing_errors_doc)]
 pub trait GitBackend {
     fn ensure_git_repo(&self) -> CoreResult<()>;
@@ -31,20 +40,114 @@ pub trait GitBackend {
     fn staged_diff_for_path(&self, path: &str, max_bytes: u64) -> CoreResult<GitDiff>;
     fn staged_files(&self) -> CoreResult<Vec<String>>;
     fn staged_numstat(&self) -> CoreResult<Vec<GitFileStat>>;
+    fn has_staged_changes(&self) -> CoreResult<bool>;
+    /// An identifier for the current staged content; changes whenever the index does.
+    fn staged_fingerprint(&self) -> CoreResult<String>;
     fn working_tree_files(&self) -> CoreResult<Vec<String>>;
+    fn tracked_files(&self) -> CoreResult<Vec<String>>;
     fn has_unstaged_changes(&self) -> CoreResult<bool>;
-    fn commit(&self, message: &str, edit: bool, no_verify: bool) -> CoreResult<String>;
+    /// Staged, unstaged, and untracked files from a single status call.
+    fn status(&self) -> CoreResult<RepoStatus>;
+    /// Changed and untracked files that `git add .` would stage from the current directory.
+    fn stage_candidates(&self) -> CoreResult<Vec<String>>;
+    /// Directories of a cone-mode sparse checkout; `None` when the checkout is not sparse
+    /// or uses non-cone patterns.
+    fn sparse_cone(&self) -> CoreResult<Option<Vec<String>>>;
+    fn commit_message(&self, rev: &str) -> CoreResult<String>;
+    /// Subject of the current `HEAD` commit; `None` before the first commit.
+    fn head_subject(&self) -> CoreResult<Option<String>>;
+    /// Working tree contents of a repo-relative file; `None` when it does not exist.
+    /// Paths that resolve outside the repository are an error.
+    fn worktree_file(&self, path: &str) -> CoreResult<Option<String>>;
+    /// Short name of the checked-out branch; `None` when `HEAD` is detached.
+    fn current_branch(&self) -> CoreResult<Option<String>>;
+    fn rev_list(&self, range: &str) -> CoreResult<Vec<String>>;
+    /// Most recent tag reachable from HEAD; `None` when there are no tags.
+    fn last_tag(&self) -> CoreResult<Option<String>>;
+    /// Full messages of the last `limit` commits on `HEAD`, newest first.
+    fn recent_messages(&self, limit: usize) -> CoreResult<Vec<String>>;
+    fn commit(&self, message: &str, no_verify: bool) -> CoreResult<String>;
+    /// The `i18n.commitEncoding` git records commit messages under; `None` when unset.
+    fn commit_encoding(&self) -> CoreResult<Option<String>>;
+    fn short_head(&self) -> CoreResult<String>;
+    fn add_note(&self, rev: &str, note: &str) -> CoreResult<()>;
+    /// Run a hook such as `pre-commit` against the current index; `None` when it is not installed.
+    fn run_hook(&self, name: &str) -> CoreResult<Option<HookOutput>>;
     fn push(&self) -> CoreResult<String>;
+    /// `push` with the terminal attached and git's credential prompts allowed, for retrying
+    /// a push that failed because it needed credentials.
+    fn push_with_prompt(&self) -> CoreResult<()>;
+    /// `git pull --rebase --autostash` from the branch `push` targets.
+    fn pull_rebase(&self) -> CoreResult<String>;
+    /// Apply the inverse of `sha` to the index and working tree without committing.
+    ///
+    /// Returns the full hash of the reverted commit.
+    fn revert_no_commit(&self, sha: &str) -> CoreResult<String>;
+    /// `git revert --abort`: drop an uncommitted revert and restore `HEAD`.
+    fn abort_revert(&self) -> CoreResult<()>;
+    /// Whether `refs/tags/<name>` exists.
+    fn tag_exists(&self, name: &str) -> CoreResult<bool>;
+    /// Create the annotated tag `name` on `HEAD`. Git's message cleanup is off, so `#` lines
+    /// and blank lines in `message` are kept.
+    fn create_annotated_tag(&self, name: &str, message: &str) -> CoreResult<()>;
+    /// Push one tag to the remote `push` would use.
+    fn push_tag(&self, name: &str) -> CoreResult<String>;
 }
 
 #[derive(Debug, Default, Clone, Copy)]
-pub struct SystemGit;
+pub struct SystemGit {
+    reword_head: bool,
+}
 
 impl SystemGit {
     #[must_use]
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Backend for `--amend-reword`: the staged-change methods describe HEAD's own diff,
+    /// and `commit` replaces HEAD's message without changing its tree.
+    #[must_use]
+    pub fn reword_head() -> Self {
+        Self { reword_head: true }
+    }
+}
+
+/// Arguments for a diff of the staged changes, or of HEAD against its first parent
+/// (every file added for a root commit) when rewording HEAD.
+fn diff_args<'a>(reword_head: bool, options: &[&'a str], paths: &[&'a str]) -> Vec<&'a str> {
+    let mut args = if reword_head {
+        vec![
+            "diff-tree",
+            "-r",
+            "--root",
+            "--no-commit-id",
+            "--diff-merges=first-parent",
+        ]
+    } else {
+        vec!["diff", "--staged"]
+    };
+    args.extend_from_slice(options);
+    if reword_head {
+        args.push("HEAD");
     }
+    args.push("--");
+    args.extend_from_slice(paths);
+    args
+}
+
+/// Arguments for committing `message`. Rewording amends HEAD with `--only` and no
+/// paths, so whatever is staged stays staged and HEAD keeps its tree.
+fn commit_args(message: &str, no_verify: bool, reword_head: bool) -> Vec<&str> {
+    let mut args = vec!["commit"];
+    if reword_head {
+        args.extend(["--amend", "--only"]);
+    }
+    args.extend(["-m", message]);
+    if no_verify {
+        args.push("--no-verify");
+    }
+    args
 }
 
 impl GitBackend for SystemGit {
@@ -82,6 +185,13 @@ impl GitBackend for SystemGit {
     }
 
     fn stage_all(&self) -> CoreResult<()> {
+        // `git add .` fails outright when it matches paths outside a sparse checkout, so a
+        // sparse repo stages the in-cone candidates by name instead.
+        if self.sparse_cone()?.is_some() {
+            let root = self.repo_root()?;
+            let candidates = self.stage_candidates()?;
+            return add_paths(Some(&root), &candidates);
+        }
         run_git_status(["add", "."])
             .map_err(|err| CoreError::Git(format!("failed to stage files: {err}")))
     }
@@ -92,18 +202,8 @@ impl GitBackend for SystemGit {
     }
 
     fn stage_paths(&self, paths: &[String]) -> CoreResult<()> {
-        if paths.is_empty() {
-            return Ok(());
-        }
-
-        let mut args: Vec<std::ffi::OsString> = Vec::with_capacity(paths.len() + 2);
-        args.push("add".into());
-        args.push("--".into());
-        for path in paths {
-            args.push(path.into());
-        }
-
-        run_git_status(args).map_err(|err| CoreError::Git(format!("failed to stage files: {err}")))
+        let paths = filter_sparse(self.sparse_cone()?, paths.to_vec());
+        add_paths(None, &paths)
     }
 
     fn unstage_all(&self) -> CoreResult<()> {
@@ -112,25 +212,26 @@ impl GitBackend for SystemGit {
     }
 
     fn staged_diff(&self) -> CoreResult<String> {
-        let output = run_git(["diff", "--staged"])?;
+        let output = run_git(diff_args(
+            self.reword_head,
+            &["-p", "--no-color", "--no-ext-diff"],
+            &[],
+        ))?;
         Ok(String::from_utf8(output.stdout)?.trim().to_string())
     }
 
     fn staged_diff_for_path(&self, path: &str, max_bytes: u64) -> CoreResult<GitDiff> {
-        let args = [
-            "diff",
-            "--staged",
-            "--no-color",
-            "--no-ext-diff",
-            "--",
-            path,
-        ];
+        let args = diff_args(
+            self.reword_head,
+            &["-p", "--no-color", "--no-ext-diff"],
+            &[path],
+        );
         let (content, truncated) = run_git_capture_limit(&args, max_bytes)?;
         Ok(GitDiff { content, truncated })
     }
 
     fn staged_files(&self) -> CoreResult<Vec<String>> {
-        let output = run_git(["diff", "--staged", "--name-only", "-z", "--"])?;
+        let output = run_git(diff_args(self.reword_head, &["--name-only", "-z"], &[]))?;
         let entries = output
             .stdout
             .split(|byte| *byte == 0)
@@ -142,7 +243,7 @@ impl GitBackend for SystemGit {
     }
 
     fn staged_numstat(&self) -> CoreResult<Vec<GitFileStat>> {
-        let output = run_git(["diff", "--staged", "--numstat", "--"])?;
+        let output = run_git(diff_args(self.reword_head, &["--numstat"], &[]))?;
         let stdout = String::from_utf8(output.stdout)?;
         let mut stats = Vec::new();
 
@@ -173,6 +274,34 @@ impl GitBackend for SystemGit {
         Ok(stats)
     }
 
+    fn has_staged_changes(&self) -> CoreResult<bool> {
+        let output = run_git_raw(diff_args(self.reword_head, &["--quiet"], &[]))?;
+        match output.status.code() {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            _ => Err(CoreError::Git(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )),
+        }
+    }
+
+    fn staged_fingerprint(&self) -> CoreResult<String> {
+        let output = if self.reword_head {
+            run_git(["rev-parse", "HEAD^{tree}"])?
+        } else {
+            run_git(["write-tree"])?
+        };
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+
+    fn tracked_files(&self) -> CoreResult<Vec<String>> {
+        let output = run_git(["ls-files"])?;
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .map(str::to_string)
+            .collect())
+    }
+
     fn working_tree_files(&self) -> CoreResult<Vec<String>> {
         let mut files = Vec::new();
 
@@ -196,7 +325,7 @@ impl GitBackend for SystemGit {
 
         files.sort();
         files.dedup();
-        Ok(files)
+        Ok(filter_sparse(self.sparse_cone()?, files))
     }
 
     fn has_unstaged_changes(&self) -> CoreResult<bool> {
@@ -205,41 +334,251 @@ impl GitBackend for SystemGit {
         Ok(!stdout.trim().is_empty())
     }
 
-    fn commit(&self, message: &str, edit: bool, no_verify: bool) -> CoreResult<String> {
-        let mut args = vec!["commit", "-m", message];
-        if edit {
-            args.push("-e");
+    fn status(&self) -> CoreResult<RepoStatus> {
+        porcelain_status(&[])
+    }
+
+    fn stage_candidates(&self) -> CoreResult<Vec<String>> {
+        let candidates = porcelain_status(&["--", "."])?.working_tree_paths();
+        Ok(filter_sparse(self.sparse_cone()?, candidates))
+    }
+
+    fn sparse_cone(&self) -> CoreResult<Option<Vec<String>>> {
+        let enabled = |key: &str| {
+            run_git_raw(["config", "--bool", key])
+                .ok()
+                .and_then(|output| String::from_utf8(output.stdout).ok())
+                .is_some_and(|out| out.trim() == "true")
+        };
+        if !enabled("core.sparseCheckout") || !enabled("core.sparseCheckoutCone") {
+            return Ok(None);
+        }
+        let output = run_git(["sparse-checkout", "list"])?;
+        Ok(Some(
+            String::from_utf8(output.stdout)?
+                .lines()
+                .map(|line| line.trim().trim_end_matches('/').to_string())
+                .filter(|line| !line.is_empty())
+                .collect(),
+        ))
+    }
+
+    fn commit_message(&self, rev: &str) -> CoreResult<String> {
+        let output = run_git(["log", "-1", "--format=%B", rev, "--"])
+            .map_err(|err| CoreError::Git(format!("failed to read commit {rev}: {err}")))?;
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+
+    fn head_subject(&self) -> CoreResult<Option<String>> {
+        let output = run_git_raw(["log", "-1", "--format=%s"])?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let subject = String::from_utf8(output.stdout)?.trim().to_string();
+        Ok((!subject.is_empty()).then_some(subject))
+    }
+
+    fn worktree_file(&self, path: &str) -> CoreResult<Option<String>> {
+        let root = std::fs::canonicalize(self.repo_root()?)?;
+        let file = match std::fs::canonicalize(root.join(path)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        // Symlinks and `..` must not lead outside the repository.
+        if !file.starts_with(&root) {
+            return Err(CoreError::Git(format!(
+                "{path} resolves outside the repository"
+            )));
+        }
+        match std::fs::read(file) {
+            Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
         }
-        if no_verify {
-            args.push("--no-verify");
+    }
+
+    fn current_branch(&self) -> CoreResult<Option<String>> {
+        let output = run_git_raw(["symbolic-ref", "--short", "-q", "HEAD"])?;
+        if !output.status.success() {
+            return Ok(None);
         }
+        let branch = String::from_utf8(output.stdout)?.trim().to_string();
+        Ok((!branch.is_empty()).then_some(branch))
+    }
 
-        run_git_output(&args)
+    fn rev_list(&self, range: &str) -> CoreResult<Vec<String>> {
+        let output = run_git(["rev-list", "--reverse", range, "--"])
+            .map_err(|err| CoreError::Git(format!("failed to list commits in {range}: {err}")))?;
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .map(str::to_string)
+            .collect())
     }
 
-    fn push(&self) -> CoreResult<String> {
-        let upstream = run_git_raw(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+    fn last_tag(&self) -> CoreResult<Option<String>> {
+        let output = run_git_raw(["describe", "--tags", "--abbrev=0"])?;
+        if output.status.success() {
+            let tag = String::from_utf8(output.stdout)?.trim().to_string();
+            return Ok((!tag.is_empty()).then_some(tag));
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // "No names found" with no tags at all; "cannot describe" before the first commit.
+        if stderr.contains("No names found") || stderr.contains("cannot describe") {
+            return Ok(None);
+        }
+        Err(CoreError::Git(format!(
+            "failed to find the last tag: {}",
+            stderr.trim()
+        )))
+    }
+
+    fn recent_messages(&self, limit: usize) -> CoreResult<Vec<String>> {
+        let output = run_git_raw(["log", "-n", &limit.to_string(), "--format=%B%x00"])?;
+        if !output.status.success() {
+            // No commits yet.
+            return Ok(Vec::new());
+        }
+        Ok(String::from_utf8(output.stdout)?
+            .split('\0')
+            .map(str::trim)
+            .filter(|message| !message.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn commit(&self, message: &str, no_verify: bool) -> CoreResult<String> {
+        run_git_output(&commit_args(message, no_verify, self.reword_head))
+    }
+
+    fn commit_encoding(&self) -> CoreResult<Option<String>> {
+        Ok(run_git_raw(["config", "--get", "i18n.commitEncoding"])
             .ok()
             .and_then(|output| String::from_utf8(output.stdout).ok())
             .map(|out| out.trim().to_string())
-            .filter(|out| !out.is_empty());
+            .filter(|out| !out.is_empty()))
+    }
+
+    fn short_head(&self) -> CoreResult<String> {
+        let output = run_git(["rev-parse", "--short", "HEAD"])?;
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
 
-        if upstream.is_some() {
-            return run_git_output(&["push"]);
+    fn add_note(&self, rev: &str, note: &str) -> CoreResult<()> {
+        run_git(["notes", "add", "-f", "-m", note, rev])
+            .map(|_| ())
+            .map_err(|err| CoreError::Git(format!("failed to add note: {err}")))
+    }
+
+    fn run_hook(&self, name: &str) -> CoreResult<Option<HookOutput>> {
+        // `--git-path` honors `core.hooksPath` and worktrees.
+        let output = run_git(["rev-parse", "--git-path", &format!("hooks/{name}")])?;
+        let hook_path = PathBuf::from(String::from_utf8(output.stdout)?.trim());
+        if !hook_path.is_file() {
+            return Ok(None);
         }
 
-        let branch_output = run_git(["rev-parse", "--abbrev-ref", "HEAD"])?;
-        let branch = String::from_utf8(branch_output.stdout)?.trim().to_string();
+        let mut output = run_git_raw(["hook", "run", name])?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() && stderr.contains("is not a git command") {
+            // `git hook` needs git 2.36; older versions run the script from the repo root.
+            output = Command::new(&hook_path)
+                .current_dir(self.repo_root()?)
+                .output()
+                .map_err(|err| CoreError::Git(format!("failed to run {name} hook: {err}")))?;
+        }
 
-        let remotes_output = run_git(["remote"])?;
-        let remotes = String::from_utf8(remotes_output.stdout)?;
-        let remote = remotes
-            .lines()
-            .find(|line| !line.trim().is_empty())
-            .map(|line| line.trim().to_string())
-            .ok_or_else(|| CoreError::Git("no git remotes found".to_string()))?;
+        let combined = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(Some(HookOutput {
+            success: output.status.success(),
+            output: combined.trim().to_string(),
+        }))
+    }
+
+    fn push(&self) -> CoreResult<String> {
+        match push_target()? {
+            Some((remote, branch)) => run_git_output(&["push", &remote, &branch]),
+            None => run_git_output(&["push"]),
+        }
+    }
+
+    fn push_with_prompt(&self) -> CoreResult<()> {
+        let mut args = vec!["push".to_string()];
+        if let Some((remote, branch)) = push_target()? {
+            args.extend([remote, branch]);
+        }
+        let status = Command::new("git")
+            .env_remove("GIT_TERMINAL_PROMPT")
+            .env("GIT_PAGER", "cat")
+            .args(&args)
+            .status()
+            .map_err(|err| CoreError::Git(format!("failed to run git command: {err}")))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(CoreError::Git(format!("git push exited with {status}")))
+        }
+    }
+
+    fn pull_rebase(&self) -> CoreResult<String> {
+        match push_target()? {
+            Some((remote, branch)) => {
+                run_git_output(&["pull", "--rebase", "--autostash", &remote, &branch])
+            }
+            None => run_git_output(&["pull", "--rebase", "--autostash"]),
+        }
+    }
+
+    fn revert_no_commit(&self, sha: &str) -> CoreResult<String> {
+        let output = run_git([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{sha}^{{commit}}"),
+        ])
+        .map_err(|_| CoreError::Git(format!("not a commit: {sha}")))?;
+        let full = String::from_utf8(output.stdout)?.trim().to_string();
+        let output = run_git_raw(["revert", "--no-commit", &full])?;
+        if !output.status.success() {
+            let combined = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return Err(CoreError::Git(combined.trim().to_string()));
+        }
+        Ok(full)
+    }
+
+    fn abort_revert(&self) -> CoreResult<()> {
+        run_git(["revert", "--abort"])
+            .map(|_| ())
+            .map_err(|err| CoreError::Git(format!("failed to abort the revert: {err}")))
+    }
+
+    fn tag_exists(&self, name: &str) -> CoreResult<bool> {
+        let output = run_git_raw([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/tags/{name}"),
+        ])?;
+        Ok(output.status.success())
+    }
 
-        run_git_output(&["push", &remote, &branch])
+    fn create_annotated_tag(&self, name: &str, message: &str) -> CoreResult<()> {
+        run_git(["tag", "-a", "--cleanup=verbatim", "-m", message, name])
+            .map(|_| ())
+            .map_err(|err| CoreError::Git(format!("failed to create tag {name}: {err}")))
+    }
+
+    fn push_tag(&self, name: &str) -> CoreResult<String> {
+        let remote = tag_remote()?;
+        run_git_output(&["push", &remote, &format!("refs/tags/{name}")])
     }
 }
 
@@ -263,6 +602,160 @@ where
     }
 }
 
+/// Whether git rejected a push because the remote has commits the local branch lacks.
+#[must_use]
+pub fn is_non_fast_forward(push_error: &str) -> bool {
+    push_error.contains("[rejected]")
+        && (push_error.contains("non-fast-forward") || push_error.contains("fetch first"))
+}
+
+/// Whether a push failed because the remote wanted credentials git could not ask for, or
+/// rejected the ones it had.
+#[must_use]
+pub fn is_auth_failure(push_error: &str) -> bool {
+    [
+        "could not read Username",
+        "could not read Password",
+        "terminal prompts disabled",
+        "Authentication failed",
+        "Permission denied (publickey",
+    ]
+    .iter()
+    .any(|marker| push_error.contains(marker))
+}
+
+/// Whether `path` (repo-relative) is checked out by a cone-mode sparse checkout of `dirs`.
+///
+/// Cone mode always includes files at the root and directly inside each parent of a
+/// listed directory, plus everything below a listed directory.
+#[must_use]
+pub fn in_sparse_cone(path: &str, dirs: &[String]) -> bool {
+    let Some((parent, _)) = path.rsplit_once('/') else {
+        return true;
+    };
+    dirs.iter()
+        .any(|dir| path.starts_with(&format!("{dir}/")) || dir.starts_with(&format!("{parent}/")))
+}
+
+fn filter_sparse(cone: Option<Vec<String>>, paths: Vec<String>) -> Vec<String> {
+    match cone {
+        Some(dirs) => paths
+            .into_iter()
+            .filter(|path| in_sparse_cone(path, &dirs))
+            .collect(),
+        None => paths,
+    }
+}
+
+/// `git add -- <paths>`, run from `root` when given; a no-op for no paths.
+fn add_paths(root: Option<&Path>, paths: &[String]) -> CoreResult<()> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let mut args: Vec<std::ffi::OsString> = Vec::with_capacity(paths.len() + 4);
+    if let Some(root) = root {
+        args.push("-C".into());
+        args.push(root.into());
+    }
+    args.push("add".into());
+    args.push("--".into());
+    for path in paths {
+        args.push(path.into());
+    }
+
+    run_git_status(args).map_err(|err| CoreError::Git(format!("failed to stage files: {err}")))
+}
+
+/// Whether a git encoding name means UTF-8, the encoding goodcommit writes messages in.
+#[must_use]
+pub fn is_utf8_encoding(encoding: &str) -> bool {
+    matches!(encoding.to_ascii_lowercase().as_str(), "utf-8" | "utf8")
+}
+
+/// Whether `git revert` stopped because the inverse patch conflicts with later changes.
+#[must_use]
+pub fn is_revert_conflict(revert_error: &str) -> bool {
+    revert_error.contains("CONFLICT") || revert_error.contains("could not revert")
+}
+
+/// Remote and branch for a branch without an upstream, or `None` to let a plain
+/// `git push` decide (an upstream exists, or `push.autoSetupRemote` will create one).
+fn push_target() -> CoreResult<Option<(String, String)>> {
+    let upstream = run_git_raw(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|out| out.trim().to_string())
+        .filter(|out| !out.is_empty());
+    if upstream.is_some() {
+        return Ok(None);
+    }
+
+    let auto_setup = run_git_raw(["config", "--bool", "push.autoSetupRemote"])
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .is_some_and(|out| out.trim() == "true");
+    if auto_setup {
+        return Ok(None);
+    }
+
+    let branch_output = run_git(["rev-parse", "--abbrev-ref", "HEAD"])?;
+    let branch = String::from_utf8(branch_output.stdout)?.trim().to_string();
+
+    let remotes_output = run_git(["remote"])?;
+    let remotes = String::from_utf8(remotes_output.stdout)?;
+    let remote = remotes
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| line.trim().to_string())
+        .ok_or_else(|| CoreError::Git("no git remotes found".to_string()))?;
+
+    Ok(Some((remote, branch)))
+}
+
+/// The branch's push remote or upstream remote, else the first configured remote.
+fn tag_remote() -> CoreResult<String> {
+    let branch = run_git_raw(["symbolic-ref", "--short", "-q", "HEAD"])
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|out| out.trim().to_string())
+        .filter(|out| !out.is_empty());
+    let mut keys = Vec::new();
+    if let Some(branch) = &branch {
+        keys.push(format!("branch.{branch}.pushRemote"));
+    }
+    keys.push("remote.pushDefault".to_string());
+    if let Some(branch) = &branch {
+        keys.push(format!("branch.{branch}.remote"));
+    }
+    for key in keys {
+        let configured = run_git_raw(["config", "--get", &key])
+            .ok()
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|out| out.trim().to_string())
+            .filter(|out| !out.is_empty());
+        if let Some(remote) = configured {
+            return Ok(remote);
+        }
+    }
+
+    let remotes = String::from_utf8(run_git(["remote"])?.stdout)?;
+    remotes
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| CoreError::Git("no git remotes found".to_string()))
+}
+
+fn porcelain_status(pathspec: &[&str]) -> CoreResult<RepoStatus> {
+    let mut args = vec!["status", "--porcelain=v2", "-z", "--untracked-files=all"];
+    args.extend_from_slice(pathspec);
+    let output =
+        run_git(args).map_err(|err| CoreError::Git(format!("failed to read git status: {err}")))?;
+    parse_porcelain_v2(&String::from_utf8(output.stdout)?)
+}
+
 fn run_git_raw<I, S>(args: I) -> CoreResult<Output>
 where
     I: IntoIterator<Item = S>,
@@ -389,3 +882,70 @@ fn run_git_capture_limit(args: &[&str], max_bytes: u64) -> CoreResult<(String, b
     let content = String::from_utf8(buffer)?.trim().to_string();
     Ok((content, truncated))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_cone_includes_root_files_listed_dirs_and_their_parents() {
+        let dirs = vec!["a/x".to_string()];
+        assert!(in_sparse_cone("README.md", &dirs));
+        assert!(in_sparse_cone("a/x/n.rs", &dirs));
+        assert!(in_sparse_cone("a/x/deep/n.rs", &dirs));
+        assert!(in_sparse_cone("a/top.rs", &dirs));
+        assert!(!in_sparse_cone("a/y/n.rs", &dirs));
+        assert!(!in_sparse_cone("b/new.txt", &dirs));
+        assert!(!in_sparse_cone("a/xy/n.rs", &dirs));
+    }
+
+    #[test]
+    fn reword_amends_only_the_message() {
+        assert_eq!(
+            commit_args("fix: typo", false, false),
+            ["commit", "-m", "fix: typo"]
+        );
+        assert_eq!(
+            commit_args("fix: typo", true, true),
+            [
+                "commit",
+                "--amend",
+                "--only",
+                "-m",
+                "fix: typo",
+                "--no-verify"
+            ]
+        );
+    }
+
+    #[test]
+    fn reword_diffs_head_instead_of_the_index() {
+        assert_eq!(
+            diff_args(false, &["--numstat"], &[]),
+            ["diff", "--staged", "--numstat", "--"]
+        );
+        assert_eq!(
+            diff_args(true, &["-p", "--no-color"], &["src/lib.rs"]),
+            [
+                "diff-tree",
+                "-r",
+                "--root",
+                "--no-commit-id",
+                "--diff-merges=first-parent",
+                "-p",
+                "--no-color",
+                "HEAD",
+                "--",
+                "src/lib.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn utf8_encoding_names_are_recognized_in_any_case() {
+        assert!(is_utf8_encoding("UTF-8"));
+        assert!(is_utf8_encoding("utf8"));
+        assert!(!is_utf8_encoding("ISO-8859-1"));
+        assert!(!is_utf8_encoding("Shift_JIS"));
+    }
+}
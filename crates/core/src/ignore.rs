@@ -11,6 +11,42 @@ pub struct IgnoreMatcher {
 }
 
 impl IgnoreMatcher {
+    /// A matcher that ignores nothing, used to bypass the ignore patterns.
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            globset: GlobSet::empty(),
+        }
+    }
+
+    /// Whether this matcher ignores nothing, as with `--include-ignored`.
+    #[must_use]
+    pub fn matches_nothing(&self) -> bool {
+        self.globset.is_empty()
+    }
+
+    /// A matcher for exactly `patterns`, without the default ignore list.
+    ///
+    /// # Errors
+    /// Returns an error when the patterns cannot be compiled.
+    pub fn from_patterns(patterns: &[String]) -> CoreResult<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            if pattern.trim().is_empty() {
+                continue;
+            }
+            if let Ok(glob) = Glob::new(pattern) {
+                builder.add(glob);
+            }
+        }
+
+        let globset = builder
+            .build()
+            .map_err(|err| CoreError::Config(format!("invalid ignore patterns: {err}")))?;
+
+        Ok(Self { globset })
+    }
+
     #[must_use]
     pub fn is_ignored(&self, path: &str) -> bool {
         self.globset.is_match(path)
@@ -36,21 +72,17 @@ pub fn build_ignore_matcher(
 
     patterns.extend(config_patterns.iter().cloned());
 
-    let mut builder = GlobSetBuilder::new();
-    for pattern in &patterns {
-        if pattern.trim().is_empty() {
-            continue;
-        }
-        if let Ok(glob) = Glob::new(pattern) {
-            builder.add(glob);
-        }
-    }
-
-    let globset = builder
-        .build()
-        .map_err(|err| CoreError::Config(format!("invalid ignore patterns: {err}")))?;
+    IgnoreMatcher::from_patterns(&patterns)
+}
 
-    Ok(IgnoreMatcher { globset })
+/// Patterns that are not valid globs; [`build_ignore_matcher`] skips these.
+#[must_use]
+pub fn invalid_patterns(patterns: &[String]) -> Vec<String> {
+    patterns
+        .iter()
+        .filter(|pattern| Glob::new(pattern).is_err())
+        .cloned()
+        .collect()
 }
 
 pub fn read_ignore_file(path: &Path) -> Vec<String> {
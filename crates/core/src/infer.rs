@@ -0,0 +1,738 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::diff::DiffFile;
+use crate::language::language_for_path;
+
+/// Share of the changed lines a suggestion must explain before it is treated as reliable.
+pub const CONFIDENT_SHARE: f32 = 0.75;
+
+/// A commit type suggested by looking at the staged diff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeSuggestion {
+    pub kind: &'static str,
+    /// Fraction (0..=1) of changed lines that point at this type.
+    pub confidence: f32,
+    pub reason: &'static str,
+}
+
+impl TypeSuggestion {
+    #[must_use]
+    pub fn is_confident(&self) -> bool {
+        self.confidence >= CONFIDENT_SHARE
+    }
+}
+
+/// Rank likely commit types for the given diffs, best first.
+///
+/// Only types in `allowed` are returned. Files that give no signal still count toward the
+/// total, so a single hint in a large unrelated change stays low-confidence.
+#[must_use]
+pub fn suggest_types(files: &[DiffFile], allowed: &[String]) -> Vec<TypeSuggestion> {
+    let mut scores: BTreeMap<&'static str, (u32, &'static str)> = BTreeMap::new();
+    let mut total = 0u32;
+
+    for file in files {
+        let lines = ChangedLines::parse(&file.content);
+        let weight = u32::try_from(lines.added.len() + lines.removed.len())
+            .unwrap_or(u32::MAX)
+            .max(1);
+        total = total.saturating_add(weight);
+
+        if let Some((kind, reason)) = classify_file(&file.path, &lines) {
+            let entry = scores.entry(kind).or_insert((0, reason));
+            entry.0 = entry.0.saturating_add(weight);
+        }
+    }
+
+    if total == 0 {
+        return Vec::new();
+    }
+
+    let mut suggestions = scores
+        .into_iter()
+        .filter(|(kind, _)| allowed.iter().any(|allowed| allowed == kind))
+        .map(|(kind, (score, reason))| TypeSuggestion {
+            kind,
+            #[allow(clippy::cast_precision_loss)]
+            confidence: score as f32 / total as f32,
+            reason,
+        })
+        .collect::<Vec<_>>();
+    suggestions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    suggestions
+}
+
+/// Best-guess type for a reply that has none: a confident suggestion, `feat` when source
+/// files changed, else `chore`. `None` when the guess is not an allowed type.
+#[must_use]
+pub fn guess_type(files: &[DiffFile], allowed: &[String]) -> Option<&'static str> {
+    let kind = match suggest_types(files, allowed).first() {
+        Some(top) if top.is_confident() => top.kind,
+        _ if files
+            .iter()
+            .any(|file| language_for_path(&file.path).is_some()) =>
+        {
+            "feat"
+        }
+        _ => "chore",
+    };
+    allowed
+        .iter()
+        .any(|allowed| allowed == kind)
+        .then_some(kind)
+}
+
+/// A change set that only reformats code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormattingChange {
+    /// Formatter whose config file is part of the change, e.g. `rustfmt`.
+    pub formatter: Option<&'static str>,
+}
+
+/// Formatter config files, which may change alongside the code they reformat.
+const FORMATTER_CONFIGS: &[(&str, &str)] = &[
+    ("rustfmt.toml", "rustfmt"),
+    (".rustfmt.toml", "rustfmt"),
+    (".prettierrc", "prettier"),
+    (".clang-format", "clang-format"),
+    ("ruff.toml", "ruff"),
+    (".ruff.toml", "ruff"),
+    ("biome.json", "biome"),
+    (".swift-format", "swift-format"),
+];
+
+/// Detect a change set where every diff only changes whitespace, apart from formatter
+/// config files. Truncated diffs and changes without added or removed lines never count.
+#[must_use]
+pub fn detect_formatting_only(files: &[DiffFile]) -> Option<FormattingChange> {
+    let mut formatter = None;
+    let mut reformatted = false;
+    for file in files {
+        if let Some(name) = formatter_for_config(&file.path) {
+            formatter = Some(name);
+        } else if !file.truncated && is_whitespace_only(&file.path, &file.content) {
+            reformatted = true;
+        } else {
+            return None;
+        }
+    }
+    reformatted.then_some(FormattingChange { formatter })
+}
+
+fn formatter_for_config(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+    if name.starts_with(".prettierrc") || name.starts_with("prettier.config.") {
+        return Some("prettier");
+    }
+    FORMATTER_CONFIGS
+        .iter()
+        .find(|(file, _)| *file == name)
+        .map(|(_, formatter)| *formatter)
+}
+
+/// Every hunk's removed and added lines are equal once whitespace is normalized, so
+/// reindenting, rewrapping and blank line changes all count. Whitespace between two word
+/// characters still separates them, so `let x` and `letx` differ. Where indentation is
+/// syntax (Python, YAML, Makefiles), only trailing whitespace, line endings and blank
+/// lines may change. A comma before a closing bracket is ignored too, since formatters
+/// add trailing commas when wrapping.
+fn is_whitespace_only(path: &str, diff: &str) -> bool {
+    let normalize = if indentation_is_syntax(path) {
+        trim_line_ends
+    } else {
+        squash_whitespace
+    };
+    let same = |removed: &str, added: &str| {
+        let (removed, added) = (normalize(removed), normalize(added));
+        trailing_comma_regex().replace_all(&removed, "$1")
+            == trailing_comma_regex().replace_all(&added, "$1")
+    };
+    let mut changed = false;
+    let mut removed = String::new();
+    let mut added = String::new();
+    for line in diff.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            continue;
+        }
+        if line.starts_with("@@") {
+            if !same(&removed, &added) {
+                return false;
+            }
+            removed.clear();
+            added.clear();
+        } else if let Some(line) = line.strip_prefix('+') {
+            added.push_str(line);
+            added.push('\n');
+            changed = true;
+        } else if let Some(line) = line.strip_prefix('-') {
+            removed.push_str(line);
+            removed.push('\n');
+            changed = true;
+        }
+    }
+    changed && same(&removed, &added)
+}
+
+fn indentation_is_syntax(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+    let extension = Path::new(&name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+    matches!(extension, "py" | "pyi" | "yaml" | "yml" | "mk")
+        || matches!(name.as_str(), "makefile" | "gnumakefile")
+}
+
+/// Drop whitespace, keeping one space wherever it separated two word characters.
+fn squash_whitespace(text: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut squashed = String::with_capacity(text.len());
+    let mut gap = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            gap = true;
+            continue;
+        }
+        if gap && is_word(c) && squashed.chars().next_back().is_some_and(is_word) {
+            squashed.push(' ');
+        }
+        squashed.push(c);
+        gap = false;
+    }
+    squashed
+}
+
+/// Drop trailing whitespace (including `\r`) and blank lines, keeping indentation.
+fn trim_line_ends(text: &str) -> String {
+    text.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn trailing_comma_regex() -> &'static Regex {
+    static RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r",([)\]}])").expect("invalid regex"));
+    &RE
+}
+
+fn classify_file(path: &str, lines: &ChangedLines) -> Option<(&'static str, &'static str)> {
+    if let Some(hint) = classify_path(path) {
+        return Some(hint);
+    }
+
+    let language = language_for_path(path);
+    if lines.is_empty() {
+        return None;
+    }
+    if lines.all(|line, in_block| line.trim().is_empty() || is_comment(line, in_block, language)) {
+        return Some(("docs", "comment-only changes"));
+    }
+    if lines
+        .added
+        .iter()
+        .any(|line| adds_public_api(line, language))
+    {
+        return Some(("feat", "new public API"));
+    }
+    if lines.is_rename_only() {
+        return Some(("refactor", "renamed identifiers only"));
+    }
+    if lines.added.is_empty() {
+        return Some(("refactor", "removed code only"));
+    }
+    None
+}
+
+fn classify_path(path: &str) -> Option<(&'static str, &'static str)> {
+    let lower = path.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or(&lower);
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+    let in_dir = |dir: &str| lower.split('/').rev().skip(1).any(|part| part == dir);
+
+    if in_dir("tests")
+        || in_dir("test")
+        || in_dir("__tests__")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+        || name.contains("_test.")
+        || name.starts_with("test_")
+    {
+        return Some(("test", "test files"));
+    }
+    if matches!(extension, "md" | "mdx" | "rst" | "adoc") || in_dir("docs") {
+        return Some(("docs", "documentation files"));
+    }
+    if lower.starts_with(".github/workflows/")
+        || lower.starts_with(".circleci/")
+        || name == ".gitlab-ci.yml"
+    {
+        return Some(("ci", "CI configuration"));
+    }
+    if matches!(
+        name,
+        "cargo.toml" | "build.rs" | "package.json" | "makefile" | "pyproject.toml" | "go.mod"
+    ) {
+        return Some(("build", "build configuration"));
+    }
+    None
+}
+
+/// Added and removed lines of a unified diff, without the `+`/`-` markers.
+struct ChangedLines<'a> {
+    added: Vec<&'a str>,
+    removed: Vec<&'a str>,
+    /// Per added and removed line: whether it sits inside a `/* … */` block opened
+    /// earlier in its hunk.
+    added_in_block: Vec<bool>,
+    removed_in_block: Vec<bool>,
+}
+
+impl<'a> ChangedLines<'a> {
+    fn parse(diff: &'a str) -> Self {
+        let mut lines = Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            added_in_block: Vec::new(),
+            removed_in_block: Vec::new(),
+        };
+        // Block comment state in the old and new file; unknown before a hunk, so outside.
+        let (mut old_block, mut new_block) = (false, false);
+        for line in diff.lines() {
+            if line.starts_with("+++") || line.starts_with("---") {
+                continue;
+            }
+            if line.starts_with("@@") {
+                (old_block, new_block) = (false, false);
+            } else if let Some(line) = line.strip_prefix('+') {
+                lines.added.push(line);
+                lines.added_in_block.push(new_block);
+                new_block = block_after(line, new_block);
+            } else if let Some(line) = line.strip_prefix('-') {
+                lines.removed.push(line);
+                lines.removed_in_block.push(old_block);
+                old_block = block_after(line, old_block);
+            } else {
+                let line = line.strip_prefix(' ').unwrap_or(line);
+                old_block = block_after(line, old_block);
+                new_block = block_after(line, new_block);
+            }
+        }
+        lines
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Whether `predicate(line, in_block)` holds for every added and removed line.
+    fn all(&self, predicate: impl Fn(&str, bool) -> bool) -> bool {
+        self.added
+            .iter()
+            .zip(&self.added_in_block)
+            .chain(self.removed.iter().zip(&self.removed_in_block))
+            .all(|(line, in_block)| predicate(line, *in_block))
+    }
+
+    /// Removed and added lines match once identifiers are masked, but differ as written.
+    fn is_rename_only(&self) -> bool {
+        let normalize = |lines: &[&str]| {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+                let masked = identifier_regex()
+                    .replace_all(line.trim(), "_")
+                    .into_owned();
+                *counts.entry(masked).or_default() += 1;
+            }
+            counts
+        };
+        !self.added.is_empty()
+            && normalize(&self.added) == normalize(&self.removed)
+            && self.added != self.removed
+    }
+}
+
+/// Whether a block comment is still open after `line`, given whether it was open before.
+fn block_after(line: &str, in_block: bool) -> bool {
+    let opened = line.rfind("/*");
+    let closed = line.rfind("*/");
+    match (opened, closed) {
+        (Some(open), Some(close)) => open > close,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => in_block,
+    }
+}
+
+/// Whether `line` is a comment; `in_block` says a `/* … */` block is open, where lines
+/// such as ` * details` and ` */` belong to the comment.
+fn is_comment(line: &str, in_block: bool, language: Option<&str>) -> bool {
+    let trimmed = line.trim_start();
+    let (prefixes, block_comments): (&[&str], bool) = match language {
+        Some("python" | "ruby" | "shell" | "elixir") => (&["#", "\"\"\"", "'''"], false),
+        Some("sql") => (&["--"], false),
+        // `#` starts attributes and preprocessor lines in C-like languages.
+        Some(_) => (&["//", "/*"], true),
+        None => (&["//", "/*", "#"], true),
+    };
+    (block_comments && in_block) || prefixes.iter().any(|prefix| trimmed.starts_with(prefix))
+}
+
+fn adds_public_api(line: &str, language: Option<&str>) -> bool {
+    let pattern = match language {
+        Some("rust") => &*RUST_PUBLIC,
+        Some("typescript" | "javascript") => &*JS_EXPORT,
+        Some("python") => &*PYTHON_DEF,
+        Some("go") => &*GO_EXPORTED,
+        Some("java" | "kotlin" | "csharp" | "swift" | "scala" | "dart") => &*JVM_PUBLIC,
+        _ => return false,
+    };
+    pattern.is_match(line)
+}
+
+static RUST_PUBLIC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*pub\s+(async\s+)?(fn|struct|enum|trait|type|const)\s").expect("invalid regex")
+});
+static JS_EXPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*export\s+(default\s+)?(async\s+)?(function|class|const|interface|type)\b")
+        .expect("invalid regex")
+});
+// Top-level only: methods and helpers are indented, private names start with `_`.
+static PYTHON_DEF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(async\s+)?(def|class)\s+[A-Za-z]").expect("invalid regex"));
+static GO_EXPORTED: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^func\s+(\([^)]*\)\s*)?[A-Z]").expect("invalid regex"));
+static JVM_PUBLIC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(public|open)\s+[\w<>\[\], ]*\(|^\s*public\s+(class|interface|enum)\s")
+        .expect("invalid regex")
+});
+
+fn identifier_regex() -> &'static Regex {
+    static RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("invalid regex"));
+    &RE
+}
+
+/// Directories that hold packages rather than name one, skipped when picking a scope.
+const CONTAINER_DIRS: &[&str] = &[
+    "apps", "cmd", "crates", "internal", "lib", "libs", "modules", "packages", "pkg", "services",
+    "src",
+];
+
+/// Count paths per top-level directory (`dir/`) or root file, largest first.
+#[must_use]
+pub fn group_by_top_dir(paths: &[String]) -> Vec<(String, usize)> {
+    let mut dirs: HashMap<String, usize> = HashMap::new();
+    for path in paths {
+        let top = match path.split_once('/') {
+            Some((dir, _)) => format!("{dir}/"),
+            None => path.clone(),
+        };
+        *dirs.entry(top).or_default() += 1;
+    }
+    let mut grouped = dirs.into_iter().collect::<Vec<_>>();
+    grouped.sort_by(|(a_dir, a_count), (b_dir, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_dir.cmp(b_dir))
+    });
+    grouped
+}
+
+/// A conventional scope shared by every path, e.g. `core` for files under `crates/core/`.
+///
+/// Uses the first directory the paths have in common that is not a container such as
+/// `crates/` or `src/`. `None` when the paths share no such directory.
+#[must_use]
+pub fn suggest_scope(paths: &[String]) -> Option<String> {
+    if group_by_top_dir(paths).len() != 1 {
+        return None;
+    }
+    let mut common: Vec<&str> = paths.first()?.split('/').collect();
+    common.pop();
+    for path in &paths[1..] {
+        let dirs = path.split('/').collect::<Vec<_>>();
+        let dirs = &dirs[..dirs.len() - 1];
+        let shared = common
+            .iter()
+            .zip(dirs)
+            .take_while(|(left, right)| left == right)
+            .count();
+        common.truncate(shared);
+    }
+
+    let dir = common
+        .into_iter()
+        .find(|dir| !CONTAINER_DIRS.contains(dir) && !dir.starts_with('.'))?;
+    let scope = dir
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect::<String>();
+    (!scope.is_empty()).then_some(scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::default_conventional_types;
+
+    fn file(path: &str, content: &str) -> DiffFile {
+        DiffFile {
+            path: path.to_string(),
+            content: content.to_string(),
+            is_binary: false,
+            truncated: false,
+            additions: 0,
+            deletions: 0,
+            token_estimate: 0,
+        }
+    }
+
+    fn top(files: &[DiffFile]) -> Option<(&'static str, bool)> {
+        suggest_types(files, &default_conventional_types())
+            .first()
+            .map(|suggestion| (suggestion.kind, suggestion.is_confident()))
+    }
+
+    #[test]
+    fn comment_only_changes_suggest_docs() {
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n-// old note\n+// Explain the cache.\n+\n fn a() {}\n";
+        assert_eq!(top(&[file("src/lib.rs", diff)]), Some(("docs", true)));
+
+        let python = "@@ -1 +1,2 @@\n+# why this exists\n+\"\"\"Module docs.\"\"\"\n";
+        assert_eq!(top(&[file("app/main.py", python)]), Some(("docs", true)));
+
+        let block = "@@ -1,4 +1,4 @@\n /**\n- * Old summary.\n+ * New summary.\n  */\n";
+        assert_eq!(top(&[file("web/api.ts", block)]), Some(("docs", true)));
+    }
+
+    #[test]
+    fn star_lines_outside_a_block_comment_are_code() {
+        let pointer = "@@ -1,2 +1,2 @@\n-*count = 0;\n+*count = 1;\n";
+        assert_ne!(top(&[file("src/main.c", pointer)]), Some(("docs", true)));
+
+        let markdown = "@@ -1 +1 @@\n-* old item\n+* new item\n";
+        assert_ne!(
+            top(&[file("notes/list.txt", markdown)]),
+            Some(("docs", true))
+        );
+
+        let closed = "@@ -1,3 +1,3 @@\n /* note */\n-*ptr = 0;\n+*ptr = 1;\n";
+        assert_ne!(top(&[file("src/main.c", closed)]), Some(("docs", true)));
+    }
+
+    #[test]
+    fn rename_only_changes_suggest_refactor() {
+        let diff = "@@ -1,3 +1,3 @@\n-let count = items.len();\n-println!(\"{count}\");\n+let total = items.len();\n+println!(\"{total}\");\n";
+        assert_eq!(top(&[file("src/main.rs", diff)]), Some(("refactor", true)));
+    }
+
+    #[test]
+    fn literal_changes_are_not_renames() {
+        let diff = "@@ -1 +1 @@\n-let limit = 10;\n+let limit = 20;\n";
+        assert_eq!(top(&[file("src/main.rs", diff)]), None);
+    }
+
+    #[test]
+    fn new_public_api_suggests_feat_per_language() {
+        let cases = [
+            ("src/lib.rs", "+pub fn search(query: &str) {}\n"),
+            ("src/lib.rs", "+pub async fn fetch() {}\n"),
+            ("web/api.ts", "+export async function search() {}\n"),
+            ("web/api.js", "+export default class Client {}\n"),
+            ("pkg/search.py", "+def search(query):\n+    return []\n"),
+            ("pkg/search.go", "+func (s *Server) Search() {}\n"),
+            (
+                "src/Search.java",
+                "+    public List<String> search(String q) {\n",
+            ),
+        ];
+        for (path, diff) in cases {
+            assert_eq!(
+                top(&[file(path, diff)]).map(|(kind, _)| kind),
+                Some("feat"),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn private_additions_are_not_features() {
+        let cases = [
+            ("src/lib.rs", "+pub(crate) fn helper() {}\n"),
+            ("src/lib.rs", "+fn helper() {}\n"),
+            ("pkg/search.py", "+    def _helper(self):\n"),
+            ("pkg/search.go", "+func helper() {}\n"),
+        ];
+        for (path, diff) in cases {
+            assert_eq!(top(&[file(path, diff)]), None, "{path}");
+        }
+    }
+
+    #[test]
+    fn paths_suggest_test_docs_ci_and_build() {
+        assert_eq!(top(&[file("tests/cli.rs", "+x\n")]), Some(("test", true)));
+        assert_eq!(
+            top(&[file("web/app.test.ts", "+x\n")]),
+            Some(("test", true))
+        );
+        assert_eq!(top(&[file("README.md", "+x\n")]), Some(("docs", true)));
+        assert_eq!(
+            top(&[file(".github/workflows/ci.yml", "+x\n")]),
+            Some(("ci", true))
+        );
+        assert_eq!(top(&[file("Cargo.toml", "+x\n")]), Some(("build", true)));
+    }
+
+    #[test]
+    fn mixed_changes_lower_confidence() {
+        let files = [
+            file("README.md", "+one line\n"),
+            file("src/lib.rs", "+let a = 1;\n+let b = 2;\n+let c = 3;\n"),
+        ];
+        let suggestions = suggest_types(&files, &default_conventional_types());
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].kind, "docs");
+        assert!(!suggestions[0].is_confident());
+    }
+
+    #[test]
+    fn guess_type_prefers_confident_suggestions_then_code_then_chore() {
+        let types = default_conventional_types();
+        assert_eq!(
+            guess_type(&[file("README.md", "+x\n")], &types),
+            Some("docs")
+        );
+        let edit = "@@ -1 +1 @@\n-let limit = 10;\n+let limit = 20;\n";
+        assert_eq!(
+            guess_type(&[file("src/main.rs", edit)], &types),
+            Some("feat")
+        );
+        assert_eq!(
+            guess_type(&[file("config/app.ini", "+x = 1\n")], &types),
+            Some("chore")
+        );
+        assert_eq!(
+            guess_type(&[file("config/app.ini", "+x = 1\n")], &["fix".to_string()]),
+            None
+        );
+    }
+
+    #[test]
+    fn whitespace_only_changes_are_formatting() {
+        let reindent = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,3 +1,3 @@\n fn a() {\n-  let x = 1;\n+    let x = 1;\n }\n@@ -9,2 +9,4 @@\n-call(one, two);\n+call(\n+    one,\n+    two,\n+);\n";
+        assert!(detect_formatting_only(&[file("src/lib.rs", reindent)]).is_some());
+
+        let blank_lines = "@@ -1,2 +1,3 @@\n a\n+\n b\n";
+        let change = detect_formatting_only(&[
+            file("web/app.ts", "@@ -1 +1 @@\n-const a=1\n+const a = 1\n"),
+            file("web/util.ts", blank_lines),
+            file(".prettierrc.json", "@@ -1 +1 @@\n-{}\n+{\"semi\": false}\n"),
+        ]);
+        assert_eq!(
+            change,
+            Some(FormattingChange {
+                formatter: Some("prettier")
+            })
+        );
+    }
+
+    #[test]
+    fn mixed_or_config_only_changes_are_not_formatting() {
+        let reformat = file("src/lib.rs", "@@ -1 +1 @@\n-let a=1;\n+let a = 1;\n");
+        let edit = file("src/main.rs", "@@ -1 +1 @@\n-let a = 1;\n+let a = 2;\n");
+        assert!(detect_formatting_only(&[reformat.clone(), edit]).is_none());
+
+        let moved = "@@ -1,2 +1,2 @@\n-a\n+b\n@@ -5,2 +5,2 @@\n-b\n+a\n";
+        assert!(detect_formatting_only(&[file("src/lib.rs", moved)]).is_none());
+
+        let config = file("rustfmt.toml", "@@ -0,0 +1 @@\n+max_width = 80\n");
+        assert!(detect_formatting_only(std::slice::from_ref(&config)).is_none());
+        assert_eq!(
+            detect_formatting_only(&[config, reformat.clone()]),
+            Some(FormattingChange {
+                formatter: Some("rustfmt")
+            })
+        );
+
+        let truncated = DiffFile {
+            truncated: true,
+            ..reformat
+        };
+        assert!(detect_formatting_only(&[truncated]).is_none());
+        assert!(detect_formatting_only(&[file("src/old.rs", "")]).is_none());
+    }
+
+    #[test]
+    fn whitespace_that_separates_words_or_indents_python_is_not_formatting() {
+        let joined = file(
+            "src/lib.rs",
+            "@@ -1 +1 @@
+-let x = 1;
++letx = 1;
+",
+        );
+        assert!(detect_formatting_only(&[joined]).is_none());
+
+        let dedent = "@@ -1,3 +1,3 @@
+ if ready:
+-    run()
++run()
+";
+        assert!(detect_formatting_only(&[file("app/main.py", dedent)]).is_none());
+
+        let trailing = "@@ -1,2 +1,2 @@
+ if ready:
+-    run()  \r
++    run()
+";
+        assert!(detect_formatting_only(&[file("app/main.py", trailing)]).is_some());
+    }
+
+    #[test]
+    fn disallowed_types_are_dropped() {
+        let allowed = vec!["feat".to_string(), "fix".to_string()];
+        assert!(suggest_types(&[file("README.md", "+x\n")], &allowed).is_empty());
+    }
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| (*path).to_string()).collect()
+    }
+
+    #[test]
+    fn suggest_scope_skips_container_directories() {
+        assert_eq!(
+            suggest_scope(&paths(&[
+                "crates/core/src/lib.rs",
+                "crates/core/Cargo.toml"
+            ])),
+            Some("core".to_string())
+        );
+        assert_eq!(
+            suggest_scope(&paths(&["docs/usage.md", "docs/img/flow.png"])),
+            Some("docs".to_string())
+        );
+        assert_eq!(
+            suggest_scope(&paths(&["packages/Web-App/src/a.ts"])),
+            Some("web-app".to_string())
+        );
+    }
+
+    #[test]
+    fn suggest_scope_needs_a_shared_directory() {
+        assert_eq!(
+            suggest_scope(&paths(&["crates/core/a.rs", "crates/cli/b.rs"])),
+            None
+        );
+        assert_eq!(suggest_scope(&paths(&["src/a.rs", "src/b.rs"])), None);
+        assert_eq!(suggest_scope(&paths(&["docs/a.md", "README.md"])), None);
+        assert_eq!(suggest_scope(&paths(&["README.md"])), None);
+        assert_eq!(suggest_scope(&[]), None);
+    }
+}
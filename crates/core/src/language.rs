@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const EXTENSION_LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("mts", "typescript"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("mjs", "javascript"),
+    ("cjs", "javascript"),
+    ("py", "python"),
+    ("go", "go"),
+    ("java", "java"),
+    ("kt", "kotlin"),
+    ("kts", "kotlin"),
+    ("swift", "swift"),
+    ("rb", "ruby"),
+    ("php", "php"),
+    ("cs", "csharp"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cc", "cpp"),
+    ("cpp", "cpp"),
+    ("hpp", "cpp"),
+    ("scala", "scala"),
+    ("ex", "elixir"),
+    ("exs", "elixir"),
+    ("dart", "dart"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("sql", "sql"),
+];
+
+/// Non-code formats that still deserve their own summary guidance.
+const EXTENSION_FORMATS: &[(&str, &str)] = &[
+    ("yml", "yaml"),
+    ("yaml", "yaml"),
+    ("toml", "toml"),
+    ("json", "json"),
+    ("md", "markdown"),
+    ("tf", "terraform"),
+    ("proto", "protobuf"),
+    ("graphql", "graphql"),
+];
+
+/// Extra summary prompt lines per language; `summary_hints` in config replaces an entry.
+const SUMMARY_GUIDANCE: &[(&str, &[&str])] = &[
+    (
+        "sql",
+        &["For migrations, name the tables and columns that are created, altered, or dropped."],
+    ),
+    (
+        "yaml",
+        &["For CI or deployment config, name the jobs, steps, or services affected."],
+    ),
+    (
+        "toml",
+        &["Name the settings or dependencies that were added, removed, or changed."],
+    ),
+    (
+        "json",
+        &["Name the keys or dependencies that were added, removed, or changed."],
+    ),
+    (
+        "markdown",
+        &["Say which sections of the document changed; do not restate the prose."],
+    ),
+    (
+        "terraform",
+        &["Name the resources and attributes affected."],
+    ),
+    (
+        "rust",
+        &["Call out public items (fns, types, traits) that were added, removed, or changed signature."],
+    ),
+    (
+        "typescript",
+        &["Call out exported functions, types, and components whose signature changed."],
+    ),
+    (
+        "python",
+        &["Call out public functions and classes whose signature changed."],
+    ),
+    (
+        "go",
+        &["Call out exported identifiers whose signature changed."],
+    ),
+];
+
+/// Map a path to its programming language using the file extension.
+#[must_use]
+pub fn language_for_path(path: &str) -> Option<&'static str> {
+    let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+    EXTENSION_LANGUAGES
+        .iter()
+        .find(|(candidate, _)| *candidate == ext)
+        .map(|(_, language)| *language)
+}
+
+/// Map a path to a language or file format for per-file summary prompts.
+#[must_use]
+pub fn file_kind_for_path(path: &str) -> Option<&'static str> {
+    language_for_path(path).or_else(|| {
+        let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+        EXTENSION_FORMATS
+            .iter()
+            .find(|(candidate, _)| *candidate == ext)
+            .map(|(_, format)| *format)
+    })
+}
+
+/// Summary guidance for `kind`, preferring `overrides` over the built-in table.
+#[must_use]
+pub fn summary_guidance(kind: &str, overrides: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    if let Some(lines) = overrides.get(kind) {
+        return lines.clone();
+    }
+    SUMMARY_GUIDANCE
+        .iter()
+        .find(|(candidate, _)| *candidate == kind)
+        .map(|(_, lines)| lines.iter().map(|line| (*line).to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Pick the most common language across the given paths.
+#[must_use]
+pub fn dominant_language(paths: &[String]) -> Option<&'static str> {
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    for path in paths {
+        if let Some(language) = language_for_path(path) {
+            *counts.entry(language).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .max_by(|(a_lang, a_count), (b_lang, b_count)| {
+            a_count.cmp(b_count).then_with(|| b_lang.cmp(a_lang))
+        })
+        .map(|(language, _)| language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dominant_language_uses_extension_histogram() {
+        let paths = vec![
+            "src/main.rs".to_string(),
+            "src/lib.rs".to_string(),
+            "web/app.ts".to_string(),
+            "README.md".to_string(),
+        ];
+        assert_eq!(dominant_language(&paths), Some("rust"));
+    }
+
+    #[test]
+    fn dominant_language_ignores_unknown_extensions() {
+        let paths = vec!["README.md".to_string(), "Makefile".to_string()];
+        assert_eq!(dominant_language(&paths), None);
+    }
+
+    #[test]
+    fn file_kind_covers_code_and_config_formats() {
+        assert_eq!(file_kind_for_path("migrations/001_init.sql"), Some("sql"));
+        assert_eq!(file_kind_for_path(".github/workflows/ci.yml"), Some("yaml"));
+        assert_eq!(file_kind_for_path("src/lib.rs"), Some("rust"));
+        assert_eq!(file_kind_for_path("Makefile"), None);
+    }
+
+    #[test]
+    fn summary_guidance_can_be_overridden() {
+        let mut overrides = BTreeMap::new();
+        assert!(summary_guidance("sql", &overrides)[0].contains("tables and columns"));
+        assert!(summary_guidance("kotlin", &overrides).is_empty());
+
+        overrides.insert("sql".to_string(), vec!["Mention the schema.".to_string()]);
+        overrides.insert("kotlin".to_string(), Vec::new());
+        assert_eq!(
+            summary_guidance("sql", &overrides),
+            vec!["Mention the schema."]
+        );
+        assert!(summary_guidance("kotlin", &overrides).is_empty());
+    }
+}
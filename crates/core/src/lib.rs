@@ -10,7 +10,16 @@ pub mod diff;
 pub mod error;
 pub mod git;
 pub mod ignore;
+pub mod infer;
+pub mod language;
+pub mod lint;
 pub mod pipeline;
+pub mod pricing;
 pub mod prompt;
 pub mod providers;
 pub mod retry;
+pub mod stats;
+pub mod status;
+pub mod style;
+mod template;
+pub mod timing;
@@ -0,0 +1,355 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::config::EffectiveConfig;
+
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    pub conventional: bool,
+    pub max_subject_length: usize,
+    pub body_wrap: usize,
+    pub types: Vec<String>,
+}
+
+impl LintConfig {
+    #[must_use]
+    pub fn from_config(config: &EffectiveConfig) -> Self {
+        Self {
+            conventional: config.conventional,
+            max_subject_length: config.max_subject_length,
+            body_wrap: config.body_wrap,
+            types: config.conventional_types.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: &'static str,
+    pub message: String,
+    /// 1-based line of the offending text.
+    pub line: usize,
+    /// 1-based column (in characters) of the offending text.
+    pub column: usize,
+}
+
+impl Violation {
+    fn new(rule: &'static str, message: String, line: usize, column: usize) -> Self {
+        Self {
+            rule,
+            message,
+            line,
+            column,
+        }
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.rule, self.message
+        )
+    }
+}
+
+const NON_IMPERATIVE_EXCEPTIONS: &[&str] = &[
+    "bed", "embed", "exceed", "feed", "need", "proceed", "seed", "shed", "speed", "succeed",
+    "bring", "ping", "ring", "sing", "string", "thing",
+];
+
+const THIRD_PERSON_VERBS: &[&str] = &[
+    "adds",
+    "bumps",
+    "changes",
+    "cleans",
+    "creates",
+    "deletes",
+    "fixes",
+    "implements",
+    "improves",
+    "makes",
+    "moves",
+    "refactors",
+    "removes",
+    "renames",
+    "updates",
+    "uses",
+];
+
+/// Check a commit message against the configured rules without modifying it.
+#[must_use]
+pub fn check_message(message: &str, config: &LintConfig) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let lines = message.trim_end().lines().collect::<Vec<_>>();
+    let subject = lines.first().copied().unwrap_or("");
+
+    if subject.trim().is_empty() {
+        violations.push(Violation::new(
+            "empty",
+            "message has no subject line".to_string(),
+            1,
+            1,
+        ));
+        return violations;
+    }
+
+    if let Some((line, column)) = find_char(&lines, '`') {
+        violations.push(Violation::new(
+            "markdown",
+            "message contains backticks or code fences".to_string(),
+            line,
+            column,
+        ));
+    }
+
+    let trimmed = subject.trim();
+    if trimmed.len() > 1 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+        violations.push(Violation::new(
+            "quotes",
+            "subject is wrapped in quotes".to_string(),
+            1,
+            1,
+        ));
+    }
+
+    if config.conventional {
+        check_conventional(subject, config, &mut violations);
+    }
+
+    if let Some((word, column)) = non_imperative_word(subject, config.conventional) {
+        violations.push(Violation::new(
+            "imperative",
+            format!("subject should use the imperative mood (found `{word}`)"),
+            1,
+            column,
+        ));
+    }
+
+    let subject_len = subject.chars().count();
+    if config.max_subject_length > 0 && subject_len > config.max_subject_length {
+        violations.push(Violation::new(
+            "subject-length",
+            format!(
+                "subject is {subject_len} characters (max {})",
+                config.max_subject_length
+            ),
+            1,
+            config.max_subject_length + 1,
+        ));
+    }
+
+    if lines.len() > 1 && !lines[1].trim().is_empty() {
+        violations.push(Violation::new(
+            "body-separator",
+            "subject and body must be separated by a blank line".to_string(),
+            2,
+            1,
+        ));
+    }
+
+    if config.body_wrap > 0 {
+        for (index, line) in lines.iter().enumerate().skip(1) {
+            let len = line.chars().count();
+            if len > config.body_wrap {
+                violations.push(Violation::new(
+                    "body-wrap",
+                    format!("line is {len} characters (wrap at {})", config.body_wrap),
+                    index + 1,
+                    config.body_wrap + 1,
+                ));
+            }
+        }
+    }
+
+    violations
+}
+
+fn check_conventional(subject: &str, config: &LintConfig, violations: &mut Vec<Violation>) {
+    let Some(caps) = conventional_regex().captures(subject.trim()) else {
+        violations.push(Violation::new(
+            "conventional",
+            "subject does not match `<type>(<scope>): <subject>`".to_string(),
+            1,
+            1,
+        ));
+        return;
+    };
+
+    let kind = caps.get(1).map_or("", |kind| kind.as_str());
+    if !config.types.iter().any(|allowed| allowed == kind) {
+        violations.push(Violation::new(
+            "type",
+            format!(
+                "type `{kind}` is not allowed (expected one of: {})",
+                config.types.join(", ")
+            ),
+            1,
+            1,
+        ));
+    }
+
+    if let Some(scope) = caps.get(2) {
+        let name = scope.as_str().trim_matches(|c| c == '(' || c == ')');
+        if name.chars().any(char::is_uppercase) {
+            violations.push(Violation::new(
+                "scope",
+                format!("scope `{name}` should be lowercase"),
+                1,
+                scope.start() + 2,
+            ));
+        }
+    }
+}
+
+/// The first word of the description when it is clearly not imperative.
+fn non_imperative_word(subject: &str, conventional: bool) -> Option<(String, usize)> {
+    // A malformed header is already reported by the `conventional` rule.
+    let description_start = if conventional {
+        subject.find(": ")? + 2
+    } else {
+        0
+    };
+    let description = &subject[description_start..];
+    let offset = description.len() - description.trim_start().len();
+    let word = description
+        .split_whitespace()
+        .next()?
+        .trim_matches(|c: char| !c.is_alphabetic());
+    let lower = word.to_lowercase();
+
+    let non_imperative = if NON_IMPERATIVE_EXCEPTIONS.contains(&lower.as_str()) {
+        false
+    } else {
+        (lower.len() > 4 && (lower.ends_with("ed") || lower.ends_with("ing")))
+            || THIRD_PERSON_VERBS.contains(&lower.as_str())
+    };
+    if !non_imperative {
+        return None;
+    }
+
+    let column = subject[..description_start + offset].chars().count() + 1;
+    Some((word.to_string(), column))
+}
+
+fn find_char(lines: &[&str], needle: char) -> Option<(usize, usize)> {
+    lines.iter().enumerate().find_map(|(index, line)| {
+        line.chars()
+            .position(|c| c == needle)
+            .map(|column| (index + 1, column + 1))
+    })
+}
+
+/// Whether a subject line is a conventional commit header with an allowed type.
+#[must_use]
+pub fn is_conventional_subject(subject: &str, types: &[String]) -> bool {
+    conventional_type(subject).is_some_and(|kind| types.iter().any(|allowed| allowed == kind))
+}
+
+/// The line `git commit --verbose` puts above the diff; it and everything below is dropped.
+pub const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Drop git comment lines, anything below the scissors line, and surrounding whitespace
+/// from an edited message.
+#[must_use]
+pub fn strip_comments(raw: &str) -> String {
+    raw.lines()
+        .take_while(|line| *line != SCISSORS)
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn conventional_type(subject: &str) -> Option<&str> {
+    conventional_regex()
+        .captures(subject.trim())
+        .and_then(|caps| caps.get(1))
+        .map(|kind| kind.as_str())
+}
+
+fn conventional_regex() -> &'static Regex {
+    static RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^([a-z]+)(\([\w./-]+\))?: .+").expect("invalid regex"));
+    &RE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn lint_config() -> LintConfig {
+        LintConfig::from_config(&Config::defaults().resolve().expect("defaults resolve"))
+    }
+
+    fn rules(message: &str) -> Vec<&'static str> {
+        check_message(message, &lint_config())
+            .into_iter()
+            .map(|violation| violation.rule)
+            .collect()
+    }
+
+    #[test]
+    fn check_message_accepts_valid_message() {
+        assert!(rules("feat(cli): add lint command\n\nChecks messages in CI.").is_empty());
+    }
+
+    #[test]
+    fn check_message_reports_format_and_type() {
+        assert_eq!(rules("added stuff"), vec!["conventional"]);
+        assert_eq!(rules("wip: stuff"), vec!["type"]);
+        assert_eq!(rules("feat(CLI): add flag"), vec!["scope"]);
+    }
+
+    #[test]
+    fn check_message_reports_non_imperative_subjects() {
+        assert_eq!(rules("fix: fixed the parser"), vec!["imperative"]);
+        assert_eq!(rules("feat: adds search"), vec!["imperative"]);
+        assert!(rules("feat: embed fonts").is_empty());
+        assert!(rules("fix: string escaping").is_empty());
+    }
+
+    #[test]
+    fn violations_carry_positions() {
+        let violations = check_message("feat(api): updated `x`\n\nbody", &lint_config());
+        let positions = violations
+            .iter()
+            .map(|violation| (violation.rule, violation.line, violation.column))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![("markdown", 1, 20), ("imperative", 1, 12)]);
+        assert_eq!(violations[1].to_string().split(':').next(), Some("1"));
+    }
+
+    #[test]
+    fn check_message_reports_lengths_and_separator() {
+        let long_subject = format!("feat: {}", "a".repeat(60));
+        assert_eq!(rules(&long_subject), vec!["subject-length"]);
+
+        let body = format!("fix: wrap\nno blank line\n\n{}", "b".repeat(80));
+        assert_eq!(rules(&body), vec!["body-separator", "body-wrap"]);
+    }
+
+    #[test]
+    fn strip_comments_removes_git_comments() {
+        assert_eq!(
+            strip_comments("fix: a\n# Please enter the commit message\n"),
+            "fix: a"
+        );
+    }
+
+    #[test]
+    fn strip_comments_cuts_at_scissors() {
+        let raw = format!(
+            "fix: a\n\nbody\n{SCISSORS}\n# Do not modify or remove the line above.\ndiff --git a/x b/x\n+added\n"
+        );
+        assert_eq!(strip_comments(&raw), "fix: a\n\nbody");
+        assert_eq!(
+            strip_comments("fix: a\n# ------ >8 ------ not quite\nkept"),
+            "fix: a\nkept"
+        );
+    }
+}
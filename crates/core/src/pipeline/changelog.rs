@@ -0,0 +1,260 @@
+use tracing::warn;
+
+use crate::config::EffectiveConfig;
+use crate::diff::truncate_to_tokens;
+use crate::error::{CallPhase, CoreResult};
+use crate::git::GitBackend;
+use crate::prompt::{changelog_system_prompt, changelog_user_prompt, tag_system_prompt};
+use crate::providers::{Provider, ProviderRequest};
+
+use super::generation::{call_with_deadline, Deadline};
+use super::sanitize::conventional_type;
+
+/// Markdown release notes for a range of commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changelog {
+    pub markdown: String,
+    /// Commits in the range.
+    pub commits: usize,
+    /// The notes were grouped from commit subjects because the provider was unavailable or failed.
+    pub used_fallback: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Annotated tag message for a release: the tag name as the title, then grouped highlights.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagMessage {
+    pub message: String,
+    /// Commits since the previous tag.
+    pub commits: usize,
+    /// The highlights were grouped from commit subjects because the provider was unavailable or failed.
+    pub used_fallback: bool,
+    pub warnings: Vec<String>,
+}
+
+struct RangeCommit {
+    short: String,
+    message: String,
+}
+
+/// Layout of the generated notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NotesFormat {
+    /// `### Features` sections with a bullet per commit.
+    Markdown,
+    /// Plain `Features:` groups capped at [`TAG_HIGHLIGHTS`] bullets; git drops `#` lines from tags.
+    Tag,
+}
+
+impl NotesFormat {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Markdown => "changelog",
+            Self::Tag => "tag message",
+        }
+    }
+}
+
+/// Bullets per group in a tag message before the rest are counted as "more".
+const TAG_HIGHLIGHTS: usize = 5;
+
+/// Summarize the commits in `range` (any `git rev-list` range) as Markdown under `heading`.
+///
+/// Returns `None` when the range has no commits.
+///
+/// # Errors
+/// Returns an error if git access fails.
+pub async fn generate_changelog(
+    git: &impl GitBackend,
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    range: &str,
+    heading: &str,
+) -> CoreResult<Option<Changelog>> {
+    let commits = range_commits(git, range)?;
+    if commits.is_empty() {
+        return Ok(None);
+    }
+
+    let mut warnings = Vec::new();
+    let notes = ai_notes(
+        provider,
+        config,
+        &commits,
+        NotesFormat::Markdown,
+        &mut warnings,
+    )
+    .await;
+    let used_fallback = notes.is_none();
+    let notes = notes.unwrap_or_else(|| grouped_subjects(&commits, NotesFormat::Markdown));
+
+    Ok(Some(Changelog {
+        markdown: format!("## {heading}\n\n{}\n", notes.trim()),
+        commits: commits.len(),
+        used_fallback,
+        warnings,
+    }))
+}
+
+/// Message for the annotated tag `tag`, highlighting the commits in `range`.
+///
+/// Returns `None` when the range has no commits.
+///
+/// # Errors
+/// Returns an error if git access fails.
+pub async fn generate_tag_message(
+    git: &impl GitBackend,
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    range: &str,
+    tag: &str,
+) -> CoreResult<Option<TagMessage>> {
+    let commits = range_commits(git, range)?;
+    if commits.is_empty() {
+        return Ok(None);
+    }
+
+    let mut warnings = Vec::new();
+    let notes = ai_notes(provider, config, &commits, NotesFormat::Tag, &mut warnings).await;
+    let used_fallback = notes.is_none();
+    let notes = notes.unwrap_or_else(|| grouped_subjects(&commits, NotesFormat::Tag));
+
+    Ok(Some(TagMessage {
+        message: format!("{tag}\n\n{}", notes.trim()),
+        commits: commits.len(),
+        used_fallback,
+        warnings,
+    }))
+}
+
+/// Commits in `range`, oldest first, without merge commits.
+fn range_commits(git: &impl GitBackend, range: &str) -> CoreResult<Vec<RangeCommit>> {
+    let mut commits = Vec::new();
+    for sha in git.rev_list(range)? {
+        let message = git.commit_message(&sha)?;
+        // Merge commits add nothing the merged commits do not already say.
+        if message.starts_with("Merge ") {
+            continue;
+        }
+        commits.push(RangeCommit {
+            short: sha[..sha.len().min(7)].to_string(),
+            message: message.trim().to_string(),
+        });
+    }
+    Ok(commits)
+}
+
+/// The provider's notes, or `None` (with a warning) when the subjects should be used instead.
+async fn ai_notes(
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    commits: &[RangeCommit],
+    format: NotesFormat,
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let Some(provider) = provider else {
+        warnings.push("provider unavailable, using commit subjects".to_string());
+        return None;
+    };
+    let label = format.label();
+    match summarize(provider, config, commits, format).await {
+        Ok(notes) if !notes.trim().is_empty() => Some(notes),
+        Ok(_) => {
+            warnings.push(format!("ai {label} was empty, using commit subjects"));
+            None
+        }
+        Err(err) => {
+            warn!("{label} generation failed: {err}");
+            warnings.push(format!("ai {label} failed, using commit subjects: {err}"));
+            None
+        }
+    }
+}
+
+async fn summarize(
+    provider: &dyn Provider,
+    config: &EffectiveConfig,
+    commits: &[RangeCommit],
+    format: NotesFormat,
+) -> CoreResult<String> {
+    let listing = commits
+        .iter()
+        .map(|commit| {
+            format!(
+                "- {} {}",
+                commit.short,
+                commit.message.replace('\n', "\n  ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let listing = truncate_to_tokens(&listing, config.max_input_tokens as usize);
+    let request = ProviderRequest {
+        max_output_tokens: config.max_output_tokens,
+        temperature: config.temperature,
+        seed: config.seed,
+        response_schema: None,
+        stop: Vec::new(),
+    };
+    let (phase, system_prompt) = match format {
+        NotesFormat::Markdown => (CallPhase::Changelog, changelog_system_prompt()),
+        NotesFormat::Tag => (CallPhase::Tag, tag_system_prompt()),
+    };
+
+    call_with_deadline(
+        Deadline::new(config.timeout_secs),
+        phase,
+        None,
+        provider.complete(system_prompt, &changelog_user_prompt(&listing), request),
+    )
+    .await
+}
+
+/// Deterministic notes: commit subjects grouped by conventional type.
+fn grouped_subjects(commits: &[RangeCommit], format: NotesFormat) -> String {
+    let mut sections: [(&str, Vec<String>); 3] = [
+        ("Features", Vec::new()),
+        ("Fixes", Vec::new()),
+        ("Other", Vec::new()),
+    ];
+    for commit in commits {
+        let subject = commit.message.lines().next().unwrap_or_default();
+        let index = match conventional_type(subject) {
+            Some("feat") => 0,
+            Some("fix") => 1,
+            _ => 2,
+        };
+        let text = subject
+            .split_once(": ")
+            .filter(|_| index < 2)
+            .map_or(subject, |(_, rest)| rest);
+        let item = match format {
+            NotesFormat::Markdown => format!("- {text} ({})", commit.short),
+            NotesFormat::Tag => format!("- {text}"),
+        };
+        sections[index].1.push(item);
+    }
+
+    sections
+        .iter()
+        .filter(|(_, items)| !items.is_empty())
+        .map(|(title, items)| match format {
+            NotesFormat::Markdown => format!("### {title}\n\n{}", items.join("\n")),
+            NotesFormat::Tag => format!("{title}:\n{}", capped(items).join("\n")),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The first [`TAG_HIGHLIGHTS`] items, plus a line counting the rest.
+fn capped(items: &[String]) -> Vec<String> {
+    let mut shown = items
+        .iter()
+        .take(TAG_HIGHLIGHTS)
+        .cloned()
+        .collect::<Vec<_>>();
+    if items.len() > TAG_HIGHLIGHTS {
+        shown.push(format!("- and {} more", items.len() - TAG_HIGHLIGHTS));
+    }
+    shown
+}
@@ -1,15 +1,66 @@
+use std::cmp::Reverse;
+use std::path::{Component, Path};
+
 use crate::config::EffectiveConfig;
-use crate::diff::{estimate_tokens, truncate_lines, DiffFile};
+use crate::diff::{estimate_tokens, truncate_lines, truncate_to_tokens, DiffFile};
 use crate::error::CoreResult;
-use crate::git::GitBackend;
+use crate::git::{GitBackend, GitFileStat};
 use crate::ignore::IgnoreMatcher;
 
+use super::ignored::only_lockfiles;
+
 pub(super) struct DiffContext {
     pub(super) all_paths: Vec<String>,
+    /// Lines added and deleted across every staged file.
+    pub(super) additions: u32,
+    pub(super) deletions: u32,
     pub(super) ai_files: Vec<DiffFile>,
+    /// Staged files skipped because they match the ignore patterns.
+    pub(super) ignored: Vec<GitFileStat>,
+    /// Read-only reference files from `context_files`, in config order.
+    pub(super) context_files: Vec<DiffFile>,
     pub(super) warnings: Vec<String>,
 }
 
+impl DiffContext {
+    /// A context with nothing for the AI to read.
+    fn without_diff(
+        all_paths: Vec<String>,
+        ignored: Vec<GitFileStat>,
+        warnings: Vec<String>,
+    ) -> Self {
+        Self {
+            all_paths,
+            additions: 0,
+            deletions: 0,
+            ai_files: Vec::new(),
+            ignored,
+            context_files: Vec::new(),
+            warnings,
+        }
+    }
+}
+
+/// Down-ranked (`deprioritize`) diffs keep this fraction of `max_file_lines`.
+const DEPRIORITIZED_LINE_DIVISOR: u32 = 4;
+
+/// Biggest changes first, so `max_files` and the fallback keep them; a deleted module
+/// outranks a formatting pass of the same line count. Ignored files such as lockfiles rank
+/// after the rest however large they are.
+fn rank_stats(
+    mut stats: Vec<GitFileStat>,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+) -> Vec<GitFileStat> {
+    stats.sort_by_key(|stat| {
+        (
+            ignore.is_ignored(&stat.path),
+            Reverse(change_weight(stat, config.deletion_weight)),
+        )
+    });
+    stats
+}
+
 pub(super) fn collect_diff_context(
     git: &impl GitBackend,
     config: &EffectiveConfig,
@@ -17,31 +68,58 @@ pub(super) fn collect_diff_context(
 ) -> CoreResult<DiffContext> {
     let stats = git.staged_numstat()?;
     if stats.is_empty() {
-        return Ok(DiffContext {
-            all_paths: Vec::new(),
-            ai_files: Vec::new(),
-            warnings: Vec::new(),
-        });
+        return Ok(DiffContext::without_diff(
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        ));
     }
 
+    let stats = rank_stats(stats, config, ignore);
+
     let mut warnings = Vec::new();
     let all_paths = stats
         .iter()
         .map(|stat| stat.path.clone())
         .collect::<Vec<_>>();
+    let additions = stats.iter().map(|stat| stat.additions).sum();
+    let deletions = stats.iter().map(|stat| stat.deletions).sum();
+
+    // A pure dependency bump gets a deterministic message; its diff is noise to the model.
+    // `--include-ignored` asks for the lockfiles to be sent, so it skips the shortcut.
+    if config.skip_lockfile_only
+        && !ignore.matches_nothing()
+        && only_lockfiles(all_paths.iter().map(String::as_str))
+    {
+        return Ok(DiffContext {
+            additions,
+            deletions,
+            ..DiffContext::without_diff(all_paths, stats, warnings)
+        });
+    }
 
+    let allow_large = IgnoreMatcher::from_patterns(&config.allow_large)?;
+    let deprioritize = IgnoreMatcher::from_patterns(&config.deprioritize)?;
+    // Down-ranked files go last, so the model reads them after the rest and `max_files`
+    // drops them first.
+    let (stats, noise): (Vec<_>, Vec<_>) = stats
+        .into_iter()
+        .partition(|stat| !deprioritize.is_ignored(&stat.path));
     let mut ai_files = Vec::new();
+    let mut ignored = Vec::new();
     let mut hit_limit = false;
 
-    for stat in stats {
+    for stat in stats.into_iter().chain(noise) {
+        // Ignored files are listed even past `max_files`, so their count stays whole.
+        if ignore.is_ignored(&stat.path) {
+            ignored.push(stat);
+            continue;
+        }
         if ai_files.len() >= config.max_files {
             hit_limit = true;
-            break;
-        }
-        if stat.is_binary {
             continue;
         }
-        if ignore.is_ignored(&stat.path) {
+        if stat.is_binary {
             continue;
         }
 
@@ -49,49 +127,36 @@ pub(super) fn collect_diff_context(
         let additions = stat.additions;
         let deletions = stat.deletions;
         let change_lines = additions.saturating_add(deletions);
-        if change_lines > config.max_file_lines {
-            warnings.push(format!(
-                "diff omitted for {} ({} lines)",
-                &path, change_lines
-            ));
-            let content = format!(
-                "file {} changed: +{} -{} (diff omitted due to size)",
-                &path, additions, deletions
-            );
-            let token_estimate = estimate_tokens(&content);
-            ai_files.push(DiffFile {
-                path,
-                content,
-                is_binary: false,
-                truncated: true,
-                additions,
-                deletions,
-                token_estimate,
-            });
+        // Allowlisted files skip the line cap; `max_input_tokens` still bounds the prompt.
+        let allowed_large = allow_large.is_ignored(&path);
+        let line_cap = if allowed_large {
+            u32::MAX
+        } else {
+            config.max_file_lines
+        };
+        let down_ranked = !allowed_large && deprioritize.is_ignored(&path);
+        if change_lines > line_cap {
+            let counts = (additions, deletions);
+            if let Some(file) = oversized_diff_file(git, config, path, counts, &mut warnings)? {
+                ai_files.push(file);
+            }
             continue;
         }
 
-        let diff = git.staged_diff_for_path(&path, config.max_file_bytes)?;
-        let (content, truncated_by_lines) = truncate_lines(&diff.content, config.max_file_lines);
-        let truncated = diff.truncated || truncated_by_lines;
-        if content.trim().is_empty() {
+        let content_cap = if down_ranked {
+            (line_cap / DEPRIORITIZED_LINE_DIVISOR).max(1)
+        } else {
+            line_cap
+        };
+        let Some(file) = staged_diff_file(git, config, path, (additions, deletions), content_cap)?
+        else {
             continue;
+        };
+        // Cutting a down-ranked file short is expected, not worth a warning.
+        if file.truncated && !down_ranked {
+            warnings.push(format!("diff truncated for {}", &file.path));
         }
-
-        if truncated {
-            warnings.push(format!("diff truncated for {}", &path));
-        }
-
-        let token_estimate = estimate_tokens(&content);
-        ai_files.push(DiffFile {
-            path,
-            content,
-            is_binary: false,
-            truncated,
-            additions,
-            deletions,
-            token_estimate,
-        });
+        ai_files.push(file);
     }
 
     if hit_limit {
@@ -101,9 +166,164 @@ pub(super) fn collect_diff_context(
         ));
     }
 
+    let context_files = if ai_files.is_empty() {
+        Vec::new()
+    } else {
+        load_context_files(git, config, ignore, &mut warnings)
+    };
+
     Ok(DiffContext {
         all_paths,
+        additions,
+        deletions,
         ai_files,
+        ignored,
+        context_files,
         warnings,
     })
 }
+
+/// Relevance of a staged file: added lines plus `deletion_weight` times deleted lines.
+pub(super) fn change_weight(stat: &GitFileStat, deletion_weight: u32) -> u64 {
+    u64::from(stat.additions) + u64::from(deletion_weight) * u64::from(stat.deletions)
+}
+
+/// The staged diff of `path`, cut to `line_cap` lines; `None` when it is empty.
+fn staged_diff_file(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+    path: String,
+    (additions, deletions): (u32, u32),
+    line_cap: u32,
+) -> CoreResult<Option<DiffFile>> {
+    let diff = git.staged_diff_for_path(&path, config.max_file_bytes)?;
+    let (content, truncated_by_lines) = truncate_lines(&diff.content, line_cap);
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+    let token_estimate = estimate_tokens(&content);
+    Ok(Some(DiffFile {
+        path,
+        content,
+        is_binary: false,
+        truncated: diff.truncated || truncated_by_lines,
+        additions,
+        deletions,
+        token_estimate,
+    }))
+}
+
+/// A diff over the line cap: sent whole for the summary pass to split by hunk, or, with
+/// `summary_max_hunk_groups = 0`, replaced by [`omitted_diff_file`].
+fn oversized_diff_file(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+    path: String,
+    (additions, deletions): (u32, u32),
+    warnings: &mut Vec<String>,
+) -> CoreResult<Option<DiffFile>> {
+    let change_lines = additions.saturating_add(deletions);
+    if config.summary_max_hunk_groups == 0 {
+        warnings.push(format!("diff omitted for {path} ({change_lines} lines)"));
+        return Ok(Some(omitted_diff_file(path, additions, deletions)));
+    }
+    let file = staged_diff_file(git, config, path, (additions, deletions), u32::MAX)?;
+    if let Some(file) = &file {
+        warnings.push(format!(
+            "diff summarized hunk by hunk for {} ({change_lines} lines)",
+            file.path
+        ));
+    }
+    Ok(file)
+}
+
+/// Stand-in for a diff over the line cap: just the path and its line counts.
+fn omitted_diff_file(path: String, additions: u32, deletions: u32) -> DiffFile {
+    let content =
+        format!("file {path} changed: +{additions} -{deletions} (diff omitted due to size)");
+    let token_estimate = estimate_tokens(&content);
+    DiffFile {
+        path,
+        content,
+        is_binary: false,
+        truncated: true,
+        additions,
+        deletions,
+        token_estimate,
+    }
+}
+
+/// Token cap for each `context_files` entry.
+pub(super) const CONTEXT_FILE_TOKENS: usize = 1_000;
+
+/// Read `context_files` from the working tree. Entries outside the repo or matching the
+/// ignore patterns are skipped with a warning, since a repo config can name any path.
+fn load_context_files(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+    warnings: &mut Vec<String>,
+) -> Vec<DiffFile> {
+    let mut files = Vec::new();
+    for path in &config.context_files {
+        if !is_repo_relative(path) {
+            warnings.push(format!(
+                "context file {path} is outside the repository; skipped"
+            ));
+            continue;
+        }
+        if in_git_dir(path) {
+            warnings.push(format!("context file {path} is inside .git; skipped"));
+            continue;
+        }
+        if ignore.is_ignored(path) {
+            warnings.push(format!(
+                "context file {path} matches the ignore patterns; skipped"
+            ));
+            continue;
+        }
+        let content = match git.worktree_file(path) {
+            Ok(Some(content)) => content,
+            Ok(None) => {
+                warnings.push(format!("context file {path} not found"));
+                continue;
+            }
+            Err(err) => {
+                warnings.push(format!("context file {path} skipped: {err}"));
+                continue;
+            }
+        };
+        if content.trim().is_empty() {
+            continue;
+        }
+        let truncated_content = truncate_to_tokens(&content, CONTEXT_FILE_TOKENS);
+        // Compare lines, not bytes: CRLF endings come back as LF.
+        let truncated = truncated_content.lines().count() < content.trim_end().lines().count();
+        let token_estimate = estimate_tokens(&truncated_content);
+        files.push(DiffFile {
+            path: path.clone(),
+            content: truncated_content,
+            is_binary: false,
+            truncated,
+            additions: 0,
+            deletions: 0,
+            token_estimate,
+        });
+    }
+    files
+}
+
+/// Whether `path` stays inside the repo: relative, with no `..` components.
+fn is_repo_relative(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Whether `path` points into the repo's `.git` directory (config, hooks, and the like).
+fn in_git_dir(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .find(|component| !matches!(component, Component::CurDir))
+        .is_some_and(|component| component.as_os_str().eq_ignore_ascii_case(".git"))
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::config::EffectiveConfig;
+use crate::infer::group_by_top_dir;
+use crate::template::expand_placeholders;
+
+/// Why the deterministic fallback message was used instead of the model's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FallbackReason {
+    /// No provider could be set up, e.g. the API key is missing.
+    ProviderUnavailable,
+    /// Every staged file matches the ignore patterns.
+    AllIgnored,
+    /// Only lockfiles changed and `skip_lockfile_only` is set.
+    LockfilesOnly,
+    /// The staged files had no diff text to send, e.g. binaries or mode changes.
+    NoUsableDiff,
+    /// The provider call failed or ran out of time; holds the error.
+    ProviderFailed(String),
+    /// The model replied, but the reply could not be used.
+    Rejected(Rejection),
+}
+
+impl fmt::Display for FallbackReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProviderUnavailable => f.write_str("the provider is not set up"),
+            Self::AllIgnored => f.write_str("every staged file is ignored for AI"),
+            Self::LockfilesOnly => f.write_str("only lockfiles changed"),
+            Self::NoUsableDiff => f.write_str("the staged files have no text diff"),
+            Self::ProviderFailed(err) => write!(f, "the provider call failed: {err}"),
+            Self::Rejected(rejection) => write!(f, "the model's reply was rejected: {rejection}"),
+        }
+    }
+}
+
+/// Why sanitizing threw away the model's reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// Nothing was left after trimming quotes, fences, and whitespace.
+    Empty,
+    /// No line of the reply was a conventional subject with an allowed type.
+    NotConventional { first_line: String },
+    /// A `pr_draft` reply was JSON, but no commit message could be read from it.
+    NoCommitInJson,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("it was empty"),
+            Self::NotConventional { first_line } => write!(
+                f,
+                "no line was a conventional commit subject (first line: \"{first_line}\")"
+            ),
+            Self::NoCommitInJson => f.write_str("it was JSON without a commit message"),
+        }
+    }
+}
+
+/// Decision points recorded during one run, in order, to explain a fallback.
+#[derive(Debug, Default)]
+pub(super) struct Trace {
+    pub(super) steps: Vec<String>,
+    pub(super) reason: Option<FallbackReason>,
+    /// Latency of each `summary` and `final` provider call, for the stats file; the repair
+    /// call is timed where it is made.
+    pub(super) calls: Vec<(&'static str, Duration)>,
+}
+
+impl Trace {
+    pub(super) fn step(&mut self, step: impl Into<String>) {
+        self.steps.push(step.into());
+    }
+
+    pub(super) fn call_took(&mut self, call: &'static str, latency: Duration) {
+        self.calls.push((call, latency));
+    }
+
+    /// Record why the fallback is used; the first reason wins.
+    pub(super) fn fall_back(&mut self, reason: FallbackReason) {
+        if self.reason.is_none() {
+            self.reason = Some(reason);
+        }
+    }
+}
+
+/// Template used when `fallback_template` is unset; `chore: ` is added for conventional commits.
+const DEFAULT_TEMPLATE: &str = "update {files}";
+
+/// What the fallback placeholders expand to for one set of staged files.
+pub(super) struct FallbackFacts<'a> {
+    pub(super) paths: &'a [String],
+    pub(super) additions: u32,
+    pub(super) deletions: u32,
+}
+
+impl FallbackFacts<'_> {
+    fn value(&self, name: &str) -> Option<String> {
+        Some(match name {
+            "files" if self.paths.is_empty() => "files".to_string(),
+            "files" => self
+                .paths
+                .iter()
+                .take(3)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", "),
+            "count" => self.paths.len().to_string(),
+            "top_dir" => group_by_top_dir(self.paths).first().map_or_else(
+                || ".".to_string(),
+                |(dir, _)| dir.trim_end_matches('/').to_string(),
+            ),
+            "stats" => format!("+{} -{}", self.additions, self.deletions),
+            _ => return None,
+        })
+    }
+}
+
+/// The deterministic message used when the model's cannot be.
+///
+/// A custom `fallback_template` is used as written, with its subject cut to the subject budget.
+/// The default keeps its subject under 50 characters before the `chore: ` prefix.
+pub(super) fn fallback_message(facts: &FallbackFacts<'_>, config: &EffectiveConfig) -> String {
+    let render = |template: &str| {
+        expand_placeholders(template, |name| facts.value(name))
+            .unwrap_or_else(|_| template.to_string())
+    };
+    if let Some(template) = &config.fallback_template {
+        let message = render(template);
+        let (subject, body) = message
+            .split_once('\n')
+            .map_or((message.as_str(), None), |(subject, body)| {
+                (subject, Some(body))
+            });
+        let subject = truncate_chars(subject, config.subject_budget());
+        return match body {
+            Some(body) => format!("{subject}\n{body}"),
+            None => subject,
+        };
+    }
+
+    let subject = truncate_chars(&render(DEFAULT_TEMPLATE), config.subject_budget().min(50));
+    if config.conventional {
+        format!("chore: {subject}")
+    } else {
+        subject
+    }
+}
+
+fn truncate_chars(text: &str, limit: usize) -> String {
+    text.chars().take(limit).collect()
+}
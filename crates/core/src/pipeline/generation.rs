@@ -1,137 +1,762 @@
-use std::time::Instant;
+use std::fmt::Write as _;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 use futures::{stream, StreamExt};
 use tracing::{debug, instrument, warn};
 
 use crate::config::EffectiveConfig;
-use crate::diff::{diff_files_to_string, estimate_tokens, truncate_to_tokens, DiffFile};
-use crate::error::{CoreError, CoreResult};
+use crate::diff::{
+    diff_files_to_string, estimate_tokens, hunk_groups, truncate_to_tokens, DiffFile,
+};
+use crate::error::{CallPhase, CoreError, CoreResult, TimeoutError};
+use crate::pricing::context_window_for;
 use crate::prompt::{
-    commit_system_prompt, commit_user_prompt, summary_system_prompt, summary_user_prompt,
+    commit_system_prompt, commit_user_prompt, context_section, files_changed_header,
+    json_message_instructions, pr_draft_instructions, repair_user_prompt,
+    summary_batch_user_prompt, summary_system_prompt, summary_user_prompt,
 };
-use crate::providers::{Provider, ProviderRequest};
+use crate::providers::{is_context_length_error, Provider, ProviderRequest};
+
+use super::fallback::Trace;
+use super::headroom::{plan_trimming, PromptSizes};
 
-#[instrument(level = "debug", skip(provider, config, diff_files, deadline))]
+#[instrument(
+    level = "debug",
+    skip(
+        provider, config, diff_files, context, all_paths, deadline, trace, warnings
+    )
+)]
+#[allow(clippy::too_many_arguments)]
 pub(super) async fn generate_with_provider(
     provider: &dyn Provider,
     config: &EffectiveConfig,
     diff_files: &[DiffFile],
-    deadline: Instant,
+    context: &[DiffFile],
+    all_paths: &[String],
+    deadline: Deadline,
+    trace: &mut Trace,
+    warnings: &mut Vec<String>,
 ) -> CoreResult<String> {
     let total_tokens: usize = diff_files.iter().map(|file| file.token_estimate).sum();
+    let budget = format!(
+        "diff: ~{total_tokens} tokens in {} file(s), max_input_tokens = {}",
+        diff_files.len(),
+        config.max_input_tokens
+    );
 
     if total_tokens <= config.max_input_tokens as usize {
-        let diff_text = diff_files_to_string(diff_files);
-        let system_prompt = commit_system_prompt(config);
-        let user_prompt = commit_user_prompt(&diff_text, config);
-        let request = ProviderRequest {
-            max_output_tokens: config.max_output_tokens,
-            temperature: config.temperature,
-        };
+        trace.step(format!("{budget}; sent in one request"));
+        let (system_prompt, user_prompt) =
+            single_request_prompts(provider, config, diff_files, context, all_paths, warnings);
 
-        return call_with_deadline(
+        let result = final_call(
+            provider,
+            config,
+            &system_prompt,
+            &user_prompt,
             deadline,
-            provider.complete(&system_prompt, &user_prompt, request),
+            trace,
         )
         .await;
+        match result {
+            Err(err) if is_context_length_error(&err) => {
+                warn!("diff rejected as too long for the model: {err}");
+                trace.step(
+                    "provider: request too long for the model's context, so each file is \
+                     summarized instead"
+                        .to_string(),
+                );
+            }
+            result => return result,
+        }
+    } else {
+        trace.step(format!(
+            "{budget}; over the limit, so each file is summarized first"
+        ));
     }
-
-    summarize_then_commit(provider, config, diff_files, deadline).await
+    summarize_then_commit(
+        provider, config, diff_files, context, all_paths, deadline, trace, warnings,
+    )
+    .await
 }
 
-#[instrument(level = "debug", skip(provider, config, diff_files, deadline))]
+#[instrument(
+    level = "debug",
+    skip(
+        provider, config, diff_files, context, all_paths, deadline, trace, warnings
+    )
+)]
+#[allow(clippy::too_many_arguments)]
 pub(super) async fn summarize_then_commit(
     provider: &dyn Provider,
     config: &EffectiveConfig,
     diff_files: &[DiffFile],
-    deadline: Instant,
+    context: &[DiffFile],
+    all_paths: &[String],
+    deadline: Deadline,
+    trace: &mut Trace,
+    warnings: &mut Vec<String>,
 ) -> CoreResult<String> {
     let start = Instant::now();
-    let max_file_tokens = std::cmp::min(config.max_input_tokens as usize, 2000);
-    let summary_tokens = config.max_output_tokens;
-    let concurrency = std::cmp::max(config.summary_concurrency, 1);
+    let call = SummaryCall {
+        provider,
+        config,
+        max_file_tokens: summary_file_token_cap(config),
+        deadline,
+        latencies: Mutex::default(),
+    };
+    let combined = summarize_all(call, diff_files, trace, warnings).await;
+    if combined.is_empty() {
+        return Ok(String::new());
+    }
 
-    let summary_results = stream::iter(diff_files.iter())
-        .map(|file| async move {
-            let truncated = truncate_to_tokens(&file.content, max_file_tokens);
-            if truncated.trim().is_empty() {
-                return (file.path.clone(), None);
-            }
+    let system_prompt = final_system_prompt(config);
+    let max_tokens = config.max_input_tokens as usize;
+    let user_prompt = summaries_user_prompt(
+        config, diff_files, all_paths, &combined, context, max_tokens,
+    );
+    let mut message = final_call(
+        provider,
+        config,
+        &system_prompt,
+        &user_prompt,
+        deadline,
+        trace,
+    )
+    .await;
+    if message.as_ref().is_err_and(is_context_length_error) {
+        // The token estimate was off for this model; retry once with half the budget.
+        trace.step(
+            "provider: summaries too long for the model's context, retried with half the \
+             input budget and no context files"
+                .to_string(),
+        );
+        let user_prompt = summaries_user_prompt(
+            config,
+            diff_files,
+            all_paths,
+            &combined,
+            &[],
+            max_tokens / 2,
+        );
+        message = final_call(
+            provider,
+            config,
+            &system_prompt,
+            &user_prompt,
+            deadline,
+            trace,
+        )
+        .await;
+    }
 
-            let system_prompt = summary_system_prompt();
-            let user_prompt = summary_user_prompt(&file.path, &truncated);
-            let request = ProviderRequest {
-                max_output_tokens: summary_tokens,
-                temperature: config.temperature,
-            };
+    debug!(
+        elapsed_ms = start.elapsed().as_millis(),
+        "summary pipeline complete"
+    );
 
-            let result = call_with_deadline(
-                deadline,
-                provider.complete(&system_prompt, &user_prompt, request),
-            )
-            .await;
-
-            match result {
-                Ok(summary) => (file.path.clone(), Some(summary)),
-                Err(err) => {
-                    warn!(path = %file.path, "summary failed: {err}");
-                    (file.path.clone(), None)
-                }
-            }
+    message
+}
+
+/// Summarize every file, batching small related ones, and retry failed calls once.
+async fn summarize_all(
+    call: SummaryCall<'_>,
+    diff_files: &[DiffFile],
+    trace: &mut Trace,
+    warnings: &mut Vec<String>,
+) -> Vec<String> {
+    let concurrency = std::cmp::max(call.config.summary_concurrency, 1);
+
+    let batches = summary_batches(diff_files, call.max_file_tokens);
+    if batches.len() < diff_files.len() {
+        trace.step(format!(
+            "summaries: {} file(s) in {} call(s), small related files batched",
+            diff_files.len(),
+            batches.len()
+        ));
+    }
+    let summary_results = stream::iter(batches.iter())
+        .map(|batch| {
+            let call = &call;
+            async move { (batch, call.summarize(batch).await) }
         })
         .buffer_unordered(concurrency)
         .collect::<Vec<_>>()
         .await;
 
     let mut combined = Vec::new();
-    for (path, summary) in summary_results {
-        if let Some(summary) = summary {
-            combined.push(format!("{}: {}", path, summary.trim()));
+    let mut summarized = 0;
+    let mut failed = Vec::new();
+    for (batch, summary) in summary_results {
+        match summary {
+            Ok(Some(summary)) => {
+                combined.push(summary);
+                summarized += batch.len();
+            }
+            Ok(None) => {}
+            Err(_) => failed.push(batch),
+        }
+    }
+    if !failed.is_empty() {
+        let retried = call.retry(failed, &mut combined).await;
+        summarized += retried.summarized;
+        trace.step(format!(
+            "summaries: retried {} failed call(s), {} file(s) recovered",
+            retried.attempted, retried.summarized
+        ));
+        if !retried.missed.is_empty() {
+            warnings.push(format!(
+                "summaries failed for {}; the message may leave out their changes",
+                retried.missed.join(", ")
+            ));
         }
     }
 
-    if combined.is_empty() {
-        return Ok(String::new());
+    trace.step(format!(
+        "summaries: {summarized} of {} file(s) summarized",
+        diff_files.len()
+    ));
+    let latencies = call
+        .latencies
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner);
+    for latency in latencies {
+        trace.call_took("summary", latency);
     }
+    combined
+}
+
+/// User prompt for the final call: the file list header, the summaries cut to `max_tokens`,
+/// and whatever context files still fit.
+fn summaries_user_prompt(
+    config: &EffectiveConfig,
+    diff_files: &[DiffFile],
+    all_paths: &[String],
+    summaries: &[String],
+    context: &[DiffFile],
+    max_tokens: usize,
+) -> String {
+    let header = if config.summary_file_list {
+        files_changed_header(all_paths)
+    } else {
+        String::new()
+    };
+    let budget = max_tokens.saturating_sub(estimate_tokens(&header));
 
-    let mut combined_text = combined.join("\n");
+    let mut combined_text = summaries.join("\n");
     let combined_tokens = estimate_tokens(&combined_text);
-    if combined_tokens > config.max_input_tokens as usize {
-        combined_text = truncate_to_tokens(&combined_text, config.max_input_tokens as usize);
+    if combined_tokens > budget {
+        combined_text = truncate_to_tokens(&combined_text, budget);
     }
+    let remaining = budget.saturating_sub(combined_tokens);
+    if !header.is_empty() {
+        combined_text = format!("{header}\nSummaries:\n{combined_text}");
+    }
+
+    let hint = super::prompt_hints(diff_files, all_paths, config);
+    let mut user_prompt = commit_user_prompt(&combined_text, config, hint.as_deref());
+    append_context(&mut user_prompt, &fitting_context(context, remaining));
+    user_prompt
+}
+
+/// System and user prompt for sending the whole diff at once. When they come close to
+/// the model's known context window, optional sections are left out with a warning.
+fn single_request_prompts(
+    provider: &dyn Provider,
+    config: &EffectiveConfig,
+    diff_files: &[DiffFile],
+    context: &[DiffFile],
+    all_paths: &[String],
+    warnings: &mut Vec<String>,
+) -> (String, String) {
+    let total_tokens: usize = diff_files.iter().map(|file| file.token_estimate).sum();
+    let remaining = (config.max_input_tokens as usize).saturating_sub(total_tokens);
+    let context = fitting_context(context, remaining);
+    let hint = super::prompt_hints(diff_files, all_paths, config);
+    let system_prompt = final_system_prompt(config);
+    let unstyled = EffectiveConfig {
+        style: None,
+        ..config.clone()
+    };
+    let unstyled_prompt = final_system_prompt(&unstyled);
+
+    let trimming = context_window_for(config.provider, provider.model())
+        .map(|window| {
+            let sizes = PromptSizes {
+                fixed: estimate_tokens(&unstyled_prompt)
+                    + estimate_tokens(&commit_user_prompt("", config, None))
+                    + config.max_output_tokens as usize,
+                style: estimate_tokens(&system_prompt)
+                    .saturating_sub(estimate_tokens(&unstyled_prompt)),
+                context: context.iter().map(|file| file.token_estimate).sum(),
+                hints: hint.as_deref().map_or(0, estimate_tokens),
+                diff: total_tokens,
+            };
+            let trimming = plan_trimming(sizes, window);
+            warnings.extend(trimming.warning(provider.model(), window));
+            trimming
+        })
+        .unwrap_or_default();
+
+    let diff_text = diff_files_to_string(diff_files);
+    let diff_text = match trimming.diff_cap {
+        Some(cap) => truncate_to_tokens(&diff_text, cap),
+        None => diff_text,
+    };
+    let hint = hint.filter(|_| !trimming.hints);
+    let mut user_prompt = commit_user_prompt(&diff_text, config, hint.as_deref());
+    if !trimming.context {
+        append_context(&mut user_prompt, &context);
+    }
+    let system_prompt = if trimming.style {
+        unstyled_prompt
+    } else {
+        system_prompt
+    };
+    (system_prompt, user_prompt)
+}
+
+/// System prompt for the call that writes the message, asking for a PR draft or JSON reply when configured.
+fn final_system_prompt(config: &EffectiveConfig) -> String {
+    let mut prompt = commit_system_prompt(config);
+    if config.pr_draft {
+        prompt.push_str(pr_draft_instructions());
+    } else if super::reply::ollama_format(config).is_some() {
+        prompt.push_str(json_message_instructions());
+    }
+    prompt
+}
+
+/// The call that writes the message, with its latency kept in `trace`.
+async fn final_call(
+    provider: &dyn Provider,
+    config: &EffectiveConfig,
+    system_prompt: &str,
+    user_prompt: &str,
+    deadline: Deadline,
+    trace: &mut Trace,
+) -> CoreResult<String> {
+    let start = Instant::now();
+    let result = call_with_deadline(
+        deadline,
+        CallPhase::Final,
+        None,
+        provider.complete(system_prompt, user_prompt, final_request(config)),
+    )
+    .await;
+    trace.call_took("final", start.elapsed());
+    result
+}
+
+fn final_request(config: &EffectiveConfig) -> ProviderRequest {
+    let response_schema = config
+        .pr_draft
+        .then(super::pr::response_schema)
+        .or_else(|| super::reply::ollama_format(config));
+    // Structured replies end with the JSON object; a stop sequence could cut it short.
+    let stop = if response_schema.is_some() {
+        Vec::new()
+    } else {
+        config.stop_sequences.clone()
+    };
+    ProviderRequest {
+        max_output_tokens: config.max_output_tokens,
+        temperature: config.temperature,
+        seed: config.seed,
+        response_schema,
+        stop,
+    }
+}
+
+/// The context files that fit in `budget` tokens after the diff, in config order.
+///
+/// Context is optional, so it is dropped rather than squeezing the diff.
+fn fitting_context(context: &[DiffFile], budget: usize) -> Vec<&DiffFile> {
+    let mut remaining = budget;
+    let mut included = Vec::new();
+    for file in context {
+        if file.token_estimate <= remaining {
+            remaining -= file.token_estimate;
+            included.push(file);
+        } else {
+            debug!(path = %file.path, "context file dropped: over token budget");
+        }
+    }
+    included
+}
 
-    let system_prompt = commit_system_prompt(config);
-    let user_prompt = commit_user_prompt(&combined_text, config);
+fn append_context(prompt: &mut String, context: &[&DiffFile]) {
+    if !context.is_empty() {
+        prompt.push_str("\n\n");
+        prompt.push_str(&context_section(context));
+    }
+}
+
+/// Ask the model to rewrite a message that failed conventional validation.
+pub(super) async fn repair_with_provider(
+    provider: &dyn Provider,
+    config: &EffectiveConfig,
+    previous: &str,
+    deadline: Deadline,
+) -> CoreResult<String> {
     let request = ProviderRequest {
         max_output_tokens: config.max_output_tokens,
         temperature: config.temperature,
+        seed: config.seed,
+        response_schema: None,
+        stop: config.stop_sequences.clone(),
     };
 
-    let message = call_with_deadline(
+    call_with_deadline(
         deadline,
-        provider.complete(&system_prompt, &user_prompt, request),
+        CallPhase::Repair,
+        None,
+        provider.complete(
+            &commit_system_prompt(config),
+            &repair_user_prompt(previous),
+            request,
+        ),
     )
-    .await;
+    .await
+}
 
-    debug!(
-        elapsed_ms = start.elapsed().as_millis(),
-        "summary pipeline complete"
-    );
+/// Time budget shared by every provider call in one run (`timeout_secs`).
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Deadline {
+    start: Instant,
+    timeout: Duration,
+}
 
-    message
+impl Deadline {
+    pub(super) fn new(timeout_secs: u64) -> Self {
+        Self {
+            start: Instant::now(),
+            timeout: Duration::from_secs(timeout_secs),
+        }
+    }
+
+    /// How much of the budget has been used, e.g. `12.3s of the 30s budget (timeout_secs)`.
+    pub(super) fn usage(self) -> String {
+        format!(
+            "{:.1}s of the {}s budget (timeout_secs)",
+            self.start.elapsed().as_secs_f64(),
+            self.timeout.as_secs()
+        )
+    }
+
+    fn remaining(self) -> Duration {
+        self.timeout.saturating_sub(self.start.elapsed())
+    }
+
+    fn timeout_error(self, phase: CallPhase, path: Option<&str>, before_call: bool) -> CoreError {
+        CoreError::Timeout(TimeoutError {
+            phase,
+            path: path.map(str::to_string),
+            timeout_secs: self.timeout.as_secs(),
+            elapsed: self.start.elapsed(),
+            before_call,
+        })
+    }
 }
 
-pub(super) async fn call_with_deadline<F>(deadline: Instant, fut: F) -> CoreResult<String>
+pub(super) async fn call_with_deadline<F>(
+    deadline: Deadline,
+    phase: CallPhase,
+    path: Option<&str>,
+    fut: F,
+) -> CoreResult<String>
 where
     F: std::future::Future<Output = CoreResult<String>>,
 {
-    let now = Instant::now();
-    if now >= deadline {
-        return Err(CoreError::Timeout(0));
+    let remaining = deadline.remaining();
+    if remaining.is_zero() {
+        return Err(deadline.timeout_error(phase, path, true));
     }
-    let remaining = deadline.saturating_duration_since(now);
     match tokio::time::timeout(remaining, fut).await {
         Ok(result) => result,
-        Err(_) => Err(CoreError::Timeout(remaining.as_secs())),
+        Err(_) => Err(deadline.timeout_error(phase, path, false)),
+    }
+}
+
+/// Pause before retrying a failed summary call; each later retry waits one step longer.
+const SUMMARY_RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Longest pause between summary retries.
+const MAX_SUMMARY_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// What shares one run's summary calls.
+struct SummaryCall<'a> {
+    provider: &'a dyn Provider,
+    config: &'a EffectiveConfig,
+    max_file_tokens: usize,
+    deadline: Deadline,
+    /// Latency of each summary request, retries included.
+    latencies: Mutex<Vec<Duration>>,
+}
+
+/// Outcome of retrying the summary calls that failed in the first pass.
+struct SummaryRetry {
+    attempted: usize,
+    summarized: usize,
+    /// Paths whose summary still failed, or was not retried for lack of time.
+    missed: Vec<String>,
+}
+
+impl SummaryCall<'_> {
+    /// One summary call: a single file as `path: summary`, or a batch of small files whose
+    /// reply attributes each change to its path. `None` when there is nothing to summarize.
+    async fn summarize(&self, batch: &[&DiffFile]) -> CoreResult<Option<String>> {
+        let config = self.config;
+        let (label, user_prompt) = if let [file] = batch {
+            if file.token_estimate > self.max_file_tokens && config.summary_max_hunk_groups > 0 {
+                return self.summarize_hunks(file).await;
+            }
+            let truncated = truncate_to_tokens(&file.content, self.max_file_tokens);
+            if truncated.trim().is_empty() {
+                return Ok(None);
+            }
+            let prompt = summary_user_prompt(&file.path, &truncated, config);
+            (file.path.clone(), prompt)
+        } else {
+            let diffs = batch
+                .iter()
+                .map(|file| (file.path.as_str(), file.content.as_str()))
+                .collect::<Vec<_>>();
+            let label = format!("{} and {} more", batch[0].path, batch.len() - 1);
+            (label, summary_batch_user_prompt(&diffs, config))
+        };
+        let summary = self.call(&label, &user_prompt).await?;
+        if batch.len() == 1 {
+            Ok(Some(format!("{label}: {}", summary.trim())))
+        } else {
+            Ok(Some(summary.trim().to_string()))
+        }
+    }
+
+    /// Summarize a file over the per-file cap in groups of whole hunks, at most
+    /// `summary_max_hunk_groups` calls, merged into one `path: summary` entry.
+    async fn summarize_hunks(&self, file: &DiffFile) -> CoreResult<Option<String>> {
+        let config = self.config;
+        let groups = hunk_groups(&file.content, self.max_file_tokens);
+        let limit = config.summary_max_hunk_groups as usize;
+        let sent = groups.len().min(limit);
+        if sent == 0 {
+            return Ok(None);
+        }
+        let parts = stream::iter(groups.iter().take(limit).enumerate())
+            .map(|(index, group)| async move {
+                let label = format!("{} (part {} of {sent})", file.path, index + 1);
+                let prompt = summary_user_prompt(&label, group, config);
+                self.call(&label, &prompt).await
+            })
+            .buffered(std::cmp::max(config.summary_concurrency, 1))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<CoreResult<Vec<_>>>()?;
+
+        let mut summary = parts
+            .iter()
+            .map(|part| part.trim())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if groups.len() > sent {
+            let _ = write!(
+                summary,
+                "\n(the last {} of {} hunk groups were not summarized)",
+                groups.len() - sent,
+                groups.len()
+            );
+        }
+        Ok(Some(format!("{}: {summary}", file.path)))
+    }
+
+    /// One summary request for `user_prompt`, labeled for timeouts and logs.
+    async fn call(&self, label: &str, user_prompt: &str) -> CoreResult<String> {
+        let config = self.config;
+        let request = ProviderRequest {
+            max_output_tokens: config.max_output_tokens,
+            temperature: config.temperature,
+            seed: config.seed,
+            response_schema: None,
+            stop: Vec::new(),
+        };
+        let start = Instant::now();
+        let result = call_with_deadline(
+            self.deadline,
+            CallPhase::Summary,
+            Some(label),
+            self.provider
+                .complete(&summary_system_prompt(config), user_prompt, request),
+        )
+        .await;
+        self.latencies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(start.elapsed());
+        if let Err(err) = &result {
+            warn!(path = %label, "summary failed: {err}");
+        }
+        result
+    }
+
+    /// Retry failed batches once, one at a time with a growing pause, while the deadline
+    /// leaves room for the pause; summaries that succeed are added to `combined`.
+    async fn retry(
+        &self,
+        failed: Vec<&Vec<&DiffFile>>,
+        combined: &mut Vec<String>,
+    ) -> SummaryRetry {
+        let mut retried = SummaryRetry {
+            attempted: 0,
+            summarized: 0,
+            missed: Vec::new(),
+        };
+        let mut backoff = SUMMARY_RETRY_BACKOFF;
+        for batch in failed {
+            if self.deadline.remaining() <= backoff {
+                retried
+                    .missed
+                    .extend(batch.iter().map(|file| file.path.clone()));
+                continue;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff + SUMMARY_RETRY_BACKOFF).min(MAX_SUMMARY_RETRY_BACKOFF);
+            retried.attempted += 1;
+            match self.summarize(batch).await {
+                Ok(Some(summary)) => {
+                    combined.push(summary);
+                    retried.summarized += batch.len();
+                }
+                Ok(None) => {}
+                Err(_) => retried
+                    .missed
+                    .extend(batch.iter().map(|file| file.path.clone())),
+            }
+        }
+        retried
+    }
+}
+
+/// Files up to this many estimated tokens may share a summary call with related files.
+const BATCH_FILE_TOKENS: usize = 250;
+
+/// Group files into summary calls. Small files that share a directory, or failing that
+/// an extension, go into one call while their diffs fit in `cap` tokens; other files are
+/// summarized alone.
+pub(super) fn summary_batches(files: &[DiffFile], cap: usize) -> Vec<Vec<&DiffFile>> {
+    let (small, large): (Vec<&DiffFile>, Vec<&DiffFile>) = files
+        .iter()
+        .partition(|file| file.token_estimate <= BATCH_FILE_TOKENS.min(cap));
+    let mut batches = large.into_iter().map(|file| vec![file]).collect::<Vec<_>>();
+
+    let mut singles = Vec::new();
+    let parent_dir =
+        |path: &str| Some(path.rsplit_once('/').map_or("", |(dir, _)| dir).to_string());
+    for group in group_files(small, parent_dir) {
+        if group.len() > 1 {
+            batches.extend(fill_batches(group, cap));
+        } else {
+            singles.extend(group);
+        }
+    }
+    let extension = |path: &str| {
+        std::path::Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase)
+    };
+    for group in group_files(singles, extension) {
+        batches.extend(fill_batches(group, cap));
+    }
+    batches
+}
+
+/// Group files by `key` in first-seen order; files without a key stay on their own.
+fn group_files(files: Vec<&DiffFile>, key: impl Fn(&str) -> Option<String>) -> Vec<Vec<&DiffFile>> {
+    let mut groups: Vec<(Option<String>, Vec<&DiffFile>)> = Vec::new();
+    for file in files {
+        let key = key(&file.path);
+        match groups
+            .iter_mut()
+            .find(|(existing, _)| key.is_some() && *existing == key)
+        {
+            Some((_, group)) => group.push(file),
+            None => groups.push((key, vec![file])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Split a group into batches whose diffs add up to at most `cap` tokens.
+fn fill_batches(files: Vec<&DiffFile>, cap: usize) -> Vec<Vec<&DiffFile>> {
+    let mut batches = Vec::new();
+    let mut current: Vec<&DiffFile> = Vec::new();
+    let mut tokens = 0;
+    for file in files {
+        if !current.is_empty() && tokens + file.token_estimate > cap {
+            batches.push(std::mem::take(&mut current));
+            tokens = 0;
+        }
+        tokens += file.token_estimate;
+        current.push(file);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+/// Estimated `(input, output)` tokens for [`generate_with_provider`], mirroring its
+/// choice between a single call and summarizing file by file.
+pub(super) fn estimate_usage(config: &EffectiveConfig, diff_files: &[DiffFile]) -> (u64, u64) {
+    let total_tokens: usize = diff_files.iter().map(|file| file.token_estimate).sum();
+    let output = config.max_output_tokens as usize;
+    let commit_prompt = estimate_tokens(&commit_system_prompt(config));
+
+    if total_tokens <= config.max_input_tokens as usize {
+        return ((commit_prompt + total_tokens) as u64, output as u64);
+    }
+
+    let max_file_tokens = summary_file_token_cap(config);
+    let summary_prompt = estimate_tokens(&summary_system_prompt(config));
+    let batches = summary_batches(diff_files, max_file_tokens);
+    let mut calls = 0;
+    let mut summaries = 0;
+    for batch in &batches {
+        // A lone file over the cap is summarized in hunk groups, one call each.
+        let diffs = match batch.as_slice() {
+            [file]
+                if file.token_estimate > max_file_tokens && config.summary_max_hunk_groups > 0 =>
+            {
+                hunk_groups(&file.content, max_file_tokens)
+                    .iter()
+                    .take(config.summary_max_hunk_groups as usize)
+                    .map(|group| estimate_tokens(group))
+                    .collect()
+            }
+            _ => vec![batch
+                .iter()
+                .map(|file| file.token_estimate.min(max_file_tokens))
+                .sum::<usize>()],
+        };
+        calls += diffs.len();
+        summaries += diffs
+            .iter()
+            .map(|diff| summary_prompt + diff)
+            .sum::<usize>();
+    }
+    let final_input = (batches.len() * output).min(config.max_input_tokens as usize);
+    let input = summaries + commit_prompt + final_input;
+    ((input as u64), ((calls + 1) * output) as u64)
+}
+
+/// Per-file token cap for summary calls; `summary_max_file_tokens = 0` lifts it.
+pub(super) fn summary_file_token_cap(config: &EffectiveConfig) -> usize {
+    let input = config.max_input_tokens as usize;
+    match config.summary_max_file_tokens {
+        0 => input,
+        cap => input.min(cap as usize),
     }
 }
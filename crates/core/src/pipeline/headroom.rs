@@ -0,0 +1,72 @@
+/// Share of the model's context window kept free, since token counts are estimates.
+const HEADROOM_DIVISOR: usize = 20;
+
+/// Estimated tokens of each part of the single-request prompt.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct PromptSizes {
+    /// Instructions and the reply allowance, which are never trimmed.
+    pub fixed: usize,
+    /// Learned repo style in the system prompt.
+    pub style: usize,
+    /// Read-only context files.
+    pub context: usize,
+    /// Type and scope hints.
+    pub hints: usize,
+    pub diff: usize,
+}
+
+/// What to leave out so the prompt fits the model's context window.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(super) struct Trimming {
+    pub style: bool,
+    pub context: bool,
+    pub hints: bool,
+    /// Tokens of diff to keep, when its tail has to go too.
+    pub diff_cap: Option<usize>,
+}
+
+impl Trimming {
+    /// Warning naming what was trimmed; `None` when nothing was.
+    pub(super) fn warning(&self, model: &str, window: usize) -> Option<String> {
+        let mut dropped = Vec::new();
+        if self.style {
+            dropped.push("the learned repo style".to_string());
+        }
+        if self.context {
+            dropped.push("context files".to_string());
+        }
+        if self.hints {
+            dropped.push("type and scope hints".to_string());
+        }
+        if let Some(cap) = self.diff_cap {
+            dropped.push(format!("the diff after ~{cap} tokens"));
+        }
+        (!dropped.is_empty()).then(|| {
+            format!(
+                "prompt nearly filled {model}'s {window}-token context; left out {}",
+                dropped.join(", ")
+            )
+        })
+    }
+}
+
+/// Trim optional sections, in order (style, context files, hints, then the diff tail),
+/// until the prompt fits `window` with some headroom left.
+pub(super) fn plan_trimming(sizes: PromptSizes, window: usize) -> Trimming {
+    let limit = window.saturating_sub(window / HEADROOM_DIVISOR);
+    let mut total = sizes.fixed + sizes.style + sizes.context + sizes.hints + sizes.diff;
+    let mut trimming = Trimming::default();
+    let mut trim = |size: usize, trimmed: &mut bool| {
+        if total > limit && size > 0 {
+            *trimmed = true;
+            total -= size;
+        }
+    };
+    trim(sizes.style, &mut trimming.style);
+    trim(sizes.context, &mut trimming.context);
+    trim(sizes.hints, &mut trimming.hints);
+    if total > limit {
+        trimming.diff_cap = Some(sizes.diff.saturating_sub(total - limit));
+    }
+    trimming
+}
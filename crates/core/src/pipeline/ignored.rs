@@ -0,0 +1,120 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::config::EffectiveConfig;
+use crate::git::GitFileStat;
+
+const LOCKFILE_NAMES: &[&str] = &[
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "pnpm-lock.yaml",
+    "yarn.lock",
+    "bun.lock",
+    "bun.lockb",
+    "Cargo.lock",
+    "Gemfile.lock",
+    "poetry.lock",
+    "composer.lock",
+    "Podfile.lock",
+    "go.sum",
+];
+
+const BUILD_DIRS: &[&str] = &[
+    "dist",
+    "build",
+    "target",
+    ".next",
+    ".turbo",
+    ".vite",
+    "coverage",
+    "DerivedData",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IgnoredKind {
+    Lockfile,
+    BuildOutput,
+    Other,
+}
+
+/// Build a deterministic message for changes that only touch AI-ignored files.
+pub(super) fn ignored_files_message(stats: &[GitFileStat], config: &EffectiveConfig) -> String {
+    let kinds = stats
+        .iter()
+        .map(|stat| classify(&stat.path))
+        .collect::<Vec<_>>();
+    let all = |kind: IgnoredKind| kinds.iter().all(|candidate| *candidate == kind);
+
+    let (scope, subject) = if all(IgnoredKind::Lockfile) {
+        let subject = match stats {
+            [single] => format!("update {}", file_name(&single.path)),
+            _ => "update dependencies".to_string(),
+        };
+        (Some("deps"), subject)
+    } else if all(IgnoredKind::BuildOutput) {
+        (Some("build"), "update build output".to_string())
+    } else {
+        let subject = match stats {
+            [single] => format!("update {}", file_name(&single.path)),
+            _ => format!("update {} generated files", stats.len()),
+        };
+        (None, subject)
+    };
+
+    let mut message = match (config.conventional, scope) {
+        (true, Some(scope)) => format!("chore({scope}): {subject}"),
+        (true, None) => format!("chore: {subject}"),
+        (false, _) => subject,
+    };
+
+    if !config.one_line && stats.len() > 1 {
+        message.push('\n');
+        for stat in stats {
+            if stat.is_binary {
+                let _ = write!(message, "\n- {} (binary)", stat.path);
+            } else {
+                let _ = write!(
+                    message,
+                    "\n- {} (+{} -{})",
+                    stat.path, stat.additions, stat.deletions
+                );
+            }
+        }
+    }
+
+    message
+}
+
+/// Whether every path is a recognized dependency lockfile.
+pub(super) fn only_lockfiles<'a>(mut paths: impl Iterator<Item = &'a str>) -> bool {
+    paths.all(|path| classify(path) == IgnoredKind::Lockfile)
+}
+
+fn classify(path: &str) -> IgnoredKind {
+    let name = file_name(path);
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    if LOCKFILE_NAMES.contains(&name) || extension == "lock" || extension == "lockb" {
+        return IgnoredKind::Lockfile;
+    }
+
+    let in_build_dir = path
+        .split('/')
+        .any(|component| BUILD_DIRS.contains(&component));
+    let minified = Path::new(name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.to_lowercase().ends_with(".min"));
+    if in_build_dir || minified || extension == "map" {
+        return IgnoredKind::BuildOutput;
+    }
+
+    IgnoredKind::Other
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
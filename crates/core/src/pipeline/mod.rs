@@ -2,19 +2,51 @@ use std::time::{Duration, Instant};
 
 use tracing::{debug, instrument, warn};
 
-use crate::config::EffectiveConfig;
-use crate::error::CoreResult;
+use crate::config::{EffectiveConfig, TypeInference};
+use crate::diff::{
+    conflict_marker_lines, diff_files_to_string, estimate_tokens, split_file_diffs,
+    truncate_to_tokens, DiffFile,
+};
+use crate::error::{CallPhase, CoreError, CoreResult};
 use crate::git::GitBackend;
 use crate::ignore::IgnoreMatcher;
-use crate::providers::Provider;
+use crate::infer::{
+    detect_formatting_only, group_by_top_dir, guess_type, suggest_scope, suggest_types,
+    FormattingChange, TypeSuggestion,
+};
+use crate::pricing::{price_for, CostEstimate};
+use crate::prompt::{
+    explain_system_prompt, explain_user_prompt, formatting_hint, note_system_prompt,
+    note_user_prompt, scope_hint, type_hint,
+};
+use crate::providers::{Provider, ProviderRequest};
+use crate::stats::{append_record, stats_path, StatsRecord};
+use crate::timing::Timings;
 
+use fallback::{fallback_message, FallbackFacts, Trace};
+
+mod changelog;
 mod context;
+mod fallback;
 mod generation;
+mod headroom;
+mod ignored;
+mod pr;
+mod reply;
+mod revert;
 mod sanitize;
+mod stamp;
+
+pub use changelog::{generate_changelog, generate_tag_message, Changelog, TagMessage};
+pub use fallback::{FallbackReason, Rejection};
+pub use pr::PrDraft;
+pub use revert::{generate_revert_message, RevertMessage};
 
 #[cfg(test)]
 mod tests;
 
+// One per run, so the size of `PipelineOutcome` is not worth a box.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum PipelineResult {
     NoChanges,
@@ -26,85 +58,756 @@ pub struct PipelineOutcome {
     pub message: String,
     pub used_fallback: bool,
     pub warnings: Vec<String>,
+    /// Body text removed by `one_line`, kept when `one_line_keep_body_in_editor` is set.
+    pub dropped_body: Option<String>,
+    /// Estimated tokens exchanged with the provider (zero when it was not called).
+    pub estimated_tokens: u64,
+    /// Time spent in diff collection, provider calls, and sanitizing.
+    pub timings: Timings,
+    /// Staged content the message was generated from; see [`staged_content_unchanged`].
+    pub staged_fingerprint: Option<String>,
+    /// `provider/model` that wrote the message; `None` when a fallback was used.
+    pub generated_by: Option<String>,
+    /// Pull request title and body, when `pr_draft` is set and the model returned one.
+    pub pr: Option<PrDraft>,
+    /// Why the fallback message was used; `None` when the model's message was used.
+    pub fallback_reason: Option<FallbackReason>,
+    /// Decision points in order (provider, token budget, sanitizing, time used), to explain a fallback.
+    pub trace: Vec<String>,
+}
+
+/// The staged changes as the pipeline sends them, collected once so `--print-diff`, the
+/// cost check, and generation all read the same diff.
+pub struct StagedDiff {
+    context: context::DiffContext,
+    /// Staged content the diff was read from; see [`staged_content_unchanged`].
+    fingerprint: Option<String>,
+    collected_in: Duration,
+}
+
+/// Collect the staged diff after ignore patterns and truncation.
+///
+/// # Errors
+/// Returns an error if git access fails.
+pub fn collect_staged_diff(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+) -> CoreResult<StagedDiff> {
+    let start = Instant::now();
+    let fingerprint = git.staged_fingerprint().ok();
+    let context = context::collect_diff_context(git, config, ignore)?;
+    Ok(StagedDiff {
+        context,
+        fingerprint,
+        collected_in: start.elapsed(),
+    })
 }
 
 #[instrument(level = "info", skip(git, provider, config, ignore))]
 /// Generate a commit message using staged changes and the configured provider.
 ///
 /// # Errors
-/// Returns an error if git access fails, the provider fails, or timeouts occur.
+/// Returns an error if git access fails, the provider fails, or timeouts occur, or
+/// [`CoreError::FallbackDisabled`] when the model's message is unusable and
+/// `allow_fallback` is off.
 pub async fn generate_commit_message(
     git: &impl GitBackend,
     provider: Option<&dyn Provider>,
     config: &EffectiveConfig,
     ignore: &IgnoreMatcher,
+) -> CoreResult<PipelineResult> {
+    let staged = collect_staged_diff(git, config, ignore)?;
+    generate_from_staged_diff(git, provider, config, staged).await
+}
+
+#[instrument(level = "info", skip(git, provider, config, staged))]
+/// [`generate_commit_message`] for a diff already collected with [`collect_staged_diff`].
+///
+/// # Errors
+/// The same as [`generate_commit_message`].
+pub async fn generate_from_staged_diff(
+    git: &impl GitBackend,
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    staged: StagedDiff,
+) -> CoreResult<PipelineResult> {
+    // With a body from the user, the model only writes the subject.
+    let subject_only;
+    let pipeline_config = if config.user_body.is_some() {
+        subject_only = EffectiveConfig {
+            one_line: true,
+            one_line_keep_body_in_editor: false,
+            ..config.clone()
+        };
+        &subject_only
+    } else {
+        config
+    };
+    let mut result = run_pipeline(provider, pipeline_config, staged).await?;
+    if let PipelineResult::Message(outcome) = &mut result {
+        let trailers = stamp::stamp_trailers(git, config, &mut outcome.warnings)?;
+        outcome.message = stamp::append_trailers(&outcome.message, &trailers);
+    }
+    Ok(result)
+}
+
+/// The diff text the AI would see for the staged changes, after ignore patterns and truncation.
+#[must_use]
+pub fn collected_diff(staged: &StagedDiff) -> String {
+    diff_files_to_string(&staged.context.ai_files)
+}
+
+/// A staged file whose diff adds conflict markers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictMarkers {
+    pub path: String,
+    /// Line numbers of the markers in the staged file.
+    pub lines: Vec<u32>,
+}
+
+/// Staged files that still contain conflict markers, scanned in the full staged diff so
+/// files the prompt leaves out or cuts short are checked too.
+///
+/// Files matching `conflict_marker_ignore` are excused, as are binary files.
+///
+/// # Errors
+/// Returns an error if git access fails or a `conflict_marker_ignore` pattern is invalid.
+pub fn find_conflict_markers(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+) -> CoreResult<Vec<ConflictMarkers>> {
+    let excused = IgnoreMatcher::from_patterns(&config.conflict_marker_ignore)?;
+    let diff = git.staged_diff()?;
+    Ok(split_file_diffs(&diff)
+        .into_iter()
+        .filter(|(path, _)| !excused.is_ignored(path))
+        .filter_map(|(path, diff)| {
+            let lines = conflict_marker_lines(diff);
+            (!lines.is_empty()).then_some(ConflictMarkers { path, lines })
+        })
+        .collect())
+}
+
+/// Estimate what generating a message for the staged diff would cost, without calling
+/// a provider. Returns `None` when there is nothing to send or the model has no known price.
+#[must_use]
+pub fn estimate_cost(staged: &StagedDiff, config: &EffectiveConfig) -> Option<CostEstimate> {
+    let price = price_for(config.provider, &config.model)?;
+    let files = &staged.context.ai_files;
+    if files.is_empty() {
+        return None;
+    }
+    let (input_tokens, output_tokens) = generation::estimate_usage(config, files);
+    Some(CostEstimate {
+        input_tokens,
+        output_tokens,
+        usd: price.cost(input_tokens, output_tokens),
+    })
+}
+
+async fn run_pipeline(
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    staged: StagedDiff,
 ) -> CoreResult<PipelineResult> {
     let start = Instant::now();
-    let context = context::collect_diff_context(git, config, ignore)?;
+    // Collecting the diff counts toward the run, but not any wait before generation.
+    let collected_in = staged.collected_in;
+    let elapsed = || collected_in + start.elapsed();
+    let staged_fingerprint = staged.fingerprint;
+    let mut timings = Timings::new();
+    timings.record("diff", collected_in);
+    let mut context = staged.context;
     if context.all_paths.is_empty() {
         return Ok(PipelineResult::NoChanges);
     }
 
-    let fallback = fallback_message(&context.all_paths, config);
+    let facts = FallbackFacts {
+        paths: &context.all_paths,
+        additions: context.additions,
+        deletions: context.deletions,
+    };
+    let fallback = fallback_message(&facts, config);
     if context.ai_files.is_empty() {
-        let mut warnings = context.warnings;
-        warnings.push("no usable diff for AI; using fallback".to_string());
-        return Ok(PipelineResult::Message(PipelineOutcome {
-            message: fallback,
-            used_fallback: true,
-            warnings,
-        }));
+        timings.finish(elapsed());
+        let mut outcome = no_diff_outcome(context, config, fallback, timings);
+        outcome.staged_fingerprint = staged_fingerprint;
+        return Ok(PipelineResult::Message(outcome));
     }
 
-    let mut warnings = context.warnings;
-    let deadline = Instant::now() + Duration::from_secs(config.timeout_secs);
-
-    let message = if let Some(provider) = provider {
-        match generation::generate_with_provider(provider, config, &context.ai_files, deadline)
-            .await
-        {
-            Ok(message) => message,
-            Err(err) => {
-                warn!("ai generation failed: {err}");
-                warnings.push(format!("ai generation failed, using fallback: {err}"));
-                fallback.clone()
-            }
-        }
-    } else {
-        warnings.push("provider unavailable, using fallback".to_string());
-        fallback.clone()
-    };
+    let mut warnings = std::mem::take(&mut context.warnings);
+    let deadline = generation::Deadline::new(config.timeout_secs);
+    let mut trace = Trace::default();
 
-    let cleaned = sanitize::sanitize_message(&message, config, &fallback);
+    let provider_start = Instant::now();
+    let (message, pr) = generate_or_fallback(
+        provider,
+        config,
+        &context,
+        &fallback,
+        deadline,
+        &mut warnings,
+        &mut trace,
+    )
+    .await;
+    timings.record("provider", provider_start.elapsed());
+    for (call, latency) in std::mem::take(&mut trace.calls) {
+        timings.sample(call, latency);
+    }
+
+    let (mut cleaned, accepted_reply) = sanitize_or_repair(
+        provider,
+        config,
+        &context.ai_files,
+        &message,
+        &fallback,
+        deadline,
+        &mut timings,
+        &mut warnings,
+        &mut trace,
+    )
+    .await;
+    if provider.is_some() {
+        trace.step(format!("time: {}", deadline.usage()));
+    }
     let used_fallback = cleaned == fallback;
+    if used_fallback && !config.allow_fallback {
+        refuse_fallback(&trace, &mut warnings)?;
+    }
+    if !used_fallback {
+        cleaned = settle_type_and_scope(cleaned, &context, config, &mut warnings);
+    }
+    let cleaned = sanitize::apply_affixes(&cleaned, config);
+    let cleaned = sanitize::finish_body(&cleaned, &context.all_paths, config);
+    let estimated_tokens = if provider.is_some() {
+        estimate_exchange_tokens(&context.ai_files, &message)
+    } else {
+        0
+    };
+    // The body of the reply the message came from, which is the retried one after a repair.
+    let dropped_body = if config.one_line_keep_body_in_editor && !used_fallback {
+        sanitize::dropped_body(&accepted_reply, config)
+    } else {
+        None
+    };
 
+    let generated_by = provider
+        .filter(|_| !used_fallback)
+        .map(|provider| format!("{}/{}", provider.name(), provider.model()));
     debug!(
-        elapsed_ms = start.elapsed().as_millis(),
+        elapsed_ms = elapsed().as_millis(),
+        generated_by = generated_by.as_deref().unwrap_or("fallback"),
         "pipeline complete"
     );
+    timings.finish(elapsed());
 
     Ok(PipelineResult::Message(PipelineOutcome {
         message: cleaned,
         used_fallback,
         warnings,
+        dropped_body,
+        estimated_tokens,
+        timings,
+        staged_fingerprint,
+        generated_by,
+        pr,
+        fallback_reason: trace.reason.filter(|_| used_fallback),
+        trace: trace.steps,
     }))
 }
 
-fn fallback_message(paths: &[String], config: &EffectiveConfig) -> String {
-    let mut subject = if paths.is_empty() {
-        "update files".to_string()
+/// Repair a structured reply whose subject has no type by adding the type guessed from
+/// the diff, when the result passes the conventional check.
+fn add_guessed_type(
+    config: &EffectiveConfig,
+    files: &[DiffFile],
+    message: &str,
+    fallback: &str,
+    warnings: &mut Vec<String>,
+    trace: &mut Trace,
+) -> Option<String> {
+    if !config.conventional || !reply::structured_output(config) {
+        return None;
+    }
+    let kind = guess_type(files, &config.conventional_types)?;
+    let repaired = sanitize::add_missing_type(message, kind)?;
+    let cleaned = sanitize::sanitize_checked(&repaired, config, fallback).ok()?;
+    warnings.push(format!(
+        "model reply had no commit type; added `{kind}` based on the diff"
+    ));
+    trace.step(format!(
+        "repair: added the `{kind}` type guessed from the diff"
+    ));
+    Some(cleaned)
+}
+
+/// Sanitize the model's reply, asking it once to repair a non-conventional reply when
+/// `retry_on_invalid` is set. Returns the cleaned message with the reply it came from, or
+/// `fallback` twice when the reply cannot be used.
+#[allow(clippy::too_many_arguments)]
+async fn sanitize_or_repair(
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    files: &[DiffFile],
+    message: &str,
+    fallback: &str,
+    deadline: generation::Deadline,
+    timings: &mut Timings,
+    warnings: &mut Vec<String>,
+    trace: &mut Trace,
+) -> (String, String) {
+    let unusable = || (fallback.to_string(), fallback.to_string());
+    let checked = timings.time("sanitize", || {
+        sanitize::sanitize_checked(message, config, fallback)
+    });
+    let rejection = match checked {
+        Ok(cleaned) => return (cleaned, message.to_string()),
+        // A fallback chosen earlier has its reason recorded already.
+        Err(_) if message == fallback => return unusable(),
+        Err(rejection) => rejection,
+    };
+    trace.step(format!("sanitize: rejected the reply because {rejection}"));
+    if let Some(repaired) = add_guessed_type(config, files, message, fallback, warnings, trace) {
+        return (repaired, message.to_string());
+    }
+
+    let Some(provider) = provider.filter(|_| config.retry_on_invalid && config.conventional) else {
+        trace.fall_back(FallbackReason::Rejected(rejection));
+        return unusable();
+    };
+    warnings.push("model output was not a conventional commit; retried once".to_string());
+    let repair_start = Instant::now();
+    let repaired = generation::repair_with_provider(provider, config, message, deadline).await;
+    let repair_time = repair_start.elapsed();
+    timings.record("provider", repair_time);
+    timings.sample("repair", repair_time);
+    let repaired = match repaired {
+        Ok(repaired) => timings
+            .time("sanitize", || {
+                sanitize::sanitize_checked(&repaired, config, fallback)
+            })
+            .map(|cleaned| (cleaned, repaired)),
+        Err(err) => {
+            warn!("retry after invalid output failed: {err}");
+            trace.step(format!("repair: request failed: {err}"));
+            Err(rejection)
+        }
+    };
+    match repaired {
+        Ok(accepted) => {
+            trace.step("repair: the retried reply was accepted");
+            accepted
+        }
+        Err(rejection) => {
+            trace.step(format!("repair: rejected again because {rejection}"));
+            trace.fall_back(FallbackReason::Rejected(rejection));
+            unusable()
+        }
+    }
+}
+
+/// Ask the provider for a message (and PR draft when `pr_draft` is set), or use `fallback`.
+async fn generate_or_fallback(
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    context: &context::DiffContext,
+    fallback: &str,
+    deadline: generation::Deadline,
+    warnings: &mut Vec<String>,
+    trace: &mut Trace,
+) -> (String, Option<PrDraft>) {
+    let Some(provider) = provider else {
+        warnings.push("provider unavailable, using fallback".to_string());
+        trace.step(format!(
+            "provider: {} could not be set up (check the API key or endpoint)",
+            config.provider.as_str()
+        ));
+        trace.fall_back(FallbackReason::ProviderUnavailable);
+        return (fallback.to_string(), None);
+    };
+    trace.step(format!(
+        "provider: {}/{}",
+        provider.name(),
+        provider.model()
+    ));
+    let result = generation::generate_with_provider(
+        provider,
+        config,
+        &context.ai_files,
+        &context.context_files,
+        &context.all_paths,
+        deadline,
+        trace,
+        warnings,
+    )
+    .await;
+    match result {
+        Ok(message) if config.pr_draft => {
+            if let Some((commit, draft)) = pr::parse_commit_and_pr(&message) {
+                return (commit, Some(draft));
+            }
+            warnings.push("model did not return a pull request draft".to_string());
+            if let Some(commit) = pr::commit_without_pr(&message) {
+                return (commit, None);
+            }
+            trace.step("provider: the reply was JSON without a commit message");
+            trace.fall_back(FallbackReason::Rejected(Rejection::NoCommitInJson));
+            (fallback.to_string(), None)
+        }
+        Ok(message) if reply::ollama_format(config).is_some() => {
+            (reply::unwrap_message(message), None)
+        }
+        Ok(message) => (message, None),
+        Err(err) => {
+            warn!("ai generation failed: {err}");
+            warnings.push(format!("ai generation failed, using fallback: {err}"));
+            trace.step(format!("provider call failed: {err}"));
+            trace.fall_back(FallbackReason::ProviderFailed(err.to_string()));
+            (fallback.to_string(), None)
+        }
+    }
+}
+
+/// With `allow_fallback` off, a fallback caused by a failure becomes an error. When the
+/// model wrote the fallback text itself, it is kept, but not silently.
+fn refuse_fallback(trace: &Trace, warnings: &mut Vec<String>) -> CoreResult<()> {
+    if let Some(reason) = &trace.reason {
+        return Err(CoreError::FallbackDisabled(reason.to_string()));
+    }
+    warnings.push(
+        "the model's message is the same as the fallback; kept despite allow_fallback = false"
+            .to_string(),
+    );
+    Ok(())
+}
+
+/// Outcome for staged changes with no diff the AI can use.
+fn no_diff_outcome(
+    context: context::DiffContext,
+    config: &EffectiveConfig,
+    fallback: String,
+    timings: Timings,
+) -> PipelineOutcome {
+    let mut warnings = context.warnings;
+    let mut trace = Trace::default();
+    trace.step(format!(
+        "diff: {} staged file(s), {} ignored for AI, none with a text diff to send",
+        context.all_paths.len(),
+        context.ignored.len()
+    ));
+    let message = if context.ignored.len() == context.all_paths.len() {
+        let lockfiles = ignored::only_lockfiles(context.all_paths.iter().map(String::as_str));
+        if lockfiles && config.skip_lockfile_only {
+            warnings.push(
+                "only lockfiles changed; skipped AI (set skip_lockfile_only = false to send them)"
+                    .to_string(),
+            );
+            trace.fall_back(FallbackReason::LockfilesOnly);
+        } else {
+            warnings.push(
+                "all staged files are ignored for AI; use --include-ignored to send them"
+                    .to_string(),
+            );
+            trace.fall_back(FallbackReason::AllIgnored);
+        }
+        ignored::ignored_files_message(&context.ignored, config)
     } else {
-        let preview = paths.iter().take(3).cloned().collect::<Vec<_>>();
-        format!("update {}", preview.join(", "))
+        warnings.push("no usable diff for AI; using fallback".to_string());
+        trace.fall_back(FallbackReason::NoUsableDiff);
+        fallback
     };
+    let message = sanitize::apply_affixes(&message, config);
+    let message = sanitize::finish_body(&message, &context.all_paths, config);
 
-    if subject.len() > 50 {
-        subject.truncate(50);
+    PipelineOutcome {
+        message,
+        used_fallback: true,
+        warnings,
+        dropped_body: None,
+        estimated_tokens: 0,
+        timings,
+        staged_fingerprint: None,
+        generated_by: None,
+        pr: None,
+        fallback_reason: trace.reason,
+        trace: trace.steps,
+    }
+}
+
+/// Diff-based type suggestions, when conventional commits and `infer_type` are on.
+fn type_suggestions(files: &[DiffFile], config: &EffectiveConfig) -> Vec<TypeSuggestion> {
+    if !config.conventional || config.infer_type == TypeInference::Off {
+        return Vec::new();
     }
+    suggest_types(files, &config.conventional_types)
+}
 
-    if config.conventional {
-        format!("chore: {subject}")
+/// Check the type of an accepted reply against the diff (`style` for formatting-only
+/// changes) and fill in the shared directory scope.
+fn settle_type_and_scope(
+    message: String,
+    context: &context::DiffContext,
+    config: &EffectiveConfig,
+    warnings: &mut Vec<String>,
+) -> String {
+    let message = if formatting_change(&context.ai_files, config).is_some() {
+        enforce_style_type(message, warnings)
     } else {
-        subject
+        let suggestions = type_suggestions(&context.ai_files, config);
+        reconcile_type(message, &suggestions, config, warnings)
+    };
+    match inferred_scope(&context.all_paths, config) {
+        Some(scope) => sanitize::apply_scope(&message, &scope),
+        None => message,
+    }
+}
+
+/// A formatting-only change set, when conventional commits and `style_detection` are on
+/// and `style` is an allowed type.
+fn formatting_change(files: &[DiffFile], config: &EffectiveConfig) -> Option<FormattingChange> {
+    let allowed = config.conventional_types.iter().any(|kind| kind == "style");
+    if !config.conventional || !config.style_detection || !allowed {
+        return None;
+    }
+    detect_formatting_only(files)
+}
+
+/// The directory scope shared by every staged path, when conventional commits and `infer_scope` are on.
+fn inferred_scope(paths: &[String], config: &EffectiveConfig) -> Option<String> {
+    if !config.conventional || !config.infer_scope {
+        return None;
     }
+    suggest_scope(paths)
+}
+
+/// Type and scope hints for the commit prompt.
+fn prompt_hints(files: &[DiffFile], paths: &[String], config: &EffectiveConfig) -> Option<String> {
+    let type_hint = match formatting_change(files, config) {
+        Some(formatting) => Some(formatting_hint(formatting.formatter)),
+        None => type_hint(&type_suggestions(files, config)),
+    };
+    let hints = [
+        type_hint,
+        inferred_scope(paths, config).map(|scope| scope_hint(&scope)),
+    ];
+    let hints = hints.into_iter().flatten().collect::<Vec<_>>();
+    (!hints.is_empty()).then(|| hints.join("\n"))
+}
+
+/// Warn about, or with `infer_type = "enforce"` fix, a type that contradicts a confident suggestion.
+fn reconcile_type(
+    message: String,
+    suggestions: &[TypeSuggestion],
+    config: &EffectiveConfig,
+    warnings: &mut Vec<String>,
+) -> String {
+    let Some(top) = suggestions.first().filter(|top| top.is_confident()) else {
+        return message;
+    };
+    let Some(chosen) = sanitize::conventional_type(&message) else {
+        return message;
+    };
+    if chosen == top.kind {
+        return message;
+    }
+
+    match config.infer_type {
+        TypeInference::Warn => {
+            warnings.push(format!(
+                "model chose `{chosen}` but the diff looks like `{}` ({})",
+                top.kind, top.reason
+            ));
+            message
+        }
+        TypeInference::Enforce => {
+            warnings.push(format!(
+                "replaced type `{chosen}` with `{}` ({})",
+                top.kind, top.reason
+            ));
+            sanitize::replace_type(&message, top.kind)
+        }
+        TypeInference::Off | TypeInference::Hint => message,
+    }
+}
+
+/// Use the `style` type for a formatting-only change set, whatever the model chose.
+fn enforce_style_type(message: String, warnings: &mut Vec<String>) -> String {
+    match sanitize::conventional_type(&message) {
+        Some(chosen) if chosen != "style" => {
+            warnings.push(format!(
+                "replaced type `{chosen}` with `style` (formatting-only changes)"
+            ));
+            sanitize::replace_type(&message, "style")
+        }
+        _ => message,
+    }
+}
+
+fn estimate_exchange_tokens(files: &[DiffFile], reply: &str) -> u64 {
+    let input = files.iter().map(|file| file.token_estimate).sum::<usize>();
+    (input + estimate_tokens(reply)) as u64
+}
+
+/// Append the run to the local stats file when `record_stats` is on, with the pipeline's
+/// timings and `staging` unless `record_timings` is off. Failures are only logged.
+pub fn record_stats(
+    config: &EffectiveConfig,
+    outcome: &PipelineOutcome,
+    staging: Option<Duration>,
+) {
+    if !config.record_stats {
+        return;
+    }
+    let mut record = StatsRecord::new(config, outcome.estimated_tokens, outcome.used_fallback);
+    if config.record_timings {
+        let mut timings = Timings::new();
+        if let Some(staging) = staging {
+            timings.record("staging", staging);
+        }
+        timings.extend(&outcome.timings);
+        record = record.with_timings(&timings);
+    }
+    if let Err(err) = stats_path().and_then(|path| append_record(&path, &record)) {
+        warn!("failed to record stats: {err}");
+    }
+}
+
+#[instrument(level = "info", skip(git, provider, config, ignore, message))]
+/// Generate a longer explanation of the staged changes, suitable for `git notes`.
+///
+/// Returns `None` when there is no usable diff to explain.
+///
+/// # Errors
+/// Returns an error if git access fails, the provider fails, or timeouts occur.
+pub async fn generate_commit_note(
+    git: &impl GitBackend,
+    provider: &dyn Provider,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+    message: &str,
+) -> CoreResult<Option<String>> {
+    let subject = message.lines().next().unwrap_or(message);
+    ask_about_staged(
+        git,
+        provider,
+        config,
+        ignore,
+        CallPhase::Note,
+        &note_system_prompt(),
+        |diff| note_user_prompt(subject, diff),
+    )
+    .await
+}
+
+#[instrument(level = "info", skip(git, provider, config, ignore, message))]
+/// Ask the model why `message` fits the staged changes, in a sentence or two, for
+/// `--explain`. The answer is shown to the user, never committed.
+///
+/// Returns `None` when there is no usable diff to explain.
+///
+/// # Errors
+/// Returns an error if git access fails, the provider fails, or timeouts occur.
+pub async fn generate_explanation(
+    git: &impl GitBackend,
+    provider: &dyn Provider,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+    message: &str,
+) -> CoreResult<Option<String>> {
+    ask_about_staged(
+        git,
+        provider,
+        config,
+        ignore,
+        CallPhase::Explain,
+        explain_system_prompt(),
+        |diff| explain_user_prompt(message, diff),
+    )
+    .await
+}
+
+/// One provider call about the staged diff (capped at `max_input_tokens`); `None` when
+/// there is no usable diff or the reply is empty.
+async fn ask_about_staged(
+    git: &impl GitBackend,
+    provider: &dyn Provider,
+    config: &EffectiveConfig,
+    ignore: &IgnoreMatcher,
+    phase: CallPhase,
+    system_prompt: &str,
+    user_prompt: impl FnOnce(&str) -> String,
+) -> CoreResult<Option<String>> {
+    let context = context::collect_diff_context(git, config, ignore)?;
+    if context.ai_files.is_empty() {
+        return Ok(None);
+    }
+
+    let diff = truncate_to_tokens(
+        &diff_files_to_string(&context.ai_files),
+        config.max_input_tokens as usize,
+    );
+    let request = ProviderRequest {
+        max_output_tokens: config.max_output_tokens,
+        temperature: config.temperature,
+        seed: config.seed,
+        response_schema: None,
+        stop: Vec::new(),
+    };
+    let deadline = generation::Deadline::new(config.timeout_secs);
+
+    let reply = generation::call_with_deadline(
+        deadline,
+        phase,
+        None,
+        provider.complete(system_prompt, &user_prompt(&diff), request),
+    )
+    .await?;
+
+    let reply = reply.trim().to_string();
+    Ok(if reply.is_empty() { None } else { Some(reply) })
+}
+
+/// Whether the staged content still matches a fingerprint captured at generation time.
+///
+/// # Errors
+/// Returns an error if git access fails.
+pub fn staged_content_unchanged(git: &impl GitBackend, fingerprint: &str) -> CoreResult<bool> {
+    Ok(git.staged_fingerprint()? == fingerprint)
+}
+
+/// Files `git add .` would stage, reported when there are more than `max_files`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageOverflow {
+    pub count: usize,
+    /// Top-level directories (or root files) with their file counts, largest first.
+    pub top_dirs: Vec<(String, usize)>,
+}
+
+/// Check whether auto-staging would pick up more than `max_files` files; `0` disables the check.
+///
+/// # Errors
+/// Returns an error if git access fails.
+pub fn stage_overflow(
+    git: &impl GitBackend,
+    max_files: usize,
+) -> CoreResult<Option<StageOverflow>> {
+    if max_files == 0 {
+        return Ok(None);
+    }
+    let candidates = git.stage_candidates()?;
+    if candidates.len() <= max_files {
+        return Ok(None);
+    }
+
+    Ok(Some(StageOverflow {
+        count: candidates.len(),
+        top_dirs: group_by_top_dir(&candidates),
+    }))
+}
+
+/// Apply the sanitize rules to a message edited in the user's editor.
+#[must_use]
+pub fn sanitize_edited_message(raw: &str, config: &EffectiveConfig) -> String {
+    sanitize::sanitize_edited(raw, config)
 }
@@ -0,0 +1,73 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Pull request title and description generated in the same call as the commit message.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PrDraft {
+    pub title: String,
+    pub body: String,
+}
+
+#[derive(Deserialize)]
+struct CommitAndPr {
+    commit: String,
+    pr: PrDraft,
+}
+
+/// JSON schema for a `{commit, pr: {title, body}}` reply.
+pub(super) fn response_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "commit": { "type": "string" },
+            "pr": {
+                "type": "object",
+                "properties": {
+                    "title": { "type": "string" },
+                    "body": { "type": "string" }
+                },
+                "required": ["title", "body"],
+                "additionalProperties": false
+            }
+        },
+        "required": ["commit", "pr"],
+        "additionalProperties": false
+    })
+}
+
+/// Split a `{commit, pr}` reply into the commit message and the PR draft.
+///
+/// Text around the JSON object, such as a code fence, is ignored.
+pub(super) fn parse_commit_and_pr(text: &str) -> Option<(String, PrDraft)> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    let parsed = serde_json::from_str::<CommitAndPr>(text.get(start..=end)?).ok()?;
+    if parsed.commit.trim().is_empty() {
+        return None;
+    }
+    Some((parsed.commit, parsed.pr))
+}
+
+/// The commit message from a `pr_draft` reply that did not parse as `{commit, pr}`: the
+/// `commit` (or `subject`) string of a JSON object, or the text itself when it is not
+/// JSON. `None` for JSON with no usable message, which must not be committed as is.
+pub(super) fn commit_without_pr(text: &str) -> Option<String> {
+    let unfenced = text
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_start();
+    if !unfenced.starts_with('{') {
+        return Some(text.to_string());
+    }
+    let end = unfenced.rfind('}')?;
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(&unfenced[..=end]) else {
+        return None;
+    };
+    ["commit", "subject"]
+        .iter()
+        .find_map(|key| fields.get(*key).and_then(Value::as_str))
+        .map(str::trim)
+        .filter(|commit| !commit.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,49 @@
+use serde_json::Value;
+
+use crate::config::{EffectiveConfig, OllamaFormat, ProviderKind};
+
+/// Value for Ollama's `format` field on the commit message call, per `ollama_format`.
+///
+/// `None` for other providers and when `pr_draft` already asks for its own schema.
+pub(super) fn ollama_format(config: &EffectiveConfig) -> Option<Value> {
+    if config.provider != ProviderKind::Ollama || config.pr_draft {
+        return None;
+    }
+    match config.ollama_format {
+        OllamaFormat::Off => None,
+        OllamaFormat::Json => Some(Value::String("json".to_string())),
+        OllamaFormat::Schema => Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "message": { "type": "string" }
+            },
+            "required": ["message"]
+        })),
+    }
+}
+
+/// Whether the commit message call asks for a JSON reply, via `ollama_format` or `pr_draft`.
+pub(super) fn structured_output(config: &EffectiveConfig) -> bool {
+    config.pr_draft || ollama_format(config).is_some()
+}
+
+/// Pull the commit message out of a structured reply, or return the text unchanged.
+///
+/// Not every model honors `format`, so anything that is not a JSON object with a
+/// `message` string (or a single string field) is parsed as a plain message.
+pub(super) fn unwrap_message(text: String) -> String {
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(text.trim()) else {
+        return text;
+    };
+    let message = fields.get("message").and_then(Value::as_str).or_else(|| {
+        let mut strings = fields.values().filter_map(Value::as_str);
+        match (strings.next(), strings.next()) {
+            (Some(only), None) => Some(only),
+            _ => None,
+        }
+    });
+    match message {
+        Some(message) if !message.trim().is_empty() => message.trim().to_string(),
+        _ => text,
+    }
+}
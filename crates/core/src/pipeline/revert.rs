@@ -0,0 +1,118 @@
+use tracing::warn;
+
+use crate::config::EffectiveConfig;
+use crate::error::{CallPhase, CoreResult};
+use crate::git::GitBackend;
+use crate::prompt::{revert_system_prompt, revert_user_prompt};
+use crate::providers::{Provider, ProviderRequest};
+
+use super::generation::{call_with_deadline, Deadline};
+use super::sanitize;
+
+/// Commit message for reverting one commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevertMessage {
+    pub message: String,
+    /// The reason was used as written because the provider was unavailable or failed.
+    pub used_fallback: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Message for a commit that reverts `sha`: `revert: <original subject>`, an optional
+/// paragraph explaining why (written from `reason` when a provider is available), and
+/// git's `This reverts commit <sha>.` line. Reverting a revert brings back the subject
+/// of the commit it undid.
+///
+/// The body is kept even with `one_line`, since it records which commit was reverted.
+///
+/// # Errors
+/// Returns an error if git access fails.
+pub async fn generate_revert_message(
+    git: &impl GitBackend,
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    sha: &str,
+    reason: Option<&str>,
+) -> CoreResult<RevertMessage> {
+    let original = git.commit_message(sha)?;
+    let original_subject = original.lines().next().unwrap_or_default().trim();
+    let subject = if let Some(reapplied) = reverted_subject(original_subject) {
+        reapplied.to_string()
+    } else if config.conventional {
+        format!("revert: {original_subject}")
+    } else {
+        format!("Revert \"{original_subject}\"")
+    };
+
+    let mut warnings = Vec::new();
+    let mut used_fallback = false;
+    let mut paragraphs = vec![subject];
+    if let Some(reason) = reason.map(str::trim).filter(|reason| !reason.is_empty()) {
+        let why = explain(provider, config, &original, reason, &mut warnings).await;
+        used_fallback = why.is_none();
+        paragraphs.push(why.unwrap_or_else(|| reason.to_string()));
+    }
+    paragraphs.push(format!("This reverts commit {sha}."));
+
+    Ok(RevertMessage {
+        message: sanitize::apply_affixes(&paragraphs.join("\n\n"), config),
+        used_fallback,
+        warnings,
+    })
+}
+
+/// The subject a revert commit undid, from either `revert: <subject>` or git's
+/// `Revert "<subject>"`.
+fn reverted_subject(subject: &str) -> Option<&str> {
+    let reverted = subject
+        .strip_prefix("revert: ")
+        .or_else(|| subject.strip_prefix("Revert \"")?.strip_suffix('"'))?
+        .trim();
+    (!reverted.is_empty()).then_some(reverted)
+}
+
+/// The provider's explanation, or `None` (with a warning) when the reason should be used as written.
+async fn explain(
+    provider: Option<&dyn Provider>,
+    config: &EffectiveConfig,
+    original: &str,
+    reason: &str,
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let Some(provider) = provider else {
+        warnings.push("provider unavailable, using the reason as written".to_string());
+        return None;
+    };
+    let request = ProviderRequest {
+        max_output_tokens: config.max_output_tokens,
+        temperature: config.temperature,
+        seed: config.seed,
+        response_schema: None,
+        stop: Vec::new(),
+    };
+    let reply = call_with_deadline(
+        Deadline::new(config.timeout_secs),
+        CallPhase::Revert,
+        None,
+        provider.complete(
+            revert_system_prompt(),
+            &revert_user_prompt(original, reason),
+            request,
+        ),
+    )
+    .await;
+    match reply {
+        Ok(why) if !why.trim().is_empty() => Some(why.trim().to_string()),
+        Ok(_) => {
+            warnings.push("ai explanation was empty, using the reason as written".to_string());
+            None
+        }
+        Err(err) => {
+            warn!("revert explanation failed: {err}");
+            warnings.push(format!(
+                "ai explanation failed, using the reason as written: {err}"
+            ));
+            None
+        }
+    }
+}
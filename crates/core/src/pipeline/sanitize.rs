@@ -1,9 +1,23 @@
-use regex::Regex;
+use std::fmt::Write as _;
 use std::sync::LazyLock;
 
-use crate::config::EffectiveConfig;
+use regex::Regex;
+
+use crate::config::{EffectiveConfig, PrefixPlacement, SubjectCase};
+use crate::lint::is_conventional_subject;
+
+use super::fallback::Rejection;
 
 pub(super) fn sanitize_message(raw: &str, config: &EffectiveConfig, fallback: &str) -> String {
+    sanitize_checked(raw, config, fallback).unwrap_or_else(|_| fallback.to_string())
+}
+
+/// Like [`sanitize_message`], but says why the reply was replaced by the fallback.
+pub(super) fn sanitize_checked(
+    raw: &str,
+    config: &EffectiveConfig,
+    fallback: &str,
+) -> Result<String, Rejection> {
     let cleaned = trim_quotes(raw);
     let mut message = cleaned.trim().to_string();
 
@@ -14,22 +28,276 @@ pub(super) fn sanitize_message(raw: &str, config: &EffectiveConfig, fallback: &s
     message = message.replace("```", "").replace('`', "");
 
     if config.conventional {
-        let re = conventional_regex();
+        let types = &config.conventional_types;
         let first_line = message.lines().next().unwrap_or("").trim();
-        if !re.is_match(first_line) {
-            if let Some(found) = cleaned.lines().find(|line| re.is_match(line.trim())) {
+        if !is_conventional_subject(first_line, types) {
+            if let Some(found) = cleaned
+                .lines()
+                .find(|line| is_conventional_subject(line, types))
+            {
                 message = found.trim().to_string();
+            } else if first_line.is_empty() {
+                return Err(Rejection::Empty);
             } else {
-                message = fallback.to_string();
+                return Err(Rejection::NotConventional {
+                    first_line: first_line.to_string(),
+                });
             }
         }
     }
 
+    // The fallback is returned as given: it is either generated or the user's own wording.
+    if message == fallback {
+        return Ok(message);
+    }
+    message = strip_trailing_period(&message);
+    message = apply_subject_case(&message, config.subject_case);
+
     if message.is_empty() {
-        fallback.to_string()
+        Err(Rejection::Empty)
+    } else {
+        Ok(message)
+    }
+}
+
+/// Prefix `kind: ` to a reply whose first line is a plain description without a type.
+///
+/// `None` when the first line is empty or already has a `type:` header, so a wrong type
+/// is never hidden behind a second one.
+pub(super) fn add_missing_type(raw: &str, kind: &str) -> Option<String> {
+    let cleaned = trim_quotes(raw).replace("```", "");
+    let cleaned = cleaned.trim();
+    let (first_line, rest) = match cleaned.split_once('\n') {
+        Some((first_line, rest)) => (first_line.trim(), Some(rest)),
+        None => (cleaned, None),
+    };
+    if first_line.is_empty() || type_header_regex().is_match(first_line) {
+        return None;
+    }
+    Some(match rest {
+        Some(rest) => format!("{kind}: {first_line}\n{rest}"),
+        None => format!("{kind}: {first_line}"),
+    })
+}
+
+/// Add the configured subject prefix and suffix to the first line.
+///
+/// Affixes already present are not added again.
+pub(super) fn apply_affixes(message: &str, config: &EffectiveConfig) -> String {
+    let (subject, rest) = match message.split_once('\n') {
+        Some((subject, rest)) => (subject, Some(rest)),
+        None => (message, None),
+    };
+    let mut subject = subject.trim().to_string();
+
+    if let Some(prefix) = &config.subject_prefix {
+        if !subject.contains(prefix.as_str()) {
+            let after_type = config.subject_prefix_placement == PrefixPlacement::AfterType
+                && is_conventional_subject(&subject, &config.conventional_types);
+            subject = match subject.split_once(": ") {
+                Some((header, description)) if after_type => {
+                    format!("{header}: {prefix} {description}")
+                }
+                _ => format!("{prefix} {subject}"),
+            };
+        }
+    }
+
+    if let Some(suffix) = &config.subject_suffix {
+        if !subject.ends_with(suffix.as_str()) {
+            subject = format!("{subject} {suffix}");
+        }
+    }
+
+    match rest {
+        Some(rest) => format!("{subject}\n{rest}"),
+        None => subject,
+    }
+}
+
+/// Put the user's body (`--body`) under the subject, then the `Changed files:` list.
+pub(super) fn finish_body(message: &str, paths: &[String], config: &EffectiveConfig) -> String {
+    let message = match &config.user_body {
+        Some(body) => with_user_body(message, body, config),
+        None => message.to_string(),
+    };
+    append_file_summary(&message, paths, config)
+}
+
+/// Append a `Changed files:` list of `paths` after the body when `body_file_summary` is
+/// on, listing at most `max_files` paths. A one-line message gets none unless the user
+/// gave the body.
+fn append_file_summary(message: &str, paths: &[String], config: &EffectiveConfig) -> String {
+    let one_line = config.one_line && config.user_body.is_none();
+    if !config.body_file_summary || one_line || paths.is_empty() {
+        return message.to_string();
+    }
+    let mut summary = String::from("Changed files:");
+    for path in paths.iter().take(config.max_files) {
+        let _ = write!(summary, "\n- {path}");
+    }
+    if paths.len() > config.max_files {
+        let _ = write!(
+            summary,
+            "\n- ... and {} more",
+            paths.len() - config.max_files
+        );
+    }
+    format!("{}\n\n{summary}", message.trim_end())
+}
+
+/// Put the user's `body` under the first line of `message`, wrapped at `body_wrap`.
+fn with_user_body(message: &str, body: &str, config: &EffectiveConfig) -> String {
+    let subject = message.lines().next().unwrap_or_default().trim();
+    let body = wrap_body(&body.replace("\r\n", "\n"), config.body_wrap);
+    if body.is_empty() {
+        subject.to_string()
+    } else {
+        format!("{subject}\n\n{body}")
+    }
+}
+
+/// Wrap body lines longer than `width` at word boundaries (`0` leaves them alone).
+///
+/// Lines are not reflowed together. Indented lines (code) and trailers such as
+/// `Signed-off-by: ...` stay as written; a wrapped list item keeps its items aligned.
+pub(super) fn wrap_body(body: &str, width: usize) -> String {
+    let mut wrapped = Vec::new();
+    for line in body.trim().lines().map(str::trim_end) {
+        if width == 0
+            || line.chars().count() <= width
+            || line.starts_with([' ', '\t'])
+            || is_trailer_line(line)
+        {
+            wrapped.push(line.to_string());
+            continue;
+        }
+        let indent = if line.starts_with("- ") || line.starts_with("* ") {
+            "  "
+        } else {
+            ""
+        };
+        let mut current = String::new();
+        for word in line.split(' ').filter(|word| !word.is_empty()) {
+            if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+                wrapped.push(std::mem::replace(&mut current, indent.to_string()));
+            } else if !current.trim().is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        wrapped.push(current);
+    }
+    wrapped.join("\n")
+}
+
+/// A git trailer line such as `Signed-off-by: Name <mail>`: one word of letters, digits,
+/// and dashes, then `: ` and a value.
+pub(super) fn is_trailer_line(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(key, value)| {
+        !key.is_empty()
+            && !value.trim().is_empty()
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+/// Sanitize a message the user edited by hand.
+///
+/// Formatting cleanup still applies, but an edit that fails the conventional
+/// check is kept as written rather than replaced, and the body survives when
+/// it was deliberately offered via `one_line_keep_body_in_editor`.
+pub(super) fn sanitize_edited(raw: &str, config: &EffectiveConfig) -> String {
+    let keep_body = config.one_line_keep_body_in_editor;
+    let as_written = if keep_body {
+        raw.replace("```", "").replace('`', "").trim().to_string()
+    } else {
+        let cleaned = trim_quotes(raw).replace("```", "").replace('`', "");
+        let cleaned = cleaned.trim();
+        if config.one_line {
+            cleaned.lines().next().unwrap_or("").trim().to_string()
+        } else {
+            cleaned.to_string()
+        }
+    };
+
+    if keep_body {
+        return as_written;
+    }
+    sanitize_message(raw, config, &as_written)
+}
+
+/// The body lines a one-line sanitize pass drops from the raw model output.
+pub(super) fn dropped_body(raw: &str, config: &EffectiveConfig) -> Option<String> {
+    if !config.one_line {
+        return None;
+    }
+
+    let cleaned = trim_quotes(raw).replace("```", "").replace('`', "");
+    let body = cleaned
+        .trim()
+        .lines()
+        .skip(1)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let body = body.trim();
+    if body.is_empty() {
+        None
     } else {
-        message
+        Some(body.to_string())
+    }
+}
+
+/// Drop the period Conventional Commits discourages at the end of the subject.
+fn strip_trailing_period(message: &str) -> String {
+    let (subject, rest) = match message.split_once('\n') {
+        Some((subject, rest)) => (subject, Some(rest)),
+        None => (message, None),
+    };
+    let subject = subject.trim_end().trim_end_matches('.');
+    match rest {
+        Some(rest) => format!("{subject}\n{rest}"),
+        None => subject.to_string(),
+    }
+}
+
+/// Recase the first letter of the description, after any `type(scope): ` header and emoji.
+///
+/// Words that look like acronyms (`HTTP`, `CI`) are left alone.
+pub(super) fn apply_subject_case(message: &str, case: SubjectCase) -> String {
+    if case == SubjectCase::Preserve {
+        return message.to_string();
+    }
+    let subject = message.lines().next().unwrap_or("");
+    let start = conventional_type(subject)
+        .and_then(|_| subject.find(": ").map(|index| index + 2))
+        .unwrap_or(0);
+    let Some((offset, first)) = subject[start..]
+        .char_indices()
+        .find(|(_, c)| c.is_alphanumeric())
+    else {
+        return message.to_string();
+    };
+    if !first.is_alphabetic() {
+        return message.to_string();
     }
+    let index = start + offset;
+    let rest = &message[index + first.len_utf8()..];
+    if rest.chars().next().is_some_and(char::is_uppercase) {
+        return message.to_string();
+    }
+
+    let recased: String = match case {
+        SubjectCase::Lower => first.to_lowercase().collect(),
+        SubjectCase::Sentence => first.to_uppercase().collect(),
+        SubjectCase::Preserve => first.to_string(),
+    };
+    format!("{}{recased}{rest}", &message[..index])
+}
+
+fn type_header_regex() -> &'static Regex {
+    static RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^[\w-]+(\([^)]*\))?!?:").expect("invalid regex"));
+    &RE
 }
 
 fn trim_quotes(input: &str) -> String {
@@ -41,10 +309,35 @@ fn trim_quotes(input: &str) -> String {
         .to_string()
 }
 
-fn conventional_regex() -> &'static Regex {
-    static RE: LazyLock<Regex> = LazyLock::new(|| {
-        Regex::new(r"^(feat|fix|build|chore|ci|docs|style|refactor|perf|test)(\([\w./-]+\))?: .+")
-            .expect("invalid regex")
-    });
-    &RE
+/// The type of a conventional subject, e.g. `feat` for `feat(cli)!: add flag`.
+pub(super) fn conventional_type(message: &str) -> Option<&str> {
+    let subject = message.lines().next()?.trim();
+    let (header, _) = subject.split_once(':')?;
+    let end = header.find(['(', '!']).unwrap_or(header.len());
+    let kind = &header[..end];
+    (!kind.is_empty() && kind.chars().all(|c| c.is_ascii_lowercase())).then_some(kind)
+}
+
+/// Add `scope` to a conventional subject that has none, e.g. `feat: x` to `feat(core): x`.
+pub(super) fn apply_scope(message: &str, scope: &str) -> String {
+    let Some(kind) = conventional_type(message) else {
+        return message.to_string();
+    };
+    let start = message.len() - message.trim_start().len();
+    let rest = &message[start + kind.len()..];
+    if rest.starts_with('(') {
+        return message.to_string();
+    }
+    format!("{}{kind}({scope}){rest}", &message[..start])
+}
+
+/// Swap the type of a conventional message, keeping scope, breaking marker, and body.
+pub(super) fn replace_type(message: &str, kind: &str) -> String {
+    match conventional_type(message) {
+        Some(current) => {
+            let start = message.len() - message.trim_start().len();
+            format!("{kind}{}", &message[start + current.len()..])
+        }
+        None => message.to_string(),
+    }
 }
@@ -0,0 +1,83 @@
+use std::path::Path;
+use std::process::Command;
+
+use tracing::debug;
+
+use crate::config::EffectiveConfig;
+use crate::error::CoreResult;
+use crate::git::GitBackend;
+use crate::ignore::IgnoreMatcher;
+
+use super::sanitize::is_trailer_line;
+
+/// Trailers from the `[stamp]` config whose paths match the staged changes, as
+/// `(name, value)` pairs in name order. A command that fails or prints nothing is skipped
+/// with a warning.
+///
+/// # Errors
+/// Returns an error if git access fails or a `paths` pattern is invalid.
+pub(super) fn stamp_trailers(
+    git: &impl GitBackend,
+    config: &EffectiveConfig,
+    warnings: &mut Vec<String>,
+) -> CoreResult<Vec<(String, String)>> {
+    if config.stamp.is_empty() {
+        return Ok(Vec::new());
+    }
+    let staged = git.staged_files()?;
+    let repo_root = git.repo_root()?;
+    let mut trailers = Vec::new();
+    for (name, stamp) in &config.stamp {
+        let matcher = IgnoreMatcher::from_patterns(&stamp.paths)?;
+        if !stamp.paths.is_empty() && !staged.iter().any(|path| matcher.is_ignored(path)) {
+            continue;
+        }
+        match run_stamp_command(&stamp.command, &repo_root) {
+            Ok(value) => trailers.push((name.clone(), value)),
+            Err(reason) => warnings.push(format!("skipped the {name} trailer: {reason}")),
+        }
+    }
+    Ok(trailers)
+}
+
+/// First line of the command's trimmed stdout, run with `sh -c` from the repo root.
+fn run_stamp_command(command: &str, repo_root: &Path) -> Result<String, String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(repo_root)
+        .output()
+        .map_err(|err| format!("`{command}` could not run: {err}"))?;
+    if !output.status.success() {
+        debug!(
+            stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+            "stamp command failed"
+        );
+        return Err(format!("`{command}` exited with {}", output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = stdout.trim().lines().next().unwrap_or_default().trim();
+    if value.is_empty() {
+        return Err(format!("`{command}` printed nothing"));
+    }
+    Ok(value.to_string())
+}
+
+/// Append `trailers` as `Name: value` lines: to the message's trailer block when its last
+/// paragraph already is one (say a `Signed-off-by:` from `--body`), else as a new paragraph.
+pub(super) fn append_trailers(message: &str, trailers: &[(String, String)]) -> String {
+    if trailers.is_empty() {
+        return message.to_string();
+    }
+    let message = message.trim_end();
+    let block = trailers
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let ends_in_trailers = message
+        .rsplit_once("\n\n")
+        .is_some_and(|(_, last)| last.lines().all(is_trailer_line));
+    let separator = if ends_in_trailers { "\n" } else { "\n\n" };
+    format!("{message}{separator}{block}")
+}
@@ -1,12 +1,36 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
 
-use crate::config::{Config, ConfigPaths};
+use crate::config::{
+    Config, ConfigPaths, EffectiveConfig, PrefixPlacement, ProviderKind, StampConfig, TypeInference,
+};
+use crate::diff::{estimate_tokens, DiffFile};
+use crate::error::{CallPhase, CoreError, TimeoutError};
 use crate::git::{GitBackend, GitDiff, GitFileStat};
 use crate::ignore::build_ignore_matcher;
+use crate::lint::is_conventional_subject;
+use crate::providers::{is_context_length_error, Provider, ProviderRequest};
+use crate::template::check_fallback_template;
 
 use super::context::collect_diff_context;
-use super::sanitize::sanitize_message;
+use super::fallback::{fallback_message, FallbackFacts, FallbackReason, Rejection, Trace};
+use super::generation::{
+    call_with_deadline, estimate_usage, generate_with_provider, summarize_then_commit,
+    summary_batches, summary_file_token_cap, Deadline,
+};
+use super::headroom::{plan_trimming, PromptSizes, Trimming};
+use super::reply;
+use super::sanitize::{
+    apply_affixes, apply_scope, apply_subject_case, dropped_body, replace_type, sanitize_edited,
+    sanitize_message, wrap_body,
+};
+use super::{
+    generate_changelog, generate_commit_message, generate_commit_note, generate_explanation,
+    generate_revert_message, generate_tag_message, staged_content_unchanged, PipelineResult,
+};
 
 #[test]
 fn sanitize_message_falls_back_for_invalid_conventional() {
@@ -24,9 +48,164 @@ fn sanitize_message_strips_code_fences() {
     assert_eq!(cleaned, "feat: add api");
 }
 
+#[test]
+fn sanitize_message_strips_trailing_period() {
+    let mut config = Config::defaults();
+    config.one_line = Some(false);
+    let config = config.resolve().expect("config");
+    let fallback = "chore: update files";
+    assert_eq!(
+        sanitize_message("feat: add thing.", &config, fallback),
+        "feat: add thing"
+    );
+    assert_eq!(
+        sanitize_message("feat: add thing.\n\nKeeps the body.", &config, fallback),
+        "feat: add thing\n\nKeeps the body."
+    );
+}
+
+#[test]
+fn subject_case_recases_first_letter_only() {
+    use crate::config::SubjectCase::{Lower, Preserve, Sentence};
+
+    assert_eq!(
+        apply_subject_case("feat: Add search", Lower),
+        "feat: add search"
+    );
+    assert_eq!(
+        apply_subject_case("fix(api): HTTP retries", Lower),
+        "fix(api): HTTP retries"
+    );
+    assert_eq!(
+        apply_subject_case("feat: add search", Sentence),
+        "feat: Add search"
+    );
+    assert_eq!(
+        apply_subject_case("feat: Add search", Preserve),
+        "feat: Add search"
+    );
+    assert_eq!(
+        apply_subject_case("feat: 2fa support", Sentence),
+        "feat: 2fa support"
+    );
+}
+
+#[test]
+fn subject_case_skips_emoji_and_handles_non_ascii() {
+    use crate::config::SubjectCase::{Lower, Sentence};
+
+    assert_eq!(
+        apply_subject_case("feat: ✨ add search", Sentence),
+        "feat: ✨ Add search"
+    );
+    assert_eq!(apply_subject_case("✨ Add search", Lower), "✨ add search");
+    assert_eq!(
+        apply_subject_case("docs: Élargir la doc", Lower),
+        "docs: élargir la doc"
+    );
+    assert_eq!(
+        apply_subject_case("fix: über cache\n\nBody Stays.", Sentence),
+        "fix: Über cache\n\nBody Stays."
+    );
+
+    let mut config = Config::defaults();
+    config.subject_case = Some(Sentence);
+    let config = config.resolve().expect("config");
+    assert_eq!(
+        sanitize_message("feat: add thing.", &config, "chore: update files"),
+        "feat: Add thing"
+    );
+}
+
+#[test]
+fn dropped_body_keeps_lines_after_subject() {
+    let config = Config::defaults().resolve().expect("defaults resolve");
+    let body = dropped_body("feat: add api\n\n- adds `GET /items`\n", &config);
+    assert_eq!(body.as_deref(), Some("- adds GET /items"));
+    assert_eq!(dropped_body("feat: add api", &config), None);
+}
+
+#[test]
+fn sanitize_edited_keeps_manual_wording() {
+    let config = Config::defaults().resolve().expect("defaults resolve");
+    assert_eq!(
+        sanitize_edited("`fix: handle empty diff`\n\nmore detail", &config),
+        "fix: handle empty diff"
+    );
+    assert_eq!(
+        sanitize_edited("Handle empty diff", &config),
+        "Handle empty diff"
+    );
+
+    let mut keep_body = Config::defaults();
+    keep_body.one_line_keep_body_in_editor = Some(true);
+    let keep_body = keep_body.resolve().expect("config");
+    assert_eq!(
+        sanitize_edited("fix: handle empty diff\n\nmore detail", &keep_body),
+        "fix: handle empty diff\n\nmore detail"
+    );
+}
+
+fn affix_config(placement: PrefixPlacement) -> crate::config::EffectiveConfig {
+    let mut config = Config::defaults();
+    config.subject_prefix = Some("[ABC-12]".to_string());
+    config.subject_suffix = Some("(#7)".to_string());
+    config.subject_prefix_placement = Some(placement);
+    config.resolve().expect("config")
+}
+
+#[test]
+fn apply_affixes_keeps_conventional_subject_valid() {
+    let config = affix_config(PrefixPlacement::AfterType);
+    let message = apply_affixes("feat(cli): add flag", &config);
+    assert_eq!(message, "feat(cli): [ABC-12] add flag (#7)");
+    assert!(is_conventional_subject(
+        &message,
+        &config.conventional_types
+    ));
+    assert_eq!(apply_affixes(&message, &config), message);
+    assert_eq!(config.subject_budget(), 50 - 9 - 5);
+}
+
+#[test]
+fn apply_affixes_supports_start_placement_and_bodies() {
+    let config = affix_config(PrefixPlacement::Start);
+    assert_eq!(
+        apply_affixes("fix: handle empty diff\n\nbody", &config),
+        "[ABC-12] fix: handle empty diff (#7)\n\nbody"
+    );
+}
+
+#[test]
+fn summary_file_token_cap_is_configurable() {
+    let mut config = Config::defaults();
+    config.max_input_tokens = Some(8000);
+    assert_eq!(
+        summary_file_token_cap(&config.clone().resolve().expect("config")),
+        2000
+    );
+
+    config.summary_max_file_tokens = Some(0);
+    assert_eq!(
+        summary_file_token_cap(&config.clone().resolve().expect("config")),
+        8000
+    );
+
+    config.summary_max_file_tokens = Some(20_000);
+    assert_eq!(
+        summary_file_token_cap(&config.resolve().expect("config")),
+        8000
+    );
+}
+
+#[derive(Default)]
 struct StubGit {
     stats: Vec<GitFileStat>,
     diffs: HashMap<String, String>,
+    /// Unstaged paths reported by `stage_candidates`.
+    unstaged: Vec<String>,
+    /// `(sha, message)` pairs returned by `rev_list` and `commit_message`, oldest first.
+    commits: Vec<(String, String)>,
 }
 
 impl GitBackend for StubGit {
@@ -65,13 +244,15 @@ impl GitBackend for StubGit {
     fn staged_diff_for_path(
         &self,
         path: &str,
-        _max_bytes: u64,
+        max_bytes: u64,
     ) -> crate::error::CoreResult<GitDiff> {
-        let content = self.diffs.get(path).cloned().unwrap_or_default();
-        Ok(GitDiff {
-            content,
-            truncated: false,
-        })
+        let mut content = self.diffs.get(path).cloned().unwrap_or_default();
+        let max_bytes = usize::try_from(max_bytes).unwrap_or(usize::MAX);
+        let truncated = content.len() > max_bytes;
+        if truncated {
+            content.truncate(max_bytes);
+        }
+        Ok(GitDiff { content, truncated })
     }
 
     fn staged_files(&self) -> crate::error::CoreResult<Vec<String>> {
@@ -82,26 +263,139 @@ impl GitBackend for StubGit {
         Ok(self.stats.clone())
     }
 
+    fn has_staged_changes(&self) -> crate::error::CoreResult<bool> {
+        Ok(!self.stats.is_empty())
+    }
+
+    fn staged_fingerprint(&self) -> crate::error::CoreResult<String> {
+        Ok(self
+            .stats
+            .iter()
+            .map(|stat| format!("{}:{}:{}", stat.path, stat.additions, stat.deletions))
+            .collect::<Vec<_>>()
+            .join(","))
+    }
+
     fn working_tree_files(&self) -> crate::error::CoreResult<Vec<String>> {
         Ok(Vec::new())
     }
 
     fn has_unstaged_changes(&self) -> crate::error::CoreResult<bool> {
-        Ok(false)
+        Ok(!self.unstaged.is_empty())
     }
 
-    fn commit(
-        &self,
-        _message: &str,
-        _edit: bool,
-        _no_verify: bool,
-    ) -> crate::error::CoreResult<String> {
+    fn status(&self) -> crate::error::CoreResult<crate::status::RepoStatus> {
+        use crate::status::{FileChange, FileEntry, RepoStatus};
+        let entry = |path: &String| FileEntry {
+            path: path.clone(),
+            change: FileChange::Modified,
+            original_path: None,
+            submodule: false,
+        };
+        Ok(RepoStatus {
+            staged: self.stats.iter().map(|stat| entry(&stat.path)).collect(),
+            unstaged: self.unstaged.iter().map(entry).collect(),
+            untracked: Vec::new(),
+        })
+    }
+
+    fn stage_candidates(&self) -> crate::error::CoreResult<Vec<String>> {
+        Ok(self.unstaged.clone())
+    }
+
+    fn sparse_cone(&self) -> crate::error::CoreResult<Option<Vec<String>>> {
+        Ok(None)
+    }
+
+    fn commit_message(&self, rev: &str) -> crate::error::CoreResult<String> {
+        Ok(self
+            .commits
+            .iter()
+            .find(|(sha, _)| sha == rev)
+            .map(|(_, message)| message.clone())
+            .unwrap_or_default())
+    }
+
+    fn head_subject(&self) -> crate::error::CoreResult<Option<String>> {
+        Ok(None)
+    }
+
+    // Unstaged files live in `diffs` too; only paths in `stats` count as staged.
+    fn worktree_file(&self, path: &str) -> crate::error::CoreResult<Option<String>> {
+        Ok(self.diffs.get(path).cloned())
+    }
+
+    fn current_branch(&self) -> crate::error::CoreResult<Option<String>> {
+        Ok(Some("feature".to_string()))
+    }
+
+    fn tracked_files(&self) -> crate::error::CoreResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn recent_messages(&self, _limit: usize) -> crate::error::CoreResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn rev_list(&self, _range: &str) -> crate::error::CoreResult<Vec<String>> {
+        Ok(self.commits.iter().map(|(sha, _)| sha.clone()).collect())
+    }
+
+    fn last_tag(&self) -> crate::error::CoreResult<Option<String>> {
+        Ok(None)
+    }
+
+    fn commit(&self, _message: &str, _no_verify: bool) -> crate::error::CoreResult<String> {
+        Ok(String::new())
+    }
+
+    fn commit_encoding(&self) -> crate::error::CoreResult<Option<String>> {
+        Ok(None)
+    }
+
+    fn short_head(&self) -> crate::error::CoreResult<String> {
         Ok(String::new())
     }
 
+    fn add_note(&self, _rev: &str, _note: &str) -> crate::error::CoreResult<()> {
+        Ok(())
+    }
+
+    fn run_hook(&self, _name: &str) -> crate::error::CoreResult<Option<crate::git::HookOutput>> {
+        Ok(None)
+    }
+
     fn push(&self) -> crate::error::CoreResult<String> {
         Ok(String::new())
     }
+
+    fn push_with_prompt(&self) -> crate::error::CoreResult<()> {
+        Ok(())
+    }
+
+    fn pull_rebase(&self) -> crate::error::CoreResult<String> {
+        Ok(String::new())
+    }
+
+    fn revert_no_commit(&self, sha: &str) -> crate::error::CoreResult<String> {
+        Ok(sha.to_string())
+    }
+
+    fn abort_revert(&self) -> crate::error::CoreResult<()> {
+        Ok(())
+    }
+
+    fn tag_exists(&self, _name: &str) -> crate::error::CoreResult<bool> {
+        Ok(false)
+    }
+
+    fn create_annotated_tag(&self, _name: &str, _message: &str) -> crate::error::CoreResult<()> {
+        Ok(())
+    }
+
+    fn push_tag(&self, _name: &str) -> crate::error::CoreResult<String> {
+        Ok(String::new())
+    }
 }
 
 #[test]
@@ -130,7 +424,11 @@ fn collect_diff_context_skips_empty_diffs_before_limit() {
     let mut diffs = HashMap::new();
     diffs.insert("file3.txt".to_string(), "diff --git a b".to_string());
 
-    let git = StubGit { stats, diffs };
+    let git = StubGit {
+        stats,
+        diffs,
+        ..StubGit::default()
+    };
     let mut config = Config::defaults();
     config.max_files = Some(1);
     let config = config.resolve().expect("config");
@@ -140,6 +438,7 @@ fn collect_diff_context_skips_empty_diffs_before_limit() {
         repo_config: None,
         global_ignore: PathBuf::from("missing"),
         repo_ignore: None,
+        legacy_config: None,
     };
     let ignore = build_ignore_matcher(&[], &paths).expect("ignore");
 
@@ -147,3 +446,2451 @@ fn collect_diff_context_skips_empty_diffs_before_limit() {
     assert_eq!(context.ai_files.len(), 1);
     assert_eq!(context.ai_files[0].path, "file3.txt");
 }
+
+#[tokio::test]
+async fn big_deletions_outrank_small_edits_for_max_files_and_the_fallback() {
+    let stat = |path: &str, additions, deletions| GitFileStat {
+        path: path.to_string(),
+        additions,
+        deletions,
+        is_binary: false,
+    };
+    let stats = vec![
+        stat("src/format.rs", 40, 40),
+        stat("src/legacy.rs", 0, 100),
+        stat("src/tweak.rs", 10, 0),
+    ];
+    let git = StubGit {
+        diffs: stats
+            .iter()
+            .map(|stat| (stat.path.clone(), format!("+change in {}", stat.path)))
+            .collect(),
+        stats,
+        ..StubGit::default()
+    };
+    let mut config = Config::defaults();
+    config.max_files = Some(2);
+    let config = config.resolve().expect("config");
+
+    let context = collect_diff_context(&git, &config, &empty_ignore()).expect("context");
+    let sent = context
+        .ai_files
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(sent, ["src/legacy.rs", "src/format.rs"]);
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, None, &config, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(
+        outcome.message,
+        "chore: update src/legacy.rs, src/format.rs, src/tweak.rs"
+    );
+
+    let mut by_additions = config.clone();
+    by_additions.deletion_weight = 0;
+    let context = collect_diff_context(&git, &by_additions, &empty_ignore()).expect("context");
+    assert_eq!(
+        context.all_paths,
+        ["src/format.rs", "src/tweak.rs", "src/legacy.rs"]
+    );
+}
+
+#[test]
+fn crlf_diffs_are_normalized_within_max_file_bytes() {
+    let diff = "diff --git a/win.txt b/win.txt\r\n+one\r\n+two\r\n+three\r\n";
+    // Cut between the `\r` and `\n` after `+two`.
+    let max_file_bytes = diff.find("+two").expect("line") + "+two\r".len();
+    let git = StubGit {
+        stats: vec![GitFileStat {
+            path: "win.txt".to_string(),
+            additions: 3,
+            deletions: 0,
+            is_binary: false,
+        }],
+        diffs: HashMap::from([("win.txt".to_string(), diff.to_string())]),
+        ..StubGit::default()
+    };
+    let mut config = Config::defaults();
+    config.max_file_bytes = Some(max_file_bytes as u64);
+    let config = config.resolve().expect("config");
+
+    let context = collect_diff_context(&git, &config, &empty_ignore()).expect("context");
+
+    let file = &context.ai_files[0];
+    assert_eq!(file.content, "diff --git a/win.txt b/win.txt\n+one\n+two");
+    assert!(file.truncated);
+    assert!(file.content.len() <= max_file_bytes);
+    assert_eq!(file.token_estimate, estimate_tokens(&file.content));
+}
+
+#[test]
+fn allow_large_lifts_the_line_cap_for_matching_files() {
+    let big = |path: &str| GitFileStat {
+        path: path.to_string(),
+        additions: 30,
+        deletions: 0,
+        is_binary: false,
+    };
+    let diff = (0..30)
+        .map(|n| format!("+line {n}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let git = StubGit {
+        stats: vec![big("src/engine.rs"), big("src/other.rs")],
+        diffs: HashMap::from([
+            ("src/engine.rs".to_string(), diff.clone()),
+            ("src/other.rs".to_string(), diff.clone()),
+        ]),
+        ..StubGit::default()
+    };
+    let mut config = Config::defaults();
+    config.max_file_lines = Some(10);
+    config.allow_large = Some(vec!["src/engine.rs".to_string()]);
+    config.summary_max_hunk_groups = Some(0);
+    let config = config.resolve().expect("config");
+
+    let context = collect_diff_context(&git, &config, &crate::ignore::IgnoreMatcher::none())
+        .expect("context");
+
+    assert_eq!(context.ai_files[0].content, diff);
+    assert!(!context.ai_files[0].truncated);
+    assert!(context.ai_files[1]
+        .content
+        .contains("diff omitted due to size"));
+    assert_eq!(
+        context.warnings,
+        vec!["diff omitted for src/other.rs (30 lines)"]
+    );
+}
+
+/// A diff of `hunks` hunks, each changing `lines` lines.
+fn many_hunks(hunks: usize, lines: usize) -> String {
+    let mut diff = String::from("diff --git a/src/big.rs b/src/big.rs\n");
+    for hunk in 0..hunks {
+        let start = hunk * 100 + 1;
+        let _ = writeln!(diff, "@@ -{start},{lines} +{start},{lines} @@");
+        for line in 0..lines {
+            let _ = writeln!(
+                diff,
+                "-let old_{hunk}_{line} = 0;\n+let new_{hunk}_{line} = 1;"
+            );
+        }
+    }
+    diff
+}
+
+#[tokio::test]
+async fn diffs_over_max_file_lines_are_summarized_by_hunk_group() {
+    let provider = StubProvider::with_replies(&[
+        "- renames the first values",
+        "- renames the second values",
+        "- renames the third values",
+        "- renames the last values",
+        "refactor: rename values",
+    ]);
+    let diff = many_hunks(4, 10);
+    let git = StubGit {
+        stats: vec![GitFileStat {
+            path: "src/big.rs".to_string(),
+            additions: 40,
+            deletions: 40,
+            is_binary: false,
+        }],
+        diffs: HashMap::from([("src/big.rs".to_string(), diff.clone())]),
+        ..StubGit::default()
+    };
+    let mut config = Config::defaults();
+    config.max_file_lines = Some(20);
+    let hunk_tokens = estimate_tokens(&diff) / 4;
+    config.summary_max_file_tokens = Some(u32::try_from(hunk_tokens + 20).expect("cap"));
+    let config = config.resolve().expect("config");
+
+    let context = collect_diff_context(&git, &config, &empty_ignore()).expect("context");
+
+    assert_eq!(context.ai_files[0].content, diff.trim_end());
+    assert_eq!(
+        context.warnings,
+        vec!["diff summarized hunk by hunk for src/big.rs (80 lines)"]
+    );
+
+    let mut trace = Trace::default();
+    let message = summarize_then_commit(
+        &provider,
+        &config,
+        &context.ai_files,
+        &[],
+        &context.all_paths,
+        Deadline::new(5),
+        &mut trace,
+        &mut Vec::new(),
+    )
+    .await
+    .expect("message");
+
+    assert_eq!(message, "refactor: rename values");
+    let prompts = provider.prompts();
+    assert_eq!(prompts.len(), 5, "four hunk groups and the final call");
+    assert!(prompts[0].contains("Summarize changes for src/big.rs (part 1 of 4)"));
+    assert!(!prompts[4].contains("diff omitted due to size"));
+}
+
+#[test]
+fn usage_estimate_counts_each_hunk_group_call() {
+    let content = many_hunks(4, 100);
+    let hunk_tokens = estimate_tokens(&content) / 4;
+    let files = vec![diff_file("src/big.rs", &content)];
+    let estimate = |groups: u32| {
+        let mut config = Config::defaults();
+        config.max_input_tokens = Some(u32::try_from(hunk_tokens * 2).expect("budget"));
+        config.summary_max_file_tokens = Some(u32::try_from(hunk_tokens + 20).expect("cap"));
+        config.summary_max_hunk_groups = Some(groups);
+        let config = config.resolve().expect("config");
+        let output = u64::from(config.max_output_tokens);
+        let (input, outputs) = estimate_usage(&config, &files);
+        (input, outputs / output)
+    };
+
+    let (one_call_input, one_call) = estimate(0);
+    let (three_calls_input, three_calls) = estimate(3);
+    let (all_calls_input, all_calls) = estimate(8);
+
+    assert_eq!(one_call, 2, "one summary and the final call");
+    assert_eq!(three_calls, 4, "three hunk groups and the final call");
+    assert_eq!(all_calls, 5, "all four hunk groups and the final call");
+    assert!(one_call_input < three_calls_input && three_calls_input < all_calls_input);
+}
+
+#[tokio::test]
+async fn oversized_files_are_summarized_in_bounded_hunk_groups() {
+    let provider = StubProvider::with_replies(&[
+        "- renames the first values",
+        "- renames the middle values",
+        "- renames the last values",
+        "refactor: rename values",
+    ]);
+    let content = many_hunks(4, 10);
+    let hunk_tokens = estimate_tokens(&content) / 4;
+    let mut config = Config::defaults();
+    config.summary_max_file_tokens = Some(u32::try_from(hunk_tokens + 20).expect("cap"));
+    config.summary_max_hunk_groups = Some(3);
+    let config = config.resolve().expect("config");
+    let files = vec![diff_file("src/big.rs", &content)];
+    let all_paths = vec!["src/big.rs".to_string()];
+    let mut trace = Trace::default();
+
+    let message = summarize_then_commit(
+        &provider,
+        &config,
+        &files,
+        &[],
+        &all_paths,
+        Deadline::new(5),
+        &mut trace,
+        &mut Vec::new(),
+    )
+    .await
+    .expect("message");
+
+    assert_eq!(message, "refactor: rename values");
+    let calls = trace
+        .calls
+        .iter()
+        .map(|(call, _)| *call)
+        .collect::<Vec<_>>();
+    assert_eq!(calls, ["summary", "summary", "summary", "final"]);
+    let prompts = provider.prompts();
+    assert_eq!(prompts.len(), 4, "three hunk groups and the final call");
+    assert!(prompts[0].contains("Summarize changes for src/big.rs (part 1 of 3)"));
+    assert!(prompts[0].contains("diff --git a/src/big.rs b/src/big.rs\n@@ -1,10 +1,10 @@"));
+    assert!(prompts[2].contains("@@ -201,10 +201,10 @@"));
+    assert!(!prompts.iter().any(|prompt| prompt.contains("@@ -301,10")));
+    assert!(prompts[3].contains(
+        "src/big.rs: - renames the first values\n- renames the middle values\n\
+         - renames the last values\n(the last 1 of 4 hunk groups were not summarized)"
+    ));
+}
+
+#[test]
+fn deprioritized_files_go_last_and_are_cut_shorter() {
+    let stat = |path: &str| GitFileStat {
+        path: path.to_string(),
+        additions: 20,
+        deletions: 0,
+        is_binary: false,
+    };
+    let diff = (0..20)
+        .map(|n| format!("+line {n}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let paths = ["src/__snapshots__/app.snap", "src/app.rs", "docs/api.md"];
+    let git = StubGit {
+        stats: paths.iter().map(|path| stat(path)).collect(),
+        diffs: paths
+            .iter()
+            .map(|path| ((*path).to_string(), diff.clone()))
+            .collect(),
+        ..StubGit::default()
+    };
+    let mut config = Config::defaults();
+    config.max_file_lines = Some(20);
+    config.deprioritize = Some(vec!["**/__snapshots__/**".to_string()]);
+    let mut config = config.resolve().expect("config");
+
+    let context = collect_diff_context(&git, &config, &empty_ignore()).expect("context");
+
+    let order = context
+        .ai_files
+        .iter()
+        .map(|file| file.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        order,
+        vec!["src/app.rs", "docs/api.md", "src/__snapshots__/app.snap"]
+    );
+    assert_eq!(context.ai_files[0].content, diff);
+    let snapshot = &context.ai_files[2];
+    assert_eq!(snapshot.content.lines().count(), 5);
+    assert!(snapshot.truncated);
+    assert!(context.warnings.is_empty(), "{:?}", context.warnings);
+    assert_eq!(context.all_paths[0], "src/__snapshots__/app.snap");
+
+    config.max_files = 2;
+    let context = collect_diff_context(&git, &config, &empty_ignore()).expect("context");
+    assert_eq!(context.ai_files.len(), 2);
+    assert!(context
+        .ai_files
+        .iter()
+        .all(|file| !file.path.contains("__snapshots__")));
+}
+
+struct StubProvider {
+    replies: Vec<String>,
+    prompts: Mutex<Vec<String>>,
+    model: &'static str,
+}
+
+impl StubProvider {
+    fn new(reply: &str) -> Self {
+        Self::with_replies(&[reply])
+    }
+
+    /// Replies are returned in order; the last one repeats.
+    fn with_replies(replies: &[&str]) -> Self {
+        Self {
+            replies: replies.iter().map(|reply| (*reply).to_string()).collect(),
+            prompts: Mutex::new(Vec::new()),
+            model: "stub-model",
+        }
+    }
+
+    /// Report `model`, e.g. one with a known context window.
+    fn with_model(self, model: &'static str) -> Self {
+        Self { model, ..self }
+    }
+
+    fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().expect("prompts lock").clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for StubProvider {
+    async fn complete(
+        &self,
+        _system_prompt: &str,
+        user_prompt: &str,
+        _request: ProviderRequest,
+    ) -> crate::error::CoreResult<String> {
+        let mut prompts = self.prompts.lock().expect("prompts lock");
+        let index = std::cmp::min(prompts.len(), self.replies.len() - 1);
+        prompts.push(user_prompt.to_string());
+        Ok(self.replies[index].clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "stub"
+    }
+
+    fn model(&self) -> &'static str {
+        self.model
+    }
+}
+
+fn diff_file(path: &str, content: &str) -> DiffFile {
+    DiffFile {
+        path: path.to_string(),
+        content: content.to_string(),
+        is_binary: false,
+        truncated: false,
+        additions: 1,
+        deletions: 0,
+        token_estimate: estimate_tokens(content),
+    }
+}
+
+#[tokio::test]
+async fn summarize_then_commit_lists_all_changed_paths() {
+    let provider = StubProvider::new("feat: add thing");
+    let config = Config::defaults().resolve().expect("config");
+    let files = vec![diff_file("src/lib.rs", "+fn added() {}")];
+    let all_paths = vec!["src/lib.rs".to_string(), "assets/logo.png".to_string()];
+    let deadline = Deadline::new(5);
+
+    let mut trace = Trace::default();
+
+    let message = summarize_then_commit(
+        &provider,
+        &config,
+        &files,
+        &[],
+        &all_paths,
+        deadline,
+        &mut trace,
+        &mut Vec::new(),
+    )
+    .await
+    .expect("message");
+
+    assert_eq!(message, "feat: add thing");
+    assert_eq!(trace.steps, vec!["summaries: 1 of 1 file(s) summarized"]);
+    let prompts = provider.prompts();
+    let final_prompt = prompts.last().expect("final prompt");
+    assert!(final_prompt.contains("Files changed:"));
+    assert!(final_prompt.contains("- assets/logo.png"));
+}
+
+/// Fails summary calls for `flaky` paths the first time and for `broken` paths always.
+struct FlakyProvider {
+    flaky: Vec<String>,
+    broken: Vec<String>,
+    failed: Mutex<Vec<String>>,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl FlakyProvider {
+    fn new(flaky: &[&str], broken: &[&str]) -> Self {
+        Self {
+            flaky: flaky.iter().map(|path| (*path).to_string()).collect(),
+            broken: broken.iter().map(|path| (*path).to_string()).collect(),
+            failed: Mutex::new(Vec::new()),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for FlakyProvider {
+    async fn complete(
+        &self,
+        _system_prompt: &str,
+        user_prompt: &str,
+        _request: ProviderRequest,
+    ) -> crate::error::CoreResult<String> {
+        self.prompts
+            .lock()
+            .expect("prompts lock")
+            .push(user_prompt.to_string());
+        let Some(path) = user_prompt
+            .split_once("Summarize changes for ")
+            .and_then(|(_, rest)| rest.split_once(':'))
+            .map(|(path, _)| path.to_string())
+        else {
+            return Ok("feat: add helpers".to_string());
+        };
+        let mut failed = self.failed.lock().expect("failed lock");
+        let first_failure = self.flaky.contains(&path) && !failed.contains(&path);
+        if first_failure || self.broken.contains(&path) {
+            failed.push(path);
+            return Err(CoreError::Provider("429 too many requests".to_string()));
+        }
+        Ok("adds a helper".to_string())
+    }
+
+    fn name(&self) -> &'static str {
+        "flaky"
+    }
+
+    fn model(&self) -> &'static str {
+        "flaky-model"
+    }
+}
+
+#[tokio::test]
+async fn failed_summaries_are_retried_once_and_final_misses_warned() {
+    let provider = FlakyProvider::new(&["src/a.rs"], &["src/b.rs"]);
+    let config = Config::defaults().resolve().expect("config");
+    let body = "+let value = compute_something_long(argument_one, argument_two);\n".repeat(40);
+    let files = vec![diff_file("src/a.rs", &body), diff_file("src/b.rs", &body)];
+    let all_paths = vec!["src/a.rs".to_string(), "src/b.rs".to_string()];
+    let mut trace = Trace::default();
+    let mut warnings = Vec::new();
+
+    let message = summarize_then_commit(
+        &provider,
+        &config,
+        &files,
+        &[],
+        &all_paths,
+        Deadline::new(5),
+        &mut trace,
+        &mut warnings,
+    )
+    .await
+    .expect("message");
+
+    assert_eq!(message, "feat: add helpers");
+    assert_eq!(
+        trace.steps,
+        vec![
+            "summaries: retried 2 failed call(s), 1 file(s) recovered",
+            "summaries: 1 of 2 file(s) summarized",
+        ]
+    );
+    assert_eq!(
+        warnings,
+        vec!["summaries failed for src/b.rs; the message may leave out their changes"]
+    );
+    let prompts = provider.prompts.lock().expect("prompts lock").clone();
+    assert_eq!(
+        prompts.len(),
+        5,
+        "two summaries, two retries, and the final call"
+    );
+    let final_prompt = prompts.last().expect("final prompt");
+    assert!(final_prompt.contains("src/a.rs: adds a helper"));
+}
+
+/// Rejects the listed calls (counting from 1) as over the context length; the rest go to `replies`.
+struct ContextLimitedProvider {
+    rejected_calls: Vec<usize>,
+    replies: StubProvider,
+    calls: Mutex<usize>,
+}
+
+impl ContextLimitedProvider {
+    fn new(rejected_calls: &[usize], replies: &[&str]) -> Self {
+        Self {
+            rejected_calls: rejected_calls.to_vec(),
+            replies: StubProvider::with_replies(replies),
+            calls: Mutex::new(0),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for ContextLimitedProvider {
+    async fn complete(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        request: ProviderRequest,
+    ) -> crate::error::CoreResult<String> {
+        let call = {
+            let mut calls = self.calls.lock().expect("calls lock");
+            *calls += 1;
+            *calls
+        };
+        if self.rejected_calls.contains(&call) {
+            return Err(CoreError::Provider(
+                r#"openai error 400 Bad Request: {"error":{"message":"This model's maximum context length is 8192 tokens.","code":"context_length_exceeded"}}"#
+                    .to_string(),
+            ));
+        }
+        self.replies
+            .complete(system_prompt, user_prompt, request)
+            .await
+    }
+
+    fn name(&self) -> &'static str {
+        "limited"
+    }
+
+    fn model(&self) -> &'static str {
+        "limited-model"
+    }
+}
+
+#[test]
+fn trimming_drops_optional_sections_in_order_until_the_prompt_fits() {
+    let sizes = PromptSizes {
+        fixed: 1_000,
+        style: 100,
+        context: 400,
+        hints: 50,
+        diff: 8_000,
+    };
+    // 9_550 tokens; 5% of each window is kept free.
+    assert_eq!(plan_trimming(sizes, 20_000), Trimming::default());
+    assert_eq!(
+        plan_trimming(sizes, 10_000),
+        Trimming {
+            style: true,
+            ..Trimming::default()
+        }
+    );
+    assert_eq!(
+        plan_trimming(sizes, 9_800),
+        Trimming {
+            style: true,
+            context: true,
+            ..Trimming::default()
+        }
+    );
+    let tight = plan_trimming(sizes, 8_000);
+    assert_eq!(
+        tight,
+        Trimming {
+            style: true,
+            context: true,
+            hints: true,
+            diff_cap: Some(6_600),
+        }
+    );
+    assert_eq!(
+        tight.warning("gpt-4", 8_000).as_deref(),
+        Some(
+            "prompt nearly filled gpt-4's 8000-token context; left out the learned repo style, \
+             context files, type and scope hints, the diff after ~6600 tokens"
+        )
+    );
+
+    let no_style = PromptSizes { style: 0, ..sizes };
+    assert!(!plan_trimming(no_style, 9_800).style);
+}
+
+#[tokio::test]
+async fn prompts_near_a_known_context_window_lose_the_diff_tail() {
+    let provider = StubProvider::new("feat: add helpers").with_model("gpt-4-0613");
+    let mut config = Config::defaults();
+    config.provider = Some(ProviderKind::OpenAi);
+    config.max_input_tokens = Some(20_000);
+    let config = config.resolve().expect("config");
+    let mut content = String::new();
+    for line in 0..1_500 {
+        let _ = writeln!(content, "+fn helper_{line:04}() {{}}");
+    }
+    let files = vec![diff_file("src/a.rs", &content)];
+    let all_paths = vec!["src/a.rs".to_string()];
+    let mut warnings = Vec::new();
+
+    generate_with_provider(
+        &provider,
+        &config,
+        &files,
+        &[],
+        &all_paths,
+        Deadline::new(5),
+        &mut Trace::default(),
+        &mut warnings,
+    )
+    .await
+    .expect("message");
+
+    let prompt = &provider.prompts()[0];
+    assert!(prompt.contains("helper_0000"));
+    assert!(!prompt.contains("helper_1499"));
+    assert!(estimate_tokens(prompt) < 8_192);
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert!(warnings[0].starts_with("prompt nearly filled gpt-4-0613's 8192-token context"));
+
+    let unknown = StubProvider::new("feat: add helpers");
+    let mut warnings = Vec::new();
+    generate_with_provider(
+        &unknown,
+        &config,
+        &files,
+        &[],
+        &all_paths,
+        Deadline::new(5),
+        &mut Trace::default(),
+        &mut warnings,
+    )
+    .await
+    .expect("message");
+    assert!(unknown.prompts()[0].contains("helper_1499"));
+    assert!(warnings.is_empty());
+}
+
+#[tokio::test]
+async fn context_length_errors_retry_through_summaries() {
+    let provider = ContextLimitedProvider::new(&[1], &["adds a helper", "feat: add helper"]);
+    let config = Config::defaults().resolve().expect("config");
+    let files = vec![diff_file("src/a.rs", "+fn helper() {}\n")];
+    let all_paths = vec!["src/a.rs".to_string()];
+    let mut trace = Trace::default();
+
+    let message = generate_with_provider(
+        &provider,
+        &config,
+        &files,
+        &[],
+        &all_paths,
+        Deadline::new(5),
+        &mut trace,
+        &mut Vec::new(),
+    )
+    .await
+    .expect("message");
+
+    assert_eq!(message, "feat: add helper");
+    assert_eq!(
+        trace.steps[1..],
+        [
+            "provider: request too long for the model's context, so each file is summarized instead",
+            "summaries: 1 of 1 file(s) summarized",
+        ]
+    );
+    let prompts = provider.replies.prompts();
+    assert_eq!(prompts.len(), 2, "one summary and the final call");
+    assert!(prompts[1].contains("src/a.rs: adds a helper"));
+}
+
+#[tokio::test]
+async fn final_call_over_the_context_length_is_retried_once_with_half_the_budget() {
+    let provider = ContextLimitedProvider::new(&[2], &["adds a helper", "feat: add helper"]);
+    let config = Config::defaults().resolve().expect("config");
+    let files = vec![diff_file("src/a.rs", "+fn helper() {}\n")];
+    let context = vec![diff_file("docs/guide.md", "Helpers live in src.\n")];
+    let all_paths = vec!["src/a.rs".to_string()];
+    let mut trace = Trace::default();
+
+    let message = summarize_then_commit(
+        &provider,
+        &config,
+        &files,
+        &context,
+        &all_paths,
+        Deadline::new(5),
+        &mut trace,
+        &mut Vec::new(),
+    )
+    .await
+    .expect("message");
+
+    assert_eq!(message, "feat: add helper");
+    assert_eq!(
+        trace.steps.last().map(String::as_str),
+        Some(
+            "provider: summaries too long for the model's context, retried with half the \
+             input budget and no context files"
+        )
+    );
+    let prompts = provider.replies.prompts();
+    assert_eq!(prompts.len(), 2, "the summary and the retried final call");
+    assert!(prompts[1].contains("src/a.rs: adds a helper"));
+    assert!(!prompts[1].contains("Helpers live in src."));
+
+    let provider = ContextLimitedProvider::new(&[2, 3], &["adds a helper"]);
+    let result = summarize_then_commit(
+        &provider,
+        &config,
+        &files,
+        &[],
+        &all_paths,
+        Deadline::new(5),
+        &mut Trace::default(),
+        &mut Vec::new(),
+    )
+    .await;
+    assert!(result.is_err_and(|err| is_context_length_error(&err)));
+}
+
+fn renamed_prop(path: &str) -> DiffFile {
+    diff_file(
+        path,
+        "@@ -3,7 +3,7 @@\n-export function Card({ title }: Props) {\n+export function Card({ heading }: Props) {\n-  return <h2>{title}</h2>;\n+  return <h2>{heading}</h2>;\n",
+    )
+}
+
+#[tokio::test]
+async fn small_related_files_share_one_summary_call() {
+    let provider = StubProvider::with_replies(&[
+        "web/components/*.tsx: rename the title prop to heading",
+        "refactor(web): rename title prop to heading",
+    ]);
+    let config = Config::defaults().resolve().expect("config");
+    let files = (0..12)
+        .map(|n| renamed_prop(&format!("web/components/Card{n}.tsx")))
+        .collect::<Vec<_>>();
+    let all_paths = files
+        .iter()
+        .map(|file| file.path.clone())
+        .collect::<Vec<_>>();
+    let mut trace = Trace::default();
+
+    let message = summarize_then_commit(
+        &provider,
+        &config,
+        &files,
+        &[],
+        &all_paths,
+        Deadline::new(5),
+        &mut trace,
+        &mut Vec::new(),
+    )
+    .await
+    .expect("message");
+
+    assert_eq!(message, "refactor(web): rename title prop to heading");
+    let prompts = provider.prompts();
+    assert_eq!(prompts.len(), 2, "one batched summary and the final call");
+    assert!(prompts[0].starts_with("These are typescript files."));
+    assert!(prompts[0].contains("### web/components/Card0.tsx\n"));
+    assert!(prompts[0].contains("### web/components/Card11.tsx\n"));
+    assert!(prompts[1].contains("web/components/*.tsx: rename the title prop"));
+    assert_eq!(
+        trace.steps,
+        vec![
+            "summaries: 12 file(s) in 1 call(s), small related files batched",
+            "summaries: 12 of 12 file(s) summarized",
+        ]
+    );
+}
+
+#[test]
+fn summary_batches_respect_the_token_cap_and_keep_large_files_alone() {
+    let files = (0..12)
+        .map(|n| renamed_prop(&format!("web/components/Card{n}.tsx")))
+        .collect::<Vec<_>>();
+    let per_file = files[0].token_estimate;
+    let batches = summary_batches(&files, per_file * 5);
+    assert_eq!(
+        batches.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![5, 5, 2]
+    );
+
+    let large = diff_file("src/engine.rs", &"+let value = compute();\n".repeat(200));
+    let mixed = [
+        large,
+        diff_file("api/users.py", "+x = 1\n"),
+        diff_file("jobs/cleanup.py", "+y = 2\n"),
+        diff_file("Makefile", "+all:\n"),
+    ];
+    let batches = summary_batches(&mixed, 2_000);
+    let paths = batches
+        .iter()
+        .map(|batch| {
+            batch
+                .iter()
+                .map(|file| file.path.as_str())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            vec!["src/engine.rs"],
+            vec!["api/users.py", "jobs/cleanup.py"],
+            vec!["Makefile"],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn expired_deadline_skips_the_call_and_says_so() {
+    let err = call_with_deadline(
+        Deadline::new(0),
+        CallPhase::Summary,
+        Some("src/lib.rs"),
+        async { Ok(String::from("unused")) },
+    )
+    .await
+    .expect_err("deadline already passed");
+
+    let CoreError::Timeout(timeout) = &err else {
+        panic!("expected timeout, got {err:?}");
+    };
+    assert!(timeout.before_call);
+    assert_eq!(timeout.phase, CallPhase::Summary);
+    assert_eq!(timeout.path.as_deref(), Some("src/lib.rs"));
+    let message = err.to_string();
+    assert!(message.starts_with("summary request for src/lib.rs not sent"));
+    assert!(message.contains("0s timeout (timeout_secs)"));
+}
+
+#[test]
+fn timeout_message_names_phase_and_budget() {
+    let err = TimeoutError {
+        phase: CallPhase::Final,
+        path: None,
+        timeout_secs: 30,
+        elapsed: Duration::from_millis(30_040),
+        before_call: false,
+    };
+    assert_eq!(
+        err.to_string(),
+        "commit message request timed out after 30.0s (timeout_secs = 30)"
+    );
+}
+
+fn single_file_git() -> StubGit {
+    let stats = vec![GitFileStat {
+        path: "src/lib.rs".to_string(),
+        additions: 1,
+        deletions: 0,
+        is_binary: false,
+    }];
+    let mut diffs = HashMap::new();
+    diffs.insert("src/lib.rs".to_string(), "+fn added() {}".to_string());
+    StubGit {
+        stats,
+        diffs,
+        ..StubGit::default()
+    }
+}
+
+#[test]
+fn apply_scope_fills_a_missing_scope_only() {
+    assert_eq!(
+        apply_scope("feat: add flag", "core"),
+        "feat(core): add flag"
+    );
+    assert_eq!(
+        apply_scope("feat!: drop flag", "core"),
+        "feat(core)!: drop flag"
+    );
+    assert_eq!(apply_scope("fix(cli): x", "core"), "fix(cli): x");
+    assert_eq!(apply_scope("Add flag", "core"), "Add flag");
+}
+
+#[tokio::test]
+async fn infer_scope_hints_and_fills_the_shared_directory() {
+    let path = "crates/core/src/lib.rs";
+    let git = StubGit {
+        stats: vec![GitFileStat {
+            path: path.to_string(),
+            additions: 1,
+            deletions: 0,
+            is_binary: false,
+        }],
+        diffs: HashMap::from([(path.to_string(), "+fn added() {}".to_string())]),
+        ..StubGit::default()
+    };
+    let provider = StubProvider::new("feat: add function");
+    let mut config = Config::defaults();
+    config.infer_scope = Some(true);
+    let config = config.resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat(core): add function");
+    assert!(provider.prompts()[0].contains("use the scope `core`"));
+}
+
+fn empty_ignore() -> crate::ignore::IgnoreMatcher {
+    let paths = ConfigPaths {
+        global_config: None,
+        repo_config: None,
+        global_ignore: PathBuf::from("missing"),
+        repo_ignore: None,
+        legacy_config: None,
+    };
+    build_ignore_matcher(&[], &paths).expect("ignore")
+}
+
+#[tokio::test]
+async fn retry_on_invalid_reprompts_once() {
+    let git = single_file_git();
+    let provider = StubProvider::with_replies(&["added a function", "feat: add function"]);
+    let mut config = Config::defaults();
+    config.retry_on_invalid = Some(true);
+    let config = config.resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat: add function");
+    assert!(!outcome.used_fallback);
+    assert_eq!(outcome.generated_by.as_deref(), Some("stub/stub-model"));
+    assert_eq!(provider.prompts().len(), 2);
+    let calls = outcome
+        .timings
+        .samples()
+        .iter()
+        .map(|(call, _)| *call)
+        .collect::<Vec<_>>();
+    assert_eq!(calls, ["final", "repair"]);
+}
+
+#[tokio::test]
+async fn dropped_body_comes_from_the_repaired_reply() {
+    let git = single_file_git();
+    let provider = StubProvider::with_replies(&[
+        "added a function\n\nfirst attempt body",
+        "feat: add function\n\nrepaired body",
+    ]);
+    let mut config = Config::defaults();
+    config.retry_on_invalid = Some(true);
+    config.one_line_keep_body_in_editor = Some(true);
+    let config = config.resolve().expect("config");
+
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat: add function");
+    assert_eq!(outcome.dropped_body.as_deref(), Some("repaired body"));
+}
+
+#[tokio::test]
+async fn pr_draft_splits_commit_and_pull_request() {
+    let git = single_file_git();
+    let reply = r#"```json
+{"commit": "feat: add function", "pr": {"title": "Add function", "body": "- adds `added`"}}
+```"#;
+    let provider = StubProvider::new(reply);
+    let mut config = Config::defaults();
+    config.pr_draft = Some(true);
+    let config = config.resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat: add function");
+    let pr = outcome.pr.expect("pr draft");
+    assert_eq!(pr.title, "Add function");
+    assert_eq!(pr.body, "- adds `added`");
+}
+
+#[tokio::test]
+async fn pr_draft_warns_when_reply_is_not_json() {
+    let git = single_file_git();
+    let provider = StubProvider::new("feat: add function");
+    let mut config = Config::defaults();
+    config.pr_draft = Some(true);
+    let config = config.resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat: add function");
+    assert_eq!(outcome.pr, None);
+    assert!(outcome
+        .warnings
+        .contains(&"model did not return a pull request draft".to_string()));
+}
+
+#[tokio::test]
+async fn pr_draft_json_that_does_not_parse_is_never_committed() {
+    let git = single_file_git();
+    let mut config = Config::defaults();
+    config.pr_draft = Some(true);
+    let config = config.resolve().expect("config");
+
+    let cases = [
+        (
+            r#"{"commit": "feat: add function", "pr": "Add function"}"#,
+            false,
+        ),
+        (r#"{"subject": "feat: add function"}"#, false),
+        (r#"{"commit": "feat: add function", "pr": {"title": "#, true),
+    ];
+    for (reply, falls_back) in cases {
+        let provider = StubProvider::new(reply);
+        let PipelineResult::Message(outcome) =
+            generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+                .await
+                .expect("pipeline")
+        else {
+            panic!("expected message");
+        };
+        assert_eq!(outcome.used_fallback, falls_back, "{reply}");
+        assert!(
+            !outcome.message.contains('{'),
+            "{reply}: {}",
+            outcome.message
+        );
+        if !falls_back {
+            assert_eq!(outcome.message, "feat: add function");
+        }
+    }
+}
+
+#[tokio::test]
+async fn ollama_format_unwraps_json_and_falls_back_to_plain_text() {
+    let git = single_file_git();
+    let mut config = Config::defaults();
+    config.provider = Some(crate::config::ProviderKind::Ollama);
+    config.ollama_format = Some(crate::config::OllamaFormat::Json);
+    let config = config.resolve().expect("config");
+
+    for reply in [r#"{"message": "feat: add function"}"#, "feat: add function"] {
+        let provider = StubProvider::new(reply);
+        let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+            .await
+            .expect("pipeline");
+
+        let PipelineResult::Message(outcome) = result else {
+            panic!("expected message");
+        };
+        assert_eq!(outcome.message, "feat: add function", "reply: {reply}");
+        assert!(!outcome.used_fallback);
+    }
+}
+
+#[tokio::test]
+async fn structured_reply_without_a_type_gets_one_from_the_diff() {
+    let mut config = Config::defaults();
+    config.provider = Some(crate::config::ProviderKind::Ollama);
+    config.ollama_format = Some(crate::config::OllamaFormat::Json);
+    let config = config.resolve().expect("config");
+
+    let provider = StubProvider::new(r#"{"message": "add helper function"}"#);
+    let result = generate_commit_message(
+        &single_file_git(),
+        Some(&provider),
+        &config,
+        &empty_ignore(),
+    )
+    .await
+    .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat: add helper function");
+    assert!(!outcome.used_fallback);
+    assert!(outcome
+        .warnings
+        .iter()
+        .any(|warning| warning.contains("added `feat` based on the diff")));
+
+    let config_only = StubGit {
+        stats: vec![GitFileStat {
+            path: "config/app.ini".to_string(),
+            additions: 1,
+            deletions: 0,
+            is_binary: false,
+        }],
+        diffs: HashMap::from([("config/app.ini".to_string(), "+retries = 3".to_string())]),
+        ..StubGit::default()
+    };
+    let provider = StubProvider::new(r#"{"message": "raise retry count"}"#);
+    let result = generate_commit_message(&config_only, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "chore: raise retry count");
+}
+
+#[tokio::test]
+async fn missing_type_is_not_guessed_for_plain_text_or_unknown_types() {
+    let git = single_file_git();
+    let mut config = Config::defaults();
+    config.provider = Some(crate::config::ProviderKind::Ollama);
+    let plain = config.clone().resolve().expect("config");
+    config.ollama_format = Some(crate::config::OllamaFormat::Json);
+    let json = config.resolve().expect("config");
+
+    for (reply, config) in [
+        ("add helper function", &plain),
+        (r#"{"message": "feature: add helper function"}"#, &json),
+    ] {
+        let provider = StubProvider::new(reply);
+        let result = generate_commit_message(&git, Some(&provider), config, &empty_ignore())
+            .await
+            .expect("pipeline");
+        let PipelineResult::Message(outcome) = result else {
+            panic!("expected message");
+        };
+        assert!(outcome.used_fallback, "reply: {reply}");
+    }
+}
+
+#[test]
+fn add_missing_type_keeps_the_body() {
+    assert_eq!(
+        super::sanitize::add_missing_type("Add flag\n\nWhy it exists.", "feat").as_deref(),
+        Some("feat: Add flag\n\nWhy it exists.")
+    );
+    assert_eq!(
+        super::sanitize::add_missing_type("\"Add flag\"", "feat").as_deref(),
+        Some("feat: Add flag")
+    );
+    assert_eq!(
+        super::sanitize::add_missing_type("fix(cli): x", "feat"),
+        None
+    );
+    assert_eq!(super::sanitize::add_missing_type("  ", "feat"), None);
+}
+
+#[test]
+fn ollama_format_applies_only_to_ollama() {
+    let mut config = Config::defaults();
+    config.ollama_format = Some(crate::config::OllamaFormat::Schema);
+    config.provider = Some(crate::config::ProviderKind::OpenAi);
+    assert_eq!(
+        reply::ollama_format(&config.clone().resolve().expect("config")),
+        None
+    );
+
+    config.provider = Some(crate::config::ProviderKind::Ollama);
+    let format = reply::ollama_format(&config.clone().resolve().expect("config")).expect("schema");
+    assert_eq!(format["required"], serde_json::json!(["message"]));
+
+    config.pr_draft = Some(true);
+    assert_eq!(
+        reply::ollama_format(&config.resolve().expect("config")),
+        None
+    );
+}
+
+#[test]
+fn unwrap_message_accepts_a_single_string_field() {
+    assert_eq!(
+        reply::unwrap_message(r#"{"commit": "fix: handle empty input"}"#.to_string()),
+        "fix: handle empty input"
+    );
+    assert_eq!(
+        reply::unwrap_message(r#"{"a": "one", "b": "two"}"#.to_string()),
+        r#"{"a": "one", "b": "two"}"#
+    );
+}
+
+fn tagged_history_git() -> StubGit {
+    let commits = [
+        ("1111111aaaa", "feat(cli): add search\n\nSearch by subject."),
+        ("2222222bbbb", "Merge branch 'topic'"),
+        ("3333333cccc", "fix: handle empty input"),
+        ("4444444dddd", "docs: explain search"),
+    ];
+    StubGit {
+        commits: commits
+            .iter()
+            .map(|(sha, message)| ((*sha).to_string(), (*message).to_string()))
+            .collect(),
+        ..StubGit::default()
+    }
+}
+
+#[tokio::test]
+async fn changelog_groups_subjects_without_a_provider() {
+    let git = tagged_history_git();
+    let config = Config::defaults().resolve().expect("config");
+
+    let changelog = generate_changelog(&git, None, &config, "v1.0.0..HEAD", "Changes since v1.0.0")
+        .await
+        .expect("changelog")
+        .expect("commits");
+
+    assert!(changelog.used_fallback);
+    assert_eq!(changelog.commits, 3);
+    assert_eq!(
+        changelog.markdown,
+        "## Changes since v1.0.0\n\n### Features\n\n- add search (1111111)\n\n### Fixes\n\n- handle empty input (3333333)\n\n### Other\n\n- docs: explain search (4444444)\n"
+    );
+}
+
+#[tokio::test]
+async fn changelog_sends_commit_messages_to_the_provider() {
+    let git = tagged_history_git();
+    let provider = StubProvider::new("### Features\n\n- Search by subject");
+    let config = Config::defaults().resolve().expect("config");
+
+    let changelog = generate_changelog(
+        &git,
+        Some(&provider),
+        &config,
+        "v1.0.0..HEAD",
+        "Changes since v1.0.0",
+    )
+    .await
+    .expect("changelog")
+    .expect("commits");
+
+    assert!(!changelog.used_fallback);
+    assert_eq!(
+        changelog.markdown,
+        "## Changes since v1.0.0\n\n### Features\n\n- Search by subject\n"
+    );
+    let prompt = &provider.prompts()[0];
+    assert!(prompt.contains("- 1111111 feat(cli): add search\n  \n  Search by subject."));
+    assert!(!prompt.contains("Merge branch"));
+}
+
+#[tokio::test]
+async fn revert_message_names_the_original_subject_and_commit() {
+    let git = tagged_history_git();
+    let config = Config::defaults().resolve().expect("config");
+
+    let revert = generate_revert_message(&git, None, &config, "1111111aaaa", None)
+        .await
+        .expect("revert");
+    assert_eq!(
+        revert.message,
+        "revert: feat(cli): add search\n\nThis reverts commit 1111111aaaa."
+    );
+    assert!(!revert.used_fallback);
+
+    let mut config = config;
+    config.conventional = false;
+    let revert = generate_revert_message(&git, None, &config, "3333333cccc", Some("breaks CI"))
+        .await
+        .expect("revert");
+    assert_eq!(
+        revert.message,
+        "Revert \"fix: handle empty input\"\n\nbreaks CI\n\nThis reverts commit 3333333cccc."
+    );
+    assert!(revert.used_fallback);
+}
+
+#[tokio::test]
+async fn reverting_a_revert_restores_the_original_subject() {
+    let config = Config::defaults().resolve().expect("config");
+    for (subject, conventional) in [
+        ("revert: feat(cli): add search", true),
+        ("Revert \"feat(cli): add search\"", false),
+    ] {
+        let git = StubGit {
+            commits: vec![(
+                "5555555eeee".to_string(),
+                format!("{subject}\n\nThis reverts commit 1111111aaaa."),
+            )],
+            ..StubGit::default()
+        };
+        let mut config = config.clone();
+        config.conventional = conventional;
+
+        let revert = generate_revert_message(&git, None, &config, "5555555eeee", None)
+            .await
+            .expect("revert");
+        assert_eq!(
+            revert.message, "feat(cli): add search\n\nThis reverts commit 5555555eeee.",
+            "{subject}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn revert_message_explains_the_reason_with_the_provider() {
+    let git = tagged_history_git();
+    let provider = StubProvider::new("Search slowed every page load, so it is rolled back.");
+    let config = Config::defaults().resolve().expect("config");
+
+    let revert = generate_revert_message(
+        &git,
+        Some(&provider),
+        &config,
+        "1111111aaaa",
+        Some("search made pages slow"),
+    )
+    .await
+    .expect("revert");
+
+    assert_eq!(
+        revert.message,
+        "revert: feat(cli): add search\n\nSearch slowed every page load, so it is rolled back.\n\nThis reverts commit 1111111aaaa."
+    );
+    let prompt = &provider.prompts()[0];
+    assert!(prompt.contains("feat(cli): add search\n\nSearch by subject."));
+    assert!(prompt.ends_with("search made pages slow"));
+}
+
+#[tokio::test]
+async fn tag_message_groups_subjects_without_hashes_or_headings() {
+    let git = tagged_history_git();
+    let config = Config::defaults().resolve().expect("config");
+
+    let tag = generate_tag_message(&git, None, &config, "v1.0.0..HEAD", "v1.1.0")
+        .await
+        .expect("tag")
+        .expect("commits");
+
+    assert!(tag.used_fallback);
+    assert_eq!(
+        tag.message,
+        "v1.1.0\n\nFeatures:\n- add search\n\nFixes:\n- handle empty input\n\nOther:\n- docs: explain search"
+    );
+}
+
+#[tokio::test]
+async fn tag_message_caps_each_group_and_keeps_ai_highlights() {
+    let commits = (1..=7)
+        .map(|n| (format!("{n:07}"), format!("feat: feature {n}")))
+        .collect();
+    let git = StubGit {
+        commits,
+        ..StubGit::default()
+    };
+    let config = Config::defaults().resolve().expect("config");
+
+    let tag = generate_tag_message(&git, None, &config, "v1.0.0..HEAD", "v2.0.0")
+        .await
+        .expect("tag")
+        .expect("commits");
+    assert!(
+        tag.message.ends_with("- feature 5\n- and 2 more"),
+        "{}",
+        tag.message
+    );
+
+    let provider = StubProvider::new("Features:\n- Seven new features");
+    let tag = generate_tag_message(&git, Some(&provider), &config, "v1.0.0..HEAD", "v2.0.0")
+        .await
+        .expect("tag")
+        .expect("commits");
+    assert!(!tag.used_fallback);
+    assert_eq!(tag.message, "v2.0.0\n\nFeatures:\n- Seven new features");
+}
+
+#[tokio::test]
+async fn changelog_is_none_for_an_empty_range() {
+    let git = StubGit::default();
+    let config = Config::defaults().resolve().expect("config");
+
+    let changelog = generate_changelog(&git, None, &config, "HEAD..HEAD", "Changes")
+        .await
+        .expect("changelog");
+
+    assert_eq!(changelog, None);
+}
+
+fn lockfile_stat(path: &str) -> GitFileStat {
+    GitFileStat {
+        path: path.to_string(),
+        additions: 12,
+        deletions: 4,
+        is_binary: false,
+    }
+}
+
+#[tokio::test]
+async fn large_ignored_lockfiles_do_not_lead_the_fallback() {
+    let stats = vec![
+        GitFileStat {
+            additions: 4000,
+            deletions: 3000,
+            ..lockfile_stat("Cargo.lock")
+        },
+        GitFileStat {
+            path: "src/main.rs".to_string(),
+            additions: 3,
+            deletions: 1,
+            is_binary: false,
+        },
+    ];
+    let git = StubGit {
+        diffs: HashMap::from([("src/main.rs".to_string(), "+fn main() {}".to_string())]),
+        stats,
+        ..StubGit::default()
+    };
+    let config = Config::defaults().resolve().expect("config");
+    let ignore =
+        crate::ignore::IgnoreMatcher::from_patterns(&["Cargo.lock".to_string()]).expect("ignore");
+
+    let context = collect_diff_context(&git, &config, &ignore).expect("context");
+    assert_eq!(context.all_paths, ["src/main.rs", "Cargo.lock"]);
+
+    let PipelineResult::Message(outcome) = generate_commit_message(&git, None, &config, &ignore)
+        .await
+        .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "chore: update src/main.rs, Cargo.lock");
+}
+
+#[tokio::test]
+async fn all_ignored_files_get_deterministic_message() {
+    let stats = vec![
+        lockfile_stat("package-lock.json"),
+        lockfile_stat("web/Cargo.lock"),
+    ];
+    let git = StubGit {
+        stats,
+        ..StubGit::default()
+    };
+    let provider = StubProvider::new("feat: should not be called");
+    let config = Config::defaults().resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "chore(deps): update dependencies");
+    assert!(outcome.used_fallback);
+    assert_eq!(outcome.generated_by, None);
+    assert_eq!(outcome.fallback_reason, Some(FallbackReason::LockfilesOnly));
+    assert!(provider.prompts().is_empty());
+}
+
+#[tokio::test]
+async fn rejected_reply_records_reason_and_decision_chain() {
+    let git = single_file_git();
+    let provider = StubProvider::new("Here is your commit message!");
+    let config = Config::defaults().resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert!(outcome.used_fallback);
+    assert_eq!(
+        outcome.fallback_reason,
+        Some(FallbackReason::Rejected(Rejection::NotConventional {
+            first_line: "Here is your commit message!".to_string()
+        }))
+    );
+    let steps = outcome.trace;
+    assert_eq!(steps[0], "provider: stub/stub-model");
+    assert!(steps[1].starts_with("diff: ~"), "{steps:?}");
+    assert!(steps[1].ends_with("sent in one request"), "{steps:?}");
+    assert!(
+        steps[2].starts_with("sanitize: rejected the reply"),
+        "{steps:?}"
+    );
+    assert!(steps[3].ends_with("budget (timeout_secs)"), "{steps:?}");
+}
+
+#[tokio::test]
+async fn disallowed_fallback_is_an_error_with_the_reason() {
+    let git = single_file_git();
+    let mut config = Config::defaults().resolve().expect("config");
+    config.allow_fallback = false;
+
+    let provider = StubProvider::new("Here is your commit message!");
+    let err = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect_err("rejected reply");
+    assert!(matches!(
+        err,
+        CoreError::FallbackDisabled(ref reason) if reason.starts_with("the model's reply was rejected")
+    ));
+
+    let err = generate_commit_message(&git, None, &config, &empty_ignore())
+        .await
+        .expect_err("no provider");
+    assert!(err.to_string().contains("allow_fallback = false"));
+    assert!(err.to_string().ends_with("the provider is not set up"));
+
+    let provider = StubProvider::new("feat: add function");
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("usable reply");
+    assert!(matches!(result, PipelineResult::Message(outcome) if !outcome.used_fallback));
+}
+
+#[tokio::test]
+async fn disallowed_fallback_written_by_the_model_is_kept_with_a_warning() {
+    let git = single_file_git();
+    let mut config = Config::defaults().resolve().expect("config");
+    config.allow_fallback = false;
+    let paths = vec!["src/lib.rs".to_string()];
+    let fallback = fallback_message(
+        &FallbackFacts {
+            paths: &paths,
+            additions: 1,
+            deletions: 0,
+        },
+        &config,
+    );
+
+    let provider = StubProvider::new(&fallback);
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("reply matching the fallback");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, fallback);
+    assert!(outcome
+        .warnings
+        .iter()
+        .any(|w| w.contains("same as the fallback") && w.contains("allow_fallback = false")));
+}
+
+#[tokio::test]
+async fn missing_provider_and_accepted_replies_are_told_apart() {
+    let git = single_file_git();
+    let config = Config::defaults().resolve().expect("config");
+
+    let result = generate_commit_message(&git, None, &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(
+        outcome.fallback_reason,
+        Some(FallbackReason::ProviderUnavailable)
+    );
+
+    let provider = StubProvider::new("feat: add function");
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert!(!outcome.used_fallback);
+    assert_eq!(outcome.fallback_reason, None);
+}
+
+#[tokio::test]
+async fn stamps_add_trailers_for_matching_paths_and_skip_failures() {
+    let stamp = |command: &str, paths: &[&str]| StampConfig {
+        command: command.to_string(),
+        paths: paths.iter().map(|path| (*path).to_string()).collect(),
+    };
+    let git = single_file_git();
+    let mut config = Config::defaults();
+    config.stamp = Some(BTreeMap::from([
+        (
+            "Rust-Toolchain".to_string(),
+            stamp("echo '  1.79.0  '", &["**/*.rs"]),
+        ),
+        ("Node-Version".to_string(), stamp("echo 20.11", &["*.js"])),
+        ("Built-On".to_string(), stamp("echo ci-runner", &[])),
+        ("Broken".to_string(), stamp("exit 3", &[])),
+        ("Silent".to_string(), stamp("true", &[])),
+    ]));
+    let config = config.resolve().expect("config");
+    let provider = StubProvider::new("feat: add function");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+
+    assert_eq!(
+        outcome.message,
+        "feat: add function\n\nBuilt-On: ci-runner\nRust-Toolchain: 1.79.0"
+    );
+    assert_eq!(
+        outcome.warnings,
+        vec![
+            "skipped the Broken trailer: `exit 3` exited with exit status: 3",
+            "skipped the Silent trailer: `true` printed nothing",
+        ]
+    );
+}
+
+#[test]
+fn stamp_names_must_be_trailer_keys() {
+    let mut config = Config::defaults();
+    config.stamp = Some(BTreeMap::from([(
+        "Rust Toolchain".to_string(),
+        StampConfig {
+            command: "rustc --version".to_string(),
+            paths: Vec::new(),
+        },
+    )]));
+    let err = config.resolve().expect_err("invalid name");
+    assert!(err
+        .to_string()
+        .contains("stamp trailer name `Rust Toolchain`"));
+}
+
+#[tokio::test]
+async fn user_body_replaces_the_generated_body_and_joins_stamp_trailers() {
+    let git = single_file_git();
+    let mut config = Config::defaults();
+    config.one_line = Some(false);
+    config.stamp = Some(BTreeMap::from([(
+        "Built-On".to_string(),
+        StampConfig {
+            command: "echo ci-runner".to_string(),
+            paths: Vec::new(),
+        },
+    )]));
+    let mut config = config.resolve().expect("config");
+    config.user_body =
+        Some("Explains why.\r\n\nSigned-off-by: Dev <dev@example.com>\n".to_string());
+    let provider = StubProvider::new("feat: add function\n\nA body the model wrote anyway.");
+
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(
+        outcome.message,
+        "feat: add function\n\nExplains why.\n\nSigned-off-by: Dev <dev@example.com>\nBuilt-On: ci-runner"
+    );
+    assert_eq!(outcome.dropped_body, None);
+}
+
+#[test]
+fn user_body_wraps_long_lines_but_not_code_or_trailers() {
+    let body = "- one two three four\n    let code = one_two_three_four();\nReviewed-by: Someone With A Long Name";
+    assert_eq!(
+        wrap_body(body, 12),
+        "- one two\n  three four\n    let code = one_two_three_four();\nReviewed-by: Someone With A Long Name"
+    );
+    assert_eq!(wrap_body("one two three", 7), "one two\nthree");
+    assert_eq!(wrap_body("one two three", 0), "one two three");
+}
+
+#[tokio::test]
+async fn mixed_ignored_files_still_use_provider() {
+    let mut git = single_file_git();
+    git.stats.push(lockfile_stat("Cargo.lock"));
+    let provider = StubProvider::new("feat: add function");
+    let config = Config::defaults().resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat: add function");
+    assert_eq!(provider.prompts().len(), 1);
+}
+
+#[tokio::test]
+async fn lockfile_only_changes_skip_provider_even_when_not_ignored() {
+    let git = StubGit {
+        stats: vec![lockfile_stat("Cargo.lock"), lockfile_stat("web/yarn.lock")],
+        diffs: HashMap::from([("Cargo.lock".to_string(), "+version = 2".to_string())]),
+        ..StubGit::default()
+    };
+    let provider = StubProvider::new("feat: should not be called");
+    let config = Config::defaults().resolve().expect("config");
+    let ignore =
+        crate::ignore::IgnoreMatcher::from_patterns(&["*.log".to_string()]).expect("ignore");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &ignore)
+        .await
+        .expect("pipeline");
+
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert!(outcome
+        .message
+        .starts_with("chore(deps): update dependencies"));
+    assert!(outcome
+        .warnings
+        .iter()
+        .any(|w| w.contains("skip_lockfile_only")));
+    assert!(provider.prompts().is_empty());
+}
+
+#[test]
+fn include_ignored_matcher_sends_lockfiles() {
+    let git = StubGit {
+        stats: vec![lockfile_stat("Cargo.lock")],
+        diffs: HashMap::from([("Cargo.lock".to_string(), "+version = 2".to_string())]),
+        ..StubGit::default()
+    };
+    let config = Config::defaults().resolve().expect("config");
+
+    let context = collect_diff_context(&git, &config, &crate::ignore::IgnoreMatcher::none())
+        .expect("context");
+    assert_eq!(context.ai_files.len(), 1);
+    assert!(context.ignored.is_empty());
+}
+
+#[test]
+fn ignored_files_past_max_files_are_still_counted() {
+    let git = StubGit {
+        stats: vec![
+            GitFileStat {
+                path: "src/main.rs".to_string(),
+                additions: 30,
+                deletions: 10,
+                is_binary: false,
+            },
+            GitFileStat {
+                path: "src/lib.rs".to_string(),
+                additions: 20,
+                deletions: 5,
+                is_binary: false,
+            },
+            lockfile_stat("Cargo.lock"),
+        ],
+        diffs: HashMap::from([
+            ("src/main.rs".to_string(), "+fn main() {}".to_string()),
+            ("src/lib.rs".to_string(), "+pub fn run() {}".to_string()),
+        ]),
+        ..StubGit::default()
+    };
+    let mut config = Config::defaults().resolve().expect("config");
+    config.max_files = 1;
+    let ignore =
+        crate::ignore::IgnoreMatcher::from_patterns(&["Cargo.lock".to_string()]).expect("ignore");
+
+    let context = collect_diff_context(&git, &config, &ignore).expect("context");
+    assert_eq!(context.ai_files.len(), 1);
+    assert_eq!(context.ignored.len(), 1);
+    assert!(context
+        .warnings
+        .iter()
+        .any(|w| w.contains("only first 1 files")));
+}
+
+#[tokio::test]
+async fn staged_content_change_is_detected_before_commit() {
+    let mut git = single_file_git();
+    let provider = StubProvider::new("feat: add function");
+    let config = Config::defaults().resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    let fingerprint = outcome.staged_fingerprint.expect("fingerprint");
+    assert!(staged_content_unchanged(&git, &fingerprint).expect("check"));
+
+    git.stats[0].additions += 3;
+    assert!(!staged_content_unchanged(&git, &fingerprint).expect("check"));
+}
+
+#[test]
+fn replace_type_keeps_scope_breaking_marker_and_body() {
+    assert_eq!(
+        replace_type("feat(cli)!: drop flag\n\nbody", "refactor"),
+        "refactor(cli)!: drop flag\n\nbody"
+    );
+    assert_eq!(replace_type("not conventional", "docs"), "not conventional");
+}
+
+#[tokio::test]
+async fn inferred_type_is_hinted_and_disagreement_warned() {
+    let git = StubGit {
+        stats: vec![GitFileStat {
+            path: "src/lib.rs".to_string(),
+            additions: 2,
+            deletions: 0,
+            is_binary: false,
+        }],
+        diffs: HashMap::from([(
+            "src/lib.rs".to_string(),
+            "+/// Explains the cache.\n+// Keep in sync with the CLI.".to_string(),
+        )]),
+        ..StubGit::default()
+    };
+    let provider = StubProvider::new("feat: explain cache");
+    let mut config = Config::defaults().resolve().expect("config");
+    config.infer_type = TypeInference::Warn;
+
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    let prompts = provider.prompts();
+    assert!(prompts[0].contains("Type hint from a diff heuristic"));
+    assert!(prompts[0].contains("docs (comment-only changes"));
+    assert_eq!(outcome.message, "feat: explain cache");
+    assert!(outcome
+        .warnings
+        .iter()
+        .any(|warning| warning.contains("diff looks like `docs`")));
+}
+
+#[tokio::test]
+async fn inferred_type_can_be_enforced_or_disabled() {
+    let git = StubGit {
+        stats: vec![GitFileStat {
+            path: "src/lib.rs".to_string(),
+            additions: 2,
+            deletions: 0,
+            is_binary: false,
+        }],
+        diffs: HashMap::from([(
+            "src/lib.rs".to_string(),
+            "+/// Explains the cache.\n+// Keep in sync with the CLI.".to_string(),
+        )]),
+        ..StubGit::default()
+    };
+    let mut config = Config::defaults().resolve().expect("config");
+
+    config.infer_type = TypeInference::Enforce;
+    let provider = StubProvider::new("feat: explain cache");
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "docs: explain cache");
+
+    config.infer_type = TypeInference::Off;
+    let provider = StubProvider::new("feat: explain cache");
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat: explain cache");
+    assert!(!provider.prompts()[0].contains("Type hint"));
+    assert!(outcome.warnings.is_empty());
+}
+
+#[tokio::test]
+async fn formatting_only_changes_are_labeled_style() {
+    let git = StubGit {
+        stats: vec![
+            GitFileStat {
+                path: "src/lib.rs".to_string(),
+                additions: 1,
+                deletions: 1,
+                is_binary: false,
+            },
+            GitFileStat {
+                path: "rustfmt.toml".to_string(),
+                additions: 1,
+                deletions: 0,
+                is_binary: false,
+            },
+        ],
+        diffs: HashMap::from([
+            (
+                "src/lib.rs".to_string(),
+                "@@ -1,2 +1,2 @@\n fn a() {\n-  let x=1;\n+    let x = 1;\n".to_string(),
+            ),
+            (
+                "rustfmt.toml".to_string(),
+                "@@ -0,0 +1 @@\n+tab_spaces = 4\n".to_string(),
+            ),
+        ]),
+        ..StubGit::default()
+    };
+    let mut config = Config::defaults().resolve().expect("config");
+
+    let provider = StubProvider::new("refactor: tidy indentation");
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    let prompts = provider.prompts();
+    assert!(
+        prompts[0]
+            .contains("only touches whitespace or formatting (rustfmt); use the `style` type"),
+        "{}",
+        prompts[0]
+    );
+    assert_eq!(outcome.message, "style: tidy indentation");
+    assert!(outcome
+        .warnings
+        .iter()
+        .any(|warning| warning.contains("replaced type `refactor` with `style`")));
+
+    config.style_detection = false;
+    let provider = StubProvider::new("refactor: tidy indentation");
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "refactor: tidy indentation");
+    assert!(!provider.prompts()[0].contains("use the `style` type"));
+}
+
+#[tokio::test]
+async fn mixed_changes_are_not_forced_to_style() {
+    let git = StubGit {
+        stats: vec![
+            GitFileStat {
+                path: "src/lib.rs".to_string(),
+                additions: 1,
+                deletions: 1,
+                is_binary: false,
+            },
+            GitFileStat {
+                path: "src/main.rs".to_string(),
+                additions: 1,
+                deletions: 1,
+                is_binary: false,
+            },
+        ],
+        diffs: HashMap::from([
+            (
+                "src/lib.rs".to_string(),
+                "@@ -1,2 +1,2 @@\n fn a() {\n-  let x=1;\n+    let x = 1;\n".to_string(),
+            ),
+            (
+                "src/main.rs".to_string(),
+                "@@ -1 +1 @@\n-let limit = 10;\n+let limit = 20;\n".to_string(),
+            ),
+        ]),
+        ..StubGit::default()
+    };
+    let provider = StubProvider::new("refactor: tidy indentation");
+    let config = Config::defaults().resolve().expect("config");
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "refactor: tidy indentation");
+    assert!(!provider.prompts()[0].contains("use the `style` type"));
+}
+
+#[test]
+fn estimate_cost_prices_the_staged_diff() {
+    let git = single_file_git();
+    let mut config = Config::defaults();
+    config.provider = Some(crate::config::ProviderKind::OpenAi);
+    config.model = Some("gpt-5-mini".to_string());
+    let config = config.resolve().expect("config");
+
+    let staged = super::collect_staged_diff(&git, &config, &empty_ignore()).expect("diff");
+    let estimate = super::estimate_cost(&staged, &config).expect("known price");
+    assert!(estimate.input_tokens > 0);
+    assert_eq!(estimate.output_tokens, u64::from(config.max_output_tokens));
+    assert!(estimate.usd > 0.0);
+
+    let mut config = config;
+    config.model = "unknown-model".to_string();
+    assert!(super::estimate_cost(&staged, &config).is_none());
+}
+
+#[test]
+fn summary_prompt_leads_with_language_guidance() {
+    let config = Config::defaults().resolve().expect("config");
+    let migration = crate::prompt::summary_user_prompt("db/001_users.sql", "+ALTER TABLE", &config);
+    assert!(migration.starts_with("This is a sql file.\n- For migrations"));
+    let workflow = crate::prompt::summary_user_prompt(".github/workflows/ci.yaml", "+x", &config);
+    assert!(workflow.contains("jobs, steps, or services"));
+    let unknown = crate::prompt::summary_user_prompt("Makefile", "+x", &config);
+    assert!(unknown.starts_with("Summarize changes for Makefile"));
+}
+
+#[test]
+fn summary_style_switches_the_summary_prompt() {
+    let bullets = Config::defaults().resolve().expect("config");
+    assert!(crate::prompt::summary_system_prompt(&bullets).contains("short bullet points"));
+
+    let mut config = Config::defaults();
+    config.summary_style = Some(crate::config::SummaryStyle::Prose);
+    let prose = config.resolve().expect("config");
+    let prompt = crate::prompt::summary_system_prompt(&prose);
+    assert!(prompt.contains("plain sentences"));
+    assert!(!prompt.contains("short bullet points"));
+}
+
+#[tokio::test]
+async fn context_files_follow_the_diff_and_missing_files_warn() {
+    let mut git = single_file_git();
+    git.diffs.insert(
+        "api/openapi.yaml".to_string(),
+        "paths:\n  /items: {}\n".repeat(40),
+    );
+    git.diffs
+        .insert("api/errors.md".to_string(), "404 means missing".to_string());
+    let provider = StubProvider::new("feat: add thing");
+    let mut config = Config::defaults();
+    config.context_files = Some(vec![
+        "api/openapi.yaml".to_string(),
+        "docs/missing.md".to_string(),
+        "api/errors.md".to_string(),
+    ]);
+    let config = config.resolve().expect("config");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+    let prompt = provider.prompts().pop().expect("prompt");
+
+    let diff_at = prompt.find("+fn added() {}").expect("diff");
+    let context_at = prompt.find("READ-ONLY CONTEXT").expect("context");
+    assert!(diff_at < context_at, "{prompt}");
+    assert!(prompt.contains("--- api/openapi.yaml ---\npaths:"));
+    assert!(prompt.contains("--- api/errors.md ---\n404 means missing"));
+    assert!(outcome
+        .warnings
+        .iter()
+        .any(|warning| warning == "context file docs/missing.md not found"));
+}
+
+#[tokio::test]
+async fn context_files_are_dropped_before_the_diff_when_tight() {
+    let diff = format!("+{}", "x".repeat(1_600));
+    let mut git = single_file_git();
+    git.diffs.insert("src/lib.rs".to_string(), diff.clone());
+    git.diffs.insert(
+        "api/openapi.yaml".to_string(),
+        "paths:\n  /items: {}\n".repeat(40),
+    );
+    git.diffs
+        .insert("api/errors.md".to_string(), "404 means missing".to_string());
+    let provider = StubProvider::new("feat: add thing");
+    let mut config = Config::defaults();
+    config.max_input_tokens = Some(512);
+    config.context_files = Some(vec![
+        "api/openapi.yaml".to_string(),
+        "api/errors.md".to_string(),
+    ]);
+    let config = config.resolve().expect("config");
+
+    generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+        .await
+        .expect("pipeline");
+    let prompt = provider.prompts().pop().expect("prompt");
+
+    assert!(prompt.contains(&diff), "diff must stay intact");
+    assert!(!prompt.contains("--- api/openapi.yaml ---"));
+    assert!(
+        prompt.contains("--- api/errors.md ---"),
+        "small files still fit"
+    );
+}
+
+#[tokio::test]
+async fn context_files_outside_the_repo_in_git_dir_or_ignored_are_skipped() {
+    let mut git = single_file_git();
+    for path in [
+        "/home/u/.ssh/id_ed25519",
+        "../../.aws/credentials",
+        "docs/../../secret.txt",
+        ".git/config",
+        "./.git/hooks/pre-commit",
+        "secrets/prod.env",
+    ] {
+        git.diffs.insert(path.to_string(), "TOP SECRET".to_string());
+    }
+    let provider = StubProvider::new("feat: add thing");
+    let mut config = Config::defaults();
+    config.context_files = Some(vec![
+        "/home/u/.ssh/id_ed25519".to_string(),
+        "../../.aws/credentials".to_string(),
+        "docs/../../secret.txt".to_string(),
+        ".git/config".to_string(),
+        "./.git/hooks/pre-commit".to_string(),
+        "secrets/prod.env".to_string(),
+    ]);
+    let config = config.resolve().expect("config");
+    let ignore =
+        crate::ignore::IgnoreMatcher::from_patterns(&["secrets/**".to_string()]).expect("ignore");
+
+    let result = generate_commit_message(&git, Some(&provider), &config, &ignore)
+        .await
+        .expect("pipeline");
+    let PipelineResult::Message(outcome) = result else {
+        panic!("expected message");
+    };
+
+    assert!(!provider
+        .prompts()
+        .iter()
+        .any(|prompt| prompt.contains("TOP SECRET")));
+    let skipped = |needle: &str| {
+        outcome
+            .warnings
+            .iter()
+            .any(|warning| warning.contains(needle))
+    };
+    assert!(skipped("/home/u/.ssh/id_ed25519 is outside the repository"));
+    assert!(skipped("../../.aws/credentials is outside the repository"));
+    assert!(skipped("docs/../../secret.txt is outside the repository"));
+    assert!(skipped(".git/config is inside .git"));
+    assert!(skipped("./.git/hooks/pre-commit is inside .git"));
+    assert!(skipped("secrets/prod.env matches the ignore patterns"));
+}
+
+#[test]
+fn stage_overflow_reports_top_level_directories() {
+    let mut unstaged = (0..4)
+        .map(|index| format!("vendor/lib{index}/mod.rs"))
+        .collect::<Vec<_>>();
+    unstaged.push("src/main.rs".to_string());
+    unstaged.push("README.md".to_string());
+    let git = StubGit {
+        unstaged,
+        ..StubGit::default()
+    };
+
+    assert_eq!(super::stage_overflow(&git, 6).expect("check"), None);
+    assert_eq!(super::stage_overflow(&git, 0).expect("check"), None);
+    let overflow = super::stage_overflow(&git, 5)
+        .expect("check")
+        .expect("overflow");
+    assert_eq!(overflow.count, 6);
+    assert_eq!(
+        overflow.top_dirs,
+        vec![
+            ("vendor/".to_string(), 4),
+            ("README.md".to_string(), 1),
+            ("src/".to_string(), 1),
+        ]
+    );
+}
+
+fn fallback_facts(paths: &[String]) -> FallbackFacts<'_> {
+    FallbackFacts {
+        paths,
+        additions: 12,
+        deletions: 3,
+    }
+}
+
+fn with_template(template: &str) -> EffectiveConfig {
+    Config {
+        fallback_template: Some(template.to_string()),
+        ..Config::defaults()
+    }
+    .resolve()
+    .expect("config")
+}
+
+#[test]
+fn default_fallback_lists_three_files_under_fifty_characters() {
+    let config = Config::defaults().resolve().expect("config");
+    let paths = ["a.rs", "b.rs", "c.rs", "d.rs"].map(String::from);
+    assert_eq!(
+        fallback_message(&fallback_facts(&paths), &config),
+        "chore: update a.rs, b.rs, c.rs"
+    );
+
+    let long = ["crates/core/src/pipeline/some_rather_long_module_name.rs".to_string()];
+    let message = fallback_message(&fallback_facts(&long), &config);
+    assert_eq!(message.len(), "chore: ".len() + 50);
+    assert!(message.starts_with("chore: update crates/core/"));
+}
+
+#[test]
+fn fallback_template_expands_each_placeholder() {
+    let paths = ["src/a.rs", "src/b.rs", "README.md"].map(String::from);
+    let facts = fallback_facts(&paths);
+    let expand = |template: &str| fallback_message(&facts, &with_template(template));
+
+    assert_eq!(
+        expand("chore(bot): {files}"),
+        "chore(bot): src/a.rs, src/b.rs, README.md"
+    );
+    assert_eq!(expand("chore: {count} files"), "chore: 3 files");
+    assert_eq!(expand("chore: update {top_dir}"), "chore: update src");
+    assert_eq!(expand("chore: sync ({stats})"), "chore: sync (+12 -3)");
+    assert_eq!(
+        expand("chore: {count} files\n\n{stats}"),
+        "chore: 3 files\n\n+12 -3"
+    );
+}
+
+#[test]
+fn fallback_template_truncates_long_expansions_and_does_not_reexpand_paths() {
+    let paths = (0..3)
+        .map(|n| format!("docs/{n}-{}.md", "é".repeat(40)))
+        .collect::<Vec<_>>();
+    let config = with_template("chore: {files}");
+    let message = fallback_message(&fallback_facts(&paths), &config);
+    assert_eq!(message.chars().count(), config.subject_budget());
+
+    let odd = ["notes/{count}.md".to_string()];
+    assert_eq!(
+        fallback_message(&fallback_facts(&odd), &config),
+        "chore: notes/{count}.md"
+    );
+}
+
+#[test]
+fn fallback_template_rejects_unknown_or_unclosed_placeholders() {
+    assert!(check_fallback_template("chore: {files} ({count})").is_ok());
+
+    let err = check_fallback_template("chore: {branch}").expect_err("unknown");
+    assert!(err.to_string().contains("`{branch}`"), "{err}");
+    assert!(check_fallback_template("chore: {files").is_err());
+    assert!(check_fallback_template("   ").is_err());
+
+    let resolved = Config {
+        fallback_template: Some("chore: {nope}".to_string()),
+        ..Config::defaults()
+    }
+    .resolve();
+    assert!(resolved.is_err());
+}
+
+#[tokio::test]
+async fn body_file_summary_lists_changed_files_after_the_body() {
+    let paths = ["src/a.rs", "src/b.rs", "src/c.rs"];
+    let git = StubGit {
+        stats: paths
+            .iter()
+            .map(|path| GitFileStat {
+                path: (*path).to_string(),
+                additions: 1,
+                deletions: 0,
+                is_binary: false,
+            })
+            .collect(),
+        diffs: paths
+            .iter()
+            .map(|path| ((*path).to_string(), "+fn added() {}".to_string()))
+            .collect(),
+        ..StubGit::default()
+    };
+    let provider = StubProvider::new("feat: add helpers\n\nAdds three helpers.");
+    let mut config = Config::defaults();
+    config.one_line = Some(false);
+    config.body_file_summary = Some(true);
+    config.max_files = Some(2);
+    let config = config.resolve().expect("config");
+
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(
+        outcome.message,
+        "feat: add helpers\n\nAdds three helpers.\n\nChanged files:\n- src/a.rs\n- src/b.rs\n- ... and 1 more"
+    );
+
+    let mut one_line = config.clone();
+    one_line.one_line = true;
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, Some(&provider), &one_line, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(outcome.message, "feat: add helpers");
+}
+
+#[tokio::test]
+async fn body_file_summary_follows_a_user_body() {
+    let git = single_file_git();
+    let provider = StubProvider::new("feat: add function\n\nA body the model wrote anyway.");
+    let mut config = Config::defaults();
+    config.body_file_summary = Some(true);
+    let mut config = config.resolve().expect("config");
+    config.user_body = Some("Explains why.".to_string());
+
+    let PipelineResult::Message(outcome) =
+        generate_commit_message(&git, Some(&provider), &config, &empty_ignore())
+            .await
+            .expect("pipeline")
+    else {
+        panic!("expected message");
+    };
+    assert_eq!(
+        outcome.message,
+        "feat: add function\n\nExplains why.\n\nChanged files:\n- src/lib.rs"
+    );
+}
+
+#[tokio::test]
+async fn explanation_asks_about_the_message_and_staged_diff() {
+    let git = single_file_git();
+    let provider = StubProvider::new("  adds a function in lib.rs, hence feat  ");
+    let config = Config::defaults().resolve().expect("config");
+
+    let explanation = generate_explanation(
+        &git,
+        &provider,
+        &config,
+        &empty_ignore(),
+        "feat: add function",
+    )
+    .await
+    .expect("explanation");
+
+    assert_eq!(
+        explanation.as_deref(),
+        Some("adds a function in lib.rs, hence feat")
+    );
+    let prompt = &provider.prompts()[0];
+    assert!(prompt.starts_with("Commit message:\nfeat: add function\n\nDiff:\n"));
+    assert!(prompt.contains("+fn added() {}"));
+}
+
+#[tokio::test]
+async fn note_explains_the_staged_diff_under_the_subject() {
+    let git = single_file_git();
+    let provider = StubProvider::new("\n  Adds a helper.\n- used by the CLI\n");
+    let config = Config::defaults().resolve().expect("config");
+
+    let note = generate_commit_note(
+        &git,
+        &provider,
+        &config,
+        &empty_ignore(),
+        "feat: add function\n\nLonger body.",
+    )
+    .await
+    .expect("note");
+
+    assert_eq!(note.as_deref(), Some("Adds a helper.\n- used by the CLI"));
+    let prompt = &provider.prompts()[0];
+    assert!(prompt.starts_with("Commit subject: feat: add function\n\nDiff:\n"));
+    assert!(prompt.contains("+fn added() {}"));
+    assert!(!prompt.contains("Longer body."));
+}
+
+#[tokio::test]
+async fn note_is_skipped_without_a_usable_diff() {
+    let git = StubGit {
+        stats: vec![lockfile_stat("Cargo.lock")],
+        ..StubGit::default()
+    };
+    let provider = StubProvider::new("never asked");
+    let config = Config::defaults().resolve().expect("config");
+    let ignore =
+        crate::ignore::IgnoreMatcher::from_patterns(&["Cargo.lock".to_string()]).expect("ignore");
+
+    let note = generate_commit_note(&git, &provider, &config, &ignore, "chore: bump deps")
+        .await
+        .expect("note");
+
+    assert_eq!(note, None);
+    assert!(provider.prompts().is_empty());
+
+    let provider = StubProvider::new("   ");
+    let note = generate_commit_note(
+        &single_file_git(),
+        &provider,
+        &config,
+        &empty_ignore(),
+        "feat: add function",
+    )
+    .await
+    .expect("note");
+    assert_eq!(note, None);
+}
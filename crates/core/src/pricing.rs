@@ -0,0 +1,129 @@
+use crate::config::ProviderKind;
+
+/// Price of a model in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    const FREE: Self = Self {
+        input: 0.0,
+        output: 0.0,
+    };
+
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cost(self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input + output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// Estimated size and price of the provider calls for one run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostEstimate {
+    pub input_tokens: u64,
+    /// Upper bound: every call is assumed to use its full output budget.
+    pub output_tokens: u64,
+    pub usd: f64,
+}
+
+// Longest matching prefix wins, so dated snapshots resolve to their model family.
+const OPENAI_PRICES: &[(&str, ModelPrice)] = &[
+    ("gpt-5", price(1.25, 10.0)),
+    ("gpt-5-mini", price(0.25, 2.0)),
+    ("gpt-5-nano", price(0.05, 0.4)),
+    ("gpt-4.1", price(2.0, 8.0)),
+    ("gpt-4.1-mini", price(0.4, 1.6)),
+    ("gpt-4.1-nano", price(0.1, 0.4)),
+    ("gpt-4o", price(2.5, 10.0)),
+    ("gpt-4o-mini", price(0.15, 0.6)),
+    ("o3", price(2.0, 8.0)),
+    ("o3-mini", price(1.1, 4.4)),
+    ("o4-mini", price(1.1, 4.4)),
+];
+
+// Context windows in tokens (prompt and reply together), matched the same way.
+const OPENAI_CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    ("gpt-5", 400_000),
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+];
+
+const fn price(input: f64, output: f64) -> ModelPrice {
+    ModelPrice { input, output }
+}
+
+/// Known price for `model`; local providers are free, unknown models return `None`.
+#[must_use]
+pub fn price_for(provider: ProviderKind, model: &str) -> Option<ModelPrice> {
+    match provider {
+        ProviderKind::Ollama => Some(ModelPrice::FREE),
+        ProviderKind::OpenAi => longest_prefix(OPENAI_PRICES, model),
+    }
+}
+
+/// Known context window of `model` in tokens. `None` for unknown models and for Ollama,
+/// which truncates long prompts to its `num_ctx` instead of rejecting them.
+#[must_use]
+pub fn context_window_for(provider: ProviderKind, model: &str) -> Option<usize> {
+    match provider {
+        ProviderKind::Ollama => None,
+        ProviderKind::OpenAi => longest_prefix(OPENAI_CONTEXT_WINDOWS, model),
+    }
+}
+
+fn longest_prefix<T: Copy>(table: &[(&str, T)], model: &str) -> Option<T> {
+    table
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, value)| *value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_for_uses_longest_prefix() {
+        let nano = price_for(ProviderKind::OpenAi, "gpt-5-nano-2025-08-07").expect("price");
+        assert_eq!(nano, price(0.05, 0.4));
+        let full = price_for(ProviderKind::OpenAi, "gpt-5").expect("price");
+        assert_eq!(full, price(1.25, 10.0));
+        assert_eq!(price_for(ProviderKind::OpenAi, "my-finetune"), None);
+        assert_eq!(
+            price_for(ProviderKind::Ollama, "qwen2.5-coder:7b"),
+            Some(ModelPrice::FREE)
+        );
+    }
+
+    #[test]
+    fn context_windows_use_longest_prefix() {
+        let registry = [("small", 1_000), ("small-long", 9_000)];
+        assert_eq!(longest_prefix(&registry, "small-2025"), Some(1_000));
+        assert_eq!(longest_prefix(&registry, "small-long-preview"), Some(9_000));
+        assert_eq!(longest_prefix(&registry, "other"), None);
+        assert_eq!(
+            context_window_for(ProviderKind::OpenAi, "gpt-4-0613"),
+            Some(8_192)
+        );
+        assert_eq!(
+            context_window_for(ProviderKind::OpenAi, "gpt-4o-mini"),
+            Some(128_000)
+        );
+        assert_eq!(context_window_for(ProviderKind::Ollama, "llama3"), None);
+    }
+
+    #[test]
+    fn cost_is_per_million_tokens() {
+        let cost = price(1.25, 10.0).cost(1_000, 200);
+        assert!((cost - 0.003_25).abs() < 1e-9);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::config::EffectiveConfig;
+use std::fmt::Write;
+
+use crate::config::{EffectiveConfig, SubjectCase, SummaryStyle};
+use crate::diff::DiffFile;
+use crate::infer::TypeSuggestion;
+use crate::language::{file_kind_for_path, summary_guidance};
 
 #[must_use]
 pub fn commit_system_prompt(config: &EffectiveConfig) -> String {
@@ -9,7 +14,11 @@ pub fn commit_system_prompt(config: &EffectiveConfig) -> String {
     if config.conventional {
         prompt.push_str("TASK: Generate a commit message in Conventional Commits format.\n");
         prompt.push_str("FORMAT: <type>(<scope>): <subject>\n");
-        prompt.push_str("<type> MUST be one of: feat, fix, build, chore, ci, docs, style, refactor, perf, test\n");
+        let _ = writeln!(
+            prompt,
+            "<type> MUST be one of: {}",
+            config.conventional_types.join(", ")
+        );
         prompt.push_str("(<scope>) is optional and should be a short noun.\n");
     } else {
         prompt.push_str("TASK: Generate a concise commit message.\n");
@@ -28,30 +37,241 @@ pub fn commit_system_prompt(config: &EffectiveConfig) -> String {
     }
 
     prompt.push_str("RULES:\n");
-    prompt.push_str("- Subject must be imperative, lowercase, and concise (max 50 chars).\n");
+    let _ = writeln!(
+        prompt,
+        "- Subject must be imperative and concise (max {} chars).",
+        config.subject_budget()
+    );
+    match config.subject_case {
+        SubjectCase::Lower => prompt.push_str(
+            "- Start the subject description with a lowercase letter (keep acronyms like HTTP).\n",
+        ),
+        SubjectCase::Sentence => {
+            prompt.push_str("- Start the subject description with a capital letter.\n");
+        }
+        SubjectCase::Preserve => {}
+    }
+    prompt.push_str("- Do not end the subject with a period.\n");
     prompt.push_str("- Entire message should be plain text, no markdown.\n");
     prompt.push_str("- Do not wrap in quotes or code fences.\n");
     prompt.push_str("- Respond with only the commit message text.\n");
 
+    if let Some(style) = &config.style {
+        let lines = style.prompt_lines();
+        if !lines.is_empty() {
+            prompt.push_str("REPO STYLE (learned from recent commits; follow it when it fits):\n");
+            prompt.push_str(&lines);
+        }
+    }
+
     prompt
 }
 
 #[must_use]
-pub fn commit_user_prompt(diff: &str, config: &EffectiveConfig) -> String {
-    if let Some(lang) = &config.lang {
+pub fn commit_user_prompt(diff: &str, config: &EffectiveConfig, type_hint: Option<&str>) -> String {
+    let mut prompt = if let Some(lang) = &config.lang {
         format!("Generate the commit message in {lang}.\n\nDiff:\n{diff}")
     } else {
         format!("Generate the commit message from this diff:\n\n{diff}")
+    };
+    if let Some(hint) = type_hint {
+        let _ = write!(prompt, "\n\n{hint}");
+    }
+    prompt
+}
+
+/// Ask for a pull request title and body next to the commit message, as one JSON object.
+#[must_use]
+pub fn pr_draft_instructions() -> &'static str {
+    "ALSO: Write a pull request for this change.\n\
+     Respond with a JSON object instead of the bare commit message:\n\
+     {\"commit\": \"<the commit message, following the rules above>\", \"pr\": {\"title\": \"<PR title>\", \"body\": \"<PR description>\"}}\n\
+     The PR body may use Markdown: a short summary, then the notable changes as a list.\n"
+}
+
+/// Ask for the commit message wrapped in a JSON object, for Ollama's `format` option.
+#[must_use]
+pub fn json_message_instructions() -> &'static str {
+    "Respond with a JSON object instead of the bare commit message:\n\
+     {\"message\": \"<the commit message, following the rules above>\"}\n"
+}
+
+/// Unchanged reference files for the model, marked so they are not described as changes.
+#[must_use]
+pub fn context_section(files: &[&DiffFile]) -> String {
+    let mut section = String::from(
+        "READ-ONLY CONTEXT (unchanged reference files; do not describe them as changes):\n",
+    );
+    for file in files {
+        let _ = write!(section, "\n--- {} ---\n{}\n", file.path, file.content);
+    }
+    section
+}
+
+/// Describe the top diff-based type suggestions for the model to weigh.
+#[must_use]
+pub fn type_hint(suggestions: &[TypeSuggestion]) -> Option<String> {
+    if suggestions.is_empty() {
+        return None;
     }
+    let ranked = suggestions
+        .iter()
+        .take(2)
+        .map(|suggestion| {
+            format!(
+                "{} ({}, {:.0}% of changed lines)",
+                suggestion.kind,
+                suggestion.reason,
+                suggestion.confidence * 100.0
+            )
+        })
+        .collect::<Vec<_>>();
+    Some(format!(
+        "Type hint from a diff heuristic (verify against the diff): {}",
+        ranked.join("; ")
+    ))
+}
+
+/// Ask for the `style` type when every change only reformats code.
+#[must_use]
+pub fn formatting_hint(formatter: Option<&str>) -> String {
+    let source = formatter.map_or_else(String::new, |formatter| format!(" ({formatter})"));
+    format!(
+        "Type hint: every change only touches whitespace or formatting{source}; use the `style` type."
+    )
 }
 
+/// Ask for the scope every changed file shares, e.g. `core` for `crates/core/`.
+#[must_use]
+pub fn scope_hint(scope: &str) -> String {
+    format!("Scope hint: every changed file is in the same directory; use the scope `{scope}`.")
+}
+
+const MAX_LISTED_FILES: usize = 100;
+
+/// List every changed path so the model sees the full scope of a summarized commit.
 #[must_use]
-pub fn summary_system_prompt() -> String {
-    "You are a code reviewer summarizing diffs. Summarize the changes briefly and factually.\nRULES:\n- Use short bullet points.\n- Mention files and key changes.\n- No markdown code blocks.\n"
+pub fn files_changed_header(paths: &[String]) -> String {
+    let mut header = String::from("Files changed:\n");
+    for path in paths.iter().take(MAX_LISTED_FILES) {
+        let _ = writeln!(header, "- {path}");
+    }
+    if paths.len() > MAX_LISTED_FILES {
+        let _ = writeln!(header, "- ... and {} more", paths.len() - MAX_LISTED_FILES);
+    }
+    header
+}
+
+#[must_use]
+pub fn repair_user_prompt(previous: &str) -> String {
+    format!(
+        "Your previous output wasn't valid Conventional Commits:\n\n{previous}\n\nRewrite it. Output only `type: subject` (or `type(scope): subject`), nothing else."
+    )
+}
+
+#[must_use]
+pub fn note_system_prompt() -> String {
+    "You explain git commits for reviewers. Given a diff and its commit subject, describe what changed and why it matters.\nRULES:\n- Plain text, no markdown code blocks.\n- At most one short paragraph followed by a few bullet points.\n- Do not repeat the subject verbatim.\n"
         .to_string()
 }
 
 #[must_use]
-pub fn summary_user_prompt(path: &str, diff: &str) -> String {
-    format!("Summarize changes for {path}:\n\n{diff}")
+pub fn note_user_prompt(subject: &str, diff: &str) -> String {
+    format!("Commit subject: {subject}\n\nDiff:\n{diff}")
+}
+
+#[must_use]
+pub fn explain_system_prompt() -> &'static str {
+    "You explain why a commit message fits its diff, for someone learning Conventional Commits.\nRULES:\n- Plain text, one to three short sentences.\n- Name the change in the diff that decided the type and scope, e.g. \"adds a new endpoint in api.rs, hence feat(api)\".\n- Do not suggest a different message.\n"
+}
+
+#[must_use]
+pub fn explain_user_prompt(message: &str, diff: &str) -> String {
+    format!("Commit message:\n{message}\n\nDiff:\n{diff}")
+}
+
+#[must_use]
+pub fn changelog_system_prompt() -> &'static str {
+    "You write release notes from git commit messages.\nRULES:\n- Markdown only, starting with the first section heading.\n- Group changes under `### Features`, `### Fixes`, and `### Other`; leave out empty sections.\n- One short bullet per user-visible change; merge commits that describe the same change.\n- Skip commits that only touch CI, formatting, or tests unless they matter to users.\n- No code blocks and no closing remarks.\n"
+}
+
+#[must_use]
+pub fn tag_system_prompt() -> &'static str {
+    "You write the message for an annotated git release tag from commit messages.\nRULES:\n- Plain text only: no Markdown headings and no line starting with `#`.\n- Group highlights under `Features:`, `Fixes:`, and `Other:`; leave out empty groups.\n- At most five short bullets per group, starting with `- `; keep only the changes users will notice.\n- No title line, no code blocks, and no closing remarks.\n"
+}
+
+#[must_use]
+pub fn changelog_user_prompt(commits: &str) -> String {
+    format!("Commits, oldest first:\n\n{commits}")
+}
+
+#[must_use]
+pub fn revert_system_prompt() -> &'static str {
+    "You explain why a git commit is being reverted.\nRULES:\n- Plain text only: one to three sentences, no subject line, no bullet points.\n- Base the explanation on the stated reason; do not invent causes.\n- Do not repeat the reverted commit's hash.\n"
+}
+
+#[must_use]
+pub fn revert_user_prompt(original: &str, reason: &str) -> String {
+    format!("Commit being reverted:\n\n{original}\n\nReason for reverting:\n\n{reason}")
+}
+
+#[must_use]
+pub fn summary_system_prompt(config: &EffectiveConfig) -> String {
+    let shape = match config.summary_style {
+        SummaryStyle::Bullets => "- Use short bullet points.",
+        SummaryStyle::Prose => "- Write one or two plain sentences, no bullet points or lists.",
+    };
+    format!(
+        "You are a code reviewer summarizing diffs. Summarize the changes briefly and factually.\nRULES:\n{shape}\n- Mention files and key changes.\n- No markdown code blocks.\n"
+    )
+}
+
+/// Per-file summary prompt, led by the file's language and any guidance for it.
+#[must_use]
+pub fn summary_user_prompt(path: &str, diff: &str, config: &EffectiveConfig) -> String {
+    let mut prompt = String::new();
+    if let Some(kind) = file_kind_for_path(path) {
+        push_kind_guidance(&mut prompt, kind, "This is a", "file", config);
+    }
+    let _ = write!(prompt, "Summarize changes for {path}:\n\n{diff}");
+    prompt
+}
+
+/// Summary prompt for several small files at once, each diff under a `### path` heading.
+///
+/// The model is asked to attribute every change to its file, so the combined summary
+/// still reads per file. Guidance is added when all files are of one kind.
+#[must_use]
+pub fn summary_batch_user_prompt(files: &[(&str, &str)], config: &EffectiveConfig) -> String {
+    let mut prompt = String::new();
+    let mut kinds = files.iter().map(|(path, _)| file_kind_for_path(path));
+    if let Some(Some(kind)) = kinds.next() {
+        if kinds.all(|other| other == Some(kind)) {
+            push_kind_guidance(&mut prompt, kind, "These are", "files", config);
+        }
+    }
+    let _ = writeln!(
+        prompt,
+        "Summarize the changes in these {} files. Start each point with the file path and a colon; \
+         files with the same change can share one point that lists their paths.",
+        files.len()
+    );
+    for (path, diff) in files {
+        let _ = write!(prompt, "\n### {path}\n{diff}\n");
+    }
+    prompt
+}
+
+fn push_kind_guidance(
+    prompt: &mut String,
+    kind: &str,
+    lead: &str,
+    noun: &str,
+    config: &EffectiveConfig,
+) {
+    let _ = writeln!(prompt, "{lead} {kind} {noun}.");
+    for line in summary_guidance(kind, &config.summary_hints) {
+        let _ = writeln!(prompt, "- {line}");
+    }
+    prompt.push('\n');
 }
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 
 use crate::config::{EffectiveConfig, OpenAiMode, ProviderKind};
-use crate::error::CoreResult;
+use crate::error::{CoreError, CoreResult};
 
 mod ollama;
 mod openai;
@@ -13,6 +13,14 @@ pub use openai::OpenAiProvider;
 pub struct ProviderRequest {
     pub max_output_tokens: u32,
     pub temperature: f32,
+    /// Sampling seed for reproducible output, where the API supports one.
+    pub seed: Option<u64>,
+    /// JSON schema the reply must follow, where the API supports structured output.
+    /// Ollama also accepts the string `"json"` here for its plain JSON mode.
+    pub response_schema: Option<serde_json::Value>,
+    /// Sequences that end the reply early; empty for none. Not sent to the responses API,
+    /// which has no stop parameter.
+    pub stop: Vec<String>,
 }
 
 #[async_trait]
@@ -23,6 +31,17 @@ pub trait Provider: Send + Sync {
         user_prompt: &str,
         request: ProviderRequest,
     ) -> CoreResult<String>;
+
+    /// Short provider name for diagnostics, such as `openai`.
+    fn name(&self) -> &str;
+
+    /// Model the provider sends requests to.
+    fn model(&self) -> &str;
+
+    /// Best-effort request that gets the model ready before the real prompt.
+    async fn warm_up(&self) -> CoreResult<()> {
+        Ok(())
+    }
 }
 
 /// Build the configured AI provider.
@@ -46,6 +65,26 @@ pub fn build_provider(config: &EffectiveConfig) -> CoreResult<Box<dyn Provider>>
     }
 }
 
+/// Whether the provider rejected the prompt as longer than the model's context window, so a
+/// shorter prompt may still succeed. Matches `OpenAI`'s `context_length_exceeded` code and the
+/// wording `OpenAI`-compatible servers and Ollama use for the same error.
+#[must_use]
+pub fn is_context_length_error(err: &CoreError) -> bool {
+    let CoreError::Provider(message) = err else {
+        return false;
+    };
+    let message = message.to_lowercase();
+    [
+        "context_length_exceeded",
+        "context length",
+        "context window",
+        "prompt is too long",
+        "prompt too long",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
 #[must_use]
 pub fn openai_mode_for(model: &str, mode: OpenAiMode) -> OpenAiMode {
     let model = model.trim().to_lowercase();
@@ -92,6 +131,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn context_length_errors_are_told_apart_from_other_failures() {
+        let openai = CoreError::Provider(
+            r#"openai error 400 Bad Request: {"error":{"message":"This model's maximum context length is 8192 tokens.","code":"context_length_exceeded"}}"#
+                .to_string(),
+        );
+        assert!(is_context_length_error(&openai));
+        let ollama = CoreError::Provider(
+            "ollama error: input length exceeds the context length".to_string(),
+        );
+        assert!(is_context_length_error(&ollama));
+
+        let rate_limited =
+            CoreError::Provider("openai error 429 Too Many Requests: slow down".to_string());
+        assert!(!is_context_length_error(&rate_limited));
+        assert!(!is_context_length_error(&CoreError::Config(
+            "context length".to_string()
+        )));
+    }
+
     #[test]
     fn openai_mode_for_non_gpt5_auto_uses_chat() {
         assert_eq!(
@@ -1,7 +1,10 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use serde_json::Value;
+use tracing::debug;
 
+use crate::config::ProviderKind;
 use crate::error::{CoreError, CoreResult};
 use crate::providers::{Provider, ProviderRequest};
 use crate::retry::sleep_with_jitter;
@@ -10,6 +13,7 @@ pub struct OllamaProvider {
     client: reqwest::Client,
     endpoint: String,
     model: String,
+    use_generate: AtomicBool,
 }
 
 impl OllamaProvider {
@@ -27,29 +31,46 @@ impl OllamaProvider {
             client,
             endpoint,
             model,
+            use_generate: AtomicBool::new(false),
         })
     }
 
-    async fn send_with_retries(&self, body: Value) -> CoreResult<Value> {
+    async fn send_with_retries(&self, url: &str, body: Value) -> CoreResult<Value> {
         let mut attempt = 0usize;
         let max_attempts = 3usize;
         let mut last_error = None;
 
         while attempt < max_attempts {
-            let response = self.client.post(&self.endpoint).json(&body).send().await;
+            let response = self.client.post(url).json(&body).send().await;
 
             match response {
                 Ok(resp) => {
                     let status = resp.status();
-                    let json: Value = resp.json().await?;
+                    // Proxies in front of Ollama answer with HTML pages, so parse the text ourselves.
+                    let text = resp.text().await?;
+                    let json = serde_json::from_str::<Value>(&text).ok();
                     if status.is_success() {
-                        return Ok(json);
+                        return json.ok_or_else(|| {
+                            CoreError::Provider(format!(
+                                "ollama returned a non-JSON response ({status}): {}",
+                                body_snippet(&text)
+                            ))
+                        });
                     }
 
-                    let err = if let Some(error) = json.get("error").and_then(|v| v.as_str()) {
-                        CoreError::Provider(format!("ollama error: {error}"))
-                    } else {
-                        CoreError::Provider(format!("ollama error: {status}"))
+                    let error = json
+                        .as_ref()
+                        .and_then(|json| json.get("error"))
+                        .and_then(Value::as_str);
+                    let err = match error {
+                        Some(error) => CoreError::Provider(format!("ollama error: {error}")),
+                        None if text.trim().is_empty() => {
+                            CoreError::Provider(format!("ollama error: {status}"))
+                        }
+                        None => CoreError::Provider(format!(
+                            "ollama error: {status}: {}",
+                            body_snippet(&text)
+                        )),
                     };
 
                     if status.is_server_error() || status == reqwest::StatusCode::REQUEST_TIMEOUT {
@@ -71,6 +92,38 @@ impl OllamaProvider {
 
         Err(last_error.unwrap_or_else(|| CoreError::Provider("ollama request failed".to_string())))
     }
+
+    async fn complete_chat(
+        &self,
+        system_prompt: &str,
+        user_prompt: &str,
+        request: &ProviderRequest,
+    ) -> CoreResult<String> {
+        let body = chat_payload(&self.model, system_prompt, user_prompt, request);
+        let json = self.send_with_retries(&self.endpoint, body).await?;
+        json.get("message")
+            .and_then(|msg| msg.get("content"))
+            .and_then(|content| content.as_str())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| CoreError::Provider("ollama response missing content".to_string()))
+    }
+
+    async fn complete_generate(
+        &self,
+        url: &str,
+        system_prompt: &str,
+        user_prompt: &str,
+        request: &ProviderRequest,
+    ) -> CoreResult<String> {
+        let body = generate_payload(&self.model, system_prompt, user_prompt, request);
+        let json = self.send_with_retries(url, body).await?;
+        json.get("response")
+            .and_then(|content| content.as_str())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .ok_or_else(|| CoreError::Provider("ollama response missing content".to_string()))
+    }
 }
 
 #[async_trait::async_trait]
@@ -81,25 +134,307 @@ impl Provider for OllamaProvider {
         user_prompt: &str,
         request: ProviderRequest,
     ) -> CoreResult<String> {
-        let body = serde_json::json!({
-            "model": self.model,
-            "messages": [
-                { "role": "system", "content": system_prompt },
-                { "role": "user", "content": user_prompt }
-            ],
-            "stream": false,
-            "options": {
-                "temperature": request.temperature,
-                "num_predict": request.max_output_tokens
+        let generate_url = generate_endpoint(&self.endpoint);
+
+        if let Some(url) = generate_url.as_deref() {
+            if self.use_generate.load(Ordering::Relaxed) {
+                return self
+                    .complete_generate(url, system_prompt, user_prompt, &request)
+                    .await;
             }
-        });
+        }
 
-        let json = self.send_with_retries(body).await?;
-        json.get("message")
-            .and_then(|msg| msg.get("content"))
-            .and_then(|content| content.as_str())
-            .map(|text| text.trim().to_string())
-            .filter(|text| !text.is_empty())
-            .ok_or_else(|| CoreError::Provider("ollama response missing content".to_string()))
+        match self
+            .complete_chat(system_prompt, user_prompt, &request)
+            .await
+        {
+            Ok(message) => Ok(message),
+            Err(err) => match generate_url.as_deref() {
+                Some(url) if is_missing_chat_template(&err) => {
+                    debug!("ollama model has no chat template; switching to {url}");
+                    self.use_generate.store(true, Ordering::Relaxed);
+                    self.complete_generate(url, system_prompt, user_prompt, &request)
+                        .await
+                }
+                _ => Err(err),
+            },
+        }
+    }
+
+    fn name(&self) -> &str {
+        ProviderKind::Ollama.as_str()
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn warm_up(&self) -> CoreResult<()> {
+        // An empty message list makes Ollama load the model without generating.
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(&warmup_payload(&self.model))
+            .send()
+            .await?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(CoreError::Provider(format!(
+                "ollama warm-up failed: {}",
+                response.status()
+            )))
+        }
+    }
+}
+
+fn warmup_payload(model: &str) -> Value {
+    serde_json::json!({
+        "model": model,
+        "messages": [],
+        "stream": false
+    })
+}
+
+fn chat_payload(
+    model: &str,
+    system_prompt: &str,
+    user_prompt: &str,
+    request: &ProviderRequest,
+) -> Value {
+    let mut payload = serde_json::json!({
+        "model": model,
+        "messages": [
+            { "role": "system", "content": system_prompt },
+            { "role": "user", "content": user_prompt }
+        ],
+        "stream": false,
+        "options": options(request)
+    });
+    if let (Some(schema), Some(obj)) = (&request.response_schema, payload.as_object_mut()) {
+        obj.insert("format".to_string(), schema.clone());
+    }
+    payload
+}
+
+fn generate_payload(
+    model: &str,
+    system_prompt: &str,
+    user_prompt: &str,
+    request: &ProviderRequest,
+) -> Value {
+    let mut payload = serde_json::json!({
+        "model": model,
+        "prompt": format!("{system_prompt}\n\n---\n\n{user_prompt}"),
+        "stream": false,
+        "options": options(request)
+    });
+    if let (Some(schema), Some(obj)) = (&request.response_schema, payload.as_object_mut()) {
+        obj.insert("format".to_string(), schema.clone());
+    }
+    payload
+}
+
+fn options(request: &ProviderRequest) -> Value {
+    let mut options = serde_json::json!({
+        "temperature": request.temperature,
+        "num_predict": request.max_output_tokens
+    });
+    if let Some(obj) = options.as_object_mut() {
+        if let Some(seed) = request.seed {
+            obj.insert("seed".to_string(), serde_json::json!(seed));
+        }
+        if !request.stop.is_empty() {
+            obj.insert("stop".to_string(), serde_json::json!(request.stop));
+        }
+    }
+    options
+}
+
+/// Longest piece of an unexpected response body quoted in errors.
+const BODY_SNIPPET_CHARS: usize = 200;
+
+/// The start of `text` on one line, for error messages.
+fn body_snippet(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= BODY_SNIPPET_CHARS {
+        return collapsed;
+    }
+    let mut snippet = collapsed
+        .chars()
+        .take(BODY_SNIPPET_CHARS)
+        .collect::<String>();
+    snippet.push_str("...");
+    snippet
+}
+
+fn generate_endpoint(chat_endpoint: &str) -> Option<String> {
+    chat_endpoint
+        .trim_end_matches('/')
+        .strip_suffix("/chat")
+        .map(|base| format!("{base}/generate"))
+}
+
+fn is_missing_chat_template(err: &CoreError) -> bool {
+    let CoreError::Provider(message) = err else {
+        return false;
+    };
+    let message = message.to_lowercase();
+    message.contains("chat template")
+        || message.contains("does not support chat")
+        || message.contains("no template")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> ProviderRequest {
+        ProviderRequest {
+            max_output_tokens: 64,
+            temperature: 0.2,
+            seed: None,
+            response_schema: None,
+            stop: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn missing_chat_template_detection() {
+        let err = CoreError::Provider(
+            "ollama error: model \"tiny\" does not support chat (no chat template)".to_string(),
+        );
+        assert!(is_missing_chat_template(&err));
+
+        let err = CoreError::Provider("ollama error: model not found".to_string());
+        assert!(!is_missing_chat_template(&err));
+        assert!(!is_missing_chat_template(&CoreError::Cancelled));
+    }
+
+    #[test]
+    fn generate_payload_stitches_prompts() {
+        let payload = generate_payload("tiny", "system", "user", &request());
+        assert_eq!(
+            payload.get("prompt").and_then(Value::as_str),
+            Some("system\n\n---\n\nuser")
+        );
+        assert_eq!(payload["options"]["num_predict"], 64);
+        assert!(payload.get("messages").is_none());
+        assert!(payload["options"].get("seed").is_none());
+    }
+
+    #[test]
+    fn chat_payload_forwards_seed_in_options() {
+        let request = ProviderRequest {
+            seed: Some(42),
+            ..request()
+        };
+        let payload = chat_payload("tiny", "system", "user", &request);
+        assert_eq!(payload["options"]["seed"], 42);
+        assert_eq!(payload["options"]["num_predict"], 64);
+    }
+
+    #[test]
+    fn stop_sequences_go_in_options_only_when_set() {
+        let payload = generate_payload("tiny", "system", "user", &request());
+        assert!(payload["options"].get("stop").is_none());
+
+        let request = ProviderRequest {
+            stop: vec!["\n\n\n".to_string(), "Explanation:".to_string()],
+            ..request()
+        };
+        let payload = chat_payload("tiny", "system", "user", &request);
+        assert_eq!(
+            payload["options"]["stop"],
+            serde_json::json!(["\n\n\n", "Explanation:"])
+        );
+    }
+
+    #[test]
+    fn warmup_payload_loads_without_messages() {
+        let payload = warmup_payload("tiny");
+        assert_eq!(payload["model"], "tiny");
+        assert_eq!(payload["messages"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn body_snippet_collapses_and_truncates() {
+        assert_eq!(
+            body_snippet("<html>\n  <h1>502 Bad Gateway</h1>\n</html>"),
+            "<html> <h1>502 Bad Gateway</h1> </html>"
+        );
+        let long = "x".repeat(BODY_SNIPPET_CHARS + 10);
+        assert_eq!(
+            body_snippet(&long),
+            format!("{}...", "x".repeat(BODY_SNIPPET_CHARS))
+        );
+    }
+
+    const NGINX_502: &str =
+        "<html>\r\n<head><title>502 Bad Gateway</title></head>\r\n<body>\r\n<center><h1>502 Bad Gateway</h1></center>\r\n<hr><center>nginx</center>\r\n</body>\r\n</html>\r\n";
+
+    async fn html_server(status: u16, expected_calls: u64) -> wiremock::MockServer {
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(status).set_body_raw(NGINX_502, "text/html"))
+            .expect(expected_calls)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn html_502_is_retried_and_quoted() {
+        let server = html_server(502, 3).await;
+        let provider =
+            OllamaProvider::new("tiny".to_string(), format!("{}/api/chat", server.uri()), 5)
+                .expect("provider");
+
+        let err = provider
+            .complete_chat("system", "user", &request())
+            .await
+            .expect_err("502");
+
+        let message = err.to_string();
+        assert!(
+            message.contains("ollama error: 502 Bad Gateway"),
+            "{message}"
+        );
+        assert!(
+            message.contains("<title>502 Bad Gateway</title>"),
+            "{message}"
+        );
+    }
+
+    #[tokio::test]
+    async fn html_200_is_an_error_without_retry() {
+        let server = html_server(200, 1).await;
+        let provider =
+            OllamaProvider::new("tiny".to_string(), format!("{}/api/chat", server.uri()), 5)
+                .expect("provider");
+
+        let err = provider
+            .complete_chat("system", "user", &request())
+            .await
+            .expect_err("html body");
+
+        let message = err.to_string();
+        assert!(
+            message.starts_with(
+                "provider error: ollama returned a non-JSON response (200 OK): <html>"
+            ),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn generate_endpoint_derives_from_chat_endpoint() {
+        assert_eq!(
+            generate_endpoint("http://localhost:11434/api/chat/").as_deref(),
+            Some("http://localhost:11434/api/generate")
+        );
+        assert_eq!(generate_endpoint("http://proxy/ollama"), None);
     }
 }
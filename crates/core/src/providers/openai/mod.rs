@@ -3,7 +3,7 @@ use std::time::Duration;
 use serde_json::Value;
 use tracing::instrument;
 
-use crate::config::{openai_api_key_env, OpenAiMode};
+use crate::config::{openai_api_key_env, OpenAiMode, ProviderKind};
 use crate::error::{CoreError, CoreResult};
 use crate::providers::{openai_mode_for, Provider, ProviderRequest};
 use crate::retry::sleep_with_jitter;
@@ -81,12 +81,19 @@ impl OpenAiProvider {
                     }
 
                     let status = resp.status();
+                    let retry_after = retry::retry_after(resp.headers());
                     let body = resp.text().await.unwrap_or_default();
                     let err = CoreError::Provider(format!("openai error {status}: {body}"));
                     if retry::should_retry(status) {
                         last_error = Some(err);
-                        sleep_with_jitter(attempt, 200, 2000).await;
                         attempt += 1;
+                        if attempt >= max_attempts {
+                            break;
+                        }
+                        match retry_after {
+                            Some(delay) => tokio::time::sleep(delay).await,
+                            None => sleep_with_jitter(attempt - 1, 200, 2000).await,
+                        }
                         continue;
                     }
 
@@ -120,6 +127,7 @@ impl OpenAiProvider {
             user_prompt,
             temperature,
             self.is_gpt5(),
+            request.response_schema.as_ref(),
         );
 
         match self
@@ -135,6 +143,7 @@ impl OpenAiProvider {
                         user_prompt,
                         None,
                         self.is_gpt5(),
+                        request.response_schema.as_ref(),
                     );
                     return self
                         .complete_responses_with_fallbacks(&base, request.max_output_tokens)
@@ -156,44 +165,50 @@ impl OpenAiProvider {
         } else {
             Some(request.temperature)
         };
-        let body = payloads::chat_payload(
-            &self.model,
-            system_prompt,
-            user_prompt,
-            request.max_output_tokens,
-            temperature,
-        );
-
-        let http_request = self
-            .client
-            .post(self.chat_url())
-            .bearer_auth(&self.api_key)
-            .json(&body);
+        // Drop each optional parameter the model rejects, then try again.
+        let mut temperature = temperature;
+        let mut seed = request.seed;
+        let mut schema = request.response_schema.as_ref();
+        let mut stop = request.stop.as_slice();
+        loop {
+            let body = payloads::chat_payload(
+                &self.model,
+                system_prompt,
+                user_prompt,
+                request.max_output_tokens,
+                temperature,
+                seed,
+                schema,
+                stop,
+            );
+            let http_request = self
+                .client
+                .post(self.chat_url())
+                .bearer_auth(&self.api_key)
+                .json(&body);
 
-        let json = match self.send_with_retries(http_request).await {
-            Ok(json) => json,
-            Err(err) => {
-                if retry::is_unsupported_param(&err, "temperature") {
-                    let body = payloads::chat_payload(
-                        &self.model,
-                        system_prompt,
-                        user_prompt,
-                        request.max_output_tokens,
-                        None,
-                    );
-                    let http_request = self
-                        .client
-                        .post(self.chat_url())
-                        .bearer_auth(&self.api_key)
-                        .json(&body);
-                    let json = self.send_with_retries(http_request).await?;
-                    return parse::parse_chat_output(&json);
+            match self.send_with_retries(http_request).await {
+                Ok(json) => return parse::parse_chat_output(&json),
+                Err(err)
+                    if temperature.is_some()
+                        && retry::is_unsupported_param(&err, "temperature") =>
+                {
+                    temperature = None;
+                }
+                Err(err) if seed.is_some() && retry::is_unsupported_param(&err, "seed") => {
+                    seed = None;
+                }
+                Err(err)
+                    if schema.is_some() && retry::is_unsupported_param(&err, "response_format") =>
+                {
+                    schema = None;
+                }
+                Err(err) if !stop.is_empty() && retry::is_unsupported_param(&err, "stop") => {
+                    stop = &[];
                 }
-                return Err(err);
+                Err(err) => return Err(err),
             }
-        };
-
-        parse::parse_chat_output(&json)
+        }
     }
 
     async fn complete_responses_with_fallbacks(
@@ -256,6 +271,9 @@ impl Provider for OpenAiProvider {
         let request = ProviderRequest {
             max_output_tokens: request.max_output_tokens,
             temperature: request.temperature,
+            seed: request.seed,
+            response_schema: request.response_schema,
+            stop: request.stop,
         };
 
         match mode {
@@ -270,4 +288,12 @@ impl Provider for OpenAiProvider {
             OpenAiMode::Auto => unreachable!(),
         }
     }
+
+    fn name(&self) -> &str {
+        ProviderKind::OpenAi.as_str()
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
 }
@@ -49,3 +49,27 @@ pub(super) fn parse_chat_output(json: &Value) -> CoreResult<String> {
 
     content.ok_or_else(|| CoreError::Provider("openai response missing content".to_string()))
 }
+
+/// The `error` object the API returns with a failed request.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct ApiError {
+    pub(super) message: String,
+    pub(super) param: Option<String>,
+    pub(super) code: Option<String>,
+}
+
+/// Parse the JSON body of an `openai error {status}: {body}` provider error.
+pub(super) fn parse_api_error(err: &CoreError) -> Option<ApiError> {
+    let CoreError::Provider(text) = err else {
+        return None;
+    };
+    let (_, body) = text.strip_prefix("openai error ")?.split_once(": ")?;
+    let json = serde_json::from_str::<Value>(body).ok()?;
+    let error = json.get("error")?;
+    let field = |name: &str| error.get(name).and_then(Value::as_str).map(str::to_string);
+    Some(ApiError {
+        message: field("message").unwrap_or_default(),
+        param: field("param"),
+        code: field("code"),
+    })
+}
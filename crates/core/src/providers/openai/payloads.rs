@@ -6,6 +6,7 @@ pub(super) fn responses_base_payload(
     user_prompt: &str,
     temperature: Option<f32>,
     gpt5: bool,
+    schema: Option<&Value>,
 ) -> Value {
     let mut payload = serde_json::json!({
         "model": model,
@@ -35,17 +36,26 @@ pub(super) fn responses_base_payload(
         if let Some(value) = temperature {
             obj.insert("temperature".to_string(), serde_json::json!(value));
         }
+        if let Some(schema) = schema {
+            let mut format = json_schema(schema);
+            format["type"] = serde_json::json!("json_schema");
+            obj.insert("text".to_string(), serde_json::json!({ "format": format }));
+        }
     }
 
     payload
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn chat_payload(
     model: &str,
     system_prompt: &str,
     user_prompt: &str,
     max_tokens: u32,
     temperature: Option<f32>,
+    seed: Option<u64>,
+    schema: Option<&Value>,
+    stop: &[String],
 ) -> Value {
     let mut payload = serde_json::json!({
         "model": model,
@@ -60,11 +70,35 @@ pub(super) fn chat_payload(
         if let Some(value) = temperature {
             obj.insert("temperature".to_string(), serde_json::json!(value));
         }
+        if let Some(value) = seed {
+            obj.insert("seed".to_string(), serde_json::json!(value));
+        }
+        if !stop.is_empty() {
+            obj.insert("stop".to_string(), serde_json::json!(stop));
+        }
+        if let Some(schema) = schema {
+            obj.insert(
+                "response_format".to_string(),
+                serde_json::json!({
+                    "type": "json_schema",
+                    "json_schema": json_schema(schema)
+                }),
+            );
+        }
     }
 
     payload
 }
 
+/// Strict structured-output settings shared by the chat and responses APIs.
+fn json_schema(schema: &Value) -> Value {
+    serde_json::json!({
+        "name": "reply",
+        "strict": true,
+        "schema": schema
+    })
+}
+
 pub(super) fn is_gpt5_model(model: &str) -> bool {
     model.trim().to_lowercase().starts_with("gpt-5")
 }
@@ -1,16 +1,52 @@
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
 use reqwest::StatusCode;
 
 use crate::error::CoreError;
 
+use super::parse::parse_api_error;
+
 pub(super) fn should_retry(status: StatusCode) -> bool {
     matches!(status, StatusCode::TOO_MANY_REQUESTS)
         || status.is_server_error()
         || status == StatusCode::REQUEST_TIMEOUT
 }
 
+/// Longest server-requested wait we honor before retrying anyway.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Delay requested by a `Retry-After` header given in seconds, capped at [`MAX_RETRY_AFTER`].
+pub(super) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// Whether the API rejected `param` for this model, so the request can be retried without it.
+///
+/// Reads `error.param` and `error.code` from the response body; the message text is only
+/// scanned when the body is not the API's JSON error shape.
 pub(super) fn is_unsupported_param(err: &CoreError, param: &str) -> bool {
-    let message = err.to_string().to_lowercase();
-    let param = param.to_lowercase();
-    (message.contains("unsupported_parameter") || message.contains("unsupported parameter"))
-        && message.contains(&param)
+    let Some(api_error) = parse_api_error(err) else {
+        let message = err.to_string().to_lowercase();
+        return (message.contains("unsupported_parameter")
+            || message.contains("unsupported parameter"))
+            && message.contains(&param.to_lowercase());
+    };
+
+    let names_param = api_error
+        .param
+        .as_deref()
+        .is_some_and(|value| value.eq_ignore_ascii_case(param));
+    let unsupported = match api_error.code.as_deref() {
+        Some(code) => matches!(code, "unsupported_parameter" | "unsupported_value"),
+        None => api_error.message.to_lowercase().contains("unsupported"),
+    };
+    names_param && unsupported
 }
@@ -12,6 +12,7 @@ fn responses_payload_uses_input_text_parts() {
         "user",
         Some(0.2),
         true,
+        None,
     );
     let input = payload
         .get("input")
@@ -47,15 +48,27 @@ fn responses_payload_uses_input_text_parts() {
 
 #[test]
 fn responses_payload_omits_temperature_when_none() {
-    let payload =
-        payloads::responses_base_payload("gpt-5-nano-2025-08-07", "system", "user", None, true);
+    let payload = payloads::responses_base_payload(
+        "gpt-5-nano-2025-08-07",
+        "system",
+        "user",
+        None,
+        true,
+        None,
+    );
     assert!(payload.get("temperature").is_none());
 }
 
 #[test]
 fn responses_payload_sets_reasoning_minimal_for_gpt5() {
-    let payload =
-        payloads::responses_base_payload("gpt-5-nano-2025-08-07", "system", "user", None, true);
+    let payload = payloads::responses_base_payload(
+        "gpt-5-nano-2025-08-07",
+        "system",
+        "user",
+        None,
+        true,
+        None,
+    );
     let effort = payload
         .get("reasoning")
         .and_then(|value| value.get("effort"))
@@ -66,26 +79,161 @@ fn responses_payload_sets_reasoning_minimal_for_gpt5() {
 
 #[test]
 fn responses_payload_skips_reasoning_for_non_gpt5() {
-    let payload = payloads::responses_base_payload("gpt-4o-mini", "system", "user", None, false);
+    let payload =
+        payloads::responses_base_payload("gpt-4o-mini", "system", "user", None, false, None);
     assert!(payload.get("reasoning").is_none());
 }
 
 #[test]
 fn chat_payload_omits_temperature_when_none() {
-    let payload = payloads::chat_payload("gpt-5-nano-2025-08-07", "system", "user", 100, None);
+    let payload = payloads::chat_payload(
+        "gpt-5-nano-2025-08-07",
+        "system",
+        "user",
+        100,
+        None,
+        None,
+        None,
+        &[],
+    );
     assert!(payload.get("temperature").is_none());
+    assert!(payload.get("seed").is_none());
+}
+
+#[test]
+fn chat_payload_forwards_seed() {
+    let payload = payloads::chat_payload(
+        "gpt-4o-mini",
+        "system",
+        "user",
+        100,
+        Some(0.2),
+        Some(7),
+        None,
+        &[],
+    );
+    assert_eq!(payload["seed"], 7);
+    assert!(payload.get("response_format").is_none());
+    assert!(payload.get("stop").is_none());
+}
+
+#[test]
+fn chat_payload_forwards_stop_sequences() {
+    let stop = ["\n\n\n".to_string()];
+    let payload = payloads::chat_payload(
+        "gpt-4o-mini",
+        "system",
+        "user",
+        100,
+        None,
+        None,
+        None,
+        &stop,
+    );
+    assert_eq!(payload["stop"], serde_json::json!(["\n\n\n"]));
+}
+
+#[test]
+fn payloads_request_structured_output_for_a_schema() {
+    let schema = serde_json::json!({ "type": "object" });
+    let chat = payloads::chat_payload(
+        "gpt-4o-mini",
+        "system",
+        "user",
+        100,
+        None,
+        None,
+        Some(&schema),
+        &[],
+    );
+    assert_eq!(chat["response_format"]["type"], "json_schema");
+    assert_eq!(chat["response_format"]["json_schema"]["schema"], schema);
+    assert_eq!(chat["response_format"]["json_schema"]["strict"], true);
+
+    let responses = payloads::responses_base_payload(
+        "gpt-5-nano-2025-08-07",
+        "system",
+        "user",
+        None,
+        true,
+        Some(&schema),
+    );
+    assert_eq!(responses["text"]["format"]["type"], "json_schema");
+    assert_eq!(responses["text"]["format"]["schema"], schema);
 }
 
+fn api_error(body: &str) -> CoreError {
+    CoreError::Provider(format!("openai error 400 Bad Request: {body}"))
+}
+
+const UNSUPPORTED_TEMPERATURE: &str = r#"{
+  "error": {
+    "message": "Unsupported value: 'temperature' does not support 0.2 with this model. Only the default (1) value is supported.",
+    "type": "invalid_request_error",
+    "param": "temperature",
+    "code": "unsupported_value"
+  }
+}"#;
+
+const UNSUPPORTED_MAX_TOKENS: &str = r#"{
+  "error": {
+    "message": "Unsupported parameter: 'max_tokens' is not supported with this model. Use 'max_completion_tokens' instead.",
+    "type": "invalid_request_error",
+    "param": "max_tokens",
+    "code": "unsupported_parameter"
+  }
+}"#;
+
+const INVALID_MODEL: &str = r#"{
+  "error": {
+    "message": "The model `gpt-9` does not exist or you do not have access to it.",
+    "type": "invalid_request_error",
+    "param": null,
+    "code": "model_not_found"
+  }
+}"#;
+
 #[test]
 fn unsupported_param_matches_openai_message() {
-    let err = CoreError::Provider(
-        "openai error 400 Bad Request: {\"error\": {\"message\": \"Unsupported parameter: 'temperature' is not supported with this model.\", \"type\": \"invalid_request_error\", \"param\": \"temperature\", \"code\": null}}"
-            .to_string(),
+    let err = api_error(
+        "{\"error\": {\"message\": \"Unsupported parameter: 'temperature' is not supported with this model.\", \"type\": \"invalid_request_error\", \"param\": \"temperature\", \"code\": null}}",
     );
 
     assert!(is_unsupported_param(&err, "temperature"));
 }
 
+#[test]
+fn unsupported_param_reads_param_and_code_fields() {
+    let temperature = api_error(UNSUPPORTED_TEMPERATURE);
+    assert!(is_unsupported_param(&temperature, "temperature"));
+    assert!(!is_unsupported_param(&temperature, "seed"));
+
+    let max_tokens = api_error(UNSUPPORTED_MAX_TOKENS);
+    assert!(is_unsupported_param(&max_tokens, "max_tokens"));
+    // The message names `max_completion_tokens`, but the rejected param is `max_tokens`.
+    assert!(!is_unsupported_param(&max_tokens, "max_completion_tokens"));
+    assert!(!is_unsupported_param(&max_tokens, "temperature"));
+
+    let model = api_error(INVALID_MODEL);
+    assert!(!is_unsupported_param(&model, "model"));
+    assert!(!is_unsupported_param(&model, "temperature"));
+}
+
+#[test]
+fn unsupported_param_ignores_words_outside_the_error_fields() {
+    let err = api_error(
+        r#"{"error": {"message": "Invalid 'input': the diff mentions an unsupported parameter named temperature.", "type": "invalid_request_error", "param": "input", "code": "invalid_value"}}"#,
+    );
+    assert!(!is_unsupported_param(&err, "temperature"));
+}
+
+#[test]
+fn unsupported_param_falls_back_to_text_without_json() {
+    let err = api_error("<html>Unsupported parameter: seed</html>");
+    assert!(is_unsupported_param(&err, "seed"));
+    assert!(!is_unsupported_param(&err, "temperature"));
+}
+
 #[test]
 fn provider_is_gpt5_detection() {
     let provider = OpenAiProvider::new(
@@ -99,3 +247,83 @@ fn provider_is_gpt5_detection() {
 
     assert!(provider.is_gpt5());
 }
+
+mod http {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::{OpenAiMode, OpenAiProvider};
+
+    fn provider_for(server: &MockServer) -> OpenAiProvider {
+        OpenAiProvider::new(
+            "gpt-4o-mini".to_string(),
+            format!("{}/v1", server.uri()),
+            OpenAiMode::Chat,
+            5,
+            Some("test-key".to_string()),
+        )
+        .expect("provider")
+    }
+
+    async fn mount_rate_limit_then_ok(server: &MockServer, rate_limited: ResponseTemplate) {
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(rate_limited)
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+            .mount(server)
+            .await;
+    }
+
+    async fn send(provider: &OpenAiProvider) -> (serde_json::Value, Duration) {
+        let request = provider
+            .client
+            .post(provider.chat_url())
+            .json(&json!({ "model": "gpt-4o-mini" }));
+        let start = Instant::now();
+        let value = provider
+            .send_with_retries(request)
+            .await
+            .expect("request succeeds after retry");
+        (value, start.elapsed())
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limit_with_backoff() {
+        let server = MockServer::start().await;
+        mount_rate_limit_then_ok(&server, ResponseTemplate::new(429)).await;
+
+        let (value, elapsed) = send(&provider_for(&server)).await;
+
+        assert_eq!(value, json!({ "ok": true }));
+        assert_eq!(server.received_requests().await.expect("requests").len(), 2);
+        // First backoff is the 200ms base plus up to 200ms of jitter.
+        assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limit_after_retry_after_delay() {
+        let server = MockServer::start().await;
+        mount_rate_limit_then_ok(
+            &server,
+            ResponseTemplate::new(429).insert_header("Retry-After", "1"),
+        )
+        .await;
+
+        let (value, elapsed) = send(&provider_for(&server)).await;
+
+        assert_eq!(value, json!({ "ok": true }));
+        assert_eq!(server.received_requests().await.expect("requests").len(), 2);
+        assert!(elapsed >= Duration::from_secs(1), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(3), "{elapsed:?}");
+    }
+}
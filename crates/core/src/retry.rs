@@ -1,12 +1,12 @@
 use std::time::Duration;
 
-use rand::{thread_rng, Rng};
+use rand::{rng, Rng};
 
 #[must_use]
 pub fn backoff_delay(attempt: usize, base_delay_ms: u64, max_delay_ms: u64) -> Duration {
     let exp = 2u64.saturating_pow(u32::try_from(attempt).unwrap_or(u32::MAX));
     let base = base_delay_ms.saturating_mul(exp).min(max_delay_ms);
-    let jitter: u64 = thread_rng().gen_range(0..=base_delay_ms);
+    let jitter: u64 = rng().random_range(0..=base_delay_ms);
     Duration::from_millis(base.saturating_add(jitter))
 }
 
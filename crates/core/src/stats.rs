@@ -0,0 +1,318 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{config_dir, EffectiveConfig};
+use crate::error::CoreResult;
+use crate::timing::Timings;
+
+const STATS_FILE: &str = "stats.jsonl";
+
+/// One generation run, as recorded in the local stats file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsRecord {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub provider: String,
+    pub model: String,
+    /// Estimated tokens sent to and received from the provider.
+    pub tokens: u64,
+    pub used_fallback: bool,
+    /// Milliseconds per phase (`staging`, `diff`, `provider`, `sanitize`, `total`); empty
+    /// when `record_timings` is off.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub phases_ms: BTreeMap<String, u64>,
+    /// Milliseconds of each provider call, keyed by `summary` or `final`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub calls_ms: BTreeMap<String, Vec<u64>>,
+}
+
+impl StatsRecord {
+    #[must_use]
+    pub fn new(config: &EffectiveConfig, tokens: u64, used_fallback: bool) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        Self {
+            timestamp,
+            provider: config.provider.as_str().to_string(),
+            model: config.model.clone(),
+            tokens,
+            used_fallback,
+            phases_ms: BTreeMap::new(),
+            calls_ms: BTreeMap::new(),
+        }
+    }
+
+    /// Add the run's phase and call timings.
+    #[must_use]
+    pub fn with_timings(mut self, timings: &Timings) -> Self {
+        for (phase, duration) in timings.phases() {
+            self.phases_ms
+                .insert((*phase).to_string(), millis(*duration));
+        }
+        self.phases_ms
+            .insert("total".to_string(), millis(timings.total()));
+        for (call, duration) in timings.samples() {
+            self.calls_ms
+                .entry((*call).to_string())
+                .or_default()
+                .push(millis(*duration));
+        }
+        self
+    }
+}
+
+fn millis(duration: std::time::Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelStats {
+    pub runs: u64,
+    pub fallback_runs: u64,
+    pub tokens: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatsSummary {
+    pub runs: u64,
+    pub fallback_runs: u64,
+    pub tokens: u64,
+    pub first_timestamp: Option<u64>,
+    pub last_timestamp: Option<u64>,
+    /// Keyed by `provider/model`.
+    pub by_model: BTreeMap<String, ModelStats>,
+}
+
+/// Location of the local stats file.
+///
+/// # Errors
+/// Returns an error if the config directory cannot be resolved.
+pub fn stats_path() -> CoreResult<PathBuf> {
+    Ok(config_dir()?.join(STATS_FILE))
+}
+
+/// Append one record to the stats file, creating it if needed.
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+pub fn append_record(path: &Path, record: &StatsRecord) -> CoreResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(record)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")?;
+    Ok(())
+}
+
+/// Read all records from the stats file, skipping lines that fail to parse.
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be read.
+pub fn read_records(path: &Path) -> CoreResult<Vec<StatsRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[must_use]
+pub fn summarize(records: &[StatsRecord]) -> StatsSummary {
+    let mut summary = StatsSummary::default();
+    for record in records {
+        summary.runs += 1;
+        summary.tokens += record.tokens;
+        summary.first_timestamp = Some(
+            summary
+                .first_timestamp
+                .map_or(record.timestamp, |first| first.min(record.timestamp)),
+        );
+        summary.last_timestamp = Some(
+            summary
+                .last_timestamp
+                .map_or(record.timestamp, |last| last.max(record.timestamp)),
+        );
+
+        let model = summary
+            .by_model
+            .entry(format!("{}/{}", record.provider, record.model))
+            .or_default();
+        model.runs += 1;
+        model.tokens += record.tokens;
+        if record.used_fallback {
+            summary.fallback_runs += 1;
+            model.fallback_runs += 1;
+        }
+    }
+    summary
+}
+
+/// Latency percentiles of one phase or call, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub samples: usize,
+    pub p50: u64,
+    pub p95: u64,
+}
+
+impl Percentiles {
+    fn of(mut values: Vec<u64>) -> Self {
+        values.sort_unstable();
+        // Nearest rank: the smallest value with at least `percent`% of samples at or below it.
+        let rank = |percent: usize| values[(values.len() * percent).div_ceil(100).max(1) - 1];
+        Self {
+            samples: values.len(),
+            p50: rank(50),
+            p95: rank(95),
+        }
+    }
+}
+
+/// Percentiles per `provider/model`, then per phase and per call (`summary call`,
+/// `final call`), over the records that have timings.
+#[must_use]
+pub fn perf_summary(records: &[StatsRecord]) -> BTreeMap<String, BTreeMap<String, Percentiles>> {
+    let mut values: BTreeMap<String, BTreeMap<String, Vec<u64>>> = BTreeMap::new();
+    for record in records {
+        let model = values
+            .entry(format!("{}/{}", record.provider, record.model))
+            .or_default();
+        for (phase, ms) in &record.phases_ms {
+            model.entry(phase.clone()).or_default().push(*ms);
+        }
+        for (call, latencies) in &record.calls_ms {
+            model
+                .entry(format!("{call} call"))
+                .or_default()
+                .extend(latencies);
+        }
+    }
+    values
+        .into_iter()
+        .filter(|(_, phases)| !phases.is_empty())
+        .map(|(model, phases)| {
+            let phases = phases
+                .into_iter()
+                .map(|(phase, values)| (phase, Percentiles::of(values)))
+                .collect();
+            (model, phases)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(model: &str, tokens: u64, used_fallback: bool) -> StatsRecord {
+        StatsRecord {
+            timestamp: 1_700_000_000 + tokens,
+            provider: "openai".to_string(),
+            model: model.to_string(),
+            tokens,
+            used_fallback,
+            phases_ms: BTreeMap::new(),
+            calls_ms: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn append_and_read_round_trip() {
+        let dir = std::env::temp_dir().join(format!("goodcommit-stats-{}", std::process::id()));
+        let path = dir.join(STATS_FILE);
+        let _ = fs::remove_file(&path);
+
+        append_record(&path, &record("gpt-5-nano", 120, false)).expect("append");
+        append_record(&path, &record("gpt-5-nano", 80, true)).expect("append");
+        fs::write(
+            &path,
+            fs::read_to_string(&path).expect("read") + "not json\n",
+        )
+        .expect("write");
+
+        let records = read_records(&path).expect("read");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], record("gpt-5-nano", 120, false));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn summarize_groups_by_model() {
+        let records = vec![
+            record("gpt-5-nano", 100, false),
+            record("gpt-5-nano", 50, true),
+            record("gpt-5", 300, false),
+        ];
+        let summary = summarize(&records);
+        assert_eq!(summary.runs, 3);
+        assert_eq!(summary.fallback_runs, 1);
+        assert_eq!(summary.tokens, 450);
+        assert_eq!(summary.first_timestamp, Some(1_700_000_050));
+        assert_eq!(summary.last_timestamp, Some(1_700_000_300));
+        let nano = &summary.by_model["openai/gpt-5-nano"];
+        assert_eq!((nano.runs, nano.fallback_runs, nano.tokens), (2, 1, 150));
+    }
+
+    #[test]
+    fn timings_are_recorded_per_phase_and_call() {
+        use std::time::Duration;
+
+        let mut timings = Timings::new();
+        timings.record("staging", Duration::from_millis(12));
+        timings.record("provider", Duration::from_millis(900));
+        timings.sample("summary", Duration::from_millis(300));
+        timings.sample("summary", Duration::from_millis(350));
+        timings.sample("final", Duration::from_millis(250));
+        let timed = record("gpt-5-nano", 10, false).with_timings(&timings);
+
+        assert_eq!(timed.phases_ms["staging"], 12);
+        assert_eq!(timed.phases_ms["total"], 912);
+        assert_eq!(timed.calls_ms["summary"], vec![300, 350]);
+
+        let line = r#"{"timestamp":1,"provider":"openai","model":"gpt-5","tokens":5,"used_fallback":false}"#;
+        let old: StatsRecord = serde_json::from_str(line).expect("parse record without timings");
+        assert!(old.phases_ms.is_empty() && old.calls_ms.is_empty());
+    }
+
+    #[test]
+    fn perf_summary_reports_nearest_rank_percentiles() {
+        let records = (1..=20)
+            .map(|ms| {
+                let mut timed = record("gpt-5-nano", 10, false);
+                timed.phases_ms.insert("total".to_string(), ms * 10);
+                timed
+                    .calls_ms
+                    .insert("summary".to_string(), vec![ms, ms + 100]);
+                timed
+            })
+            .chain([record("gpt-5", 10, false)])
+            .collect::<Vec<_>>();
+
+        let perf = perf_summary(&records);
+        assert!(!perf.contains_key("openai/gpt-5"));
+        let nano = &perf["openai/gpt-5-nano"];
+        assert_eq!(
+            nano["total"],
+            Percentiles {
+                samples: 20,
+                p50: 100,
+                p95: 190
+            }
+        );
+        assert_eq!(nano["summary call"].samples, 40);
+        assert_eq!(nano["summary call"].p50, 20);
+        assert_eq!(nano["summary call"].p95, 118);
+    }
+}
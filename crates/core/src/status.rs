@@ -0,0 +1,260 @@
+use crate::error::{CoreError, CoreResult};
+
+/// Working tree state from one `git status --porcelain=v2 -z` call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoStatus {
+    /// Changes in the index.
+    pub staged: Vec<FileEntry>,
+    /// Tracked files changed in the working tree but not staged, plus merge conflicts.
+    pub unstaged: Vec<FileEntry>,
+    pub untracked: Vec<FileEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    pub path: String,
+    pub change: FileChange,
+    /// Source path of a rename or copy.
+    pub original_path: Option<String>,
+    pub submodule: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Unmerged,
+    Untracked,
+}
+
+impl RepoStatus {
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.staged.is_empty() && self.unstaged.is_empty() && self.untracked.is_empty()
+    }
+
+    /// Paths with work tree changes, tracked first, in git's order.
+    #[must_use]
+    pub fn working_tree_paths(&self) -> Vec<String> {
+        self.unstaged
+            .iter()
+            .chain(&self.untracked)
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+}
+
+impl FileChange {
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            b'A' => Some(Self::Added),
+            b'M' => Some(Self::Modified),
+            b'D' => Some(Self::Deleted),
+            b'R' => Some(Self::Renamed),
+            b'C' => Some(Self::Copied),
+            b'T' => Some(Self::TypeChanged),
+            _ => None,
+        }
+    }
+}
+
+/// Parse `git status --porcelain=v2 -z` output; ignored (`!`) and header (`#`) lines are skipped.
+///
+/// # Errors
+/// Returns an error when a record does not have the expected fields.
+pub fn parse_porcelain_v2(output: &str) -> CoreResult<RepoStatus> {
+    let mut status = RepoStatus::default();
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+
+    while let Some(record) = records.next() {
+        let malformed = || CoreError::Git(format!("unexpected git status record: {record:?}"));
+        match record.as_bytes()[0] {
+            b'1' => {
+                let fields = record.splitn(9, ' ').collect::<Vec<_>>();
+                let [_, xy, sub, _, _, _, _, _, path] = fields[..] else {
+                    return Err(malformed());
+                };
+                push_changed(&mut status, xy, sub, path, None).ok_or_else(malformed)?;
+            }
+            b'2' => {
+                let fields = record.splitn(10, ' ').collect::<Vec<_>>();
+                let [_, xy, sub, _, _, _, _, _, _, path] = fields[..] else {
+                    return Err(malformed());
+                };
+                let original = records.next().ok_or_else(malformed)?;
+                push_changed(&mut status, xy, sub, path, Some(original)).ok_or_else(malformed)?;
+            }
+            b'u' => {
+                let fields = record.splitn(11, ' ').collect::<Vec<_>>();
+                let [_, _, sub, _, _, _, _, _, _, _, path] = fields[..] else {
+                    return Err(malformed());
+                };
+                status
+                    .unstaged
+                    .push(entry(path, FileChange::Unmerged, None, sub));
+            }
+            b'?' => {
+                let path = record.get(2..).ok_or_else(malformed)?;
+                status
+                    .untracked
+                    .push(entry(path, FileChange::Untracked, None, "N..."));
+            }
+            b'!' | b'#' => {}
+            _ => return Err(malformed()),
+        }
+    }
+
+    Ok(status)
+}
+
+fn push_changed(
+    status: &mut RepoStatus,
+    xy: &str,
+    sub: &str,
+    path: &str,
+    original: Option<&str>,
+) -> Option<()> {
+    let &[index, worktree] = xy.as_bytes() else {
+        return None;
+    };
+    if index != b'.' {
+        let change = FileChange::from_code(index)?;
+        status.staged.push(entry(path, change, original, sub));
+    }
+    if worktree != b'.' {
+        let change = FileChange::from_code(worktree)?;
+        // The rename lives in the index; the work tree change is to the new path.
+        status.unstaged.push(entry(path, change, None, sub));
+    }
+    Some(())
+}
+
+fn entry(path: &str, change: FileChange, original: Option<&str>, sub: &str) -> FileEntry {
+    FileEntry {
+        path: path.to_string(),
+        change,
+        original_path: original.map(str::to_string),
+        submodule: sub.starts_with('S'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn ordinary(xy: &str, sub: &str, path: &str) -> String {
+        format!("1 {xy} {sub} 100644 100644 100644 {HASH} {HASH} {path}\0")
+    }
+
+    #[test]
+    fn splits_staged_unstaged_and_untracked() {
+        let output = format!(
+            "{}{}{}? notes.txt\0! target/\0",
+            ordinary("M.", "N...", "src/lib.rs"),
+            ordinary(".M", "N...", "README.md"),
+            ordinary("AM", "N...", "src/new file.rs"),
+        );
+        let status = parse_porcelain_v2(&output).expect("parse");
+
+        let staged = status.staged.iter().map(|e| (e.path.as_str(), e.change));
+        assert_eq!(
+            staged.collect::<Vec<_>>(),
+            vec![
+                ("src/lib.rs", FileChange::Modified),
+                ("src/new file.rs", FileChange::Added)
+            ]
+        );
+        let unstaged = status.unstaged.iter().map(|e| e.path.as_str());
+        assert_eq!(
+            unstaged.collect::<Vec<_>>(),
+            vec!["README.md", "src/new file.rs"]
+        );
+        assert_eq!(status.untracked[0].path, "notes.txt");
+        assert_eq!(status.untracked[0].change, FileChange::Untracked);
+        assert_eq!(
+            status.working_tree_paths(),
+            vec!["README.md", "src/new file.rs", "notes.txt"]
+        );
+        assert!(!status.is_clean());
+    }
+
+    #[test]
+    fn renames_carry_their_original_path() {
+        let output = format!(
+            "2 RM N... 100644 100644 100644 {HASH} {HASH} R87 src/new.rs\0src/old.rs\0{}",
+            ordinary("D.", "N...", "gone.rs"),
+        );
+        let status = parse_porcelain_v2(&output).expect("parse");
+
+        assert_eq!(status.staged.len(), 2);
+        assert_eq!(status.staged[0].path, "src/new.rs");
+        assert_eq!(status.staged[0].change, FileChange::Renamed);
+        assert_eq!(
+            status.staged[0].original_path.as_deref(),
+            Some("src/old.rs")
+        );
+        assert_eq!(status.staged[1].change, FileChange::Deleted);
+        assert_eq!(status.unstaged.len(), 1);
+        assert_eq!(status.unstaged[0].path, "src/new.rs");
+        assert_eq!(status.unstaged[0].original_path, None);
+    }
+
+    #[test]
+    fn copies_and_type_changes_are_recognized() {
+        let output = format!(
+            "2 C. N... 100644 100644 100644 {HASH} {HASH} C100 b.txt\0a.txt\0{}",
+            ordinary(".T", "N...", "link"),
+        );
+        let status = parse_porcelain_v2(&output).expect("parse");
+        assert_eq!(status.staged[0].change, FileChange::Copied);
+        assert_eq!(status.staged[0].original_path.as_deref(), Some("a.txt"));
+        assert_eq!(status.unstaged[0].change, FileChange::TypeChanged);
+    }
+
+    #[test]
+    fn submodules_are_flagged() {
+        let output = format!(
+            "{}{}",
+            ordinary(".M", "S.M.", "vendor/lib"),
+            ordinary("A.", "S...", "vendor/new"),
+        );
+        let status = parse_porcelain_v2(&output).expect("parse");
+        assert!(status.unstaged[0].submodule);
+        assert!(status.staged[0].submodule);
+        assert_eq!(status.staged[0].path, "vendor/new");
+    }
+
+    #[test]
+    fn unmerged_entries_are_unstaged_conflicts() {
+        let output =
+            format!("u UU N... 100644 100644 100644 100644 {HASH} {HASH} {HASH} src/conflict.rs\0");
+        let status = parse_porcelain_v2(&output).expect("parse");
+        assert!(status.staged.is_empty());
+        assert_eq!(status.unstaged[0].path, "src/conflict.rs");
+        assert_eq!(status.unstaged[0].change, FileChange::Unmerged);
+    }
+
+    #[test]
+    fn headers_and_empty_output_are_clean() {
+        let status =
+            parse_porcelain_v2("# branch.oid (initial)\0# branch.head main\0").expect("parse");
+        assert!(status.is_clean());
+        assert!(parse_porcelain_v2("").expect("parse").is_clean());
+    }
+
+    #[test]
+    fn malformed_records_are_errors() {
+        assert!(parse_porcelain_v2("1 M. N...\0").is_err());
+        assert!(parse_porcelain_v2(&format!(
+            "2 R. N... 100644 100644 100644 {HASH} {HASH} R100 new.rs\0"
+        ))
+        .is_err());
+        assert!(parse_porcelain_v2("x what\0").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::CoreResult;
+
+const STYLE_FILE: &str = "style.toml";
+const MAX_SCOPES: usize = 5;
+const MAX_TYPES: usize = 3;
+
+/// Commit conventions learned from a repo's history by `goodcommit learn`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StyleProfile {
+    /// Seconds since the Unix epoch.
+    pub learned_at: u64,
+    /// Number of commits the profile was derived from.
+    pub commits: usize,
+    /// Most used scopes, most frequent first.
+    pub scopes: Vec<String>,
+    /// Most used types with their share of conventional commits, most frequent first.
+    pub types: Vec<TypeShare>,
+    /// Share of commits with a body.
+    pub body_ratio: f32,
+    pub average_subject_length: usize,
+    /// Dominant programming language of the tracked files.
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeShare {
+    pub kind: String,
+    pub share: f32,
+}
+
+impl StyleProfile {
+    /// Derive a profile from full commit messages, newest first.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_messages(messages: &[String], language: Option<&str>) -> Self {
+        let mut types: HashMap<String, usize> = HashMap::new();
+        let mut scopes: HashMap<String, usize> = HashMap::new();
+        let mut with_body = 0usize;
+        let mut subject_chars = 0usize;
+        let mut conventional = 0usize;
+        let mut commits = 0usize;
+
+        for message in messages {
+            let mut lines = message.trim().lines();
+            let Some(subject) = lines.next().map(str::trim).filter(|s| !s.is_empty()) else {
+                continue;
+            };
+            commits += 1;
+            subject_chars += subject.chars().count();
+            if lines.any(|line| !line.trim().is_empty()) {
+                with_body += 1;
+            }
+
+            if let Some(caps) = header_regex().captures(subject) {
+                conventional += 1;
+                *types.entry(caps[1].to_string()).or_default() += 1;
+                if let Some(scope) = caps.get(2) {
+                    *scopes.entry(scope.as_str().to_string()).or_default() += 1;
+                }
+            }
+        }
+
+        if commits == 0 {
+            return Self::default();
+        }
+
+        // A scope used once is noise, not a convention.
+        let scopes = ranked(scopes)
+            .into_iter()
+            .filter(|(_, count)| *count >= 2)
+            .take(MAX_SCOPES)
+            .map(|(scope, _)| scope)
+            .collect();
+        let types = ranked(types)
+            .into_iter()
+            .take(MAX_TYPES)
+            .map(|(kind, count)| TypeShare {
+                kind,
+                share: count as f32 / conventional as f32,
+            })
+            .collect();
+
+        Self {
+            learned_at: now_secs(),
+            commits,
+            scopes,
+            types,
+            body_ratio: with_body as f32 / commits as f32,
+            average_subject_length: subject_chars / commits,
+            language: language.map(str::to_string),
+        }
+    }
+
+    /// Whether the profile is older than `max_age_days`; `0` means it never expires.
+    #[must_use]
+    pub fn is_stale(&self, max_age_days: u32) -> bool {
+        max_age_days > 0
+            && now_secs().saturating_sub(self.learned_at) > u64::from(max_age_days) * 86_400
+    }
+
+    /// Prompt lines describing the profile; empty when there is nothing useful to say.
+    #[must_use]
+    pub fn prompt_lines(&self) -> String {
+        let mut lines = String::new();
+        if self.commits == 0 {
+            return lines;
+        }
+        if !self.scopes.is_empty() {
+            let _ = writeln!(
+                lines,
+                "- This repo commonly uses scopes: {}.",
+                self.scopes.join(", ")
+            );
+        }
+        if !self.types.is_empty() {
+            let types = self
+                .types
+                .iter()
+                .map(|share| format!("{} ({:.0}%)", share.kind, share.share * 100.0))
+                .collect::<Vec<_>>();
+            let _ = writeln!(lines, "- Most common types: {}.", types.join(", "));
+        }
+        if self.average_subject_length > 0 {
+            let _ = writeln!(
+                lines,
+                "- Subjects are typically around {} characters.",
+                self.average_subject_length
+            );
+        }
+        if self.body_ratio >= 0.6 {
+            lines.push_str("- Most commits include a short body.\n");
+        } else if self.body_ratio <= 0.2 {
+            lines.push_str("- Most commits are a single subject line.\n");
+        }
+        lines
+    }
+}
+
+/// Where the learned profile lives, inside the repo's git directory.
+#[must_use]
+pub fn style_path(git_dir: &Path) -> PathBuf {
+    git_dir.join("goodcommit").join(STYLE_FILE)
+}
+
+/// Read a saved profile; `None` when there is none or it cannot be parsed.
+#[must_use]
+pub fn load_profile(path: &Path) -> Option<StyleProfile> {
+    let content = fs::read_to_string(path).ok()?;
+    toml::from_str(&content).ok()
+}
+
+/// Write a profile, creating its directory if needed.
+///
+/// # Errors
+/// Returns an error when the file cannot be serialized or written.
+pub fn save_profile(path: &Path, profile: &StyleProfile) -> CoreResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, toml::to_string_pretty(profile)?)?;
+    Ok(())
+}
+
+fn ranked(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut ranked = counts.into_iter().collect::<Vec<_>>();
+    ranked.sort_by(|(a_name, a_count), (b_name, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_name.cmp(b_name))
+    });
+    ranked
+}
+
+fn header_regex() -> &'static Regex {
+    static RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^([a-z]+)(?:\(([\w./-]+)\))?!?: ").expect("invalid regex"));
+    &RE
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> Vec<String> {
+        [
+            "feat(cli): add learn command\n\nScans recent history.",
+            "fix(core): handle empty diff",
+            "feat(cli): add --json",
+            "fix(providers): retry on 429",
+            "fix(core): trim quotes",
+            "docs: update readme",
+            "chore(release): 0.3.3",
+            "Merge branch 'main'",
+        ]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+    }
+
+    #[test]
+    fn from_messages_extracts_scopes_types_and_body_ratio() {
+        let profile = StyleProfile::from_messages(&history(), Some("rust"));
+
+        assert_eq!(profile.commits, 8);
+        assert_eq!(profile.scopes, vec!["cli", "core"]);
+        assert_eq!(profile.types[0].kind, "fix");
+        assert!((profile.types[0].share - 3.0 / 7.0).abs() < 0.01);
+        assert_eq!(profile.types[1].kind, "feat");
+        assert!((profile.body_ratio - 0.125).abs() < 0.01);
+        assert_eq!(profile.language.as_deref(), Some("rust"));
+    }
+
+    #[test]
+    fn prompt_lines_describe_the_profile() {
+        let lines = StyleProfile::from_messages(&history(), None).prompt_lines();
+        assert!(lines.contains("scopes: cli, core."));
+        assert!(lines.contains("fix (43%)"));
+        assert!(lines.contains("single subject line"));
+    }
+
+    #[test]
+    fn empty_history_gives_empty_profile() {
+        let profile = StyleProfile::from_messages(&[String::new()], None);
+        assert_eq!(profile, StyleProfile::default());
+        assert!(profile.prompt_lines().is_empty());
+    }
+
+    #[test]
+    fn profile_round_trips_and_ages() {
+        let dir = std::env::temp_dir().join(format!("goodcommit-style-{}", std::process::id()));
+        let path = style_path(&dir);
+        let mut profile = StyleProfile::from_messages(&history(), Some("rust"));
+        save_profile(&path, &profile).expect("save");
+        assert_eq!(load_profile(&path), Some(profile.clone()));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(!profile.is_stale(30));
+        profile.learned_at -= 31 * 86_400;
+        assert!(profile.is_stale(30));
+        assert!(!profile.is_stale(0));
+    }
+}
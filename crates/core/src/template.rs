@@ -0,0 +1,51 @@
+use crate::error::{CoreError, CoreResult};
+
+/// Placeholders `fallback_template` may use.
+pub(crate) const FALLBACK_PLACEHOLDERS: [&str; 4] = ["files", "count", "top_dir", "stats"];
+
+/// Check that `template` only uses known placeholders and closes every brace.
+///
+/// # Errors
+/// Returns a config error naming the first bad placeholder.
+pub(crate) fn check_fallback_template(template: &str) -> CoreResult<()> {
+    if template.trim().is_empty() {
+        return Err(CoreError::Config("fallback_template is empty".to_string()));
+    }
+    expand_placeholders(template, |name| {
+        FALLBACK_PLACEHOLDERS.contains(&name).then(String::new)
+    })
+    .map(|_| ())
+    .map_err(|bad| {
+        CoreError::Config(format!(
+            "fallback_template has an unknown or unclosed placeholder `{bad}` (use {})",
+            FALLBACK_PLACEHOLDERS
+                .iter()
+                .map(|name| format!("{{{name}}}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    })
+}
+
+/// Replace each `{name}` in one pass, so expanded values are never expanded again.
+///
+/// Returns the offending text for an unknown or unclosed placeholder.
+pub(crate) fn expand_placeholders(
+    template: &str,
+    value: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            return Err(rest[start..].to_string());
+        };
+        let name = &after[..end];
+        out.push_str(&value(name).ok_or_else(|| format!("{{{name}}}"))?);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
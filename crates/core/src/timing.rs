@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+
+/// Wall-clock durations per named phase, in the order phases first ran, plus the latency
+/// of each provider call.
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+    samples: Vec<(&'static str, Duration)>,
+    /// Time the run took outside any phase, set by [`Timings::finish`].
+    untimed: Duration,
+}
+
+impl Timings {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `duration` to `phase`; repeated phases (e.g. several provider calls) accumulate.
+    pub fn record(&mut self, phase: &'static str, duration: Duration) {
+        if let Some((_, total)) = self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            *total += duration;
+        } else {
+            self.phases.push((phase, duration));
+        }
+    }
+
+    /// Run `f` and record how long it took under `phase`.
+    pub fn time<T>(&mut self, phase: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let value = f();
+        self.record(phase, start.elapsed());
+        value
+    }
+
+    /// Keep the latency of one call (e.g. one summary request) without summing it.
+    pub fn sample(&mut self, call: &'static str, duration: Duration) {
+        self.samples.push((call, duration));
+    }
+
+    /// Note that the run took `elapsed` from start to end, so [`Timings::total`] also
+    /// counts the time spent between phases.
+    pub fn finish(&mut self, elapsed: Duration) {
+        self.untimed = elapsed.saturating_sub(self.total());
+    }
+
+    pub fn extend(&mut self, other: &Timings) {
+        for (phase, duration) in &other.phases {
+            self.record(phase, *duration);
+        }
+        self.samples.extend_from_slice(&other.samples);
+        self.untimed += other.untimed;
+    }
+
+    #[must_use]
+    pub fn phases(&self) -> &[(&'static str, Duration)] {
+        &self.phases
+    }
+
+    #[must_use]
+    pub fn phase(&self, phase: &str) -> Option<Duration> {
+        self.phases
+            .iter()
+            .find(|(name, _)| *name == phase)
+            .map(|(_, duration)| *duration)
+    }
+
+    #[must_use]
+    pub fn samples(&self) -> &[(&'static str, Duration)] {
+        &self.samples
+    }
+
+    /// Wall-clock time of the run: every phase, plus the time between them once
+    /// [`Timings::finish`] has been called.
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.phases
+            .iter()
+            .map(|(_, duration)| *duration)
+            .sum::<Duration>()
+            + self.untimed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_accumulates_repeated_phases_in_order() {
+        let mut timings = Timings::new();
+        timings.record("diff", Duration::from_millis(5));
+        timings.record("provider", Duration::from_millis(100));
+        timings.record("provider", Duration::from_millis(50));
+
+        let phases = timings.phases();
+        assert_eq!(phases.len(), 2);
+        assert_eq!(phases[0], ("diff", Duration::from_millis(5)));
+        assert_eq!(phases[1], ("provider", Duration::from_millis(150)));
+        assert_eq!(timings.total(), Duration::from_millis(155));
+        assert_eq!(timings.phase("provider"), Some(Duration::from_millis(150)));
+        assert_eq!(timings.phase("push"), None);
+    }
+
+    #[test]
+    fn finish_counts_time_between_phases_in_the_total() {
+        let mut pipeline = Timings::new();
+        pipeline.record("diff", Duration::from_millis(5));
+        pipeline.record("provider", Duration::from_millis(100));
+        pipeline.finish(Duration::from_millis(120));
+        assert_eq!(pipeline.total(), Duration::from_millis(120));
+
+        let mut run = Timings::new();
+        run.record("staging", Duration::from_millis(10));
+        run.extend(&pipeline);
+        run.record("commit", Duration::from_millis(30));
+        assert_eq!(run.total(), Duration::from_millis(160));
+        assert_eq!(run.phase("provider"), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn samples_are_kept_apart_and_extended() {
+        let mut timings = Timings::new();
+        timings.sample("summary", Duration::from_millis(40));
+        timings.sample("summary", Duration::from_millis(60));
+        let mut run = Timings::new();
+        run.extend(&timings);
+
+        assert!(run.phases().is_empty());
+        assert_eq!(run.total(), Duration::ZERO);
+        assert_eq!(
+            run.samples(),
+            [
+                ("summary", Duration::from_millis(40)),
+                ("summary", Duration::from_millis(60))
+            ]
+        );
+    }
+}